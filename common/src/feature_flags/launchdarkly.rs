@@ -0,0 +1,413 @@
+//! Feature flags evaluated locally from the flag configuration LaunchDarkly serves to server-side
+//! SDKs. Only a subset of LaunchDarkly's targeting is supported:
+//!
+//! - the off variation of flags turned off,
+//! - individual targets of `client` contexts, keyed by client id,
+//! - the fallthrough variation, when it's a fixed variation.
+//!
+//! Flags using prerequisites, targeting rules, percentage rollouts or individual targets of any
+//! other context kind can't be evaluated. They have no value, so callers use their own default,
+//! and a warning is logged the first time each of them is evaluated.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::FeatureFlags;
+
+const LAUNCHDARKLY_SDK_URL: &str = "https://sdk.launchdarkly.com";
+
+/// Flags are fetched again once they are older than this.
+const FLAGS_TTL: Duration = Duration::from_secs(30);
+
+/// Bounds on fetching flags, so an unreachable LaunchDarkly can't stall invocations.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A failed fetch is retried after this delay, doubled on every further failure up to the max.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// LaunchDarkly context kind requests are evaluated with, keyed by client id.
+const CLIENT_CONTEXT_KIND: &str = "client";
+
+#[derive(Deserialize)]
+struct LaunchDarklyFlags {
+    flags: HashMap<String, LaunchDarklyFlag>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LaunchDarklyFlag {
+    on: bool,
+    #[serde(default)]
+    prerequisites: Vec<Value>,
+    #[serde(default)]
+    targets: Vec<LaunchDarklyTarget>,
+    #[serde(default)]
+    context_targets: Vec<LaunchDarklyTarget>,
+    #[serde(default)]
+    rules: Vec<Value>,
+    fallthrough: LaunchDarklyFallthrough,
+    off_variation: Option<usize>,
+    variations: Vec<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LaunchDarklyTarget {
+    /// Missing on the legacy `targets`, which are all user contexts.
+    #[serde(default = "user_context_kind")]
+    context_kind: String,
+    values: Vec<String>,
+    variation: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct LaunchDarklyFallthrough {
+    variation: Option<usize>,
+}
+
+fn user_context_kind() -> String {
+    "user".to_owned()
+}
+
+impl LaunchDarklyFlag {
+    /// Individually targeted clients get their variation and everyone else the fallthrough one.
+    /// Flags using anything else the module docs list as unsupported have no value.
+    fn evaluate(&self, client_id: Option<&str>) -> Option<&Value> {
+        if !self.on {
+            return self.variations.get(self.off_variation?);
+        }
+        if !self.is_supported() {
+            return None;
+        }
+
+        let target = client_id.and_then(|client_id| {
+            self.context_targets.iter().find(|target| {
+                target.context_kind == CLIENT_CONTEXT_KIND
+                    && target.values.iter().any(|value| value == client_id)
+            })
+        });
+        let variation = match target {
+            Some(target) => target.variation,
+            None => self.fallthrough.variation?,
+        };
+
+        self.variations.get(variation)
+    }
+
+    fn is_supported(&self) -> bool {
+        self.prerequisites.is_empty()
+            && self.rules.is_empty()
+            && self
+                .targets
+                .iter()
+                .chain(&self.context_targets)
+                .all(|target| {
+                    target.values.is_empty() || target.context_kind == CLIENT_CONTEXT_KIND
+                })
+    }
+}
+
+#[derive(Default)]
+struct FlagsCache {
+    /// Last configuration fetched successfully.
+    flags: Option<HashMap<String, LaunchDarklyFlag>>,
+    /// Unset until the first fetch.
+    next_fetch_at: Option<Instant>,
+    failed_fetches: u32,
+}
+
+impl FlagsCache {
+    fn is_due(&self, now: Instant) -> bool {
+        self.next_fetch_at
+            .is_none_or(|next_fetch_at| now >= next_fetch_at)
+    }
+
+    fn fetched(&mut self, flags: HashMap<String, LaunchDarklyFlag>, now: Instant) {
+        self.flags = Some(flags);
+        self.failed_fetches = 0;
+        self.next_fetch_at = Some(now + FLAGS_TTL);
+    }
+
+    /// Keeps the last fetched flags and backs off before fetching again.
+    fn fetch_failed(&mut self, now: Instant) {
+        let retry_delay = FIRST_RETRY_DELAY
+            .saturating_mul(2u32.saturating_pow(self.failed_fetches))
+            .min(MAX_RETRY_DELAY);
+        self.failed_fetches = self.failed_fetches.saturating_add(1);
+        self.next_fetch_at = Some(now + retry_delay);
+    }
+}
+
+/// Feature flags evaluated from LaunchDarkly's flag configuration, polled with a server-side SDK
+/// key. Clients are `client` contexts keyed by their id. If LaunchDarkly can't be reached the
+/// last fetched configuration keeps being used while fetches are retried with a backoff.
+pub struct LaunchDarklyFeatureFlags {
+    sdk_key: String,
+    base_url: String,
+    http: reqwest::Client,
+    cache: RwLock<FlagsCache>,
+    /// Flags already warned about not being evaluable, so each is only logged once.
+    warned_flags: Mutex<HashSet<String>>,
+}
+
+impl LaunchDarklyFeatureFlags {
+    pub fn new(sdk_key: String) -> Self {
+        Self::new_at_url(sdk_key, LAUNCHDARKLY_SDK_URL.to_owned())
+    }
+
+    pub fn new_at_url(sdk_key: String, base_url: String) -> Self {
+        Self {
+            sdk_key,
+            base_url,
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("LaunchDarkly HTTP client should build"),
+            cache: RwLock::new(FlagsCache::default()),
+            warned_flags: Mutex::new(HashSet::new()),
+        }
+    }
+
+    async fn fetch_flags(&self) -> reqwest::Result<HashMap<String, LaunchDarklyFlag>> {
+        let response = self
+            .http
+            .get(format!("{}/sdk/latest-all", self.base_url))
+            .header(reqwest::header::AUTHORIZATION, &self.sdk_key)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.json::<LaunchDarklyFlags>().await?.flags)
+    }
+
+    async fn refresh_if_due(&self) {
+        let is_due = self
+            .cache
+            .read()
+            .map(|cache| cache.is_due(Instant::now()))
+            .unwrap_or(false);
+        if !is_due {
+            return;
+        }
+
+        let fetched = self.fetch_flags().await;
+        if let Ok(mut cache) = self.cache.write() {
+            match fetched {
+                Ok(flags) => cache.fetched(flags, Instant::now()),
+                Err(_) => cache.fetch_failed(Instant::now()),
+            }
+        }
+    }
+
+    async fn evaluate(&self, flag: &str, client_id: Option<&str>) -> Option<Value> {
+        self.refresh_if_due().await;
+        self.evaluate_cached(flag, client_id)
+    }
+
+    /// Evaluates `flag` with the last fetched configuration.
+    fn evaluate_cached(&self, flag: &str, client_id: Option<&str>) -> Option<Value> {
+        let cache = self.cache.read().ok()?;
+        let value = cache
+            .flags
+            .as_ref()?
+            .get(flag)?
+            .evaluate(client_id)
+            .cloned();
+        if value.is_none() {
+            self.warn_once(flag);
+        }
+
+        value
+    }
+
+    fn warn_once(&self, flag: &str) {
+        if let Ok(mut warned_flags) = self.warned_flags.lock() {
+            if warned_flags.insert(flag.to_owned()) {
+                tracing::warn!(
+                    flag,
+                    "LaunchDarkly flag can't be evaluated, falling back to the default value"
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FeatureFlags for LaunchDarklyFeatureFlags {
+    async fn string_flag(&self, flag: &str, client_id: Option<&str>) -> Option<String> {
+        self.evaluate(flag, client_id)
+            .await
+            .and_then(|value| value.as_str().map(str::to_owned))
+    }
+
+    async fn bool_flag(&self, flag: &str, client_id: Option<&str>) -> Option<bool> {
+        self.evaluate(flag, client_id)
+            .await
+            .and_then(|value| value.as_bool())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use super::{
+        FlagsCache, LaunchDarklyFeatureFlags, LaunchDarklyFlag, FLAGS_TTL, MAX_RETRY_DELAY,
+    };
+
+    fn flag(on: bool) -> LaunchDarklyFlag {
+        serde_json::from_value(json!({
+            "key": "log-level",
+            "on": on,
+            "targets": [],
+            "contextTargets": [
+                { "contextKind": "client", "values": ["debugged_client"], "variation": 1 },
+                { "contextKind": "user", "values": [], "variation": 1 }
+            ],
+            "rules": [],
+            "fallthrough": { "variation": 0 },
+            "offVariation": 2,
+            "variations": ["warn", "debug", "error"],
+            "version": 7
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn flag_variation() {
+        let flag = flag(true);
+
+        assert_eq!(
+            Some(&json!("debug")),
+            flag.evaluate(Some("debugged_client"))
+        );
+        assert_eq!(Some(&json!("warn")), flag.evaluate(Some("other_client")));
+        assert_eq!(Some(&json!("warn")), flag.evaluate(None));
+    }
+
+    #[test]
+    fn off_flag_variation() {
+        assert_eq!(
+            Some(&json!("error")),
+            flag(false).evaluate(Some("debugged_client"))
+        );
+    }
+
+    #[test]
+    fn rollout_fallthrough_has_no_variation() {
+        let flag: LaunchDarklyFlag = serde_json::from_value(json!({
+            "on": true,
+            "fallthrough": { "rollout": { "variations": [] } },
+            "variations": [true, false]
+        }))
+        .unwrap();
+
+        assert_eq!(None, flag.evaluate(Some("some_client")));
+    }
+
+    #[test]
+    fn unsupported_flag_has_no_value() {
+        let unsupported = [
+            json!({ "prerequisites": [{ "key": "other-flag", "variation": 0 }] }),
+            json!({ "rules": [{ "clauses": [], "variation": 1 }] }),
+            json!({ "targets": [{ "values": ["some_client"], "variation": 1 }] }),
+            json!({
+                "contextTargets": [{ "contextKind": "user", "values": ["some_client"], "variation": 1 }]
+            }),
+        ];
+
+        for fields in unsupported {
+            let mut flag = json!({
+                "on": true,
+                "fallthrough": { "variation": 0 },
+                "offVariation": 1,
+                "variations": [true, false]
+            });
+            flag.as_object_mut()
+                .unwrap()
+                .extend(fields.as_object().unwrap().clone());
+            let flag: LaunchDarklyFlag = serde_json::from_value(flag).unwrap();
+
+            assert_eq!(None, flag.evaluate(Some("some_client")));
+            let flag = LaunchDarklyFlag { on: false, ..flag };
+            assert_eq!(Some(&json!(false)), flag.evaluate(Some("some_client")));
+        }
+    }
+
+    #[test]
+    fn failed_fetch_keeps_flags() {
+        let now = Instant::now();
+        let mut cache = FlagsCache::default();
+        assert!(cache.is_due(now));
+
+        cache.fetched(HashMap::new(), now);
+        assert!(!cache.is_due(now + FLAGS_TTL - Duration::from_secs(1)));
+        assert!(cache.is_due(now + FLAGS_TTL));
+
+        cache.fetch_failed(now + FLAGS_TTL);
+        assert!(cache.flags.is_some());
+    }
+
+    #[test]
+    fn failed_fetch_backs_off() {
+        let now = Instant::now();
+        let mut cache = FlagsCache::default();
+
+        cache.fetch_failed(now);
+        assert!(!cache.is_due(now));
+        assert!(cache.is_due(now + Duration::from_secs(1)));
+
+        cache.fetch_failed(now);
+        assert!(!cache.is_due(now + Duration::from_secs(1)));
+        assert!(cache.is_due(now + Duration::from_secs(2)));
+
+        for _ in 0..40 {
+            cache.fetch_failed(now);
+        }
+        assert!(cache.is_due(now + MAX_RETRY_DELAY));
+
+        cache.fetched(HashMap::new(), now);
+        cache.fetch_failed(now);
+        assert!(cache.is_due(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn unevaluable_flag_is_warned_once() {
+        let feature_flags = LaunchDarklyFeatureFlags::new("sdk_key".to_owned());
+        let rollout: LaunchDarklyFlag = serde_json::from_value(json!({
+            "on": true,
+            "fallthrough": { "rollout": { "variations": [] } },
+            "variations": [true, false]
+        }))
+        .unwrap();
+        feature_flags.cache.write().unwrap().fetched(
+            HashMap::from([
+                ("rollout".to_owned(), rollout),
+                ("log-level".to_owned(), flag(true)),
+            ]),
+            Instant::now(),
+        );
+
+        assert_eq!(None, feature_flags.evaluate_cached("rollout", None));
+        assert_eq!(None, feature_flags.evaluate_cached("rollout", None));
+        assert_eq!(
+            Some(json!("warn")),
+            feature_flags.evaluate_cached("log-level", None)
+        );
+
+        let warned_flags = feature_flags.warned_flags.lock().unwrap();
+        assert_eq!(1, warned_flags.len());
+        assert!(warned_flags.contains("rollout"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fs;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::FeatureFlags;
+
+/// Flag of a local flags file, either a value for every client or a default value with some
+/// clients overridden.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum LocalFlag {
+    PerClient(PerClientFlag),
+    Value(Value),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct PerClientFlag {
+    #[serde(default)]
+    default: Option<Value>,
+    #[serde(default)]
+    clients: HashMap<String, Value>,
+}
+
+impl LocalFlag {
+    fn evaluate(&self, client_id: Option<&str>) -> Option<&Value> {
+        match self {
+            LocalFlag::Value(value) => Some(value),
+            LocalFlag::PerClient(flag) => client_id
+                .and_then(|client_id| flag.clients.get(client_id))
+                .or(flag.default.as_ref()),
+        }
+    }
+}
+
+/// Feature flags read from a JSON file, or set in memory with [`LocalFeatureFlags::with_flag`],
+/// for local development and tests:
+///
+/// ```json
+/// {
+///   "log-level": { "default": "warn", "clients": { "some_client": "debug" } },
+///   "chain-137": { "default": false, "clients": { "some_client": true } },
+///   "some-flag": "on"
+/// }
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct LocalFeatureFlags {
+    flags: HashMap<String, LocalFlag>,
+}
+
+impl LocalFeatureFlags {
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            flags: serde_json::from_str(json).context("parsing feature flags")?,
+        })
+    }
+
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("reading feature flags file {path}"))?;
+        Self::from_json(&json)
+    }
+
+    /// Sets `flag` in memory, taking the same values as the flags file.
+    pub fn with_flag(mut self, flag: &str, value: Value) -> Self {
+        let flag_value = serde_json::from_value(value.clone()).unwrap_or(LocalFlag::Value(value));
+        self.flags.insert(flag.to_owned(), flag_value);
+        self
+    }
+
+    fn evaluate(&self, flag: &str, client_id: Option<&str>) -> Option<&Value> {
+        self.flags.get(flag)?.evaluate(client_id)
+    }
+}
+
+#[async_trait]
+impl FeatureFlags for LocalFeatureFlags {
+    async fn string_flag(&self, flag: &str, client_id: Option<&str>) -> Option<String> {
+        self.evaluate(flag, client_id)
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+    }
+
+    async fn bool_flag(&self, flag: &str, client_id: Option<&str>) -> Option<bool> {
+        self.evaluate(flag, client_id).and_then(Value::as_bool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::LocalFeatureFlags;
+
+    const FLAGS: &str = r#"{
+        "log-level": { "default": "warn", "clients": { "debugged_client": "debug" } },
+        "overrides-only": { "clients": { "some_client": "on" } },
+        "same-for-everyone": "on"
+    }"#;
+
+    #[test]
+    fn flag_values() {
+        let flags = LocalFeatureFlags::from_json(FLAGS).unwrap();
+        let value = |flag, client_id| flags.evaluate(flag, client_id).cloned();
+
+        assert_eq!(
+            Some(json!("debug")),
+            value("log-level", Some("debugged_client"))
+        );
+        assert_eq!(
+            Some(json!("warn")),
+            value("log-level", Some("other_client"))
+        );
+        assert_eq!(Some(json!("warn")), value("log-level", None));
+        assert_eq!(
+            Some(json!("on")),
+            value("overrides-only", Some("some_client"))
+        );
+        assert_eq!(None::<Value>, value("overrides-only", Some("other_client")));
+        assert_eq!(
+            Some(json!("on")),
+            value("same-for-everyone", Some("some_client"))
+        );
+        assert_eq!(None::<Value>, value("missing", None));
+    }
+
+    #[test]
+    fn in_memory_flags() {
+        let flags = LocalFeatureFlags::default()
+            .with_flag("same-for-everyone", json!(true))
+            .with_flag(
+                "overrides-only",
+                json!({ "clients": { "some_client": false } }),
+            );
+
+        assert_eq!(
+            Some(&json!(true)),
+            flags.evaluate("same-for-everyone", Some("some_client"))
+        );
+        assert_eq!(
+            Some(&json!(false)),
+            flags.evaluate("overrides-only", Some("some_client"))
+        );
+        assert_eq!(None, flags.evaluate("overrides-only", Some("other_client")));
+    }
+
+    #[test]
+    fn invalid_flags() {
+        assert!(LocalFeatureFlags::from_json("[]").is_err());
+    }
+}
@@ -0,0 +1,24 @@
+mod launchdarkly;
+mod local;
+
+use async_trait::async_trait;
+
+pub use launchdarkly::LaunchDarklyFeatureFlags;
+pub use local::LocalFeatureFlags;
+
+/// String flag holding the log level of an invocation, e.g. `debug`.
+pub const LOG_LEVEL_FLAG: &str = "log-level";
+
+/// Evaluates feature flags for the client a request comes from, so behaviour can be changed per
+/// client without deploying.
+#[async_trait]
+pub trait FeatureFlags: Sync + Send {
+    /// Variation of the string flag `flag` for `client_id`. Returns `None` if the flag doesn't
+    /// exist, isn't a string or can't be evaluated, so callers fall back to their own default.
+    /// Flags are evaluated without a client when `client_id` is `None`.
+    async fn string_flag(&self, flag: &str, client_id: Option<&str>) -> Option<String>;
+
+    /// Variation of the boolean flag `flag` for `client_id`, with the same fallback rules as
+    /// [`FeatureFlags::string_flag`].
+    async fn bool_flag(&self, flag: &str, client_id: Option<&str>) -> Option<bool>;
+}
@@ -1,6 +1,7 @@
 pub mod aws_clients;
 pub mod config;
 pub mod deserializers;
+pub mod feature_flags;
 pub mod macros;
 pub mod serializers;
 pub mod test_tools;
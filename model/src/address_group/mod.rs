@@ -0,0 +1,17 @@
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+/// Named list of addresses of a client, e.g. the exchanges or custodians it sends funds to.
+/// GROUP policy mappings apply to transactions sent to any address of the group.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct AddressGroup {
+    pub client_id: String,
+    pub name: String,
+    pub addresses: Vec<Address>,
+}
+
+impl AddressGroup {
+    pub fn contains(&self, address: &Address) -> bool {
+        self.addresses.contains(address)
+    }
+}
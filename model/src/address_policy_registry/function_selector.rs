@@ -0,0 +1,56 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use ethers::utils::hex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// First 4 bytes of a contract call's calldata, identifying the function being called. Written
+/// and parsed as a 0x prefixed hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FunctionSelector(pub [u8; 4]);
+
+impl FunctionSelector {
+    /// Takes the selector out of a transaction's calldata. Plain transfers and calldata shorter
+    /// than 4 bytes have none.
+    pub fn from_calldata(data: &[u8]) -> Option<Self> {
+        data.get(..4)
+            .and_then(|selector| selector.try_into().ok())
+            .map(Self)
+    }
+}
+
+impl fmt::Display for FunctionSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for FunctionSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix("0x")
+            .ok_or_else(|| anyhow!("function selector must be 0x prefixed"))?;
+        let bytes = hex::decode(digits).map_err(|e| anyhow!("invalid function selector: {e}"))?;
+        let selector = bytes
+            .try_into()
+            .map_err(|_| anyhow!("function selector must be 4 bytes long"))?;
+
+        Ok(Self(selector))
+    }
+}
+
+impl Serialize for FunctionSelector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FunctionSelector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -1,11 +1,45 @@
-use ethers::types::Address;
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub mod function_selector;
+pub mod plan;
+pub mod value_band;
+
+use function_selector::FunctionSelector;
+use value_band::PolicyValueBand;
+
+/// Chain id of client wide mappings. They are stored under the `*` chain and apply on every chain
+/// that has no mapping of its own. Only DEFAULT mappings can be client wide.
+pub const ANY_CHAIN_ID: u64 = 0;
+
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq, Hash)]
 pub enum AddressPolicyRegistryType {
     Default,
-    AddressTo { address: Address },
-    AddressFrom { address: Address },
+    AddressTo {
+        address: Address,
+    },
+    AddressFrom {
+        address: Address,
+    },
+    /// Calls to the contract at `address` whose calldata starts with `selector`.
+    ContractCall {
+        address: Address,
+        selector: FunctionSelector,
+    },
+    /// Transactions signed with the key `key_id`.
+    KeyId {
+        key_id: Uuid,
+    },
+    /// Transactions signed with any key of the end user `client_user_id`.
+    ClientUser {
+        client_user_id: String,
+    },
+    /// Transactions sent to any address of the address group `name`.
+    Group {
+        name: String,
+    },
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
@@ -14,12 +48,114 @@ pub struct AddressPolicyRegistry {
     pub chain_id: u64,
     pub policy: String,
     pub r#type: AddressPolicyRegistryType,
+    /// Incremented on every update, used for optimistic concurrency. Mappings stored before
+    /// versioning was introduced have version 0.
+    #[serde(default)]
+    pub version: u64,
+    /// The mapping is ignored before this instant. `None` means it applies right away.
+    #[serde(default)]
+    pub effective_from: Option<DateTime<Utc>>,
+    /// The mapping is ignored from this instant on. `None` means it never expires.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Bands sorted by their upper bound that pick another policy depending on the native value
+    /// a transaction moves. `policy` applies to values above every band.
+    #[serde(default)]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+impl AddressPolicyRegistry {
+    /// Returns true if the mapping applies on every chain of the client.
+    pub fn is_client_wide(&self) -> bool {
+        self.chain_id == ANY_CHAIN_ID
+    }
+
+    /// Returns true if the mapping applies at `now`.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        !matches!(self.effective_from, Some(from) if from > now) && !self.has_expired_at(now)
+    }
+
+    /// Returns true if the mapping no longer applies at `now` and never will again.
+    pub fn has_expired_at(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+
+    /// Returns the policy for a transaction moving `value` wei: the one of the first band the
+    /// value is below, or the mapping policy if there is no such band.
+    pub fn policy_for_value(&self, value: U256) -> &str {
+        self.value_bands
+            .iter()
+            .find(|band| value < band.below)
+            .map_or(&self.policy, |band| &band.policy)
+    }
+
+    /// Every policy the mapping points to, its own first and then the ones of its bands.
+    pub fn policies(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.policy.as_str())
+            .chain(self.value_bands.iter().map(|band| band.policy.as_str()))
+    }
 }
 
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AddressPolicyRegistryChange {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Activity window and value bands of a mapping, recorded along its policy on each side of a
+/// change.
+#[derive(Deserialize, Debug, Serialize, Clone, Default, PartialEq)]
+pub struct AddressPolicyRegistryTerms {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+impl From<&AddressPolicyRegistry> for AddressPolicyRegistryTerms {
+    fn from(value: &AddressPolicyRegistry) -> Self {
+        Self {
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+            value_bands: value.value_bands.clone(),
+        }
+    }
+}
+
+/// Immutable record of a change made to a policy mapping. `previous_policy` and `previous_terms`
+/// are `None` for created mappings, `new_policy` and `new_terms` for deleted ones. Changes
+/// recorded before terms were tracked have no terms either.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct AddressPolicyRegistryHistory {
+    pub client_id: String,
+    pub chain_id: u64,
+    pub r#type: AddressPolicyRegistryType,
+    pub change: AddressPolicyRegistryChange,
+    pub previous_policy: Option<String>,
+    pub new_policy: Option<String>,
+    #[serde(default)]
+    pub previous_terms: Option<AddressPolicyRegistryTerms>,
+    #[serde(default)]
+    pub new_terms: Option<AddressPolicyRegistryTerms>,
+    /// Subject of the authorizer that made the change, when the token carried one.
+    pub subject: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Version assigned to newly created mappings.
+pub const INITIAL_VERSION: u64 = 1;
+
 pub struct AddressPolicyRegistryBuilder {
     client_id: String,
     chain_id: u64,
     policy: String,
+    effective_from: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    value_bands: Vec<PolicyValueBand>,
 }
 
 impl AddressPolicyRegistryBuilder {
@@ -28,33 +164,72 @@ impl AddressPolicyRegistryBuilder {
             client_id,
             chain_id,
             policy,
+            effective_from: None,
+            expires_at: None,
+            value_bands: Vec::new(),
         }
     }
 
-    pub fn default(self) -> AddressPolicyRegistry {
+    /// Limits the time during which the mapping applies.
+    pub fn active_between(
+        mut self,
+        effective_from: Option<DateTime<Utc>>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.effective_from = effective_from;
+        self.expires_at = expires_at;
+        self
+    }
+
+    /// Picks the policy depending on the native value of the transaction.
+    pub fn value_bands(mut self, value_bands: Vec<PolicyValueBand>) -> Self {
+        self.value_bands = value_bands;
+        self
+    }
+
+    /// Builds the mapping for `r#type`. The per type methods below are shorthands for it.
+    pub fn build(self, r#type: AddressPolicyRegistryType) -> AddressPolicyRegistry {
         AddressPolicyRegistry {
             client_id: self.client_id,
             chain_id: self.chain_id,
             policy: self.policy,
-            r#type: AddressPolicyRegistryType::Default,
+            r#type,
+            version: INITIAL_VERSION,
+            effective_from: self.effective_from,
+            expires_at: self.expires_at,
+            value_bands: self.value_bands,
         }
     }
 
+    pub fn default(self) -> AddressPolicyRegistry {
+        self.build(AddressPolicyRegistryType::Default)
+    }
+
     pub fn address_to(self, address: Address) -> AddressPolicyRegistry {
-        AddressPolicyRegistry {
-            client_id: self.client_id,
-            chain_id: self.chain_id,
-            policy: self.policy,
-            r#type: AddressPolicyRegistryType::AddressTo { address },
-        }
+        self.build(AddressPolicyRegistryType::AddressTo { address })
     }
 
     pub fn address_from(self, address: Address) -> AddressPolicyRegistry {
-        AddressPolicyRegistry {
-            client_id: self.client_id,
-            chain_id: self.chain_id,
-            policy: self.policy,
-            r#type: AddressPolicyRegistryType::AddressFrom { address },
-        }
+        self.build(AddressPolicyRegistryType::AddressFrom { address })
+    }
+
+    pub fn contract_call(
+        self,
+        address: Address,
+        selector: FunctionSelector,
+    ) -> AddressPolicyRegistry {
+        self.build(AddressPolicyRegistryType::ContractCall { address, selector })
+    }
+
+    pub fn key_id(self, key_id: Uuid) -> AddressPolicyRegistry {
+        self.build(AddressPolicyRegistryType::KeyId { key_id })
+    }
+
+    pub fn client_user(self, client_user_id: String) -> AddressPolicyRegistry {
+        self.build(AddressPolicyRegistryType::ClientUser { client_user_id })
+    }
+
+    pub fn group(self, name: String) -> AddressPolicyRegistry {
+        self.build(AddressPolicyRegistryType::Group { name })
     }
 }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::{AddressPolicyRegistry, AddressPolicyRegistryType};
+
+/// Stored mapping that has to be rewritten to match its desired state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressPolicyRegistryUpdate {
+    pub current: AddressPolicyRegistry,
+    /// Desired state of the mapping, its version is the one it will have once written.
+    pub desired: AddressPolicyRegistry,
+}
+
+/// Changes needed to take a client's stored mappings to a desired set of mappings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressPolicyRegistryPlan {
+    pub creates: Vec<AddressPolicyRegistry>,
+    pub updates: Vec<AddressPolicyRegistryUpdate>,
+    pub deletes: Vec<AddressPolicyRegistry>,
+}
+
+impl AddressPolicyRegistryPlan {
+    /// Diffs the `current` mappings against the `desired` ones. Mappings are matched by chain
+    /// and type, and a matched mapping is updated when its policy, its window or its value bands
+    /// differ. Expired mappings are treated as already gone.
+    pub fn between(
+        current: Vec<AddressPolicyRegistry>,
+        desired: Vec<AddressPolicyRegistry>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let current = current
+            .into_iter()
+            .filter(|mapping| !mapping.has_expired_at(now))
+            .collect::<Vec<_>>();
+        let positions: HashMap<(u64, &AddressPolicyRegistryType), usize> = current
+            .iter()
+            .enumerate()
+            .map(|(position, mapping)| ((mapping.chain_id, &mapping.r#type), position))
+            .collect();
+
+        let mut plan = Self::default();
+        let mut matched = vec![false; current.len()];
+        for mut mapping in desired {
+            let position = match positions.get(&(mapping.chain_id, &mapping.r#type)) {
+                Some(position) => *position,
+                None => {
+                    plan.creates.push(mapping);
+                    continue;
+                }
+            };
+
+            matched[position] = true;
+            let stored = &current[position];
+            if stored.differs_from(&mapping) {
+                mapping.version = stored.version + 1;
+                plan.updates.push(AddressPolicyRegistryUpdate {
+                    current: stored.clone(),
+                    desired: mapping,
+                });
+            }
+        }
+
+        plan.deletes = current
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(mapping, _)| mapping)
+            .collect();
+
+        plan
+    }
+
+    /// Number of mappings the plan writes.
+    pub fn len(&self) -> usize {
+        self.creates.len() + self.updates.len() + self.deletes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the plan in consecutive plans of up to `size` changes each, keeping the creates,
+    /// updates and deletes order.
+    pub fn into_chunks(self, size: usize) -> Vec<Self> {
+        let mut chunks: Vec<Self> = vec![];
+        for mapping in self.creates {
+            open_chunk(&mut chunks, size).creates.push(mapping);
+        }
+        for update in self.updates {
+            open_chunk(&mut chunks, size).updates.push(update);
+        }
+        for mapping in self.deletes {
+            open_chunk(&mut chunks, size).deletes.push(mapping);
+        }
+        chunks
+    }
+
+    /// Appends the changes of `other` to the plan.
+    pub fn extend(&mut self, other: Self) {
+        self.creates.extend(other.creates);
+        self.updates.extend(other.updates);
+        self.deletes.extend(other.deletes);
+    }
+}
+
+fn open_chunk(
+    chunks: &mut Vec<AddressPolicyRegistryPlan>,
+    size: usize,
+) -> &mut AddressPolicyRegistryPlan {
+    if chunks.last().map_or(true, |chunk| chunk.len() >= size) {
+        chunks.push(AddressPolicyRegistryPlan::default());
+    }
+    chunks.last_mut().expect("a chunk was just pushed")
+}
+
+impl AddressPolicyRegistry {
+    fn differs_from(&self, other: &AddressPolicyRegistry) -> bool {
+        self.policy != other.policy
+            || self.effective_from != other.effective_from
+            || self.expires_at != other.expires_at
+            || self.value_bands != other.value_bands
+    }
+}
@@ -0,0 +1,14 @@
+use common::deserializers::u256::{decimal_u256, unsigned_integer_256};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// Policy that applies to transactions moving less than `below` wei of native value.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
+pub struct PolicyValueBand {
+    #[serde(
+        serialize_with = "decimal_u256",
+        deserialize_with = "unsigned_integer_256"
+    )]
+    pub below: U256,
+    pub policy: String,
+}
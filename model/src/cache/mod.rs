@@ -21,6 +21,7 @@ pub struct CacheItem<T> {
 pub enum DataType {
     FtMetadata,
     AddressLock,
+    PolicyOwnership,
 }
 
 impl Display for DataType {
@@ -35,6 +36,7 @@ impl DataType {
         match self {
             DataType::FtMetadata => "FT_METADATA",
             DataType::AddressLock => "ADDRESS_LOCK",
+            DataType::PolicyOwnership => "POLICY_OWNERSHIP",
         }
     }
 }
@@ -46,6 +48,7 @@ impl FromStr for DataType {
         let s = s.to_uppercase(); // Convert the input to uppercase for case-insensitive comparison
         match s.as_str() {
             "FT_METADATA" => Ok(DataType::FtMetadata),
+            "POLICY_OWNERSHIP" => Ok(DataType::PolicyOwnership),
             other => Err(anyhow!("Not supported DataType variant: {other}")),
         }
     }
@@ -1,3 +1,4 @@
+pub mod address_group;
 pub mod address_policy_registry;
 pub mod cache;
 pub mod key;
@@ -0,0 +1,1072 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use ethers::types::Address;
+use model::address_group::AddressGroup;
+use rusoto_core::RusotoError;
+use rusoto_dynamodb::{
+    AttributeValue, BatchWriteItemInput, BatchWriteItemOutput, DeleteItemError, DeleteItemInput,
+    DeleteRequest, DynamoDb, GetItemInput, PutItemError, PutItemInput, PutRequest, QueryInput,
+    UpdateItemError, UpdateItemInput, WriteRequest,
+};
+
+use crate::deserialize::deserialize_from_dynamo;
+
+use super::{
+    address_partition_key, client_partition_key, group_version_condition_expression,
+    lowercase_addresses, AddressGroupDynamoDbResource, AddressGroupMembershipDynamoDbResource,
+    AddressGroupPk, AddressGroupsRepository, AddressGroupsRepositoryError, ExpectedGroupVersion,
+    PartitionQueryValues, UpdateGroupAddresses,
+};
+
+/// DynamoDB limit for a single `BatchWriteItem` call.
+const BATCH_WRITE_MAX_ITEMS: usize = 25;
+/// Unprocessed items are retried with exponential backoff up to this many attempts.
+const BATCH_MAX_ATTEMPTS: u32 = 5;
+const BATCH_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// Updates and deletes read the group again and redo the memberships up to this many times when
+/// the group changed since it was read.
+const GROUP_WRITE_MAX_ATTEMPTS: u32 = 3;
+
+pub struct AddressGroupsRepositoryImpl<T: DynamoDb + Sync + Send> {
+    table_name: String,
+    dynamodb_client: T,
+}
+
+impl<T: DynamoDb + Sync + Send> AddressGroupsRepositoryImpl<T> {
+    pub fn new(table_name: String, dynamodb_client: T) -> Self {
+        Self {
+            table_name,
+            dynamodb_client,
+        }
+    }
+
+    fn build_key(
+        &self,
+        client_id: &str,
+        name: &str,
+    ) -> Result<HashMap<String, AttributeValue>, AddressGroupsRepositoryError> {
+        serde_dynamo::to_item(AddressGroupPk::new(client_id, name)).map_err(|e| {
+            AddressGroupsRepositoryError::Unknown(
+                anyhow!(e).context("Error building key for address group"),
+            )
+        })
+    }
+
+    async fn get_group_resource(
+        &self,
+        client_id: &str,
+        name: &str,
+    ) -> Result<Option<AddressGroupDynamoDbResource>, AddressGroupsRepositoryError> {
+        let input = GetItemInput {
+            key: self.build_key(client_id, name)?,
+            table_name: self.table_name.clone(),
+            ..GetItemInput::default()
+        };
+
+        let item = self
+            .dynamodb_client
+            .get_item(input)
+            .await
+            .map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(anyhow!(e).context(format!(
+                    "Error getting address group {name} of client_id: {client_id}"
+                )))
+            })?
+            .item;
+
+        item.map(deserialize_from_dynamo).transpose()
+    }
+
+    /// Writes the addresses of `group` if the stored group is still at `expected_version`.
+    /// Returns false if it isn't, or if the group was deleted.
+    async fn write_group_addresses(
+        &self,
+        group: &AddressGroup,
+        expected_version: u64,
+    ) -> Result<bool, AddressGroupsRepositoryError> {
+        let expression_attribute_values = serde_dynamo::to_item(UpdateGroupAddresses {
+            addresses: lowercase_addresses(&group.addresses),
+            last_modified_at: Utc::now(),
+            version: expected_version + 1,
+            expected_version: Some(expected_version).filter(|version| *version > 0),
+        })
+        .map_err(|e| {
+            AddressGroupsRepositoryError::Unknown(
+                anyhow!(e).context("Error serializing address group update"),
+            )
+        })?;
+
+        let input = UpdateItemInput {
+            key: self.build_key(&group.client_id, &group.name)?,
+            table_name: self.table_name.clone(),
+            update_expression: Some(
+                "SET addresses = :addresses, last_modified_at = :last_modified_at, #version = :version"
+                    .to_owned(),
+            ),
+            condition_expression: Some(group_version_condition_expression(expected_version)),
+            expression_attribute_names: Some(HashMap::from([(
+                "#version".to_owned(),
+                "version".to_owned(),
+            )])),
+            expression_attribute_values: Some(expression_attribute_values),
+            ..UpdateItemInput::default()
+        };
+
+        match self.dynamodb_client.update_item(input).await {
+            Ok(_) => Ok(true),
+            Err(RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => Ok(false),
+            Err(e) => Err(AddressGroupsRepositoryError::Unknown(
+                anyhow!(e).context(format!("Error updating address group {}", group.name)),
+            )),
+        }
+    }
+
+    /// Deletes the group item if it is still at `expected_version`. Returns false if it isn't,
+    /// or if the group was already deleted.
+    async fn delete_group_item(
+        &self,
+        client_id: &str,
+        name: &str,
+        expected_version: u64,
+    ) -> Result<bool, AddressGroupsRepositoryError> {
+        let expression_attribute_values = if expected_version == 0 {
+            None
+        } else {
+            Some(
+                serde_dynamo::to_item(ExpectedGroupVersion { expected_version }).map_err(|e| {
+                    AddressGroupsRepositoryError::Unknown(
+                        anyhow!(e).context("Error serializing address group version"),
+                    )
+                })?,
+            )
+        };
+
+        let input = DeleteItemInput {
+            key: self.build_key(client_id, name)?,
+            table_name: self.table_name.clone(),
+            condition_expression: Some(group_version_condition_expression(expected_version)),
+            expression_attribute_names: Some(HashMap::from([(
+                "#version".to_owned(),
+                "version".to_owned(),
+            )])),
+            expression_attribute_values,
+            ..DeleteItemInput::default()
+        };
+
+        match self.dynamodb_client.delete_item(input).await {
+            Ok(_) => Ok(true),
+            Err(RusotoError::Service(DeleteItemError::ConditionalCheckFailed(_))) => Ok(false),
+            Err(e) => Err(AddressGroupsRepositoryError::Unknown(anyhow!(e).context(
+                format!("Error deleting address group {name} of client_id: {client_id}"),
+            ))),
+        }
+    }
+
+    /// Items of every page of the query of the partition `pk`.
+    async fn query_partition(
+        &self,
+        pk: String,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>, AddressGroupsRepositoryError> {
+        let expression_attribute_values =
+            serde_dynamo::to_item(PartitionQueryValues { pk: pk.clone() }).map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context("Error building query for address groups"),
+                )
+            })?;
+
+        let mut items = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let input = QueryInput {
+                table_name: self.table_name.clone(),
+                key_condition_expression: Some("pk = :pk".to_owned()),
+                expression_attribute_values: Some(expression_attribute_values.clone()),
+                exclusive_start_key,
+                ..QueryInput::default()
+            };
+
+            let output = self.dynamodb_client.query(input).await.map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context(format!("Error querying address groups partition {pk}")),
+                )
+            })?;
+
+            items.extend(output.items.unwrap_or_default());
+
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Writes the membership items of `added` addresses and deletes the ones of `removed`
+    /// addresses of the group `name`. Membership items are written apart from the group, so every
+    /// write of a group rewrites all of them and retrying a failed write fixes them.
+    async fn write_memberships(
+        &self,
+        client_id: &str,
+        name: &str,
+        added: &[Address],
+        removed: &[Address],
+    ) -> Result<(), AddressGroupsRepositoryError> {
+        let mut write_requests = Vec::with_capacity(added.len() + removed.len());
+        for address in added {
+            let item = serde_dynamo::to_item(AddressGroupMembershipDynamoDbResource::new(
+                client_id, name, address,
+            ))
+            .map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context("Error serializing address group membership"),
+                )
+            })?;
+            write_requests.push(WriteRequest {
+                put_request: Some(PutRequest { item }),
+                ..WriteRequest::default()
+            });
+        }
+        for address in removed {
+            let membership = AddressGroupMembershipDynamoDbResource::new(client_id, name, address);
+            let key = serde_dynamo::to_item(AddressGroupPk {
+                pk: membership.pk,
+                sk: membership.sk,
+            })
+            .map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context("Error building key for address group membership"),
+                )
+            })?;
+            write_requests.push(WriteRequest {
+                delete_request: Some(DeleteRequest { key }),
+                ..WriteRequest::default()
+            });
+        }
+
+        for chunk in write_requests.chunks(BATCH_WRITE_MAX_ITEMS) {
+            let mut request_items = HashMap::from([(self.table_name.clone(), chunk.to_vec())]);
+            let mut attempt = 0;
+
+            loop {
+                let BatchWriteItemOutput {
+                    unprocessed_items, ..
+                } = self
+                    .dynamodb_client
+                    .batch_write_item(BatchWriteItemInput {
+                        request_items,
+                        ..BatchWriteItemInput::default()
+                    })
+                    .await
+                    .map_err(|e| {
+                        AddressGroupsRepositoryError::Unknown(anyhow!(e).context(format!(
+                            "unable to write the memberships of address group {name}"
+                        )))
+                    })?;
+
+                match unprocessed_items.filter(|unprocessed| !unprocessed.is_empty()) {
+                    Some(unprocessed) => {
+                        attempt += 1;
+                        if attempt >= BATCH_MAX_ATTEMPTS {
+                            return Err(AddressGroupsRepositoryError::Unknown(anyhow!(
+                                "unable to write the memberships of address group {name}, items left unprocessed after {attempt} attempts"
+                            )));
+                        }
+                        tokio::time::sleep(retry_delay(attempt)).await;
+                        request_items = unprocessed;
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    BATCH_RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1))
+}
+
+/// Addresses of `previous` that are not in `current`.
+fn removed_addresses(previous: &[Address], current: &[Address]) -> Vec<Address> {
+    let current = current.iter().collect::<HashSet<_>>();
+    previous
+        .iter()
+        .filter(|address| !current.contains(address))
+        .copied()
+        .collect()
+}
+
+#[async_trait]
+impl<T: DynamoDb + Sync + Send> AddressGroupsRepository for AddressGroupsRepositoryImpl<T> {
+    async fn get_group(
+        &self,
+        client_id: String,
+        name: String,
+    ) -> Result<Option<AddressGroup>, AddressGroupsRepositoryError> {
+        self.get_group_resource(&client_id, &name)
+            .await?
+            .map(AddressGroup::try_from)
+            .transpose()
+    }
+
+    async fn get_all_groups(
+        &self,
+        client_id: String,
+    ) -> Result<Vec<AddressGroup>, AddressGroupsRepositoryError> {
+        let items = self
+            .query_partition(client_partition_key(&client_id))
+            .await?;
+
+        let mut groups = Vec::with_capacity(items.len());
+        for item in items {
+            let resource: AddressGroupDynamoDbResource = deserialize_from_dynamo(item)?;
+            groups.push(AddressGroup::try_from(resource)?);
+        }
+
+        Ok(groups)
+    }
+
+    async fn get_groups_containing(
+        &self,
+        client_id: String,
+        address: Address,
+    ) -> Result<Vec<String>, AddressGroupsRepositoryError> {
+        let items = self
+            .query_partition(address_partition_key(&client_id, &address))
+            .await?;
+
+        let mut names = Vec::with_capacity(items.len());
+        for item in items {
+            let membership: AddressGroupMembershipDynamoDbResource = deserialize_from_dynamo(item)?;
+            names.push(membership.name);
+        }
+        names.sort();
+
+        Ok(names)
+    }
+
+    async fn create_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError> {
+        let client_id = group.client_id.clone();
+        let name = group.name.clone();
+        let addresses = group.addresses.clone();
+        let item =
+            serde_dynamo::to_item(AddressGroupDynamoDbResource::from(group)).map_err(|e| {
+                AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context("Error serializing address group"),
+                )
+            })?;
+
+        let input = PutItemInput {
+            item,
+            table_name: self.table_name.clone(),
+            condition_expression: Some("attribute_not_exists(pk)".to_owned()),
+            ..PutItemInput::default()
+        };
+
+        self.dynamodb_client
+            .put_item(input)
+            .await
+            .map_err(|e| match e {
+                RusotoError::Service(PutItemError::ConditionalCheckFailed(_)) => {
+                    AddressGroupsRepositoryError::AlreadyExists(format!(
+                        "address group {name} already exists"
+                    ))
+                }
+                e => AddressGroupsRepositoryError::Unknown(
+                    anyhow!(e).context(format!("Error creating address group {name}")),
+                ),
+            })?;
+
+        self.write_memberships(&client_id, &name, &addresses, &[])
+            .await
+    }
+
+    async fn update_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError> {
+        for _ in 0..GROUP_WRITE_MAX_ATTEMPTS {
+            let previous = self
+                .get_group_resource(&group.client_id, &group.name)
+                .await?
+                .ok_or_else(|| {
+                    AddressGroupsRepositoryError::GroupNotFound(format!(
+                        "address group {} not found",
+                        group.name
+                    ))
+                })?;
+            let previous_version = previous.version;
+            let previous = AddressGroup::try_from(previous)?;
+
+            // Memberships go first so a failed update leaves the group as it was and can be
+            // retried. When the group changed meanwhile its memberships are diffed again.
+            let removed = removed_addresses(&previous.addresses, &group.addresses);
+            self.write_memberships(&group.client_id, &group.name, &group.addresses, &removed)
+                .await?;
+
+            if self.write_group_addresses(&group, previous_version).await? {
+                return Ok(());
+            }
+        }
+
+        Err(AddressGroupsRepositoryError::Conflict(format!(
+            "address group {} kept changing while it was updated",
+            group.name
+        )))
+    }
+
+    async fn delete_group(
+        &self,
+        client_id: String,
+        name: String,
+    ) -> Result<(), AddressGroupsRepositoryError> {
+        for _ in 0..GROUP_WRITE_MAX_ATTEMPTS {
+            let group = match self.get_group_resource(&client_id, &name).await? {
+                Some(group) => group,
+                None => return Ok(()),
+            };
+            let version = group.version;
+            let group = AddressGroup::try_from(group)?;
+
+            // Memberships go first so a failed delete keeps the group and can be retried. When
+            // the group changed meanwhile the memberships of its new addresses are deleted too.
+            self.write_memberships(&client_id, &name, &[], &group.addresses)
+                .await?;
+
+            if self.delete_group_item(&client_id, &name, version).await? {
+                return Ok(());
+            }
+        }
+
+        Err(AddressGroupsRepositoryError::Conflict(format!(
+            "address group {name} kept changing while it was deleted"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+    use crate::address_groups::{
+        AddressGroupDynamoDbResource, AddressGroupMembershipDynamoDbResource,
+        AddressGroupsRepository, AddressGroupsRepositoryError,
+    };
+    use common::test_tools::http::constants::{
+        ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+        CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use common::test_tools::mocks::dynamodb_client::MockDbClient;
+    use ethers::types::Address;
+    use mockall::Sequence;
+    use model::address_group::AddressGroup;
+    use rstest::{fixture, rstest};
+    use rusoto_core::RusotoError;
+    use rusoto_dynamodb::{
+        BatchWriteItemOutput, DeleteItemError, DeleteItemOutput, GetItemOutput, PutItemError,
+        PutItemOutput, QueryOutput, UpdateItemError, UpdateItemOutput,
+    };
+
+    struct TestFixture {
+        pub table_name: String,
+        pub dynamodb_client: MockDbClient,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            table_name: "address_groups".to_owned(),
+            dynamodb_client: MockDbClient::new(),
+        }
+    }
+
+    fn group() -> AddressGroup {
+        AddressGroup {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            name: "exchanges".to_owned(),
+            addresses: vec![
+                Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            ],
+        }
+    }
+
+    /// `group()` as stored at `version`.
+    fn stored_group(version: u64) -> GetItemOutput {
+        let resource = AddressGroupDynamoDbResource {
+            version,
+            ..AddressGroupDynamoDbResource::from(group())
+        };
+        GetItemOutput {
+            item: Some(serde_dynamo::to_item(resource).unwrap()),
+            ..GetItemOutput::default()
+        }
+    }
+
+    /// Mocks reading `group()` before changing it.
+    fn mock_stored_group(dynamodb_client: &mut MockDbClient) {
+        dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(stored_group(1)));
+    }
+
+    fn conditional_check_failed() -> RusotoError<UpdateItemError> {
+        RusotoError::Service(UpdateItemError::ConditionalCheckFailed(
+            "conditional check failed".to_owned(),
+        ))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_group_not_found(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(GetItemOutput::default()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let result = repo
+            .get_group(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                "exchanges".to_owned(),
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_group(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .withf(|input| {
+                input.key.get("pk").and_then(|v| v.s.clone())
+                    == Some(format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}"))
+                    && input.key.get("sk").and_then(|v| v.s.clone())
+                        == Some("GROUP#exchanges".to_owned())
+            })
+            .returning(|_| {
+                Ok(GetItemOutput {
+                    item: Some(
+                        serde_dynamo::to_item(AddressGroupDynamoDbResource::from(group())).unwrap(),
+                    ),
+                    ..GetItemOutput::default()
+                })
+            });
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let result = repo
+            .get_group(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                "exchanges".to_owned(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(Some(group()), result);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_all_groups_follows_pages(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.exclusive_start_key.is_none())
+            .returning(|_| {
+                let item =
+                    serde_dynamo::to_item(AddressGroupDynamoDbResource::from(group())).unwrap();
+                Ok(QueryOutput {
+                    items: Some(vec![item.clone()]),
+                    last_evaluated_key: Some(item),
+                    ..QueryOutput::default()
+                })
+            });
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.exclusive_start_key.is_some())
+            .returning(|_| {
+                let mut group = group();
+                group.name = "custodians".to_owned();
+                Ok(QueryOutput {
+                    items: Some(vec![serde_dynamo::to_item(
+                        AddressGroupDynamoDbResource::from(group),
+                    )
+                    .unwrap()]),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let result = repo
+            .get_all_groups(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec!["exchanges", "custodians"],
+            result
+                .iter()
+                .map(|group| group.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_group_stores_lowercase_addresses(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_put_item()
+            .once()
+            .withf(|input| {
+                let stored: AddressGroupDynamoDbResource =
+                    serde_dynamo::from_item(input.item.clone()).unwrap();
+
+                input.condition_expression.as_deref() == Some("attribute_not_exists(pk)")
+                    && stored
+                        .addresses
+                        .iter()
+                        .all(|address| *address == address.to_lowercase())
+            })
+            .returning(|_| Ok(PutItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .withf(|input| {
+                let memberships = &input.request_items["address_groups"];
+                memberships.len() == 2
+                    && memberships.iter().all(|request| {
+                        let membership: AddressGroupMembershipDynamoDbResource =
+                            serde_dynamo::from_item(request.put_request.clone().unwrap().item)
+                                .unwrap();
+                        membership.pk
+                            == format!(
+                                "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#ADDRESS#{}",
+                                membership.address
+                            )
+                            && membership.sk == "GROUP#exchanges"
+                            && membership.address == membership.address.to_lowercase()
+                    })
+            })
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.create_group(group()).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_group_already_exists(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_put_item()
+            .once()
+            .returning(|_| {
+                Err(RusotoError::Service(PutItemError::ConditionalCheckFailed(
+                    "conditional check failed".to_owned(),
+                )))
+            });
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let error = repo.create_group(group()).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressGroupsRepositoryError::AlreadyExists(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_not_found(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(GetItemOutput::default()));
+        fixture.dynamodb_client.expect_batch_write_item().never();
+        fixture.dynamodb_client.expect_update_item().never();
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let error = repo.update_group(group()).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressGroupsRepositoryError::GroupNotFound(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_deleted_meanwhile(mut fixture: TestFixture) {
+        let mut sequence = Sequence::new();
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(stored_group(1)));
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_update_item()
+            .once()
+            .in_sequence(&mut sequence)
+            .withf(|input| {
+                input.condition_expression.as_deref() == Some("#version = :expected_version")
+            })
+            .returning(|_| Err(conditional_check_failed()));
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(GetItemOutput::default()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let error = repo.update_group(group()).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressGroupsRepositoryError::GroupNotFound(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_changed_meanwhile_is_retried(mut fixture: TestFixture) {
+        let mut sequence = Sequence::new();
+        for version in [1, 2] {
+            fixture
+                .dynamodb_client
+                .expect_get_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .returning(move |_| Ok(stored_group(version)));
+            fixture
+                .dynamodb_client
+                .expect_batch_write_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(BatchWriteItemOutput::default()));
+            fixture
+                .dynamodb_client
+                .expect_update_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .withf(move |input| {
+                    let values = input.expression_attribute_values.as_ref().unwrap();
+                    values[":expected_version"].n == Some(version.to_string())
+                        && values[":version"].n == Some((version + 1).to_string())
+                })
+                .returning(move |_| match version {
+                    1 => Err(conditional_check_failed()),
+                    _ => Ok(UpdateItemOutput::default()),
+                });
+        }
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.update_group(group()).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_keeps_changing(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .times(3)
+            .returning(|_| Ok(stored_group(1)));
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .times(3)
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_update_item()
+            .times(3)
+            .returning(|_| Err(conditional_check_failed()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let error = repo.update_group(group()).await.unwrap_err();
+
+        assert!(matches!(error, AddressGroupsRepositoryError::Conflict(_)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_stored_before_versioning(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(stored_group(0)));
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_update_item()
+            .once()
+            .withf(|input| {
+                let values = input.expression_attribute_values.as_ref().unwrap();
+                input.condition_expression.as_deref()
+                    == Some("attribute_exists(pk) AND attribute_not_exists(#version)")
+                    && !values.contains_key(":expected_version")
+                    && values[":version"].n == Some("1".to_owned())
+            })
+            .returning(|_| Ok(UpdateItemOutput::default()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.update_group(group()).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_group_replaces_memberships(mut fixture: TestFixture) {
+        mock_stored_group(&mut fixture.dynamodb_client);
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .withf(|input| {
+                let requests = &input.request_items["address_groups"];
+                let puts = requests
+                    .iter()
+                    .filter(|request| request.put_request.is_some())
+                    .count();
+                let deleted = requests
+                    .iter()
+                    .filter_map(|request| request.delete_request.clone())
+                    .map(|delete| delete.key["pk"].s.clone().unwrap())
+                    .collect::<Vec<String>>();
+
+                puts == 2
+                    && deleted
+                        == vec![format!(
+                            "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#ADDRESS#{}",
+                            ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS.to_lowercase()
+                        )]
+            })
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_update_item()
+            .once()
+            .returning(|_| Ok(UpdateItemOutput::default()));
+
+        let mut updated = group();
+        updated.addresses = vec![
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_low_u64_be(1),
+        ];
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.update_group(updated).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_group_db_error(mut fixture: TestFixture) {
+        mock_stored_group(&mut fixture.dynamodb_client);
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_delete_item()
+            .once()
+            .returning(|_| {
+                Err(RusotoError::Service(DeleteItemError::InternalServerError(
+                    "timeout!".to_owned(),
+                )))
+            });
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let error = repo
+            .delete_group(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                "exchanges".to_owned(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AddressGroupsRepositoryError::Unknown(_)));
+        assert!(error.to_string().contains("timeout!"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_group(mut fixture: TestFixture) {
+        mock_stored_group(&mut fixture.dynamodb_client);
+        fixture
+            .dynamodb_client
+            .expect_batch_write_item()
+            .once()
+            .withf(|input| {
+                let requests = &input.request_items["address_groups"];
+                requests.len() == 2
+                    && requests
+                        .iter()
+                        .all(|request| request.delete_request.is_some())
+            })
+            .returning(|_| Ok(BatchWriteItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_delete_item()
+            .once()
+            .withf(|input| {
+                input.condition_expression.as_deref() == Some("#version = :expected_version")
+                    && input.expression_attribute_values.as_ref().unwrap()[":expected_version"].n
+                        == Some("1".to_owned())
+            })
+            .returning(|_| Ok(DeleteItemOutput::default()));
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.delete_group(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            "exchanges".to_owned(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_group_changed_meanwhile_is_retried(mut fixture: TestFixture) {
+        let mut sequence = Sequence::new();
+        for version in [1, 2] {
+            fixture
+                .dynamodb_client
+                .expect_get_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .returning(move |_| Ok(stored_group(version)));
+            fixture
+                .dynamodb_client
+                .expect_batch_write_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(BatchWriteItemOutput::default()));
+            fixture
+                .dynamodb_client
+                .expect_delete_item()
+                .once()
+                .in_sequence(&mut sequence)
+                .returning(move |_| match version {
+                    1 => Err(RusotoError::Service(
+                        DeleteItemError::ConditionalCheckFailed(
+                            "conditional check failed".to_owned(),
+                        ),
+                    )),
+                    _ => Ok(DeleteItemOutput::default()),
+                });
+        }
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.delete_group(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            "exchanges".to_owned(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_group_not_found(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(GetItemOutput::default()));
+        fixture.dynamodb_client.expect_batch_write_item().never();
+        fixture.dynamodb_client.expect_delete_item().never();
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        repo.delete_group(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            "exchanges".to_owned(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_groups_containing(mut fixture: TestFixture) {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                input.expression_attribute_values.as_ref().unwrap()[":pk"].s
+                    == Some(format!(
+                        "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#ADDRESS#{}",
+                        ADDRESS_FOR_MOCK_REQUESTS.to_lowercase()
+                    ))
+            })
+            .returning(move |_| {
+                Ok(QueryOutput {
+                    items: Some(
+                        ["exchanges", "custodians"]
+                            .iter()
+                            .map(|name| {
+                                serde_dynamo::to_item(AddressGroupMembershipDynamoDbResource::new(
+                                    CLIENT_ID_FOR_MOCK_REQUESTS,
+                                    name,
+                                    &address,
+                                ))
+                                .unwrap()
+                            })
+                            .collect(),
+                    ),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressGroupsRepositoryImpl::new(fixture.table_name, fixture.dynamodb_client);
+        let names = repo
+            .get_groups_containing(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(), address)
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["custodians", "exchanges"], names);
+    }
+
+    #[test]
+    fn resource_round_trip() {
+        let resource = AddressGroupDynamoDbResource::from(group());
+
+        assert_eq!(format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}"), resource.pk);
+        assert_eq!("GROUP#exchanges", resource.sk);
+        assert_eq!(group(), AddressGroup::try_from(resource).unwrap());
+    }
+}
@@ -0,0 +1,255 @@
+pub mod address_groups_repository_impl;
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::serializers::h160::h160_to_lowercase_hex_string;
+use ethers::types::Address;
+use model::address_group::AddressGroup;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "test_mocks")]
+use mockall::mock;
+
+use crate::{deserialize::UnknownError, impl_unknown_error_trait};
+
+const GROUP_SORT_KEY_PREFIX: &str = "GROUP#";
+
+#[derive(Debug, thiserror::Error)]
+pub enum AddressGroupsRepositoryError {
+    #[error("{0:#}")]
+    Unknown(anyhow::Error),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error("{0}")]
+    GroupNotFound(String),
+    /// The group kept being changed concurrently and the write gave up.
+    #[error("{0}")]
+    Conflict(String),
+}
+
+impl_unknown_error_trait!(AddressGroupsRepositoryError);
+
+/// Groups of a client are stored under the same partition so all of them are read with a single
+/// query.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AddressGroupPk {
+    pub pk: String,
+    pub sk: String,
+}
+
+impl AddressGroupPk {
+    pub fn new(client_id: &str, name: &str) -> Self {
+        Self {
+            pk: client_partition_key(client_id),
+            sk: format!("{GROUP_SORT_KEY_PREFIX}{name}"),
+        }
+    }
+}
+
+fn client_partition_key(client_id: &str) -> String {
+    format!("CLIENT#{client_id}")
+}
+
+/// Partition of the membership items of an address, one per group of the client containing it,
+/// so the groups of a destination are read with a single query when resolving policies.
+fn address_partition_key(client_id: &str, address: &Address) -> String {
+    format!(
+        "CLIENT#{client_id}#ADDRESS#{}",
+        h160_to_lowercase_hex_string(*address)
+    )
+}
+
+#[derive(Serialize)]
+struct PartitionQueryValues {
+    #[serde(rename(serialize = ":pk"))]
+    pub pk: String,
+}
+
+#[derive(Serialize)]
+struct UpdateGroupAddresses {
+    #[serde(rename(serialize = ":addresses"))]
+    pub addresses: Vec<String>,
+    #[serde(rename(serialize = ":last_modified_at"))]
+    pub last_modified_at: DateTime<Utc>,
+    #[serde(rename(serialize = ":version"))]
+    pub version: u64,
+    #[serde(
+        rename(serialize = ":expected_version"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expected_version: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ExpectedGroupVersion {
+    #[serde(rename(serialize = ":expected_version"))]
+    pub expected_version: u64,
+}
+
+/// Condition expression that only lets a write through if the stored group is still at
+/// `expected_version`. Groups stored before versioning was introduced have no `version`
+/// attribute, they match version 0 and the condition does not use `:expected_version`.
+fn group_version_condition_expression(expected_version: u64) -> String {
+    if expected_version == 0 {
+        "attribute_exists(pk) AND attribute_not_exists(#version)".to_owned()
+    } else {
+        "#version = :expected_version".to_owned()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AddressGroupDynamoDbResource {
+    pub pk: String,
+    pub sk: String,
+    pub client_id: String,
+    pub name: String,
+    pub addresses: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified_at: Option<DateTime<Utc>>,
+    /// Incremented on every update, so writes based on a stale read of the group fail.
+    #[serde(default)]
+    pub version: u64,
+}
+
+/// Item recording that `address` belongs to the group `name`.
+#[derive(Deserialize, Serialize)]
+pub struct AddressGroupMembershipDynamoDbResource {
+    pub pk: String,
+    pub sk: String,
+    pub client_id: String,
+    pub name: String,
+    pub address: String,
+}
+
+impl AddressGroupMembershipDynamoDbResource {
+    pub fn new(client_id: &str, name: &str, address: &Address) -> Self {
+        Self {
+            pk: address_partition_key(client_id, address),
+            sk: format!("{GROUP_SORT_KEY_PREFIX}{name}"),
+            client_id: client_id.to_owned(),
+            name: name.to_owned(),
+            address: h160_to_lowercase_hex_string(*address),
+        }
+    }
+}
+
+impl TryFrom<AddressGroupDynamoDbResource> for AddressGroup {
+    type Error = AddressGroupsRepositoryError;
+
+    fn try_from(value: AddressGroupDynamoDbResource) -> Result<Self, Self::Error> {
+        let addresses = value
+            .addresses
+            .iter()
+            .map(|address| Address::from_str(address))
+            .collect::<Result<Vec<Address>, _>>()
+            .map_err(|e| {
+                AddressGroupsRepositoryError::unknown(e, Some("unable to parse group address"))
+            })?;
+
+        Ok(Self {
+            client_id: value.client_id,
+            name: value.name,
+            addresses,
+        })
+    }
+}
+
+impl From<AddressGroup> for AddressGroupDynamoDbResource {
+    fn from(value: AddressGroup) -> Self {
+        let key = AddressGroupPk::new(&value.client_id, &value.name);
+
+        Self {
+            pk: key.pk,
+            sk: key.sk,
+            client_id: value.client_id,
+            name: value.name,
+            addresses: lowercase_addresses(&value.addresses),
+            created_at: Utc::now(),
+            last_modified_at: None,
+            version: 1,
+        }
+    }
+}
+
+fn lowercase_addresses(addresses: &[Address]) -> Vec<String> {
+    addresses
+        .iter()
+        .map(|address| h160_to_lowercase_hex_string(*address))
+        .collect()
+}
+
+#[async_trait]
+pub trait AddressGroupsRepository
+where
+    Self: Sync + Send,
+{
+    async fn get_group(
+        &self,
+        client_id: String,
+        name: String,
+    ) -> Result<Option<AddressGroup>, AddressGroupsRepositoryError>;
+
+    async fn get_all_groups(
+        &self,
+        client_id: String,
+    ) -> Result<Vec<AddressGroup>, AddressGroupsRepositoryError>;
+
+    /// Names of the groups of `client_id` containing `address`, sorted by name.
+    async fn get_groups_containing(
+        &self,
+        client_id: String,
+        address: Address,
+    ) -> Result<Vec<String>, AddressGroupsRepositoryError>;
+
+    /// Fails with `AlreadyExists` if the client has a group with the same name.
+    async fn create_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError>;
+
+    /// Replaces the addresses of an existing group. Fails with `GroupNotFound` if there is none,
+    /// and with `Conflict` if the group keeps being changed concurrently.
+    async fn update_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError>;
+
+    /// Deletes the group along with its membership items. Deleting a group that doesn't exist is
+    /// not an error. Fails with `Conflict` if the group keeps being changed concurrently.
+    async fn delete_group(
+        &self,
+        client_id: String,
+        name: String,
+    ) -> Result<(), AddressGroupsRepositoryError>;
+}
+
+#[cfg(feature = "test_mocks")]
+mock! {
+    pub AddressGroupsRepository {}
+    #[async_trait]
+    impl AddressGroupsRepository for AddressGroupsRepository {
+        async fn get_group(
+            &self,
+            client_id: String,
+            name: String,
+        ) -> Result<Option<AddressGroup>, AddressGroupsRepositoryError>;
+
+        async fn get_all_groups(
+            &self,
+            client_id: String,
+        ) -> Result<Vec<AddressGroup>, AddressGroupsRepositoryError>;
+
+        async fn get_groups_containing(
+            &self,
+            client_id: String,
+            address: Address,
+        ) -> Result<Vec<String>, AddressGroupsRepositoryError>;
+
+        async fn create_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError>;
+
+        async fn update_group(&self, group: AddressGroup) -> Result<(), AddressGroupsRepositoryError>;
+
+        async fn delete_group(
+            &self,
+            client_id: String,
+            name: String,
+        ) -> Result<(), AddressGroupsRepositoryError>;
+    }
+}
@@ -2,48 +2,145 @@ use std::collections::HashMap;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use chrono::Utc;
-use ethers::types::{Address, H160};
-use model::address_policy_registry::AddressPolicyRegistry;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+use model::address_policy_registry::{
+    AddressPolicyRegistry, AddressPolicyRegistryChange, AddressPolicyRegistryHistory,
+    AddressPolicyRegistryTerms, AddressPolicyRegistryType,
+};
+use rusoto_core::RusotoError;
 use rusoto_dynamodb::{
-    AttributeValue, DeleteItemInput, DynamoDb, GetItemInput, PutItemInput, QueryInput,
-    UpdateItemInput,
+    AttributeValue, BatchGetItemInput, BatchGetItemOutput, Delete, DynamoDb, GetItemInput,
+    KeysAndAttributes, Put, QueryInput, TransactWriteItem, TransactWriteItemsError,
+    TransactWriteItemsInput, Update,
 };
+use std::time::Duration;
 
 use crate::{
-    address_policy_registry::{AddressPolicyRegistryPk, ClientIdGSI, CLIENT_ID_INDEX_NAME},
+    address_policy_registry::{
+        AddressPolicyRegistryFilters, AddressPolicyRegistryPage, AddressPolicyRegistryPk,
+        MappingTypeFilter, PoliciesQueryValues, PolicyRegistryCursor, CLIENT_ID_INDEX_NAME,
+        MAX_ATOMIC_PLAN_CHANGES, TYPE_ADDRESS_FROM, TYPE_ADDRESS_TO, TYPE_CLIENT_USER,
+        TYPE_CONTRACT_CALL, TYPE_GROUP, TYPE_KEY_ID,
+    },
     deserialize::deserialize_from_dynamo,
 };
 
 use super::{
-    AddressPolicyRegistryDynamoDbResource, AddressPolicyRegistryRepository,
-    AddressPolicyRegistryRepositoryError, UpdatePolicy,
+    version_condition_expression, AddressPolicyRegistryDynamoDbResource,
+    AddressPolicyRegistryHistoryDynamoDbResource, AddressPolicyRegistryHistoryPage,
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError, ExpectedVersion,
+    ExpiredBefore, PutPolicyOutcome, UpdatePolicy, PUT_POLICY_CONDITION_EXPRESSION,
 };
 
+/// DynamoDB limit for a single `BatchGetItem` call.
+const BATCH_GET_MAX_KEYS: usize = 100;
+/// Unprocessed keys are retried with exponential backoff up to this many attempts.
+const BATCH_MAX_ATTEMPTS: u32 = 5;
+const BATCH_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// Transactions `put_policies` has in flight at the same time.
+const PUT_POLICIES_CONCURRENCY: usize = 16;
+
 pub struct AddressPolicyRegistryRepositoryImpl<D: DynamoDb + Sync + Send> {
     table_name: String,
+    history_table_name: String,
     dynamodb_client: D,
 }
 
 impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepositoryImpl<D> {
-    pub fn new(table_name: String, dynamodb_client: D) -> Self {
+    pub fn new(table_name: String, history_table_name: String, dynamodb_client: D) -> Self {
         Self {
             table_name,
+            history_table_name,
             dynamodb_client,
         }
     }
 
-    fn build_policies_query_input(&self, client_id: String) -> Result<QueryInput, anyhow::Error> {
-        let key_condition_expression = "client_id = :client_id".to_owned();
+    fn build_policies_query_input(
+        &self,
+        client_id: String,
+        filters: &AddressPolicyRegistryFilters,
+        limit: Option<i64>,
+        exclusive_start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<QueryInput, anyhow::Error> {
+        let mut values = PoliciesQueryValues::default();
+        let mut filter_conditions = Vec::new();
+        let mut expression_attribute_names = HashMap::new();
+
+        let (index_name, key_condition_expression) = match filters.chain_id {
+            Some(chain_id) => {
+                values.pk = Some(AddressPolicyRegistryPk::new(client_id, chain_id, None).pk);
+                (None, "pk = :pk".to_owned())
+            }
+            None => {
+                values.client_id = Some(client_id);
+                (
+                    Some(CLIENT_ID_INDEX_NAME.to_owned()),
+                    "client_id = :client_id".to_owned(),
+                )
+            }
+        };
+
+        match filters.mapping_type {
+            Some(MappingTypeFilter::Default) => {
+                values.default_sk = Some(format!("{TYPE_ADDRESS_TO}#DEFAULT"));
+                filter_conditions.push("sk = :default_sk");
+            }
+            Some(MappingTypeFilter::AddressTo) => {
+                values.default_sk = Some(format!("{TYPE_ADDRESS_TO}#DEFAULT"));
+                values.sk_prefix = Some(format!("{TYPE_ADDRESS_TO}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix) AND sk <> :default_sk");
+            }
+            Some(MappingTypeFilter::AddressFrom) => {
+                values.sk_prefix = Some(format!("{TYPE_ADDRESS_FROM}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix)");
+            }
+            Some(MappingTypeFilter::ContractCall) => {
+                values.sk_prefix = Some(format!("{TYPE_CONTRACT_CALL}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix)");
+            }
+            Some(MappingTypeFilter::KeyId) => {
+                values.sk_prefix = Some(format!("{TYPE_KEY_ID}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix)");
+            }
+            Some(MappingTypeFilter::ClientUser) => {
+                values.sk_prefix = Some(format!("{TYPE_CLIENT_USER}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix)");
+            }
+            Some(MappingTypeFilter::Group) => {
+                values.sk_prefix = Some(format!("{TYPE_GROUP}#"));
+                filter_conditions.push("begins_with(sk, :sk_prefix)");
+            }
+            None => {}
+        }
+
+        if let Some(policy) = &filters.policy {
+            values.policy = Some(policy.clone());
+            expression_attribute_names.insert("#policy".to_owned(), "policy".to_owned());
+            filter_conditions.push("#policy = :policy");
+        }
+
+        if let Some(address_prefix) = &filters.address_prefix {
+            values.address_prefix = Some(address_prefix.to_lowercase());
+            expression_attribute_names.insert("#address".to_owned(), "address".to_owned());
+            filter_conditions.push("begins_with(#address, :address_prefix)");
+        }
 
-        let expression_attribute_values = serde_dynamo::to_item(ClientIdGSI { client_id })
+        let expression_attribute_values = serde_dynamo::to_item(values)
             .map_err(|e| anyhow!(e).context("Error building query for policies"))?;
 
         Ok(QueryInput {
             table_name: self.table_name.clone(),
-            index_name: Some(CLIENT_ID_INDEX_NAME.to_owned()),
+            index_name,
             key_condition_expression: Some(key_condition_expression),
+            filter_expression: (!filter_conditions.is_empty())
+                .then(|| filter_conditions.join(" AND ")),
+            expression_attribute_names: (!expression_attribute_names.is_empty())
+                .then_some(expression_attribute_names),
             expression_attribute_values: Some(expression_attribute_values),
+            limit,
+            exclusive_start_key,
             ..QueryInput::default()
         })
     }
@@ -51,7 +148,7 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepositoryImpl<D> {
     fn build_policy_registry_item_input(
         &self,
         policy_mapping: AddressPolicyRegistry,
-    ) -> Result<PutItemInput, AddressPolicyRegistryRepositoryError> {
+    ) -> Result<Put, AddressPolicyRegistryRepositoryError> {
         let policy_registration = AddressPolicyRegistryDynamoDbResource::from(policy_mapping);
         let item: HashMap<String, AttributeValue> = serde_dynamo::to_item(policy_registration)
             .map_err(|e| {
@@ -60,10 +157,21 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepositoryImpl<D> {
                 )
             })?;
 
-        let input = PutItemInput {
+        let expression_attribute_values = serde_dynamo::to_item(ExpiredBefore {
+            now: Utc::now().timestamp(),
+        })
+        .map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(
+                anyhow!(e).context("Error building put policy mapping condition"),
+            )
+        })?;
+
+        let input = Put {
             item,
             table_name: self.table_name.clone(),
-            ..PutItemInput::default()
+            condition_expression: Some(PUT_POLICY_CONDITION_EXPRESSION.to_owned()),
+            expression_attribute_values: Some(expression_attribute_values),
+            ..Put::default()
         };
 
         Ok(input)
@@ -71,24 +179,25 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepositoryImpl<D> {
 
     fn build_update_item_input(
         &self,
-        client_id: String,
-        chain_id: u64,
-        address: Option<H160>,
+        key: &AddressPolicyRegistryPk,
         policy: String,
-    ) -> Result<UpdateItemInput, AddressPolicyRegistryRepositoryError> {
-        let key = serde_dynamo::to_item(AddressPolicyRegistryPk::new(client_id, chain_id, address))
-            .map_err(|e| {
-                AddressPolicyRegistryRepositoryError::Unknown(
-                    anyhow!(e).context("Error building update policy mapping key"),
-                )
-            })?;
+        current_version: u64,
+    ) -> Result<Update, AddressPolicyRegistryRepositoryError> {
+        let key = serde_dynamo::to_item(key).map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(
+                anyhow!(e).context("Error building update policy mapping key"),
+            )
+        })?;
 
-        let update_expression =
-            "SET #policy = :policy, last_modified_at = :last_modified_at".to_owned();
+        let update_expression = "SET #policy = :policy, last_modified_at = :last_modified_at \
+            ADD #version :version_increment"
+            .to_owned();
 
         let expression_attribute_values = serde_dynamo::to_item(UpdatePolicy {
             policy,
             last_modified_at: Utc::now(),
+            version_increment: 1,
+            expected_version: (current_version > 0).then_some(current_version),
         })
         .map_err(|e| {
             AddressPolicyRegistryRepositoryError::Unknown(
@@ -96,18 +205,270 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepositoryImpl<D> {
             )
         })?;
 
-        let expression_attribute_names =
-            HashMap::from([(String::from("#policy"), String::from("policy"))]);
+        let expression_attribute_names = HashMap::from([
+            (String::from("#policy"), String::from("policy")),
+            (String::from("#version"), String::from("version")),
+        ]);
 
-        Ok(UpdateItemInput {
+        Ok(Update {
             key,
             table_name: self.table_name.clone(),
-            update_expression: Some(update_expression),
+            update_expression,
+            condition_expression: Some(version_condition_expression(current_version)),
             expression_attribute_values: Some(expression_attribute_values),
             expression_attribute_names: Some(expression_attribute_names),
             ..Default::default()
         })
     }
+
+    fn build_delete_item_input(
+        &self,
+        key: &AddressPolicyRegistryPk,
+        current_version: u64,
+    ) -> Result<Delete, AddressPolicyRegistryRepositoryError> {
+        let item_key = serde_dynamo::to_item(key).map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(
+                anyhow!(e).context("generate address_policy_registry key"),
+            )
+        })?;
+
+        let expression_attribute_values = (current_version > 0)
+            .then(|| {
+                serde_dynamo::to_item(ExpectedVersion {
+                    expected_version: current_version,
+                })
+            })
+            .transpose()
+            .map_err(|e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context("Error building delete policy mapping condition"),
+                )
+            })?;
+
+        Ok(Delete {
+            key: item_key,
+            table_name: self.table_name.clone(),
+            condition_expression: Some(version_condition_expression(current_version)),
+            expression_attribute_names: Some(HashMap::from([(
+                String::from("#version"),
+                String::from("version"),
+            )])),
+            expression_attribute_values,
+            ..Default::default()
+        })
+    }
+
+    /// Overwrites a stored mapping as long as it is still at `current_version`.
+    fn build_replace_item_input(
+        &self,
+        policy_mapping: AddressPolicyRegistry,
+        current_version: u64,
+    ) -> Result<Put, AddressPolicyRegistryRepositoryError> {
+        let item =
+            serde_dynamo::to_item(AddressPolicyRegistryDynamoDbResource::from(policy_mapping))
+                .map_err(|e| {
+                    AddressPolicyRegistryRepositoryError::Unknown(
+                        anyhow!(e).context("Error serializing address policy"),
+                    )
+                })?;
+
+        let expression_attribute_values = (current_version > 0)
+            .then(|| {
+                serde_dynamo::to_item(ExpectedVersion {
+                    expected_version: current_version,
+                })
+            })
+            .transpose()
+            .map_err(|e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context("Error building replace policy mapping condition"),
+                )
+            })?;
+
+        Ok(Put {
+            item,
+            table_name: self.table_name.clone(),
+            condition_expression: Some(version_condition_expression(current_version)),
+            expression_attribute_names: Some(HashMap::from([(
+                String::from("#version"),
+                String::from("version"),
+            )])),
+            expression_attribute_values,
+            ..Put::default()
+        })
+    }
+
+    fn build_history_item(
+        &self,
+        history: AddressPolicyRegistryHistory,
+    ) -> Result<HashMap<String, AttributeValue>, AddressPolicyRegistryRepositoryError> {
+        serde_dynamo::to_item(AddressPolicyRegistryHistoryDynamoDbResource::from(history)).map_err(
+            |e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context("Error serializing address policy history"),
+                )
+            },
+        )
+    }
+
+    fn build_history_put(
+        &self,
+        history: AddressPolicyRegistryHistory,
+    ) -> Result<TransactWriteItem, AddressPolicyRegistryRepositoryError> {
+        Ok(TransactWriteItem {
+            put: Some(Put {
+                item: self.build_history_item(history)?,
+                table_name: self.history_table_name.clone(),
+                ..Put::default()
+            }),
+            ..TransactWriteItem::default()
+        })
+    }
+
+    /// Transaction items writing every change of `plan` along with its history entries.
+    fn build_plan_items(
+        &self,
+        plan: AddressPolicyRegistryPlan,
+        subject: &Option<String>,
+        changed_at: DateTime<Utc>,
+    ) -> Result<Vec<TransactWriteItem>, AddressPolicyRegistryRepositoryError> {
+        let history_entry =
+            |mapping: &AddressPolicyRegistry,
+             change: AddressPolicyRegistryChange,
+             previous: Option<&AddressPolicyRegistry>,
+             new: Option<&AddressPolicyRegistry>| AddressPolicyRegistryHistory {
+                client_id: mapping.client_id.clone(),
+                chain_id: mapping.chain_id,
+                r#type: mapping.r#type.clone(),
+                change,
+                previous_policy: previous.map(|previous| previous.policy.clone()),
+                new_policy: new.map(|new| new.policy.clone()),
+                previous_terms: previous.map(AddressPolicyRegistryTerms::from),
+                new_terms: new.map(AddressPolicyRegistryTerms::from),
+                subject: subject.clone(),
+                changed_at,
+            };
+
+        let mut transact_items = Vec::with_capacity(plan.len() * 2);
+        for mapping in plan.creates {
+            let history = history_entry(
+                &mapping,
+                AddressPolicyRegistryChange::Created,
+                None,
+                Some(&mapping),
+            );
+            transact_items.push(TransactWriteItem {
+                put: Some(self.build_policy_registry_item_input(mapping)?),
+                ..TransactWriteItem::default()
+            });
+            transact_items.push(self.build_history_put(history)?);
+        }
+
+        for update in plan.updates {
+            let history = history_entry(
+                &update.desired,
+                AddressPolicyRegistryChange::Updated,
+                Some(&update.current),
+                Some(&update.desired),
+            );
+            transact_items.push(TransactWriteItem {
+                put: Some(self.build_replace_item_input(update.desired, update.current.version)?),
+                ..TransactWriteItem::default()
+            });
+            transact_items.push(self.build_history_put(history)?);
+        }
+
+        for mapping in plan.deletes {
+            let key = AddressPolicyRegistryPk::from_type(
+                mapping.client_id.clone(),
+                mapping.chain_id,
+                &mapping.r#type,
+            );
+            let history = history_entry(
+                &mapping,
+                AddressPolicyRegistryChange::Deleted,
+                Some(&mapping),
+                None,
+            );
+            transact_items.push(TransactWriteItem {
+                delete: Some(self.build_delete_item_input(&key, mapping.version)?),
+                ..TransactWriteItem::default()
+            });
+            transact_items.push(self.build_history_put(history)?);
+        }
+
+        Ok(transact_items)
+    }
+
+    /// Strongly consistent read of a stored mapping, used to know the current policy and
+    /// version before changing it.
+    async fn get_policy_item(
+        &self,
+        key: &AddressPolicyRegistryPk,
+    ) -> Result<Option<AddressPolicyRegistryDynamoDbResource>, AddressPolicyRegistryRepositoryError>
+    {
+        let item_key: HashMap<String, AttributeValue> =
+            serde_dynamo::to_item(key).map_err(|e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context("generate address_policy_registry key"),
+                )
+            })?;
+
+        self.dynamodb_client
+            .get_item(GetItemInput {
+                key: item_key,
+                table_name: self.table_name.clone(),
+                consistent_read: Some(true),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context("unable to get address policy"),
+                )
+            })?
+            .item
+            .map(
+                deserialize_from_dynamo::<
+                    AddressPolicyRegistryDynamoDbResource,
+                    AddressPolicyRegistryRepositoryError,
+                >,
+            )
+            .transpose()
+    }
+
+    async fn write_transaction(
+        &self,
+        transact_items: Vec<TransactWriteItem>,
+    ) -> Result<(), RusotoError<TransactWriteItemsError>> {
+        self.dynamodb_client
+            .transact_write_items(TransactWriteItemsInput {
+                transact_items,
+                ..TransactWriteItemsInput::default()
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// `TransactWriteItems` only reports the cancellation reasons in the error message.
+fn is_condition_check_failure(error: &RusotoError<TransactWriteItemsError>) -> bool {
+    matches!(
+        error,
+        RusotoError::Service(TransactWriteItemsError::TransactionCanceled(message))
+            if message.contains("ConditionalCheckFailed")
+    )
+}
+
+fn version_mismatch(
+    key: &AddressPolicyRegistryPk,
+    version: u64,
+) -> AddressPolicyRegistryRepositoryError {
+    AddressPolicyRegistryRepositoryError::VersionMismatch(format!(
+        "policy mapping {} for {} is not at version {version}",
+        key.sk, key.pk
+    ))
 }
 
 #[async_trait]
@@ -118,15 +479,28 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepository
         &self,
         client_id: String,
         chain_id: u64,
-        address: Option<Address>,
+        mapping_type: AddressPolicyRegistryType,
     ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError> {
-        let key: HashMap<String, AttributeValue> =
-            serde_dynamo::to_item(AddressPolicyRegistryPk::new(client_id, chain_id, address))
-                .map_err(|e| {
-                    AddressPolicyRegistryRepositoryError::Unknown(
-                        anyhow!(e).context("generate address_policy_registry key"),
-                    )
-                })?;
+        Ok(self
+            .get_stored_policy(client_id, chain_id, mapping_type)
+            .await?
+            .filter(|policy| policy.is_active_at(Utc::now())))
+    }
+
+    async fn get_stored_policy(
+        &self,
+        client_id: String,
+        chain_id: u64,
+        mapping_type: AddressPolicyRegistryType,
+    ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError> {
+        let key: HashMap<String, AttributeValue> = serde_dynamo::to_item(
+            AddressPolicyRegistryPk::from_type(client_id, chain_id, &mapping_type),
+        )
+        .map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(
+                anyhow!(e).context("generate address_policy_registry key"),
+            )
+        })?;
 
         let result = self
             .dynamodb_client
@@ -150,7 +524,8 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepository
             >(address_policy)?
             .try_into()?;
 
-            Ok(Some(address_policy))
+            // The TTL deletes expired mappings with some delay, so they are filtered here too.
+            Ok(Some(address_policy).filter(|policy| !policy.has_expired_at(Utc::now())))
         } else {
             Ok(None)
         }
@@ -159,47 +534,201 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepository
     async fn put_policy(
         &self,
         policy_mapping: AddressPolicyRegistry,
+        subject: Option<String>,
     ) -> Result<(), AddressPolicyRegistryRepositoryError> {
-        let policy_registration = self.build_policy_registry_item_input(policy_mapping)?;
+        let key = AddressPolicyRegistryPk::from_type(
+            policy_mapping.client_id.clone(),
+            policy_mapping.chain_id,
+            &policy_mapping.r#type,
+        );
+        let history = AddressPolicyRegistryHistory {
+            client_id: policy_mapping.client_id.clone(),
+            chain_id: policy_mapping.chain_id,
+            r#type: policy_mapping.r#type.clone(),
+            change: AddressPolicyRegistryChange::Created,
+            previous_policy: None,
+            new_policy: Some(policy_mapping.policy.clone()),
+            previous_terms: None,
+            new_terms: Some(AddressPolicyRegistryTerms::from(&policy_mapping)),
+            subject,
+            changed_at: Utc::now(),
+        };
+        let policy_registration = TransactWriteItem {
+            put: Some(self.build_policy_registry_item_input(policy_mapping)?),
+            ..TransactWriteItem::default()
+        };
 
-        self.dynamodb_client
-            .put_item(policy_registration)
+        self.write_transaction(vec![policy_registration, self.build_history_put(history)?])
             .await
             .map_err(|e| {
-                AddressPolicyRegistryRepositoryError::Unknown(
-                    anyhow!(e).context("unable to put policy registration"),
-                )
+                if is_condition_check_failure(&e) {
+                    AddressPolicyRegistryRepositoryError::AlreadyExists(format!(
+                        "policy mapping {} already exists for {}",
+                        key.sk, key.pk
+                    ))
+                } else {
+                    AddressPolicyRegistryRepositoryError::Unknown(
+                        anyhow!(e).context("unable to put policy registration"),
+                    )
+                }
             })?;
 
         Ok(())
     }
 
-    async fn delete_policy(
+    async fn get_policies_by_keys(
         &self,
-        client_id: String,
-        chain_id: u64,
-        address: Option<Address>,
-    ) -> Result<(), AddressPolicyRegistryRepositoryError> {
-        let pk: HashMap<String, AttributeValue> =
-            serde_dynamo::to_item(AddressPolicyRegistryPk::new(client_id, chain_id, address))
+        keys: Vec<AddressPolicyRegistryPk>,
+    ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError> {
+        let mut policies = Vec::with_capacity(keys.len());
+
+        for chunk in keys.chunks(BATCH_GET_MAX_KEYS) {
+            let keys = chunk
+                .iter()
+                .map(serde_dynamo::to_item)
+                .collect::<Result<Vec<HashMap<String, AttributeValue>>, _>>()
                 .map_err(|e| {
                     AddressPolicyRegistryRepositoryError::Unknown(
                         anyhow!(e).context("generate address_policy_registry key"),
                     )
                 })?;
 
-        self.dynamodb_client
-            .delete_item(DeleteItemInput {
-                key: pk,
-                table_name: self.table_name.clone(),
+            let mut request_items = HashMap::from([(
+                self.table_name.clone(),
+                KeysAndAttributes {
+                    keys,
+                    ..KeysAndAttributes::default()
+                },
+            )]);
+            let mut attempt = 0;
 
-                ..Default::default()
+            loop {
+                let BatchGetItemOutput {
+                    responses,
+                    unprocessed_keys,
+                    ..
+                } = self
+                    .dynamodb_client
+                    .batch_get_item(BatchGetItemInput {
+                        request_items,
+                        ..BatchGetItemInput::default()
+                    })
+                    .await
+                    .map_err(|e| {
+                        AddressPolicyRegistryRepositoryError::Unknown(
+                            anyhow!(e).context("unable to batch get address policies"),
+                        )
+                    })?;
+
+                let items = responses
+                    .and_then(|mut responses| responses.remove(&self.table_name))
+                    .unwrap_or_default();
+                for item in items {
+                    let policy = deserialize_from_dynamo::<
+                        AddressPolicyRegistryDynamoDbResource,
+                        AddressPolicyRegistryRepositoryError,
+                    >(item)?;
+                    policies.push(AddressPolicyRegistry::try_from(policy)?);
+                }
+
+                match unprocessed_keys.filter(|unprocessed| !unprocessed.is_empty()) {
+                    Some(unprocessed) => {
+                        attempt += 1;
+                        if attempt >= BATCH_MAX_ATTEMPTS {
+                            return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                                "unable to batch get address policies, keys left unprocessed after {attempt} attempts"
+                            )));
+                        }
+                        tokio::time::sleep(retry_delay(attempt)).await;
+                        request_items = unprocessed;
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        Ok(policies)
+    }
+
+    async fn put_policies(
+        &self,
+        policy_mappings: Vec<AddressPolicyRegistry>,
+        subject: Option<String>,
+    ) -> Vec<PutPolicyOutcome> {
+        // Each mapping is created in its own transaction with the same condition as `put_policy`,
+        // so a mapping created in the meantime is reported instead of overwritten and a failed
+        // write doesn't leave the outcome of the others unknown. `BatchWriteItem` would take 25
+        // mappings per call, but it can't condition its puts nor write the history along.
+        // The transactions are sent concurrently instead, outcomes keep the order of the mappings.
+        stream::iter(policy_mappings)
+            .map(|policy_mapping| async {
+                match self.put_policy(policy_mapping, subject.clone()).await {
+                    Ok(()) => PutPolicyOutcome::Created,
+                    Err(AddressPolicyRegistryRepositoryError::AlreadyExists(_)) => {
+                        PutPolicyOutcome::AlreadyExists
+                    }
+                    Err(e) => {
+                        tracing::error!(error = ?e, "unable to put policy mapping");
+                        PutPolicyOutcome::Failed
+                    }
+                }
             })
+            .buffered(PUT_POLICIES_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    async fn delete_policy(
+        &self,
+        client_id: String,
+        chain_id: u64,
+        mapping_type: AddressPolicyRegistryType,
+        expected_version: Option<u64>,
+        subject: Option<String>,
+    ) -> Result<(), AddressPolicyRegistryRepositoryError> {
+        let key = AddressPolicyRegistryPk::from_type(client_id.clone(), chain_id, &mapping_type);
+
+        let current = match self.get_policy_item(&key).await? {
+            Some(current) => current,
+            // Nothing to delete, unless the caller expected a specific version to be there.
+            None => {
+                return match expected_version {
+                    Some(version) => Err(version_mismatch(&key, version)),
+                    None => Ok(()),
+                }
+            }
+        };
+        if let Some(version) = expected_version.filter(|version| *version != current.version) {
+            return Err(version_mismatch(&key, version));
+        }
+
+        let history = AddressPolicyRegistryHistory {
+            client_id,
+            chain_id,
+            r#type: mapping_type,
+            change: AddressPolicyRegistryChange::Deleted,
+            previous_terms: Some(current.terms()),
+            previous_policy: Some(current.policy),
+            new_policy: None,
+            new_terms: None,
+            subject,
+            changed_at: Utc::now(),
+        };
+        let delete = TransactWriteItem {
+            delete: Some(self.build_delete_item_input(&key, current.version)?),
+            ..TransactWriteItem::default()
+        };
+
+        self.write_transaction(vec![delete, self.build_history_put(history)?])
             .await
             .map_err(|e| {
-                AddressPolicyRegistryRepositoryError::Unknown(
-                    anyhow!(e).context("unable to delete policy address mapping"),
-                )
+                if is_condition_check_failure(&e) {
+                    version_mismatch(&key, current.version)
+                } else {
+                    AddressPolicyRegistryRepositoryError::Unknown(
+                        anyhow!(e).context("unable to delete policy address mapping"),
+                    )
+                }
             })?;
         Ok(())
     }
@@ -208,47 +737,210 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepository
         &self,
         client_id: String,
         chain_id: u64,
-        address: Option<H160>,
+        mapping_type: AddressPolicyRegistryType,
         policy: String,
-    ) -> Result<(), AddressPolicyRegistryRepositoryError> {
-        let update_input =
-            self.build_update_item_input(client_id.clone(), chain_id, address, policy)?;
+        expected_version: Option<u64>,
+        subject: Option<String>,
+    ) -> Result<u64, AddressPolicyRegistryRepositoryError> {
+        let key = AddressPolicyRegistryPk::from_type(client_id.clone(), chain_id, &mapping_type);
 
-        self.dynamodb_client
-            .update_item(update_input)
+        let current = self.get_policy_item(&key).await?.ok_or_else(|| {
+            AddressPolicyRegistryRepositoryError::PolicyNotFound(format!(
+                "policy mapping {} not found for {}",
+                key.sk, key.pk
+            ))
+        })?;
+        if let Some(version) = expected_version.filter(|version| *version != current.version) {
+            return Err(version_mismatch(&key, version));
+        }
+
+        let history = AddressPolicyRegistryHistory {
+            client_id: client_id.clone(),
+            chain_id,
+            r#type: mapping_type.clone(),
+            change: AddressPolicyRegistryChange::Updated,
+            // Only the policy is changed, the window and value bands stay as they are.
+            previous_terms: Some(current.terms()),
+            new_terms: Some(current.terms()),
+            previous_policy: Some(current.policy),
+            new_policy: Some(policy.clone()),
+            subject,
+            changed_at: Utc::now(),
+        };
+        let update = TransactWriteItem {
+            update: Some(self.build_update_item_input(&key, policy, current.version)?),
+            ..TransactWriteItem::default()
+        };
+
+        // The update is conditioned on the version that was read, so a concurrent change
+        // between the read and the write is reported as a version mismatch.
+        self.write_transaction(vec![update, self.build_history_put(history)?])
             .await
             .map_err(|e| {
-                let address = address.map(|a| a.to_string()).unwrap_or_default();
-                AddressPolicyRegistryRepositoryError::Unknown(anyhow!(e).context(format!(
-                    "Error updating policy mapping for address: {}, chain_id: {}, client_id: {}",
-                    address, chain_id, client_id
-                )))
+                if is_condition_check_failure(&e) {
+                    version_mismatch(&key, current.version)
+                } else {
+                    AddressPolicyRegistryRepositoryError::Unknown(anyhow!(e).context(format!(
+                        "Error updating policy mapping for {:?}, chain_id: {}, client_id: {}",
+                        mapping_type, chain_id, client_id
+                    )))
+                }
             })?;
 
-        Ok(())
+        Ok(current.version + 1)
     }
 
-    async fn get_all_policies(
+    async fn get_policy_history(
         &self,
         client_id: String,
-    ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError> {
-        let input = self.build_policies_query_input(client_id.clone())?;
+        chain_id: u64,
+        mapping_type: AddressPolicyRegistryType,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<AddressPolicyRegistryHistoryPage, AddressPolicyRegistryRepositoryError> {
+        let history_pk =
+            AddressPolicyRegistryPk::from_type(client_id, chain_id, &mapping_type).history_pk();
+        let exclusive_start_key = cursor
+            .map(|cursor| decode_history_cursor(&history_pk, &cursor))
+            .transpose()?;
 
-        let items = self
+        let output = self
             .dynamodb_client
-            .query(input)
+            .query(QueryInput {
+                table_name: self.history_table_name.clone(),
+                key_condition_expression: Some("pk = :pk".to_owned()),
+                expression_attribute_values: Some(HashMap::from([(
+                    ":pk".to_owned(),
+                    AttributeValue {
+                        s: Some(history_pk.clone()),
+                        ..AttributeValue::default()
+                    },
+                )])),
+                scan_index_forward: Some(false),
+                limit,
+                exclusive_start_key,
+                ..QueryInput::default()
+            })
             .await
             .map_err(|e| {
+                AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow!(e).context(format!("Error querying policy history for {history_pk}")),
+                )
+            })?;
+
+        let items = output.items.unwrap_or_default();
+        let mut entries = Vec::with_capacity(items.len());
+        for item in items {
+            let entry = deserialize_from_dynamo::<
+                AddressPolicyRegistryHistoryDynamoDbResource,
+                AddressPolicyRegistryRepositoryError,
+            >(item)?;
+
+            entries.push(AddressPolicyRegistryHistory::try_from(entry)?);
+        }
+
+        let next_cursor = output
+            .last_evaluated_key
+            .filter(|key| !key.is_empty())
+            .map(encode_cursor)
+            .transpose()?;
+
+        Ok(AddressPolicyRegistryHistoryPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    async fn get_all_policies(
+        &self,
+        client_id: String,
+    ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError> {
+        let mut policies = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self
+                .get_policies_page(
+                    client_id.clone(),
+                    AddressPolicyRegistryFilters::default(),
+                    None,
+                    cursor,
+                )
+                .await?;
+            policies.extend(page.policies);
+
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(policies)
+    }
+
+    async fn export_policies(
+        &self,
+        client_id: String,
+    ) -> Result<Vec<AddressPolicyRegistryDynamoDbResource>, AddressPolicyRegistryRepositoryError>
+    {
+        let mut rows = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let input = self.build_policies_query_input(
+                client_id.clone(),
+                &AddressPolicyRegistryFilters::default(),
+                None,
+                exclusive_start_key,
+            )?;
+
+            let output = self.dynamodb_client.query(input).await.map_err(|e| {
                 AddressPolicyRegistryRepositoryError::Unknown(anyhow!(e).context(format!(
-                    "Error querying policies with client_id: {}",
-                    client_id.clone()
+                    "Error exporting policies of client_id: {client_id}"
                 )))
-            })?
-            .items
-            .ok_or(AddressPolicyRegistryRepositoryError::PolicyNotFound(
-                format!("Policy with client_id {} not found", client_id.clone()),
-            ))?;
+            })?;
+
+            for item in output.items.unwrap_or_default() {
+                rows.push(deserialize_from_dynamo::<
+                    AddressPolicyRegistryDynamoDbResource,
+                    AddressPolicyRegistryRepositoryError,
+                >(item)?);
+            }
+
+            exclusive_start_key = output.last_evaluated_key.filter(|key| !key.is_empty());
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(rows)
+    }
+
+    async fn get_policies_page(
+        &self,
+        client_id: String,
+        filters: AddressPolicyRegistryFilters,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<AddressPolicyRegistryPage, AddressPolicyRegistryRepositoryError> {
+        let exclusive_start_key = cursor
+            .map(|cursor| decode_cursor(&client_id, filters.chain_id, &cursor))
+            .transpose()?;
+        let input = self.build_policies_query_input(
+            client_id.clone(),
+            &filters,
+            limit,
+            exclusive_start_key,
+        )?;
+
+        let output = self.dynamodb_client.query(input).await.map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(anyhow!(e).context(format!(
+                "Error querying policies with client_id: {}",
+                client_id.clone()
+            )))
+        })?;
 
+        let items = output.items.unwrap_or_default();
         let mut policies = Vec::with_capacity(items.len());
         for item in items {
             let policy = deserialize_from_dynamo::<
@@ -259,14 +951,138 @@ impl<D: DynamoDb + Sync + Send> AddressPolicyRegistryRepository
             policies.push(AddressPolicyRegistry::try_from(policy)?);
         }
 
-        Ok(policies)
+        let next_cursor = output
+            .last_evaluated_key
+            .filter(|key| !key.is_empty())
+            .map(encode_cursor)
+            .transpose()?;
+
+        Ok(AddressPolicyRegistryPage {
+            policies,
+            next_cursor,
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
+    async fn apply_policy_plan(
+        &self,
+        plan: AddressPolicyRegistryPlan,
+        subject: Option<String>,
+    ) -> Result<(), AddressPolicyRegistryRepositoryError> {
+        let changed_at = Utc::now();
+        let mut applied = AddressPolicyRegistryPlan::default();
+        for chunk in plan.into_chunks(MAX_ATOMIC_PLAN_CHANGES) {
+            let transact_items = self.build_plan_items(chunk.clone(), &subject, changed_at)?;
+            if let Err(e) = self.write_transaction(transact_items).await {
+                let error = if !applied.is_empty() {
+                    AddressPolicyRegistryRepositoryError::PartiallyApplied(
+                        Box::new(applied),
+                        anyhow!(e).context("unable to apply policy mapping plan"),
+                    )
+                } else if is_condition_check_failure(&e) {
+                    AddressPolicyRegistryRepositoryError::VersionMismatch(
+                        "policy mappings changed since the plan was computed".to_owned(),
+                    )
+                } else {
+                    AddressPolicyRegistryRepositoryError::Unknown(
+                        anyhow!(e).context("unable to apply policy mapping plan"),
+                    )
+                };
+                return Err(error);
+            }
+            applied.extend(chunk);
+        }
 
-    use std::collections::HashMap;
+        Ok(())
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    BATCH_RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1))
+}
+
+fn encode_cursor(
+    last_evaluated_key: HashMap<String, AttributeValue>,
+) -> Result<String, AddressPolicyRegistryRepositoryError> {
+    let cursor = deserialize_from_dynamo::<
+        PolicyRegistryCursor,
+        AddressPolicyRegistryRepositoryError,
+    >(last_evaluated_key)?;
+    let cursor = serde_json::to_vec(&cursor).map_err(|e| {
+        AddressPolicyRegistryRepositoryError::Unknown(
+            anyhow!(e).context("Error serializing policies cursor"),
+        )
+    })?;
+
+    Ok(hex::encode(cursor))
+}
+
+fn parse_cursor(
+    cursor: &str,
+) -> Result<PolicyRegistryCursor, AddressPolicyRegistryRepositoryError> {
+    hex::decode(cursor)
+        .ok()
+        .and_then(|cursor| serde_json::from_slice(&cursor).ok())
+        .ok_or_else(|| {
+            AddressPolicyRegistryRepositoryError::InvalidCursor(format!("invalid cursor {cursor}"))
+        })
+}
+
+/// History cursors are only valid to continue listing the history of the same mapping.
+fn decode_history_cursor(
+    history_pk: &str,
+    cursor: &str,
+) -> Result<HashMap<String, AttributeValue>, AddressPolicyRegistryRepositoryError> {
+    let parsed = parse_cursor(cursor)?;
+    if parsed.client_id.is_some() || parsed.pk != history_pk {
+        return Err(AddressPolicyRegistryRepositoryError::InvalidCursor(
+            format!("invalid cursor {cursor}"),
+        ));
+    }
+
+    serde_dynamo::to_item(parsed).map_err(|e| {
+        AddressPolicyRegistryRepositoryError::Unknown(
+            anyhow!(e).context("Error building policy history exclusive start key"),
+        )
+    })
+}
+
+fn decode_cursor(
+    client_id: &str,
+    chain_id: Option<u64>,
+    cursor: &str,
+) -> Result<HashMap<String, AttributeValue>, AddressPolicyRegistryRepositoryError> {
+    let invalid_cursor =
+        || AddressPolicyRegistryRepositoryError::InvalidCursor(format!("invalid cursor {cursor}"));
+
+    let cursor = parse_cursor(cursor)?;
+
+    // A cursor can only be used to continue the same kind of query, for the client that got it.
+    let is_valid = match chain_id {
+        Some(chain_id) => {
+            cursor.client_id.is_none()
+                && cursor.pk
+                    == AddressPolicyRegistryPk::new(client_id.to_owned(), chain_id, None).pk
+        }
+        None => {
+            cursor.client_id.as_deref() == Some(client_id)
+                && cursor.pk.starts_with(&format!("CLIENT#{client_id}#"))
+        }
+    };
+    if !is_valid {
+        return Err(invalid_cursor());
+    }
+
+    serde_dynamo::to_item(cursor).map_err(|e| {
+        AddressPolicyRegistryRepositoryError::Unknown(
+            anyhow!(e).context("Error building policies exclusive start key"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
     use std::str::FromStr;
 
     use crate::address_policy_registry::{
@@ -274,26 +1090,37 @@ mod tests {
         AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
     };
     use crate::address_policy_registry::{
-        AddressPolicyRegistryDynamoDbResource, AddressPolicyRegistryPk,
+        AddressPolicyRegistryDynamoDbResource, AddressPolicyRegistryFilters,
+        AddressPolicyRegistryHistoryDynamoDbResource, AddressPolicyRegistryPk, MappingTypeFilter,
+        PutPolicyOutcome, MAX_ATOMIC_PLAN_CHANGES,
     };
-    use chrono::Utc;
+    use chrono::{Duration, Utc};
     use common::test_tools::http::constants::{
         ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
     };
     use common::test_tools::mocks::dynamodb_client::MockDbClient;
     use ethers::types::{Address, H160};
-    use mockall::predicate::eq;
-    use model::address_policy_registry::{AddressPolicyRegistryBuilder, AddressPolicyRegistryType};
+    use mockall::{predicate::eq, Sequence};
+    use model::address_policy_registry::function_selector::FunctionSelector;
+    use model::address_policy_registry::plan::{
+        AddressPolicyRegistryPlan, AddressPolicyRegistryUpdate,
+    };
+    use model::address_policy_registry::{
+        AddressPolicyRegistry, AddressPolicyRegistryBuilder, AddressPolicyRegistryChange,
+        AddressPolicyRegistryType,
+    };
     use rstest::{fixture, rstest};
     use rusoto_core::RusotoError;
     use rusoto_dynamodb::{
-        AttributeValue, DeleteItemOutput, GetItemError, GetItemInput, GetItemOutput, PutItemOutput,
-        UpdateItemError, UpdateItemOutput,
+        AttributeValue, BatchGetItemOutput, GetItemError, GetItemInput, GetItemOutput, QueryOutput,
+        TransactWriteItemsError, TransactWriteItemsInput, TransactWriteItemsOutput,
     };
+    use uuid::Uuid;
 
     struct TestFixture {
         pub dynamodb_client: MockDbClient,
         pub table_name: String,
+        pub history_table_name: String,
     }
 
     #[fixture]
@@ -301,6 +1128,7 @@ mod tests {
         TestFixture {
             dynamodb_client: MockDbClient::new(),
             table_name: "address_policy_registry".to_owned(),
+            history_table_name: "address_policy_registry_history".to_owned(),
         }
     }
 
@@ -319,13 +1147,16 @@ mod tests {
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
         let error = repo
             .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                Some(Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                AddressPolicyRegistryType::AddressTo {
+                    address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                },
             )
             .await
             .unwrap_err();
@@ -347,13 +1178,16 @@ mod tests {
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
         let error = repo
             .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                Some(Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                AddressPolicyRegistryType::AddressTo {
+                    address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                },
             )
             .await
             .unwrap();
@@ -388,13 +1222,14 @@ mod tests {
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
         let result = repo
             .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                None,
+                AddressPolicyRegistryType::Default,
             )
             .await
             .unwrap();
@@ -418,6 +1253,11 @@ mod tests {
                     address: Some(ADDRESS_FOR_MOCK_REQUESTS.to_string()),
                     policy: "Some Policy".to_string(),
                     created_at: now,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
                 };
                 let address_policy = serde_dynamo::to_item(address_policy_registry).unwrap();
                 Ok(GetItemOutput {
@@ -428,13 +1268,16 @@ mod tests {
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
         let result = repo
             .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                Some(Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                AddressPolicyRegistryType::AddressTo {
+                    address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                },
             )
             .await
             .unwrap();
@@ -442,165 +1285,1602 @@ mod tests {
     }
 
     #[rstest]
+    #[case::not_yet_active(Some(Duration::hours(1)), None, false, true)]
+    #[case::expired(None, Some(Duration::minutes(-1)), false, false)]
+    #[case::within_window(Some(Duration::hours(-1)), Some(Duration::hours(1)), true, true)]
     #[tokio::test]
-    async fn put_policy_ok(mut fixture: TestFixture) {
+    async fn get_policy_time_bounded_item(
+        mut fixture: TestFixture,
+        #[case] effective_from: Option<Duration>,
+        #[case] expires_at: Option<Duration>,
+        #[case] is_active: bool,
+        #[case] is_stored: bool,
+    ) {
+        let now = Utc::now();
+        let expires_at = expires_at.map(|offset| now + offset);
         fixture
             .dynamodb_client
-            .expect_put_item()
-            .once()
-            .returning(move |_| Ok(PutItemOutput::default()));
+            .expect_get_item()
+            .times(2)
+            .returning(move |_| {
+                let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
+                    pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
+                    sk: "ADDRESS#DEFAULT".to_string(),
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    address: None,
+                    policy: "Some Policy".to_string(),
+                    created_at: now,
+                    version: 1,
+                    effective_from: effective_from.map(|offset| now + offset),
+                    expires_at,
+                    expires_at_ttl: expires_at.map(|expires_at| expires_at.timestamp()),
+                    value_bands: vec![],
+                };
+                Ok(GetItemOutput {
+                    item: Some(serde_dynamo::to_item(address_policy_registry).unwrap()),
+                    ..GetItemOutput::default()
+                })
+            });
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
+        let active = repo
+            .get_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+            )
+            .await
+            .unwrap();
+        let stored = repo
+            .get_stored_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+            )
+            .await
+            .unwrap();
 
-        let mapping = AddressPolicyRegistryBuilder::new(
-            CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
-            CHAIN_ID_FOR_MOCK_REQUESTS,
-            "test-policy".to_string(),
-        )
-        .address_to(H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap());
-        assert!(repo.put_policy(mapping).await.is_ok());
+        assert_eq!(is_active, active.is_some());
+        assert_eq!(is_stored, stored.is_some());
     }
 
     #[rstest]
     #[tokio::test]
-    async fn delete_policy_ok(mut fixture: TestFixture) {
+    async fn get_policy_address_from_item(mut fixture: TestFixture) {
+        let now = Utc::now();
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let key: HashMap<String, AttributeValue> =
+            serde_dynamo::to_item(AddressPolicyRegistryPk::new_from_address(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                address,
+            ))
+            .unwrap();
         fixture
             .dynamodb_client
-            .expect_delete_item()
+            .expect_get_item()
             .once()
-            .returning(move |_| Ok(DeleteItemOutput::default()));
+            .with(eq(GetItemInput {
+                key,
+                table_name: fixture.table_name.clone(),
+                ..Default::default()
+            }))
+            .returning(move |_| {
+                let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
+                    pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
+                    sk: format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"),
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    address: Some(ADDRESS_FOR_MOCK_REQUESTS.to_string()),
+                    policy: "Some Policy".to_string(),
+                    created_at: now,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
+                };
+                let address_policy = serde_dynamo::to_item(address_policy_registry).unwrap();
+                Ok(GetItemOutput {
+                    item: Some(address_policy),
+                    ..GetItemOutput::default()
+                })
+            });
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
-        assert!(repo
-            .delete_policy(
+        let result = repo
+            .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                Some(H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                AddressPolicyRegistryType::AddressFrom { address },
             )
             .await
-            .is_ok());
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            AddressPolicyRegistryType::AddressFrom { address },
+            result.r#type
+        );
     }
 
     #[rstest]
     #[tokio::test]
-    async fn build_policy_registry_item_input_serialize_empty_address_ok(fixture: TestFixture) {
+    async fn get_policy_contract_call_item(mut fixture: TestFixture) {
+        let now = Utc::now();
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let selector = FunctionSelector([0xa9, 0x05, 0x9c, 0xbb]);
+        let key: HashMap<String, AttributeValue> =
+            serde_dynamo::to_item(AddressPolicyRegistryPk::new_contract_call(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                address,
+                selector,
+            ))
+            .unwrap();
+        assert_eq!(
+            Some(format!(
+                "CONTRACT_CALL#{ADDRESS_FOR_MOCK_REQUESTS}#0xa9059cbb"
+            )),
+            string_attribute(&key, "sk")
+        );
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .with(eq(GetItemInput {
+                key,
+                table_name: fixture.table_name.clone(),
+                ..Default::default()
+            }))
+            .returning(move |_| {
+                let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
+                    pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
+                    sk: format!("CONTRACT_CALL#{ADDRESS_FOR_MOCK_REQUESTS}#0xa9059cbb"),
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    address: Some(ADDRESS_FOR_MOCK_REQUESTS.to_string()),
+                    policy: "Some Policy".to_string(),
+                    created_at: now,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
+                };
+                let address_policy = serde_dynamo::to_item(address_policy_registry).unwrap();
+                Ok(GetItemOutput {
+                    item: Some(address_policy),
+                    ..GetItemOutput::default()
+                })
+            });
+
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
-
-        let mapping = AddressPolicyRegistryBuilder::new(
-            CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
-            CHAIN_ID_FOR_MOCK_REQUESTS,
-            "test-policy".to_string(),
-        )
-        .default();
-
-        let item = repo.build_policy_registry_item_input(mapping);
-        assert!(item.is_ok());
-        assert!(!item.unwrap().item.contains_key("address"));
+        let result = repo
+            .get_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::ContractCall { address, selector },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            AddressPolicyRegistryType::ContractCall { address, selector },
+            result.r#type
+        );
     }
 
     #[rstest]
+    #[case::key_id(
+        AddressPolicyRegistryType::KeyId {
+            key_id: Uuid::parse_str("b3b1a1e6-6a5e-4c43-9f5c-5a1d2a2c0d8e").unwrap(),
+        },
+        "KEY_ID#b3b1a1e6-6a5e-4c43-9f5c-5a1d2a2c0d8e"
+    )]
+    #[case::client_user(
+        AddressPolicyRegistryType::ClientUser {
+            client_user_id: "user#42".to_owned(),
+        },
+        "CLIENT_USER#user#42"
+    )]
+    #[case::group(
+        AddressPolicyRegistryType::Group {
+            name: "exchanges".to_owned(),
+        },
+        "GROUP#exchanges"
+    )]
     #[tokio::test]
-    async fn update_policy_status_db_error(mut fixture: TestFixture) {
+    async fn get_policy_key_owner_items(
+        mut fixture: TestFixture,
+        #[case] mapping_type: AddressPolicyRegistryType,
+        #[case] expected_sk: &'static str,
+    ) {
+        let key: HashMap<String, AttributeValue> =
+            serde_dynamo::to_item(AddressPolicyRegistryPk::from_type(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                &mapping_type,
+            ))
+            .unwrap();
+        assert_eq!(Some(expected_sk.to_owned()), string_attribute(&key, "sk"));
         fixture
             .dynamodb_client
-            .expect_update_item()
+            .expect_get_item()
             .once()
+            .with(eq(GetItemInput {
+                key,
+                table_name: fixture.table_name.clone(),
+                ..Default::default()
+            }))
             .returning(move |_| {
-                Err(RusotoError::Service(UpdateItemError::InternalServerError(
-                    "timeout!".to_owned(),
-                )))
+                let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
+                    pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
+                    sk: expected_sk.to_owned(),
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    address: None,
+                    policy: "Some Policy".to_string(),
+                    created_at: Utc::now(),
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
+                };
+                Ok(GetItemOutput {
+                    item: Some(serde_dynamo::to_item(address_policy_registry).unwrap()),
+                    ..GetItemOutput::default()
+                })
             });
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
-        let error = repo
-            .update_policy(
+        let result = repo
+            .get_policy(
                 CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
                 CHAIN_ID_FOR_MOCK_REQUESTS,
-                None,
-                "test-policy".to_string(),
+                mapping_type.clone(),
             )
             .await
-            .unwrap_err();
-        assert!(matches!(
-            error,
-            AddressPolicyRegistryRepositoryError::Unknown(_)
-        ));
-        assert!(error.to_string().contains("timeout!"));
+            .unwrap()
+            .unwrap();
+        assert_eq!(mapping_type, result.r#type);
+    }
+
+    const TRANSACTION_CONDITION_FAILED: &str = "Transaction cancelled, please refer cancellation \
+        reasons for specific reasons [ConditionalCheckFailed, None]";
+
+    /// Stored default mapping. `None` stands for an item written before versioning.
+    fn stored_policy_item(version: Option<u64>) -> GetItemOutput {
+        let mut item = serde_dynamo::to_item(AddressPolicyRegistryDynamoDbResource {
+            pk: format!(
+                "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+            ),
+            sk: "ADDRESS#DEFAULT".to_string(),
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+            address: None,
+            policy: "old-policy".to_string(),
+            created_at: Utc::now(),
+            version: version.unwrap_or_default(),
+            effective_from: None,
+            expires_at: None,
+            expires_at_ttl: None,
+            value_bands: vec![],
+        })
+        .unwrap();
+        if version.is_none() {
+            item.remove("version");
+        }
+
+        GetItemOutput {
+            item: Some(item),
+            ..GetItemOutput::default()
+        }
+    }
+
+    fn string_attribute(item: &HashMap<String, AttributeValue>, name: &str) -> Option<String> {
+        item.get(name).and_then(|value| value.s.clone())
+    }
+
+    fn history_item(input: &TransactWriteItemsInput) -> HashMap<String, AttributeValue> {
+        let put = input.transact_items[1].put.clone().unwrap();
+        assert_eq!("address_policy_registry_history", put.table_name);
+        put.item
     }
 
     #[rstest]
     #[tokio::test]
-    async fn update_policy_mapping_ok(mut fixture: TestFixture) {
+    async fn put_policy_ok(mut fixture: TestFixture) {
         fixture
             .dynamodb_client
-            .expect_update_item()
+            .expect_transact_write_items()
             .once()
-            .returning(move |_| Ok(UpdateItemOutput::default()));
+            .withf(|input| {
+                let mapping = input.transact_items[0].put.clone().unwrap();
+                let history = history_item(input);
+
+                input.transact_items.len() == 2
+                    && mapping.table_name == "address_policy_registry"
+                    && mapping.condition_expression.as_deref()
+                        == Some("attribute_not_exists(pk) OR expires_at_ttl < :now")
+                    && string_attribute(&history, "change") == Some("CREATED".to_owned())
+                    && string_attribute(&history, "new_policy") == Some("test-policy".to_owned())
+                    && string_attribute(&history, "subject") == Some("user_sub".to_owned())
+                    && !history.contains_key("previous_policy")
+                    && history.contains_key("new_terms")
+                    && !history.contains_key("previous_terms")
+            })
+            .returning(move |_| Ok(TransactWriteItemsOutput::default()));
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
-        repo.update_policy(
+
+        let mapping = AddressPolicyRegistryBuilder::new(
             CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
             CHAIN_ID_FOR_MOCK_REQUESTS,
-            None,
             "test-policy".to_string(),
         )
-        .await
-        .expect("should succeed");
+        .address_to(H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap());
+        assert!(repo
+            .put_policy(mapping, Some("user_sub".to_owned()))
+            .await
+            .is_ok());
     }
 
     #[rstest]
     #[tokio::test]
-    async fn get_all_policies_items(mut fixture: TestFixture) {
-        let now = Utc::now();
-        // Setup the expectation for the `query` operation instead of `get_item`
-        fixture.dynamodb_client.expect_query()
-        .once() // Adjust as necessary for the number of times `query` is called
-        .returning(move |_| {
-            let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
-                pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
-                sk: "ADDRESS#DEFAULT".to_string(),
-                client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
-                chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
-                address: None,
-                policy: "Some Policy".to_string(),
-                created_at: now,
-            };
-            let address_policy = serde_dynamo::to_item(address_policy_registry).unwrap();
-            Ok(rusoto_dynamodb::QueryOutput {
-                items: Some(vec![address_policy]),
-                ..Default::default()
-            })
-        });
+    async fn put_policy_already_exists(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .returning(move |_| {
+                Err(RusotoError::Service(
+                    TransactWriteItemsError::TransactionCanceled(
+                        TRANSACTION_CONDITION_FAILED.to_owned(),
+                    ),
+                ))
+            });
 
         let repo = AddressPolicyRegistryRepositoryImpl::new(
             fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
             fixture.dynamodb_client,
         );
-        let result = repo
-            .get_all_policies(CLIENT_ID_FOR_MOCK_REQUESTS.to_string())
-            .await
-            .unwrap();
 
-        result.iter().for_each(|policy| {
-            assert_eq!(policy.client_id, CLIENT_ID_FOR_MOCK_REQUESTS);
-            assert_eq!(policy.chain_id, CHAIN_ID_FOR_MOCK_REQUESTS);
-            assert_eq!(policy.r#type, AddressPolicyRegistryType::Default);
-            assert_eq!(policy.policy, "Some Policy");
-        });
+        let mapping = AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "test-policy".to_string(),
+        )
+        .default();
+        let error = repo.put_policy(mapping, None).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::AlreadyExists(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_policy_ok(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .withf(|input| input.consistent_read == Some(true))
+            .returning(|_| Ok(stored_policy_item(Some(2))));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .withf(|input| {
+                let delete = input.transact_items[0].delete.clone().unwrap();
+                let expected_version = delete
+                    .expression_attribute_values
+                    .as_ref()
+                    .and_then(|values| values.get(":expected_version"))
+                    .and_then(|value| value.n.clone());
+                let history = history_item(input);
+
+                delete.condition_expression.as_deref() == Some("#version = :expected_version")
+                    && expected_version == Some("2".to_owned())
+                    && string_attribute(&history, "change") == Some("DELETED".to_owned())
+                    && string_attribute(&history, "previous_policy")
+                        == Some("old-policy".to_owned())
+                    && !history.contains_key("new_policy")
+            })
+            .returning(move |_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        assert!(repo
+            .delete_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                None,
+                None,
+            )
+            .await
+            .is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_policy_not_found_ok(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(GetItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .never();
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        assert!(repo
+            .delete_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::AddressTo {
+                    address: H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                },
+                None,
+                None,
+            )
+            .await
+            .is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_policy_version_mismatch(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(stored_policy_item(Some(2))));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .never();
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .delete_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                Some(3),
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::VersionMismatch(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_policy_address_from_ok(mut fixture: TestFixture) {
+        let address = H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let key: HashMap<String, AttributeValue> =
+            serde_dynamo::to_item(AddressPolicyRegistryPk::new_from_address(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                address,
+            ))
+            .unwrap();
+        let get_key = key.clone();
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .withf(move |input| input.key == get_key)
+            .returning(|_| Ok(stored_policy_item(Some(1))));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .withf(move |input| {
+                let history = history_item(input);
+                input.transact_items[0]
+                    .delete
+                    .as_ref()
+                    .map(|delete| &delete.key)
+                    == Some(&key)
+                    && string_attribute(&history, "mapping_sk")
+                        == Some(format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"))
+            })
+            .returning(move |_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        assert!(repo
+            .delete_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::AddressFrom { address },
+                None,
+                None,
+            )
+            .await
+            .is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn build_policy_registry_item_input_serialize_empty_address_ok(fixture: TestFixture) {
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+
+        let mapping = AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "test-policy".to_string(),
+        )
+        .default();
+
+        let item = repo.build_policy_registry_item_input(mapping);
+        assert!(item.is_ok());
+        assert!(!item.unwrap().item.contains_key("address"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_policy_status_db_error(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(stored_policy_item(Some(1))));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .returning(move |_| {
+                Err(RusotoError::Service(
+                    TransactWriteItemsError::InternalServerError("timeout!".to_owned()),
+                ))
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .update_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                "test-policy".to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::Unknown(_)
+        ));
+        assert!(error.to_string().contains("timeout!"));
+    }
+
+    #[rstest]
+    #[case::versioned(Some(1), "#version = :expected_version")]
+    #[case::legacy(None, "attribute_exists(pk) AND attribute_not_exists(#version)")]
+    #[tokio::test]
+    async fn update_policy_mapping_ok(
+        mut fixture: TestFixture,
+        #[case] stored_version: Option<u64>,
+        #[case] expected_condition: &'static str,
+    ) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(move |_| Ok(stored_policy_item(stored_version)));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .withf(move |input| {
+                let update = input.transact_items[0].update.clone().unwrap();
+                let values = update.expression_attribute_values.unwrap();
+                let history = history_item(input);
+
+                update.condition_expression.as_deref() == Some(expected_condition)
+                    && values.contains_key(":expected_version") == stored_version.is_some()
+                    && string_attribute(&history, "change") == Some("UPDATED".to_owned())
+                    && string_attribute(&history, "previous_policy")
+                        == Some("old-policy".to_owned())
+                    && string_attribute(&history, "new_policy") == Some("test-policy".to_owned())
+                    && string_attribute(&history, "client_id")
+                        == Some(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned())
+            })
+            .returning(move |_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let version = repo
+            .update_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                "test-policy".to_string(),
+                stored_version,
+                Some("user_sub".to_owned()),
+            )
+            .await
+            .expect("should succeed");
+
+        assert_eq!(stored_version.unwrap_or_default() + 1, version);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_policy_not_found(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(GetItemOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .never();
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .update_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                "test-policy".to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::PolicyNotFound(_)
+        ));
+    }
+
+    #[rstest]
+    #[case::stale_if_match(Some(4), 0)]
+    #[case::concurrent_change(None, 1)]
+    #[tokio::test]
+    async fn update_policy_version_mismatch(
+        mut fixture: TestFixture,
+        #[case] expected_version: Option<u64>,
+        #[case] transactions: usize,
+    ) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .once()
+            .returning(|_| Ok(stored_policy_item(Some(2))));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .times(transactions)
+            .returning(move |_| {
+                Err(RusotoError::Service(
+                    TransactWriteItemsError::TransactionCanceled(
+                        TRANSACTION_CONDITION_FAILED.to_owned(),
+                    ),
+                ))
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .update_policy(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                "test-policy".to_string(),
+                expected_version,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::VersionMismatch(_)
+        ));
+    }
+
+    fn stored_history_item(changed_at: &str, policy: &str) -> HashMap<String, AttributeValue> {
+        let history_pk = format!(
+            "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}#ADDRESS#DEFAULT"
+        );
+        serde_dynamo::to_item(AddressPolicyRegistryHistoryDynamoDbResource {
+            pk: history_pk,
+            sk: format!("{changed_at}#some_uuid"),
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+            mapping_sk: "ADDRESS#DEFAULT".to_owned(),
+            address: None,
+            change: AddressPolicyRegistryChange::Updated,
+            previous_policy: Some("old-policy".to_owned()),
+            new_policy: Some(policy.to_owned()),
+            previous_terms: None,
+            new_terms: None,
+            subject: Some("user_sub".to_owned()),
+            changed_at: changed_at.parse().unwrap(),
+        })
+        .unwrap()
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policy_history_newest_first(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                let pk = input
+                    .expression_attribute_values
+                    .as_ref()
+                    .and_then(|values| string_attribute(values, ":pk"));
+
+                input.table_name == "address_policy_registry_history"
+                    && input.scan_index_forward == Some(false)
+                    && input.limit == Some(2)
+                    && pk
+                        == Some(format!(
+                            "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}#ADDRESS#DEFAULT"
+                        ))
+            })
+            .returning(|_| {
+                let newest = stored_history_item("2024-02-01T00:00:00.000000Z", "newest");
+                let last_evaluated_key = HashMap::from([
+                    ("pk".to_owned(), newest["pk"].clone()),
+                    ("sk".to_owned(), newest["sk"].clone()),
+                ]);
+                Ok(QueryOutput {
+                    items: Some(vec![
+                        newest,
+                        stored_history_item("2024-01-01T00:00:00.000000Z", "oldest"),
+                    ]),
+                    last_evaluated_key: Some(last_evaluated_key),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let page = repo
+            .get_policy_history(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::Default,
+                Some(2),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(2, page.entries.len());
+        assert_eq!(Some("newest".to_owned()), page.entries[0].new_policy);
+        assert_eq!(AddressPolicyRegistryType::Default, page.entries[0].r#type);
+        assert_eq!(Some("user_sub".to_owned()), page.entries[0].subject);
+
+        // The cursor belongs to the default mapping, it can't be used for another one.
+        let error = repo
+            .get_policy_history(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                AddressPolicyRegistryType::AddressTo {
+                    address: H160::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                },
+                None,
+                page.next_cursor,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::InvalidCursor(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_all_policies_items(mut fixture: TestFixture) {
+        let now = Utc::now();
+        // Setup the expectation for the `query` operation instead of `get_item`
+        fixture.dynamodb_client.expect_query()
+        .once() // Adjust as necessary for the number of times `query` is called
+        .returning(move |_| {
+            let address_policy_registry = AddressPolicyRegistryDynamoDbResource {
+                pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"),
+                sk: "ADDRESS#DEFAULT".to_string(),
+                client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+                chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                address: None,
+                policy: "Some Policy".to_string(),
+                created_at: now,
+                version: 1,
+                effective_from: None,
+                expires_at: None,
+                expires_at_ttl: None,
+                value_bands: vec![],
+            };
+            let address_policy = serde_dynamo::to_item(address_policy_registry).unwrap();
+            Ok(rusoto_dynamodb::QueryOutput {
+                items: Some(vec![address_policy]),
+                ..Default::default()
+            })
+        });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let result = repo
+            .get_all_policies(CLIENT_ID_FOR_MOCK_REQUESTS.to_string())
+            .await
+            .unwrap();
+
+        result.iter().for_each(|policy| {
+            assert_eq!(policy.client_id, CLIENT_ID_FOR_MOCK_REQUESTS);
+            assert_eq!(policy.chain_id, CHAIN_ID_FOR_MOCK_REQUESTS);
+            assert_eq!(policy.r#type, AddressPolicyRegistryType::Default);
+            assert_eq!(policy.policy, "Some Policy");
+        });
+    }
+
+    fn default_policy_item(chain_id: u64) -> HashMap<String, AttributeValue> {
+        serde_dynamo::to_item(AddressPolicyRegistryDynamoDbResource {
+            pk: format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{chain_id}"),
+            sk: "ADDRESS#DEFAULT".to_string(),
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_string(),
+            chain_id,
+            address: None,
+            policy: "Some Policy".to_string(),
+            created_at: Utc::now(),
+            version: 1,
+            effective_from: None,
+            expires_at: None,
+            expires_at_ttl: None,
+            value_bands: vec![],
+        })
+        .unwrap()
+    }
+
+    fn last_evaluated_key(chain_id: u64) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                "pk".to_owned(),
+                AttributeValue {
+                    s: Some(format!(
+                        "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{chain_id}"
+                    )),
+                    ..AttributeValue::default()
+                },
+            ),
+            (
+                "sk".to_owned(),
+                AttributeValue {
+                    s: Some("ADDRESS#DEFAULT".to_owned()),
+                    ..AttributeValue::default()
+                },
+            ),
+            (
+                "client_id".to_owned(),
+                AttributeValue {
+                    s: Some(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned()),
+                    ..AttributeValue::default()
+                },
+            ),
+        ])
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn export_policies_reads_every_page(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.limit.is_none() && input.exclusive_start_key.is_none())
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)]),
+                    last_evaluated_key: Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS)),
+                    ..QueryOutput::default()
+                })
+            });
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                input.exclusive_start_key == Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS))
+            })
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS + 1)]),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+
+        let rows = repo
+            .export_policies(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(2, rows.len());
+        assert_eq!("ADDRESS#DEFAULT", rows[0].sk);
+        assert_eq!(CHAIN_ID_FOR_MOCK_REQUESTS + 1, rows[1].chain_id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_page_cursor_round_trip(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.limit == Some(1) && input.exclusive_start_key.is_none())
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)]),
+                    last_evaluated_key: Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS)),
+                    ..QueryOutput::default()
+                })
+            });
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                input.exclusive_start_key == Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS))
+            })
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS + 1)]),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+
+        let first_page = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                Some(1),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(1, first_page.policies.len());
+        assert_eq!(CHAIN_ID_FOR_MOCK_REQUESTS, first_page.policies[0].chain_id);
+        assert!(first_page.next_cursor.is_some());
+
+        let second_page = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                Some(1),
+                first_page.next_cursor,
+            )
+            .await
+            .unwrap();
+        assert_eq!(1, second_page.policies.len());
+        assert_eq!(
+            CHAIN_ID_FOR_MOCK_REQUESTS + 1,
+            second_page.policies[0].chain_id
+        );
+        assert!(second_page.next_cursor.is_none());
+    }
+
+    #[rstest]
+    #[case::not_hex("not a cursor")]
+    #[case::not_json("abcdef")]
+    #[tokio::test]
+    async fn get_policies_page_invalid_cursor(mut fixture: TestFixture, #[case] cursor: &str) {
+        fixture.dynamodb_client.expect_query().never();
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                None,
+                Some(cursor.to_owned()),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::InvalidCursor(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_page_cursor_from_other_client(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)]),
+                    last_evaluated_key: Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS)),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let cursor = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .next_cursor;
+
+        let error = repo
+            .get_policies_page(
+                "another_client".to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                None,
+                cursor,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::InvalidCursor(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_all_policies_follows_pages(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.exclusive_start_key.is_none())
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)]),
+                    last_evaluated_key: Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS)),
+                    ..QueryOutput::default()
+                })
+            });
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| input.exclusive_start_key.is_some())
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS + 1)]),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let result = repo
+            .get_all_policies(CLIENT_ID_FOR_MOCK_REQUESTS.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(2, result.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_page_chain_filters(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                let values = input.expression_attribute_values.clone().unwrap();
+                let string_value = |name: &str| values.get(name).and_then(|v| v.s.clone());
+
+                input.index_name.is_none()
+                    && input.key_condition_expression.as_deref() == Some("pk = :pk")
+                    && input.filter_expression.as_deref()
+                        == Some(
+                            "begins_with(sk, :sk_prefix) AND #policy = :policy \
+                             AND begins_with(#address, :address_prefix)",
+                        )
+                    && string_value(":pk")
+                        == Some(format!(
+                            "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+                        ))
+                    && string_value(":sk_prefix") == Some("ADDRESS_FROM#".to_owned())
+                    && string_value(":policy") == Some("some_policy".to_owned())
+                    && string_value(":address_prefix") == Some("0x1c96".to_owned())
+                    && !values.contains_key(":client_id")
+            })
+            .returning(|_| Ok(QueryOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let page = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters {
+                    chain_id: Some(CHAIN_ID_FOR_MOCK_REQUESTS),
+                    mapping_type: Some(MappingTypeFilter::AddressFrom),
+                    policy: Some("some_policy".to_owned()),
+                    address_prefix: Some("0x1C96".to_owned()),
+                },
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(page.policies.is_empty());
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_page_type_filter_without_chain(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .withf(|input| {
+                input.index_name.as_deref() == Some("client_id_index")
+                    && input.filter_expression.as_deref()
+                        == Some("begins_with(sk, :sk_prefix) AND sk <> :default_sk")
+                    && input.expression_attribute_names.is_none()
+            })
+            .returning(|_| Ok(QueryOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        repo.get_policies_page(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            AddressPolicyRegistryFilters {
+                mapping_type: Some(MappingTypeFilter::AddressTo),
+                ..AddressPolicyRegistryFilters::default()
+            },
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_page_cursor_from_other_query(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_query()
+            .once()
+            .returning(|_| {
+                Ok(QueryOutput {
+                    items: Some(vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)]),
+                    last_evaluated_key: Some(last_evaluated_key(CHAIN_ID_FOR_MOCK_REQUESTS)),
+                    ..QueryOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let cursor = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .next_cursor;
+
+        // The cursor comes from an index query, it can't be used to continue a chain query.
+        let error = repo
+            .get_policies_page(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                AddressPolicyRegistryFilters {
+                    chain_id: Some(CHAIN_ID_FOR_MOCK_REQUESTS),
+                    ..AddressPolicyRegistryFilters::default()
+                },
+                None,
+                cursor,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::InvalidCursor(_)
+        ));
+    }
+
+    fn address_to_policy(index: u64) -> AddressPolicyRegistry {
+        AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "some_policy".to_owned(),
+        )
+        .address_to(H160::from_low_u64_be(index))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn put_policies_writes_each_mapping_conditionally(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .times(3)
+            .withf(|input| {
+                let mapping = input.transact_items[0].put.clone().unwrap();
+
+                input.transact_items.len() == 2
+                    && mapping.condition_expression.as_deref()
+                        == Some("attribute_not_exists(pk) OR expires_at_ttl < :now")
+                    && string_attribute(&history_item(input), "subject")
+                        == Some("user_sub".to_owned())
+            })
+            .returning(|_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let outcomes = repo
+            .put_policies(
+                (1..=3).map(address_to_policy).collect(),
+                Some("user_sub".to_owned()),
+            )
+            .await;
+
+        assert_eq!(vec![PutPolicyOutcome::Created; 3], outcomes);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn put_policies_outcome_of_each_mapping(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .times(3)
+            .returning(|input| {
+                let mapping = input.transact_items[0].put.clone().unwrap();
+                let sk = string_attribute(&mapping.item, "sk").unwrap();
+                if sk.ends_with('1') {
+                    Ok(TransactWriteItemsOutput::default())
+                } else if sk.ends_with('2') {
+                    Err(RusotoError::Service(
+                        TransactWriteItemsError::TransactionCanceled(
+                            TRANSACTION_CONDITION_FAILED.to_owned(),
+                        ),
+                    ))
+                } else {
+                    Err(RusotoError::Service(
+                        TransactWriteItemsError::InternalServerError("timeout".to_owned()),
+                    ))
+                }
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let outcomes = repo
+            .put_policies((1..=3).map(address_to_policy).collect(), None)
+            .await;
+
+        assert_eq!(
+            vec![
+                PutPolicyOutcome::Created,
+                PutPolicyOutcome::AlreadyExists,
+                PutPolicyOutcome::Failed,
+            ],
+            outcomes
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn get_policies_by_keys_ok(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_batch_get_item()
+            .once()
+            .withf(|input| input.request_items["address_policy_registry"].keys.len() == 2)
+            .returning(|_| {
+                Ok(BatchGetItemOutput {
+                    responses: Some(HashMap::from([(
+                        "address_policy_registry".to_owned(),
+                        vec![default_policy_item(CHAIN_ID_FOR_MOCK_REQUESTS)],
+                    )])),
+                    ..BatchGetItemOutput::default()
+                })
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let policies = repo
+            .get_policies_by_keys(vec![
+                AddressPolicyRegistryPk::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    None,
+                ),
+                AddressPolicyRegistryPk::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    Some(H160::from_low_u64_be(1)),
+                ),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(1, policies.len());
+        assert_eq!(AddressPolicyRegistryType::Default, policies[0].r#type);
+    }
+
+    fn mapping_plan(changes_of_each_kind: u64) -> AddressPolicyRegistryPlan {
+        let mut plan = AddressPolicyRegistryPlan::default();
+        for index in 0..changes_of_each_kind {
+            plan.creates.push(address_to_policy(index));
+
+            let current = address_to_policy(1000 + index);
+            let mut desired = current.clone();
+            desired.policy = "another_policy".to_owned();
+            desired.version = current.version + 1;
+            plan.updates
+                .push(AddressPolicyRegistryUpdate { current, desired });
+
+            plan.deletes.push(address_to_policy(2000 + index));
+        }
+        plan
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_plan_single_transaction(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .withf(|input| {
+                let create = input.transact_items[0].put.clone().unwrap();
+                let update = input.transact_items[2].put.clone().unwrap();
+                let update_history = input.transact_items[3].put.clone().unwrap();
+                let delete = input.transact_items[4].delete.clone().unwrap();
+
+                input.transact_items.len() == 6
+                    && create.condition_expression.as_deref()
+                        == Some("attribute_not_exists(pk) OR expires_at_ttl < :now")
+                    && update.condition_expression.as_deref()
+                        == Some("#version = :expected_version")
+                    && string_attribute(&update.item, "policy") == Some("another_policy".to_owned())
+                    && string_attribute(&update_history.item, "change")
+                        == Some("UPDATED".to_owned())
+                    && string_attribute(&update_history.item, "previous_policy")
+                        == Some("some_policy".to_owned())
+                    && string_attribute(&update_history.item, "subject")
+                        == Some("user_sub".to_owned())
+                    && delete.condition_expression.as_deref()
+                        == Some("#version = :expected_version")
+            })
+            .returning(|_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        repo.apply_policy_plan(mapping_plan(1), Some("user_sub".to_owned()))
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_plan_in_chunks(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .times(2)
+            .withf(|input| input.transact_items.len() == 100 || input.transact_items.len() == 20)
+            .returning(|_| Ok(TransactWriteItemsOutput::default()));
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        repo.apply_policy_plan(mapping_plan(20), None)
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_plan_partially_applied(mut fixture: TestFixture) {
+        let mut sequence = Sequence::new();
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(TransactWriteItemsOutput::default()));
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .in_sequence(&mut sequence)
+            .returning(|_| {
+                Err(RusotoError::Service(
+                    TransactWriteItemsError::TransactionCanceled(
+                        TRANSACTION_CONDITION_FAILED.to_owned(),
+                    ),
+                ))
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .apply_policy_plan(mapping_plan(20), None)
+            .await
+            .unwrap_err();
+
+        match error {
+            AddressPolicyRegistryRepositoryError::PartiallyApplied(applied, _) => {
+                assert_eq!(MAX_ATOMIC_PLAN_CHANGES, applied.len());
+                assert_eq!(20, applied.creates.len());
+                assert_eq!(20, applied.updates.len());
+                assert_eq!(10, applied.deletes.len());
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_plan_out_of_date(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_transact_write_items()
+            .once()
+            .returning(|_| {
+                Err(RusotoError::Service(
+                    TransactWriteItemsError::TransactionCanceled(
+                        TRANSACTION_CONDITION_FAILED.to_owned(),
+                    ),
+                ))
+            });
+
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name.clone(),
+            fixture.history_table_name.clone(),
+            fixture.dynamodb_client,
+        );
+        let error = repo
+            .apply_policy_plan(mapping_plan(1), None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AddressPolicyRegistryRepositoryError::VersionMismatch(_)
+        ));
     }
 }
@@ -1,25 +1,42 @@
 pub mod address_policy_registry_repository_impl;
+pub mod policy_resolver;
 
 use std::str::FromStr;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use common::serializers::h160::h160_to_lowercase_hex_string;
 use ethers::types::Address;
-use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryType};
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+use model::address_policy_registry::value_band::PolicyValueBand;
+use model::address_policy_registry::{
+    AddressPolicyRegistry, AddressPolicyRegistryChange, AddressPolicyRegistryHistory,
+    AddressPolicyRegistryTerms, AddressPolicyRegistryType, ANY_CHAIN_ID,
+};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 const CLIENT_ID_INDEX_NAME: &str = "client_id_index";
 const TYPE_ADDRESS_FROM: &str = "ADDRESS_FROM";
 const TYPE_ADDRESS_TO: &str = "ADDRESS";
+const TYPE_CONTRACT_CALL: &str = "CONTRACT_CALL";
+const TYPE_KEY_ID: &str = "KEY_ID";
+const TYPE_CLIENT_USER: &str = "CLIENT_USER";
+const TYPE_GROUP: &str = "GROUP";
+const ANY_CHAIN_KEY: &str = "*";
+
+/// Every change of a plan writes the mapping and its history item, and a single
+/// `TransactWriteItems` call takes at most 100 items.
+pub const MAX_ATOMIC_PLAN_CHANGES: usize = 50;
 
 #[cfg(feature = "test_mocks")]
 use mockall::mock;
 
 use crate::{deserialize::UnknownError, impl_unknown_error_trait};
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct AddressPolicyRegistryPk {
     pub pk: String,
     pub sk: String,
@@ -37,6 +54,16 @@ pub enum AddressPolicyRegistryRepositoryError {
     Unknown(anyhow::Error),
     #[error("{0}")]
     PolicyNotFound(String),
+    #[error("{0}")]
+    InvalidCursor(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error("{0}")]
+    VersionMismatch(String),
+    /// A plan too big for a single transaction failed after some of its transactions were
+    /// written. Holds the changes that were written.
+    #[error("policy mapping plan partially applied: {1:#}")]
+    PartiallyApplied(Box<AddressPolicyRegistryPlan>, anyhow::Error),
 }
 
 impl From<anyhow::Error> for AddressPolicyRegistryRepositoryError {
@@ -45,6 +72,83 @@ impl From<anyhow::Error> for AddressPolicyRegistryRepositoryError {
     }
 }
 
+/// Outcome of writing one of the mappings of
+/// [`AddressPolicyRegistryRepository::put_policies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutPolicyOutcome {
+    Created,
+    /// A mapping with the same key is already stored.
+    AlreadyExists,
+    /// The mapping couldn't be written, the error is logged.
+    Failed,
+}
+
+/// One page of policy mappings. `next_cursor` is an opaque value that must be sent back to fetch
+/// the next page, it is `None` once the last page was returned.
+#[derive(Debug, Clone, Default)]
+pub struct AddressPolicyRegistryPage {
+    pub policies: Vec<AddressPolicyRegistry>,
+    pub next_cursor: Option<String>,
+}
+
+/// One page of the change history of a policy mapping, newest changes first.
+#[derive(Debug, Clone, Default)]
+pub struct AddressPolicyRegistryHistoryPage {
+    pub entries: Vec<AddressPolicyRegistryHistory>,
+    pub next_cursor: Option<String>,
+}
+
+/// Mapping kinds that can be used to filter a listing. They are matched against the sort key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MappingTypeFilter {
+    Default,
+    AddressTo,
+    AddressFrom,
+    ContractCall,
+    KeyId,
+    ClientUser,
+    Group,
+}
+
+/// Server side filters for listing policy mappings. When `chain_id` is given the query goes
+/// straight to the chain partition, otherwise the `client_id_index` is queried and every other
+/// filter is applied as a filter expression.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressPolicyRegistryFilters {
+    pub chain_id: Option<u64>,
+    pub mapping_type: Option<MappingTypeFilter>,
+    /// Compared against the `policy` attribute only, the policies of the value bands are not
+    /// searched.
+    pub policy: Option<String>,
+    pub address_prefix: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct PoliciesQueryValues {
+    #[serde(rename = ":client_id", skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(rename = ":pk", skip_serializing_if = "Option::is_none")]
+    pub pk: Option<String>,
+    #[serde(rename = ":default_sk", skip_serializing_if = "Option::is_none")]
+    pub default_sk: Option<String>,
+    #[serde(rename = ":sk_prefix", skip_serializing_if = "Option::is_none")]
+    pub sk_prefix: Option<String>,
+    #[serde(rename = ":policy", skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    #[serde(rename = ":address_prefix", skip_serializing_if = "Option::is_none")]
+    pub address_prefix: Option<String>,
+}
+
+/// `LastEvaluatedKey` of a policies query. It is serialized and hex encoded to build the cursor
+/// handed to the clients. `client_id` is only present when the `client_id_index` was queried.
+#[derive(Serialize, Deserialize)]
+struct PolicyRegistryCursor {
+    pk: String,
+    sk: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+}
+
 impl ClientIdGSI {
     pub fn new(client: String) -> Self {
         Self {
@@ -55,10 +159,20 @@ impl ClientIdGSI {
 
 impl_unknown_error_trait!(AddressPolicyRegistryRepositoryError);
 
+/// Partition key of the mappings of a client on a chain. Client wide mappings go under the `*`
+/// chain.
+fn chain_partition_key(client: &str, chain_id: u64) -> String {
+    if chain_id == ANY_CHAIN_ID {
+        format!("CLIENT#{client}#CHAIN_ID#{ANY_CHAIN_KEY}")
+    } else {
+        format!("CLIENT#{client}#CHAIN_ID#{chain_id}")
+    }
+}
+
 impl AddressPolicyRegistryPk {
     pub fn new(client: String, chain_id: u64, address_to: Option<Address>) -> Self {
         Self {
-            pk: format!("CLIENT#{client}#CHAIN_ID#{chain_id}"),
+            pk: chain_partition_key(&client, chain_id),
             sk: format!(
                 "{TYPE_ADDRESS_TO}#{}",
                 address_to
@@ -69,13 +183,81 @@ impl AddressPolicyRegistryPk {
     }
     pub fn new_from_address(client: String, chain_id: u64, address_from: Address) -> Self {
         Self {
-            pk: format!("CLIENT#{client}#CHAIN_ID#{chain_id}"),
+            pk: chain_partition_key(&client, chain_id),
             sk: format!(
                 "{TYPE_ADDRESS_FROM}#{}",
                 h160_to_lowercase_hex_string(address_from)
             ),
         }
     }
+
+    pub fn new_contract_call(
+        client: String,
+        chain_id: u64,
+        contract_address: Address,
+        selector: FunctionSelector,
+    ) -> Self {
+        Self {
+            pk: chain_partition_key(&client, chain_id),
+            sk: format!(
+                "{TYPE_CONTRACT_CALL}#{}#{selector}",
+                h160_to_lowercase_hex_string(contract_address)
+            ),
+        }
+    }
+
+    pub fn new_key_id(client: String, chain_id: u64, key_id: Uuid) -> Self {
+        Self {
+            pk: chain_partition_key(&client, chain_id),
+            sk: format!("{TYPE_KEY_ID}#{key_id}"),
+        }
+    }
+
+    pub fn new_client_user(client: String, chain_id: u64, client_user_id: &str) -> Self {
+        Self {
+            pk: chain_partition_key(&client, chain_id),
+            sk: format!("{TYPE_CLIENT_USER}#{client_user_id}"),
+        }
+    }
+
+    pub fn new_group(client: String, chain_id: u64, name: &str) -> Self {
+        Self {
+            pk: chain_partition_key(&client, chain_id),
+            sk: format!("{TYPE_GROUP}#{name}"),
+        }
+    }
+
+    /// Builds the key that identifies a mapping of the given type.
+    pub fn from_type(
+        client: String,
+        chain_id: u64,
+        mapping_type: &AddressPolicyRegistryType,
+    ) -> Self {
+        match mapping_type {
+            AddressPolicyRegistryType::Default => Self::new(client, chain_id, None),
+            AddressPolicyRegistryType::AddressTo { address } => {
+                Self::new(client, chain_id, Some(*address))
+            }
+            AddressPolicyRegistryType::AddressFrom { address } => {
+                Self::new_from_address(client, chain_id, *address)
+            }
+            AddressPolicyRegistryType::ContractCall { address, selector } => {
+                Self::new_contract_call(client, chain_id, *address, *selector)
+            }
+            AddressPolicyRegistryType::KeyId { key_id } => {
+                Self::new_key_id(client, chain_id, *key_id)
+            }
+            AddressPolicyRegistryType::ClientUser { client_user_id } => {
+                Self::new_client_user(client, chain_id, client_user_id)
+            }
+            AddressPolicyRegistryType::Group { name } => Self::new_group(client, chain_id, name),
+        }
+    }
+
+    /// Partition key of the history items of the mapping identified by this key.
+    pub fn history_pk(&self) -> String {
+        format!("{}#{}", self.pk, self.sk)
+    }
 }
 
 #[derive(Serialize)]
@@ -84,6 +266,39 @@ struct UpdatePolicy {
     pub policy: String,
     #[serde(rename(serialize = ":last_modified_at"))]
     pub last_modified_at: DateTime<Utc>,
+    #[serde(rename(serialize = ":version_increment"))]
+    pub version_increment: u64,
+    #[serde(
+        rename(serialize = ":expected_version"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expected_version: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ExpiredBefore {
+    #[serde(rename(serialize = ":now"))]
+    pub now: i64,
+}
+
+/// New mappings can take the place of an expired one the TTL hasn't deleted yet.
+const PUT_POLICY_CONDITION_EXPRESSION: &str = "attribute_not_exists(pk) OR expires_at_ttl < :now";
+
+#[derive(Serialize)]
+struct ExpectedVersion {
+    #[serde(rename(serialize = ":expected_version"))]
+    pub expected_version: u64,
+}
+
+/// Condition expression that only lets a write through if the stored mapping is still at
+/// `expected_version`. Mappings stored before versioning was introduced have no `version`
+/// attribute, they match version 0 and the condition does not use `:expected_version`.
+fn version_condition_expression(expected_version: u64) -> String {
+    if expected_version == 0 {
+        "attribute_exists(pk) AND attribute_not_exists(#version)".to_owned()
+    } else {
+        "#version = :expected_version".to_owned()
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -96,6 +311,57 @@ pub struct AddressPolicyRegistryDynamoDbResource {
     pub created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// `expires_at` as epoch seconds. It's the table TTL attribute, DynamoDB deletes expired
+    /// mappings some time after they expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at_ttl: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+/// History items live in their own table, partitioned by mapping. The sort key starts with the
+/// change timestamp so a reverse query lists the newest changes first.
+#[derive(Deserialize, Serialize)]
+pub struct AddressPolicyRegistryHistoryDynamoDbResource {
+    pub pk: String,
+    pub sk: String,
+    pub client_id: String,
+    pub chain_id: u64,
+    pub mapping_sk: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub change: AddressPolicyRegistryChange,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_terms: Option<AddressPolicyRegistryTerms>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_terms: Option<AddressPolicyRegistryTerms>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+fn mapping_address(mapping_type: &AddressPolicyRegistryType) -> Option<String> {
+    match mapping_type {
+        AddressPolicyRegistryType::Default
+        | AddressPolicyRegistryType::KeyId { .. }
+        | AddressPolicyRegistryType::ClientUser { .. }
+        | AddressPolicyRegistryType::Group { .. } => None,
+        AddressPolicyRegistryType::AddressTo { address }
+        | AddressPolicyRegistryType::AddressFrom { address }
+        | AddressPolicyRegistryType::ContractCall { address, .. } => {
+            Some(h160_to_lowercase_hex_string(*address))
+        }
+    }
 }
 
 fn parse_address_from_option(
@@ -110,125 +376,250 @@ fn parse_address_from_option(
     })
 }
 
-impl TryFrom<AddressPolicyRegistryDynamoDbResource> for AddressPolicyRegistry {
-    type Error = AddressPolicyRegistryRepositoryError;
+/// Gets the mapping type out of the sort key of a mapping.
+fn parse_mapping_type(
+    pk: &str,
+    sk: &str,
+    address: Option<String>,
+) -> Result<AddressPolicyRegistryType, AddressPolicyRegistryRepositoryError> {
+    let mapping_type: Vec<&str> = sk.split('#').collect();
+    let mapping_type = match mapping_type.first() {
+        Some(&TYPE_ADDRESS_TO) => match mapping_type.get(1) {
+            Some(&"DEFAULT") => AddressPolicyRegistryType::Default,
+            Some(_) => AddressPolicyRegistryType::AddressTo {
+                address: parse_address_from_option(address)?,
+            },
+            None => {
+                return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                    "malformed sort key for pk {pk}, address or default not found"
+                )))
+            }
+        },
+        Some(&TYPE_ADDRESS_FROM) => AddressPolicyRegistryType::AddressFrom {
+            address: parse_address_from_option(address)?,
+        },
+        Some(&TYPE_CONTRACT_CALL) => {
+            let selector = mapping_type.get(2).ok_or_else(|| {
+                AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                    "malformed sort key for pk {pk}, function selector not found"
+                ))
+            })?;
 
-    fn try_from(value: AddressPolicyRegistryDynamoDbResource) -> Result<Self, Self::Error> {
-        let mapping_type: Vec<&str> = value.sk.split('#').collect();
-        let mapping_type = match mapping_type.first() {
-            Some(&TYPE_ADDRESS_TO) => match mapping_type.get(1) {
-                Some(&"DEFAULT") => AddressPolicyRegistryType::Default,
-                Some(_) => AddressPolicyRegistryType::AddressTo {
-                    address: parse_address_from_option(value.address)?,
-                },
-                None => {
-                    return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
-                        "malformed sort key for pk {}, address or default not found",
-                        value.pk
-                    )))
+            AddressPolicyRegistryType::ContractCall {
+                address: parse_address_from_option(address)?,
+                selector: FunctionSelector::from_str(selector).map_err(|e| {
+                    AddressPolicyRegistryRepositoryError::Unknown(
+                        e.context("unable to parse function selector"),
+                    )
+                })?,
+            }
+        }
+        Some(&TYPE_KEY_ID) => {
+            let key_id = mapping_type.get(1).ok_or_else(|| {
+                AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                    "malformed sort key for pk {pk}, key id not found"
+                ))
+            })?;
+
+            AddressPolicyRegistryType::KeyId {
+                key_id: Uuid::parse_str(key_id).map_err(|e| {
+                    AddressPolicyRegistryRepositoryError::unknown(e, Some("unable to parse key id"))
+                })?,
+            }
+        }
+        // Client user ids are set by the clients and can contain `#`, everything after the type
+        // is the id.
+        Some(&TYPE_CLIENT_USER) => match sk.split_once('#') {
+            Some((_, client_user_id)) if !client_user_id.is_empty() => {
+                AddressPolicyRegistryType::ClientUser {
+                    client_user_id: client_user_id.to_owned(),
                 }
-            },
-            Some(&TYPE_ADDRESS_FROM) => AddressPolicyRegistryType::AddressTo {
-                address: parse_address_from_option(value.address)?,
-            },
-            Some(_) => {
+            }
+            _ => {
                 return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
-                    "invalid address mapping type found for pk {}",
-                    value.pk
+                    "malformed sort key for pk {pk}, client user id not found"
                 )))
             }
-            None => {
+        },
+        Some(&TYPE_GROUP) => match sk.split_once('#') {
+            Some((_, name)) if !name.is_empty() => AddressPolicyRegistryType::Group {
+                name: name.to_owned(),
+            },
+            _ => {
                 return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
-                    "no sort key found for pk {}",
-                    value.pk
+                    "malformed sort key for pk {pk}, group name not found"
                 )))
             }
-        };
+        },
+        Some(_) => {
+            return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                "invalid address mapping type found for pk {pk}"
+            )))
+        }
+        None => {
+            return Err(AddressPolicyRegistryRepositoryError::Unknown(anyhow!(
+                "no sort key found for pk {pk}"
+            )))
+        }
+    };
+
+    Ok(mapping_type)
+}
+
+impl AddressPolicyRegistryDynamoDbResource {
+    pub fn terms(&self) -> AddressPolicyRegistryTerms {
+        AddressPolicyRegistryTerms {
+            effective_from: self.effective_from,
+            expires_at: self.expires_at,
+            value_bands: self.value_bands.clone(),
+        }
+    }
+}
+
+impl TryFrom<AddressPolicyRegistryDynamoDbResource> for AddressPolicyRegistry {
+    type Error = AddressPolicyRegistryRepositoryError;
+
+    fn try_from(value: AddressPolicyRegistryDynamoDbResource) -> Result<Self, Self::Error> {
+        let mapping_type = parse_mapping_type(&value.pk, &value.sk, value.address)?;
 
         Ok(Self {
             client_id: value.client_id,
             chain_id: value.chain_id,
             policy: value.policy,
             r#type: mapping_type,
+            version: value.version,
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+            value_bands: value.value_bands,
         })
     }
 }
 
 impl From<AddressPolicyRegistry> for AddressPolicyRegistryDynamoDbResource {
     fn from(value: AddressPolicyRegistry) -> Self {
-        match value.r#type {
-            AddressPolicyRegistryType::Default => {
-                let key =
-                    AddressPolicyRegistryPk::new(value.client_id.clone(), value.chain_id, None);
-                Self {
-                    pk: key.pk,
-                    sk: key.sk,
-                    client_id: value.client_id,
-                    chain_id: value.chain_id,
-                    policy: value.policy,
-                    created_at: Utc::now(),
-                    address: None,
-                }
-            }
-            AddressPolicyRegistryType::AddressTo { address } => {
-                let key = AddressPolicyRegistryPk::new(
-                    value.client_id.clone(),
-                    value.chain_id,
-                    Some(address),
-                );
-                Self {
-                    pk: key.pk,
-                    sk: key.sk,
-                    client_id: value.client_id,
-                    chain_id: value.chain_id,
-                    policy: value.policy,
-                    created_at: Utc::now(),
-                    address: Some(h160_to_lowercase_hex_string(address)),
-                }
-            }
-            AddressPolicyRegistryType::AddressFrom { address } => {
-                let key = AddressPolicyRegistryPk::new_from_address(
-                    value.client_id.clone(),
-                    value.chain_id,
-                    address,
-                );
-
-                Self {
-                    pk: key.pk,
-                    sk: key.sk,
-                    client_id: value.client_id,
-                    chain_id: value.chain_id,
-                    policy: value.policy,
-                    created_at: Utc::now(),
-                    address: Some(h160_to_lowercase_hex_string(address)),
-                }
-            }
+        let key = AddressPolicyRegistryPk::from_type(
+            value.client_id.clone(),
+            value.chain_id,
+            &value.r#type,
+        );
+
+        Self {
+            pk: key.pk,
+            sk: key.sk,
+            client_id: value.client_id,
+            chain_id: value.chain_id,
+            policy: value.policy,
+            created_at: Utc::now(),
+            address: mapping_address(&value.r#type),
+            version: value.version,
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+            expires_at_ttl: value.expires_at.map(|expires_at| expires_at.timestamp()),
+            value_bands: value.value_bands,
         }
     }
 }
 
+impl From<AddressPolicyRegistryHistory> for AddressPolicyRegistryHistoryDynamoDbResource {
+    fn from(value: AddressPolicyRegistryHistory) -> Self {
+        let key = AddressPolicyRegistryPk::from_type(
+            value.client_id.clone(),
+            value.chain_id,
+            &value.r#type,
+        );
+
+        Self {
+            pk: key.history_pk(),
+            sk: format!(
+                "{}#{}",
+                value
+                    .changed_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true),
+                Uuid::new_v4()
+            ),
+            client_id: value.client_id,
+            chain_id: value.chain_id,
+            mapping_sk: key.sk,
+            address: mapping_address(&value.r#type),
+            change: value.change,
+            previous_policy: value.previous_policy,
+            new_policy: value.new_policy,
+            previous_terms: value.previous_terms,
+            new_terms: value.new_terms,
+            subject: value.subject,
+            changed_at: value.changed_at,
+        }
+    }
+}
+
+impl TryFrom<AddressPolicyRegistryHistoryDynamoDbResource> for AddressPolicyRegistryHistory {
+    type Error = AddressPolicyRegistryRepositoryError;
+
+    fn try_from(value: AddressPolicyRegistryHistoryDynamoDbResource) -> Result<Self, Self::Error> {
+        let mapping_type = parse_mapping_type(&value.pk, &value.mapping_sk, value.address)?;
+
+        Ok(Self {
+            client_id: value.client_id,
+            chain_id: value.chain_id,
+            r#type: mapping_type,
+            change: value.change,
+            previous_policy: value.previous_policy,
+            new_policy: value.new_policy,
+            previous_terms: value.previous_terms,
+            new_terms: value.new_terms,
+            subject: value.subject,
+            changed_at: value.changed_at,
+        })
+    }
+}
+
 #[async_trait]
 pub trait AddressPolicyRegistryRepository
 where
     Self: Sync + Send,
 {
+    /// Returns the mapping if it is active now.
     async fn get_policy(
         &self,
         client_id: String,
         chain_id: u64,
-        address: Option<Address>,
+        mapping_type: AddressPolicyRegistryType,
+    ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
+
+    /// Returns the mapping unless it has expired, mappings that aren't active yet included.
+    async fn get_stored_policy(
+        &self,
+        client_id: String,
+        chain_id: u64,
+        mapping_type: AddressPolicyRegistryType,
     ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
 
     async fn put_policy(
         &self,
         policy_mapping: AddressPolicyRegistry,
+        subject: Option<String>,
     ) -> Result<(), AddressPolicyRegistryRepositoryError>;
 
+    async fn get_policies_by_keys(
+        &self,
+        keys: Vec<AddressPolicyRegistryPk>,
+    ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
+
+    /// Creates each mapping on its own, with the same conditions as `put_policy`. Returns the
+    /// outcome of each mapping, in the same order.
+    async fn put_policies(
+        &self,
+        policy_mappings: Vec<AddressPolicyRegistry>,
+        subject: Option<String>,
+    ) -> Vec<PutPolicyOutcome>;
+
     async fn delete_policy(
         &self,
         client_id: String,
         chain_id: u64,
-        address: Option<Address>,
+        mapping_type: AddressPolicyRegistryType,
+        expected_version: Option<u64>,
+        subject: Option<String>,
     ) -> Result<(), AddressPolicyRegistryRepositoryError>;
 
     async fn get_all_policies(
@@ -236,12 +627,49 @@ where
         client_id: String,
     ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
 
+    /// Returns every stored row of `client_id` as it is in the table, expired mappings the TTL
+    /// hasn't deleted yet included.
+    async fn export_policies(
+        &self,
+        client_id: String,
+    ) -> Result<Vec<AddressPolicyRegistryDynamoDbResource>, AddressPolicyRegistryRepositoryError>;
+
+    async fn get_policies_page(
+        &self,
+        client_id: String,
+        filters: AddressPolicyRegistryFilters,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<AddressPolicyRegistryPage, AddressPolicyRegistryRepositoryError>;
+
     async fn update_policy(
         &self,
         client_id: String,
         chain_id: u64,
-        address: Option<Address>,
+        mapping_type: AddressPolicyRegistryType,
         policy: String,
+        expected_version: Option<u64>,
+        subject: Option<String>,
+    ) -> Result<u64, AddressPolicyRegistryRepositoryError>;
+
+    async fn get_policy_history(
+        &self,
+        client_id: String,
+        chain_id: u64,
+        mapping_type: AddressPolicyRegistryType,
+        limit: Option<i64>,
+        cursor: Option<String>,
+    ) -> Result<AddressPolicyRegistryHistoryPage, AddressPolicyRegistryRepositoryError>;
+
+    /// Writes every change of `plan`. Plans of up to [`MAX_ATOMIC_PLAN_CHANGES`] changes are
+    /// applied in a single transaction, bigger plans in consecutive transactions of that size.
+    /// Every write is conditioned on the state the plan was computed from. When a transaction
+    /// fails after others were written the error is
+    /// [`AddressPolicyRegistryRepositoryError::PartiallyApplied`].
+    async fn apply_policy_plan(
+        &self,
+        plan: AddressPolicyRegistryPlan,
+        subject: Option<String>,
     ) -> Result<(), AddressPolicyRegistryRepositoryError>;
 }
 
@@ -254,32 +682,83 @@ mock! {
                 &self,
                 client_id: String,
                 chain_id: u64,
-                address: Option<Address>,
+                mapping_type: AddressPolicyRegistryType,
+            ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
+
+        async fn get_stored_policy(
+                &self,
+                client_id: String,
+                chain_id: u64,
+                mapping_type: AddressPolicyRegistryType,
             ) -> Result<Option<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
 
         async fn put_policy(
             &self,
             policy_mapping: AddressPolicyRegistry,
+            subject: Option<String>,
         ) -> Result<(), AddressPolicyRegistryRepositoryError>;
 
+        async fn get_policies_by_keys(
+            &self,
+            keys: Vec<AddressPolicyRegistryPk>,
+        ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
+
+        async fn put_policies(
+            &self,
+            policy_mappings: Vec<AddressPolicyRegistry>,
+            subject: Option<String>,
+        ) -> Vec<PutPolicyOutcome>;
+
         async fn get_all_policies(
             &self,
             client_id: String,
         ) -> Result<Vec<AddressPolicyRegistry>, AddressPolicyRegistryRepositoryError>;
 
+        async fn export_policies(
+            &self,
+            client_id: String,
+        ) -> Result<Vec<AddressPolicyRegistryDynamoDbResource>, AddressPolicyRegistryRepositoryError>;
+
+        async fn get_policies_page(
+            &self,
+            client_id: String,
+            filters: AddressPolicyRegistryFilters,
+            limit: Option<i64>,
+            cursor: Option<String>,
+        ) -> Result<AddressPolicyRegistryPage, AddressPolicyRegistryRepositoryError>;
+
         async fn delete_policy(
             &self,
             client_id: String,
             chain_id: u64,
-            address: Option<Address>,
+            mapping_type: AddressPolicyRegistryType,
+            expected_version: Option<u64>,
+            subject: Option<String>,
         ) -> Result<(), AddressPolicyRegistryRepositoryError>;
 
         async fn update_policy(
             &self,
             client_id: String,
             chain_id: u64,
-            address: Option<Address>,
+            mapping_type: AddressPolicyRegistryType,
             policy: String,
+            expected_version: Option<u64>,
+            subject: Option<String>,
+        ) -> Result<u64, AddressPolicyRegistryRepositoryError>;
+
+        async fn get_policy_history(
+            &self,
+            client_id: String,
+            chain_id: u64,
+            mapping_type: AddressPolicyRegistryType,
+            limit: Option<i64>,
+            cursor: Option<String>,
+        ) -> Result<AddressPolicyRegistryHistoryPage, AddressPolicyRegistryRepositoryError>;
+
+        async fn apply_policy_plan(
+            &self,
+            plan: AddressPolicyRegistryPlan,
+            subject: Option<String>,
         ) -> Result<(), AddressPolicyRegistryRepositoryError>;
     }
 }
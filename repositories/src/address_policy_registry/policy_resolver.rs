@@ -0,0 +1,975 @@
+use anyhow::anyhow;
+use chrono::Utc;
+use ethers::types::{Address, U256};
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::{
+    AddressPolicyRegistry, AddressPolicyRegistryType, ANY_CHAIN_ID,
+};
+use model::key::Key;
+
+use super::{
+    AddressPolicyRegistryPk, AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
+};
+use crate::address_groups::AddressGroupsRepository;
+use crate::keys::{KeysRepository, KeysRepositoryError};
+
+/// Outcome of evaluating a single candidate mapping during policy resolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateOutcome {
+    /// The mapping exists and it is the one that applies.
+    Selected,
+    /// There is no mapping stored for this key, or it has expired.
+    NotFound,
+    /// The mapping is scheduled to become effective later, so it doesn't apply yet.
+    NotActive,
+    /// A mapping with higher precedence was already selected, so this key was not looked up.
+    Shadowed,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyCandidate {
+    pub key: AddressPolicyRegistryPk,
+    /// Chain of the transaction, or [`ANY_CHAIN_ID`] for the client wide DEFAULT.
+    pub chain_id: u64,
+    pub r#type: AddressPolicyRegistryType,
+    pub outcome: CandidateOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyResolution {
+    /// The mapping that applies, if any.
+    pub policy: Option<AddressPolicyRegistry>,
+    /// Policy for the transaction value out of the value bands of the mapping that applies.
+    pub applied_policy: Option<String>,
+    /// Every candidate in precedence order with the reason it was selected or skipped.
+    pub candidates: Vec<PolicyCandidate>,
+}
+
+/// Candidate mappings for a transaction on `chain_id`, from highest to lowest precedence:
+/// CONTRACT_CALL (only when the transaction data has a function selector), ADDRESS_FROM, KEY_ID
+/// and CLIENT_USER (only when `key` is the key signing the transaction), ADDRESS_TO, GROUP for
+/// each of the `groups` containing `address_to` in the given order, the chain DEFAULT and finally
+/// the client wide DEFAULT.
+pub fn candidate_mappings(
+    chain_id: u64,
+    address_from: Address,
+    address_to: Address,
+    selector: Option<FunctionSelector>,
+    key: Option<&Key>,
+    groups: &[String],
+) -> Vec<(u64, AddressPolicyRegistryType)> {
+    let contract_call = selector.map(|selector| AddressPolicyRegistryType::ContractCall {
+        address: address_to,
+        selector,
+    });
+    let key_owner = key.into_iter().flat_map(|key| {
+        [
+            AddressPolicyRegistryType::KeyId { key_id: key.key_id },
+            AddressPolicyRegistryType::ClientUser {
+                client_user_id: key.client_user_id.clone(),
+            },
+        ]
+    });
+
+    contract_call
+        .into_iter()
+        .chain([AddressPolicyRegistryType::AddressFrom {
+            address: address_from,
+        }])
+        .chain(key_owner)
+        .chain([AddressPolicyRegistryType::AddressTo {
+            address: address_to,
+        }])
+        .chain(
+            groups
+                .iter()
+                .map(|name| AddressPolicyRegistryType::Group { name: name.clone() }),
+        )
+        .chain([AddressPolicyRegistryType::Default])
+        .map(|mapping_type| (chain_id, mapping_type))
+        .chain([(ANY_CHAIN_ID, AddressPolicyRegistryType::Default)])
+        .collect()
+}
+
+/// Resolves the policy mapping that applies to a transaction sent from `address_from` to
+/// `address_to`, calling the function identified by `selector` if there is one and moving
+/// `value` wei. Candidates are looked up in precedence order and the first one found wins, so
+/// mappings of the chain take priority over the client wide DEFAULT. Mappings that aren't
+/// effective yet are reported but skipped.
+///
+/// KEY_ID and CLIENT_USER mappings are only candidates when `address_from` is a key of
+/// `client_id`, and GROUP mappings only for the groups of the client containing `address_to`.
+#[allow(clippy::too_many_arguments)]
+pub async fn resolve_policy(
+    repository: &impl AddressPolicyRegistryRepository,
+    keys_repository: &impl KeysRepository,
+    address_groups_repository: &impl AddressGroupsRepository,
+    client_id: String,
+    chain_id: u64,
+    address_from: Address,
+    address_to: Address,
+    selector: Option<FunctionSelector>,
+    value: U256,
+) -> Result<PolicyResolution, AddressPolicyRegistryRepositoryError> {
+    let sender_key = signing_key(keys_repository, &client_id, address_from).await?;
+    let groups = destination_groups(address_groups_repository, &client_id, address_to).await?;
+    let now = Utc::now();
+    let mut policy = None;
+    let mut candidates = Vec::new();
+
+    for (chain_id, mapping_type) in candidate_mappings(
+        chain_id,
+        address_from,
+        address_to,
+        selector,
+        sender_key.as_ref(),
+        &groups,
+    ) {
+        let key = AddressPolicyRegistryPk::from_type(client_id.clone(), chain_id, &mapping_type);
+
+        let outcome = if policy.is_some() {
+            CandidateOutcome::Shadowed
+        } else {
+            match repository
+                .get_stored_policy(client_id.clone(), chain_id, mapping_type.clone())
+                .await?
+            {
+                Some(mapping) if mapping.is_active_at(now) => {
+                    policy = Some(mapping);
+                    CandidateOutcome::Selected
+                }
+                Some(_) => CandidateOutcome::NotActive,
+                None => CandidateOutcome::NotFound,
+            }
+        };
+
+        candidates.push(PolicyCandidate {
+            key,
+            chain_id,
+            r#type: mapping_type,
+            outcome,
+        });
+    }
+
+    let applied_policy = policy
+        .as_ref()
+        .map(|mapping| mapping.policy_for_value(value).to_owned());
+
+    Ok(PolicyResolution {
+        policy,
+        applied_policy,
+        candidates,
+    })
+}
+
+/// Key of `client_id` with address `address_from`. Addresses that aren't keys of the client
+/// simply have no key mappings.
+async fn signing_key(
+    keys_repository: &impl KeysRepository,
+    client_id: &str,
+    address_from: Address,
+) -> Result<Option<Key>, AddressPolicyRegistryRepositoryError> {
+    match keys_repository.get_key_by_address(address_from).await {
+        Ok(key) if key.client_id == client_id => Ok(Some(key)),
+        Ok(_) | Err(KeysRepositoryError::KeyNotFound(_)) => Ok(None),
+        Err(KeysRepositoryError::Unknown(e)) => Err(AddressPolicyRegistryRepositoryError::Unknown(
+            e.context("unable to get the key signing the transaction"),
+        )),
+    }
+}
+
+/// Names of the address groups of `client_id` that contain `address_to`, sorted so the
+/// precedence between GROUP mappings doesn't depend on the storage order.
+async fn destination_groups(
+    address_groups_repository: &impl AddressGroupsRepository,
+    client_id: &str,
+    address_to: Address,
+) -> Result<Vec<String>, AddressPolicyRegistryRepositoryError> {
+    address_groups_repository
+        .get_groups_containing(client_id.to_owned(), address_to)
+        .await
+        .map_err(|e| {
+            AddressPolicyRegistryRepositoryError::Unknown(
+                anyhow!(e).context("unable to get the address groups of the destination"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::constants::{
+        ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+        CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use common::test_tools::mocks::dynamodb_client::MockDbClient;
+    use ethers::types::{Address, U256};
+    use model::address_group::AddressGroup;
+    use model::address_policy_registry::function_selector::FunctionSelector;
+    use model::address_policy_registry::value_band::PolicyValueBand;
+    use model::address_policy_registry::{AddressPolicyRegistryType, ANY_CHAIN_ID};
+    use model::key::Key;
+    use rstest::{fixture, rstest};
+    use rusoto_dynamodb::{AttributeValue, GetItemOutput, QueryOutput};
+    use uuid::Uuid;
+
+    use crate::address_groups::{
+        address_groups_repository_impl::AddressGroupsRepositoryImpl,
+        AddressGroupMembershipDynamoDbResource,
+    };
+    use crate::address_policy_registry::{
+        address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl,
+        policy_resolver::{resolve_policy, CandidateOutcome},
+        AddressPolicyRegistryDynamoDbResource,
+    };
+    use crate::keys::keys_repository_impl::KeysRepositoryImpl;
+
+    const KEY_ID: &str = "b3b1a1e6-6a5e-4c43-9f5c-5a1d2a2c0d8e";
+    const CLIENT_USER_ID: &str = "some_end_user";
+
+    struct TestFixture {
+        pub dynamodb_client: MockDbClient,
+        pub table_name: String,
+        pub history_table_name: String,
+        pub keys_dynamodb_client: MockDbClient,
+        pub groups_dynamodb_client: MockDbClient,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            dynamodb_client: MockDbClient::new(),
+            table_name: "address_policy_registry".to_owned(),
+            history_table_name: "address_policy_registry_history".to_owned(),
+            keys_dynamodb_client: MockDbClient::new(),
+            groups_dynamodb_client: MockDbClient::new(),
+        }
+    }
+
+    fn key(client_id: &str) -> Key {
+        Key {
+            key_id: Uuid::parse_str(KEY_ID).unwrap(),
+            address: ADDRESS_FOR_MOCK_REQUESTS.to_owned(),
+            client_id: client_id.to_owned(),
+            client_user_id: CLIENT_USER_ID.to_owned(),
+            created_at: Utc::now(),
+            order_type: "KEY_CREATION_ORDER".to_owned(),
+            order_version: "1".to_owned(),
+            owning_user_id: Uuid::new_v4(),
+            public_key: "some_public_key".to_owned(),
+        }
+    }
+
+    /// Keys table where the sender of the transaction is `key`, or isn't a key at all.
+    fn keys_repository(
+        mut dynamodb_client: MockDbClient,
+        key: Option<Key>,
+    ) -> KeysRepositoryImpl<MockDbClient> {
+        dynamodb_client.expect_query().once().returning(move |_| {
+            Ok(QueryOutput {
+                items: key
+                    .clone()
+                    .map(|key| vec![serde_dynamo::to_item(key).unwrap()]),
+                ..QueryOutput::default()
+            })
+        });
+
+        KeysRepositoryImpl::new("keys".to_owned(), dynamodb_client)
+    }
+
+    /// Address groups table holding the memberships of `groups`.
+    fn address_groups_repository(
+        mut dynamodb_client: MockDbClient,
+        groups: Vec<AddressGroup>,
+    ) -> AddressGroupsRepositoryImpl<MockDbClient> {
+        dynamodb_client
+            .expect_query()
+            .once()
+            .returning(move |input| {
+                let pk = input.expression_attribute_values.unwrap()[":pk"].s.clone();
+                Ok(QueryOutput {
+                    items: Some(
+                        groups
+                            .iter()
+                            .flat_map(|group| {
+                                group.addresses.iter().map(|address| {
+                                    AddressGroupMembershipDynamoDbResource::new(
+                                        &group.client_id,
+                                        &group.name,
+                                        address,
+                                    )
+                                })
+                            })
+                            .filter(|membership| Some(&membership.pk) == pk.as_ref())
+                            .map(|membership| serde_dynamo::to_item(membership).unwrap())
+                            .collect(),
+                    ),
+                    ..QueryOutput::default()
+                })
+            });
+
+        AddressGroupsRepositoryImpl::new("address_groups".to_owned(), dynamodb_client)
+    }
+
+    /// Mocks the table so only the chain mappings whose sort key is in `stored_sort_keys` exist.
+    fn mock_stored_mappings(dynamodb_client: &mut MockDbClient, stored_sort_keys: Vec<String>) {
+        dynamodb_client.expect_get_item().returning(move |input| {
+            let sk = match input.key.get("sk") {
+                Some(AttributeValue { s: Some(sk), .. }) => sk.clone(),
+                _ => panic!("sort key not found"),
+            };
+            let chain_pk = format!(
+                "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+            );
+            let is_chain_mapping = matches!(
+                input.key.get("pk"),
+                Some(AttributeValue { s: Some(pk), .. }) if *pk == chain_pk
+            );
+
+            if !is_chain_mapping || !stored_sort_keys.contains(&sk) {
+                return Ok(GetItemOutput::default());
+            }
+
+            let address = sk.split('#').nth(1).filter(|a| *a != "DEFAULT");
+            let item = AddressPolicyRegistryDynamoDbResource {
+                pk: format!(
+                    "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+                ),
+                sk: sk.clone(),
+                client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                policy: format!("policy_for_{sk}"),
+                created_at: Utc::now(),
+                address: address.map(str::to_owned),
+                version: 1,
+                effective_from: None,
+                expires_at: None,
+                expires_at_ttl: None,
+                value_bands: vec![],
+            };
+
+            Ok(GetItemOutput {
+                item: Some(serde_dynamo::to_item(item).unwrap()),
+                ..GetItemOutput::default()
+            })
+        });
+    }
+
+    #[rstest]
+    #[case::address_from_wins(
+        vec![
+            format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"),
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+            "ADDRESS#DEFAULT".to_owned(),
+        ],
+        [
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::address_to_wins(
+        vec![
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+            "ADDRESS#DEFAULT".to_owned(),
+        ],
+        [
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::default_wins(
+        vec!["ADDRESS#DEFAULT".to_owned()],
+        [
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::nothing_found(
+        vec![],
+        [
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+        ]
+    )]
+    #[tokio::test]
+    async fn resolve_policy_precedence(
+        mut fixture: TestFixture,
+        #[case] stored_sort_keys: Vec<String>,
+        #[case] expected_outcomes: [CandidateOutcome; 4],
+    ) {
+        mock_stored_mappings(&mut fixture.dynamodb_client, stored_sort_keys);
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<CandidateOutcome> = resolution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(expected_outcomes.to_vec(), outcomes);
+
+        let selected = resolution
+            .candidates
+            .iter()
+            .find(|candidate| candidate.outcome == CandidateOutcome::Selected);
+        match (selected, resolution.policy) {
+            (Some(candidate), Some(policy)) => assert_eq!(candidate.r#type, policy.r#type),
+            (None, None) => {}
+            _ => panic!("selected candidate and resolved policy do not match"),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_policy_address_from_type(mut fixture: TestFixture) {
+        mock_stored_mappings(
+            &mut fixture.dynamodb_client,
+            vec![format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}")],
+        );
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+        let address_from = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            address_from,
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            AddressPolicyRegistryType::AddressFrom {
+                address: address_from
+            },
+            resolution.policy.unwrap().r#type
+        );
+    }
+
+    #[rstest]
+    #[case::contract_call_wins(
+        vec![
+            format!("CONTRACT_CALL#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}#0xa9059cbb"),
+            format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"),
+        ],
+        [
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::other_selector(
+        vec![
+            format!("CONTRACT_CALL#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}#0x095ea7b3"),
+            format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"),
+        ],
+        [
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[tokio::test]
+    async fn resolve_policy_contract_call_precedence(
+        mut fixture: TestFixture,
+        #[case] stored_sort_keys: Vec<String>,
+        #[case] expected_outcomes: [CandidateOutcome; 5],
+    ) {
+        mock_stored_mappings(&mut fixture.dynamodb_client, stored_sort_keys);
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            Some(FunctionSelector([0xa9, 0x05, 0x9c, 0xbb])),
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<CandidateOutcome> = resolution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(expected_outcomes.to_vec(), outcomes);
+        assert!(matches!(
+            resolution.candidates[0].r#type,
+            AddressPolicyRegistryType::ContractCall { .. }
+        ));
+    }
+
+    #[rstest]
+    #[case::key_id_wins(
+        vec![
+            format!("KEY_ID#{KEY_ID}"),
+            format!("CLIENT_USER#{CLIENT_USER_ID}"),
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+        ],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ],
+        CLIENT_ID_FOR_MOCK_REQUESTS
+    )]
+    #[case::client_user_wins_over_address_to(
+        vec![
+            format!("CLIENT_USER#{CLIENT_USER_ID}"),
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+        ],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ],
+        CLIENT_ID_FOR_MOCK_REQUESTS
+    )]
+    #[case::key_of_other_client(
+        vec![
+            format!("KEY_ID#{KEY_ID}"),
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+        ],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ],
+        "other_client"
+    )]
+    #[tokio::test]
+    async fn resolve_policy_key_owner_precedence(
+        mut fixture: TestFixture,
+        #[case] stored_sort_keys: Vec<String>,
+        #[case] expected_outcomes: Vec<CandidateOutcome>,
+        #[case] key_client_id: &str,
+    ) {
+        mock_stored_mappings(&mut fixture.dynamodb_client, stored_sort_keys);
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, Some(key(key_client_id))),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<CandidateOutcome> = resolution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(expected_outcomes, outcomes);
+    }
+
+    fn group(name: &str, address: &str) -> AddressGroup {
+        AddressGroup {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            name: name.to_owned(),
+            addresses: vec![Address::from_str(address).unwrap()],
+        }
+    }
+
+    #[rstest]
+    #[case::address_to_wins_over_group(
+        vec![
+            format!("ADDRESS#{ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS}"),
+            "GROUP#exchanges".to_owned(),
+        ],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::first_group_by_name_wins(
+        vec![
+            "GROUP#custodians".to_owned(),
+            "GROUP#exchanges".to_owned(),
+            "ADDRESS#DEFAULT".to_owned(),
+        ],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[case::group_wins_over_default(
+        vec!["GROUP#exchanges".to_owned(), "ADDRESS#DEFAULT".to_owned()],
+        vec![
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::NotFound,
+            CandidateOutcome::Selected,
+            CandidateOutcome::Shadowed,
+            CandidateOutcome::Shadowed,
+        ]
+    )]
+    #[tokio::test]
+    async fn resolve_policy_group_precedence(
+        mut fixture: TestFixture,
+        #[case] stored_sort_keys: Vec<String>,
+        #[case] expected_outcomes: Vec<CandidateOutcome>,
+    ) {
+        mock_stored_mappings(&mut fixture.dynamodb_client, stored_sort_keys);
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+        // Only the groups containing the destination are candidates.
+        let groups = vec![
+            group("exchanges", ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS),
+            group("custodians", ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS),
+            group("treasury", ADDRESS_FOR_MOCK_REQUESTS),
+        ];
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, groups),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<CandidateOutcome> = resolution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(expected_outcomes, outcomes);
+        assert_eq!(
+            AddressPolicyRegistryType::Group {
+                name: "custodians".to_owned()
+            },
+            resolution.candidates[2].r#type
+        );
+    }
+
+    #[rstest]
+    #[case::first_band(U256::exp10(17), "basic")]
+    #[case::band_upper_bound_is_exclusive(U256::exp10(18), "dual")]
+    #[case::second_band(U256::exp10(19), "dual")]
+    #[case::above_every_band(U256::exp10(21), "board")]
+    #[tokio::test]
+    async fn resolve_policy_value_bands(
+        mut fixture: TestFixture,
+        #[case] value: U256,
+        #[case] expected_policy: &str,
+    ) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .returning(|input| match input.key.get("sk") {
+                Some(AttributeValue { s: Some(sk), .. }) if sk == "ADDRESS#DEFAULT" => {
+                    let item = AddressPolicyRegistryDynamoDbResource {
+                        pk: format!(
+                            "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+                        ),
+                        sk: sk.clone(),
+                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                        chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                        policy: "board".to_owned(),
+                        created_at: Utc::now(),
+                        address: None,
+                        version: 1,
+                        effective_from: None,
+                        expires_at: None,
+                        expires_at_ttl: None,
+                        value_bands: vec![
+                            PolicyValueBand {
+                                below: U256::exp10(18),
+                                policy: "basic".to_owned(),
+                            },
+                            PolicyValueBand {
+                                below: U256::exp10(20),
+                                policy: "dual".to_owned(),
+                            },
+                        ],
+                    };
+
+                    Ok(GetItemOutput {
+                        item: Some(serde_dynamo::to_item(item).unwrap()),
+                        ..GetItemOutput::default()
+                    })
+                }
+                _ => Ok(GetItemOutput::default()),
+            });
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            value,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(Some(expected_policy.to_owned()), resolution.applied_policy);
+        assert_eq!("board", resolution.policy.unwrap().policy);
+    }
+
+    #[rstest]
+    #[case::chain_default_wins(
+        true,
+        CHAIN_ID_FOR_MOCK_REQUESTS,
+        [CandidateOutcome::Selected, CandidateOutcome::Shadowed]
+    )]
+    #[case::client_wide_default(
+        false,
+        ANY_CHAIN_ID,
+        [CandidateOutcome::NotFound, CandidateOutcome::Selected]
+    )]
+    #[tokio::test]
+    async fn resolve_policy_client_wide_default(
+        mut fixture: TestFixture,
+        #[case] chain_default_stored: bool,
+        #[case] expected_chain_id: u64,
+        #[case] expected_default_outcomes: [CandidateOutcome; 2],
+    ) {
+        let chain_pk =
+            format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}");
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .returning(move |input| {
+                let pk = match (input.key.get("pk"), input.key.get("sk")) {
+                    (
+                        Some(AttributeValue { s: Some(pk), .. }),
+                        Some(AttributeValue { s: Some(sk), .. }),
+                    ) if sk == "ADDRESS#DEFAULT" => pk.clone(),
+                    _ => return Ok(GetItemOutput::default()),
+                };
+                let chain_id = if pk.ends_with("#CHAIN_ID#*") {
+                    ANY_CHAIN_ID
+                } else if pk == chain_pk && chain_default_stored {
+                    CHAIN_ID_FOR_MOCK_REQUESTS
+                } else {
+                    return Ok(GetItemOutput::default());
+                };
+
+                let item = AddressPolicyRegistryDynamoDbResource {
+                    pk,
+                    sk: "ADDRESS#DEFAULT".to_owned(),
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id,
+                    policy: format!("policy_for_chain_{chain_id}"),
+                    created_at: Utc::now(),
+                    address: None,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
+                };
+
+                Ok(GetItemOutput {
+                    item: Some(serde_dynamo::to_item(item).unwrap()),
+                    ..GetItemOutput::default()
+                })
+            });
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let client_wide_pk = format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#*");
+        let default_candidates = &resolution.candidates[2..];
+        assert_eq!(
+            expected_default_outcomes.to_vec(),
+            default_candidates
+                .iter()
+                .map(|candidate| candidate.outcome)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(ANY_CHAIN_ID, default_candidates[1].chain_id);
+        assert_eq!(client_wide_pk, default_candidates[1].key.pk);
+        assert_eq!(expected_chain_id, resolution.policy.unwrap().chain_id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_policy_skips_scheduled_mappings(mut fixture: TestFixture) {
+        fixture
+            .dynamodb_client
+            .expect_get_item()
+            .returning(|input| {
+                let (sk, effective_from) = match input.key.get("sk") {
+                    Some(AttributeValue { s: Some(sk), .. }) if sk.starts_with("ADDRESS#0x") => {
+                        (sk.clone(), Some(Utc::now() + Duration::hours(1)))
+                    }
+                    Some(AttributeValue { s: Some(sk), .. }) if sk == "ADDRESS#DEFAULT" => {
+                        (sk.clone(), None)
+                    }
+                    _ => return Ok(GetItemOutput::default()),
+                };
+                let item = AddressPolicyRegistryDynamoDbResource {
+                    pk: format!(
+                        "CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}"
+                    ),
+                    address: sk
+                        .split('#')
+                        .nth(1)
+                        .filter(|address| *address != "DEFAULT")
+                        .map(str::to_owned),
+                    sk,
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "some_policy".to_owned(),
+                    created_at: Utc::now(),
+                    version: 1,
+                    effective_from,
+                    expires_at: None,
+                    expires_at_ttl: None,
+                    value_bands: vec![],
+                };
+
+                Ok(GetItemOutput {
+                    item: Some(serde_dynamo::to_item(item).unwrap()),
+                    ..GetItemOutput::default()
+                })
+            });
+        let repo = AddressPolicyRegistryRepositoryImpl::new(
+            fixture.table_name,
+            fixture.history_table_name,
+            fixture.dynamodb_client,
+        );
+
+        let resolution = resolve_policy(
+            &repo,
+            &keys_repository(fixture.keys_dynamodb_client, None),
+            &address_groups_repository(fixture.groups_dynamodb_client, vec![]),
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+            None,
+            U256::zero(),
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<CandidateOutcome> = resolution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(
+            vec![
+                CandidateOutcome::NotFound,
+                CandidateOutcome::NotActive,
+                CandidateOutcome::Selected,
+                CandidateOutcome::Shadowed,
+            ],
+            outcomes
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::Default,
+            resolution.policy.unwrap().r#type
+        );
+    }
+}
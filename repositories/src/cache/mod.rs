@@ -50,3 +50,16 @@ mock! {
         async fn set_item(&self, value: GenericJsonCache) -> Result<(), CacheRepositoryError>;
     }
 }
+
+#[cfg(feature = "test_mocks")]
+impl MockCacheRepositoryTest {
+    /// Cache that never has the requested item and accepts every write.
+    pub fn empty() -> Self {
+        let mut cache_repository = Self::new();
+        cache_repository
+            .expect_get_item()
+            .returning(|key, _| Err(CacheRepositoryError::KeyNotFound(key.to_owned())));
+        cache_repository.expect_set_item().returning(|_| Ok(()));
+        cache_repository
+    }
+}
@@ -1,3 +1,4 @@
+pub mod address_groups;
 pub mod address_policy_registry;
 pub mod cache;
 pub mod deserialize;
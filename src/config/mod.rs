@@ -1,7 +1,7 @@
 mod supported_chains;
 use serde::{self, Deserialize};
 
-pub use supported_chains::SupportedChain;
+pub use supported_chains::{chain_feature_flag, supported_chains_feature_flags, SupportedChain};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct GlobalConfig {
@@ -11,6 +11,11 @@ pub struct GlobalConfig {
     /// Secret name holding LaunchDarkly's SDK key.
     pub launchdarkly_sdk_key_secret_name: Option<String>,
 
+    /// Only used for development. JSON file with the feature flags to use instead of
+    /// LaunchDarkly's, see `LocalFeatureFlags`.
+    #[serde(default)]
+    pub feature_flags_file: Option<String>,
+
     /// Only used for development. LocalStack endpoint
     #[serde(default = "default_localstack_test_mode_endpoint")]
     pub localstack_test_mode_endpoint: Option<String>,
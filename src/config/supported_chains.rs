@@ -1,4 +1,9 @@
+use async_trait::async_trait;
+use common::feature_flags::{FeatureFlags, LocalFeatureFlags};
 use ethers::types::Chain;
+use serde_json::json;
+
+use crate::feature_flags::is_feature_enabled;
 
 // 1        -> Eth Mainnet
 // 11155111 -> Eth Sepolia Testnet
@@ -7,8 +12,44 @@ use ethers::types::Chain;
 // 1337     -> Ganache
 const SUPPORTED_CHAIN_IDS: [u64; 5] = [1, 11155111, 80002, 137, 1337];
 
-pub trait SupportedChain {
+/// Value of a chain flag that can't be evaluated. Chains fail closed, so every supported chain
+/// needs its flag turned on for the clients using it.
+const CHAIN_FLAG_DEFAULT: bool = false;
+
+/// Boolean flag turning `chain_id` on or off per client, e.g. `chain-137`.
+pub fn chain_feature_flag(chain_id: u64) -> String {
+    format!("chain-{chain_id}")
+}
+
+/// Local flags turning every supported chain on for every client, for tests and development.
+pub fn supported_chains_feature_flags() -> LocalFeatureFlags {
+    SUPPORTED_CHAIN_IDS
+        .iter()
+        .fold(LocalFeatureFlags::default(), |feature_flags, chain_id| {
+            feature_flags.with_flag(&chain_feature_flag(*chain_id), json!(true))
+        })
+}
+
+#[async_trait]
+pub trait SupportedChain: Into<u64> + Copy + Sync {
     fn is_supported(&self) -> bool;
+
+    /// Whether the chain is supported and its feature flag is on for `client_id`. Chains whose flag
+    /// is missing or can't be evaluated are disabled.
+    async fn is_enabled_for_client(
+        &self,
+        feature_flags: &dyn FeatureFlags,
+        client_id: &str,
+    ) -> bool {
+        self.is_supported()
+            && is_feature_enabled(
+                feature_flags,
+                &chain_feature_flag((*self).into()),
+                client_id,
+                CHAIN_FLAG_DEFAULT,
+            )
+            .await
+    }
 }
 
 impl SupportedChain for Chain {
@@ -0,0 +1,251 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::http::errors::{unknown_error_response, validation_error_response};
+use crate::result::error::LambdaError;
+use common::serializers::h160::h160_to_lowercase_hex_string;
+use ethers::types::Address;
+use http::Response;
+use model::address_group::AddressGroup;
+use model::address_policy_registry::AddressPolicyRegistryType;
+use repositories::address_groups::AddressGroupsRepository;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_GROUP_ADDRESSES: usize = 1000;
+pub const MAX_GROUP_NAME_LENGTH: usize = 64;
+
+/// Group names are part of the keys of GROUP mappings and are sent in query strings, so they are
+/// restricted to lowercase letters, digits, `_` and `-`.
+pub fn validate_group_name(name: &str) -> Result<(), Response<String>> {
+    let valid_characters = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+
+    if name.is_empty() || name.len() > MAX_GROUP_NAME_LENGTH || !valid_characters {
+        return Err(validation_error_response(
+            format!(
+                "name must have between 1 and {MAX_GROUP_NAME_LENGTH} lowercase letters, digits, _ or -"
+            ),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Groups have at least one address, at most [`MAX_GROUP_ADDRESSES`] and no repeated ones.
+pub fn validate_group_addresses(addresses: &[Address]) -> Result<(), Response<String>> {
+    if addresses.is_empty() {
+        return Err(validation_error_response(
+            "addresses can't be empty".to_owned(),
+            None,
+        ));
+    }
+    if addresses.len() > MAX_GROUP_ADDRESSES {
+        return Err(validation_error_response(
+            format!("addresses can't contain more than {MAX_GROUP_ADDRESSES} items"),
+            None,
+        ));
+    }
+
+    let mut seen = HashSet::with_capacity(addresses.len());
+    if let Some(index) = addresses.iter().position(|address| !seen.insert(address)) {
+        return Err(validation_error_response(
+            format!("addresses[{index}]: address is repeated in the request"),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Names of the groups GROUP mappings among `mapping_types` point to that `client_id` doesn't
+/// have. Each group is looked up once.
+pub async fn missing_mapping_groups<'a>(
+    address_groups_repository: &impl AddressGroupsRepository,
+    client_id: &str,
+    mapping_types: impl IntoIterator<Item = &'a AddressPolicyRegistryType>,
+) -> Result<BTreeSet<String>, Response<String>> {
+    let names = mapping_types
+        .into_iter()
+        .filter_map(|mapping_type| match mapping_type {
+            AddressPolicyRegistryType::Group { name } => Some(name),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut missing = BTreeSet::new();
+    for name in names {
+        let group = address_groups_repository
+            .get_group(client_id.to_owned(), name.clone())
+            .await
+            .map_err(|e| {
+                unknown_error_response(LambdaError::Unknown(
+                    anyhow::anyhow!(e).context(format!("fetching address group {name}")),
+                ))
+            })?;
+        if group.is_none() {
+            missing.insert(name.clone());
+        }
+    }
+
+    Ok(missing)
+}
+
+/// GROUP mappings can only point to groups the client has.
+pub async fn validate_mapping_groups_exist<'a>(
+    address_groups_repository: &impl AddressGroupsRepository,
+    client_id: &str,
+    mapping_types: impl IntoIterator<Item = &'a AddressPolicyRegistryType>,
+) -> Result<(), Response<String>> {
+    let missing =
+        missing_mapping_groups(address_groups_repository, client_id, mapping_types).await?;
+    match missing.into_iter().next() {
+        Some(name) => Err(validation_error_response(
+            format!("address group {name} not found"),
+            None,
+        )),
+        None => Ok(()),
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct AddressGroupResponse {
+    pub name: String,
+    pub addresses: Vec<String>,
+}
+
+impl From<AddressGroup> for AddressGroupResponse {
+    fn from(value: AddressGroup) -> Self {
+        Self {
+            name: value.name,
+            addresses: value
+                .addresses
+                .into_iter()
+                .map(h160_to_lowercase_hex_string)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        missing_mapping_groups, validate_group_addresses, validate_group_name,
+        validate_mapping_groups_exist, MAX_GROUP_ADDRESSES,
+    };
+    use common::test_tools::http::constants::CLIENT_ID_FOR_MOCK_REQUESTS;
+    use ethers::types::Address;
+    use http::StatusCode;
+    use model::address_group::AddressGroup;
+    use model::address_policy_registry::AddressPolicyRegistryType;
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::letters("exchanges")]
+    #[case::digits_and_separators("tier_1-exchanges")]
+    fn validate_group_name_ok(#[case] name: &str) {
+        assert!(validate_group_name(name).is_ok());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::uppercase("Exchanges")]
+    #[case::separator("exchanges#1")]
+    #[case::too_long(&"a".repeat(65))]
+    fn validate_group_name_invalid(#[case] name: &str) {
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            validate_group_name(name).unwrap_err().status()
+        );
+    }
+
+    #[test]
+    fn validate_group_addresses_ok() {
+        assert!(validate_group_addresses(&[
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2)
+        ])
+        .is_ok());
+    }
+
+    #[rstest]
+    #[case::empty(vec![], "addresses can't be empty")]
+    #[case::repeated(
+        vec![Address::from_low_u64_be(1), Address::from_low_u64_be(1)],
+        "addresses[1]: address is repeated in the request"
+    )]
+    #[case::too_many(
+        (0..=MAX_GROUP_ADDRESSES as u64).map(Address::from_low_u64_be).collect(),
+        "addresses can't contain more than 1000 items"
+    )]
+    fn validate_group_addresses_invalid(
+        #[case] addresses: Vec<Address>,
+        #[case] expected_message: &str,
+    ) {
+        let error = validate_group_addresses(&addresses).unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+        assert!(error.body().contains(expected_message));
+    }
+
+    fn group_mapping(name: &str) -> AddressPolicyRegistryType {
+        AddressPolicyRegistryType::Group {
+            name: name.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_mapping_groups_looks_up_each_group_once() {
+        let mut address_groups_repository = MockAddressGroupsRepository::new();
+        address_groups_repository
+            .expect_get_group()
+            .times(2)
+            .returning(|client_id, name| {
+                Ok((name == "exchanges").then(|| AddressGroup {
+                    client_id,
+                    name,
+                    addresses: vec![Address::from_low_u64_be(1)],
+                }))
+            });
+
+        let mapping_types = vec![
+            group_mapping("exchanges"),
+            AddressPolicyRegistryType::Default,
+            group_mapping("custodians"),
+            group_mapping("exchanges"),
+        ];
+        let missing = missing_mapping_groups(
+            &address_groups_repository,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+            &mapping_types,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            vec!["custodians".to_owned()],
+            missing.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_mapping_groups_exist_missing_group() {
+        let mut address_groups_repository = MockAddressGroupsRepository::new();
+        address_groups_repository
+            .expect_get_group()
+            .once()
+            .returning(|_, _| Ok(None));
+
+        let error = validate_mapping_groups_exist(
+            &address_groups_repository,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+            &[group_mapping("exchanges")],
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+        assert!(error.body().contains("address group exchanges not found"));
+    }
+}
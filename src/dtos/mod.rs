@@ -1,2 +1,10 @@
+pub mod address_group;
+pub mod policy_mapping_chain;
+pub mod policy_mapping_plan;
+pub mod policy_mapping_snapshot;
+pub mod policy_mapping_type;
+pub mod policy_mapping_window;
+pub mod policy_resolution;
+pub mod policy_value_bands;
 pub mod requests;
 pub mod responses;
@@ -0,0 +1,200 @@
+use crate::http::errors::validation_error_response;
+use crate::lambda_structure::http_lambda_main::{RequestExtractor, RequestExtractorError};
+use lambda_http::{Request, Response};
+use model::address_policy_registry::{AddressPolicyRegistryType, ANY_CHAIN_ID};
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+/// Chain id clients use for client wide mappings, in request bodies and paths.
+pub const ANY_CHAIN_WILDCARD: &str = "*";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MappingChainId {
+    Id(u64),
+    Wildcard(String),
+}
+
+/// Parses the chain id of a policy mapping, a number or `*` for client wide mappings.
+pub fn parse_mapping_chain_id(value: &str) -> Option<u64> {
+    if value == ANY_CHAIN_WILDCARD {
+        return Some(ANY_CHAIN_ID);
+    }
+
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|chain_id| *chain_id != ANY_CHAIN_ID)
+}
+
+/// Extracts a chain id path param that can also be `*`.
+pub fn extract_mapping_chain_id(
+    request: &Request,
+    param_name: &str,
+) -> Result<u64, RequestExtractorError> {
+    let chain_id: String = request.extract_path_param(param_name)?;
+    parse_mapping_chain_id(&chain_id)
+        .ok_or_else(|| RequestExtractorError::PathParamWithWrongTypeError(param_name.to_owned()))
+}
+
+/// Deserializes a chain id that is either a number or `*`. The chain id client wide mappings
+/// are stored with is not accepted as a number, so `*` is the only way to refer to them.
+pub fn deserialize_mapping_chain_id<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match MappingChainId::deserialize(deserializer)? {
+        MappingChainId::Id(chain_id) if chain_id != ANY_CHAIN_ID => Ok(chain_id),
+        MappingChainId::Wildcard(value) if value == ANY_CHAIN_WILDCARD => Ok(ANY_CHAIN_ID),
+        _ => Err(de::Error::custom(format!(
+            "chain_id must be a number or {ANY_CHAIN_WILDCARD}"
+        ))),
+    }
+}
+
+/// Same as [`deserialize_mapping_chain_id`] for optional lists of chain ids.
+pub fn deserialize_optional_mapping_chain_ids<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<u64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct ChainId(#[serde(deserialize_with = "deserialize_mapping_chain_id")] u64);
+
+    let chain_ids = Option::<Vec<ChainId>>::deserialize(deserializer)?;
+    Ok(chain_ids.map(|chain_ids| chain_ids.into_iter().map(|ChainId(id)| id).collect()))
+}
+
+/// Serializes the chain id of a mapping, client wide mappings as `*`.
+pub fn serialize_mapping_chain_id<S>(chain_id: &u64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if *chain_id == ANY_CHAIN_ID {
+        serializer.serialize_str(ANY_CHAIN_WILDCARD)
+    } else {
+        serializer.serialize_u64(*chain_id)
+    }
+}
+
+/// Same as [`serialize_mapping_chain_id`] for optional chain ids.
+pub fn serialize_optional_mapping_chain_id<S>(
+    chain_id: &Option<u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match chain_id {
+        Some(chain_id) => serialize_mapping_chain_id(chain_id, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Only DEFAULT mappings can be client wide.
+pub fn validate_mapping_chain(
+    chain_id: u64,
+    mapping_type: &AddressPolicyRegistryType,
+) -> Result<(), Response<String>> {
+    try_validate_mapping_chain(chain_id, mapping_type)
+        .map_err(|message| validation_error_response(message, None))
+}
+
+/// Same as [`validate_mapping_chain`] but returns the bare validation message, for callers that
+/// report errors per item instead of failing the whole request.
+pub fn try_validate_mapping_chain(
+    chain_id: u64,
+    mapping_type: &AddressPolicyRegistryType,
+) -> Result<(), String> {
+    if chain_id == ANY_CHAIN_ID && *mapping_type != AddressPolicyRegistryType::Default {
+        return Err(format!(
+            "only DEFAULT mappings can have chain_id {ANY_CHAIN_WILDCARD}"
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        deserialize_mapping_chain_id, deserialize_optional_mapping_chain_ids,
+        parse_mapping_chain_id, serialize_mapping_chain_id, try_validate_mapping_chain,
+    };
+    use ethers::types::Address;
+    use model::address_policy_registry::{AddressPolicyRegistryType, ANY_CHAIN_ID};
+    use rstest::rstest;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize)]
+    struct Mapping {
+        #[serde(
+            deserialize_with = "deserialize_mapping_chain_id",
+            serialize_with = "serialize_mapping_chain_id"
+        )]
+        chain_id: u64,
+    }
+
+    #[rstest]
+    #[case::chain(r#"{"chain_id":137}"#, 137)]
+    #[case::client_wide(r#"{"chain_id":"*"}"#, ANY_CHAIN_ID)]
+    fn mapping_chain_id_round_trip(#[case] json: &str, #[case] expected_chain_id: u64) {
+        let mapping: Mapping = serde_json::from_str(json).unwrap();
+
+        assert_eq!(expected_chain_id, mapping.chain_id);
+        assert_eq!(json, serde_json::to_string(&mapping).unwrap());
+    }
+
+    #[rstest]
+    #[case::any_chain_id_as_number(r#"{"chain_id":0}"#)]
+    #[case::other_string(r#"{"chain_id":"all"}"#)]
+    fn invalid_mapping_chain_id(#[case] json: &str) {
+        assert!(serde_json::from_str::<Mapping>(json).is_err());
+    }
+
+    #[derive(Deserialize)]
+    struct Filter {
+        #[serde(default, deserialize_with = "deserialize_optional_mapping_chain_ids")]
+        chain_ids: Option<Vec<u64>>,
+    }
+
+    #[rstest]
+    #[case::chains(r#"{"chain_ids":[1,137]}"#, Some(vec![1, 137]))]
+    #[case::client_wide(r#"{"chain_ids":["*",137]}"#, Some(vec![ANY_CHAIN_ID, 137]))]
+    #[case::missing("{}", None)]
+    fn optional_mapping_chain_ids(#[case] json: &str, #[case] expected: Option<Vec<u64>>) {
+        let filter: Filter = serde_json::from_str(json).unwrap();
+
+        assert_eq!(expected, filter.chain_ids);
+    }
+
+    #[test]
+    fn invalid_optional_mapping_chain_ids() {
+        assert!(serde_json::from_str::<Filter>(r#"{"chain_ids":[0]}"#).is_err());
+    }
+
+    #[rstest]
+    #[case::chain("137", Some(137))]
+    #[case::client_wide("*", Some(ANY_CHAIN_ID))]
+    #[case::any_chain_id_as_number("0", None)]
+    #[case::invalid("all", None)]
+    fn parse_mapping_chain_id_values(#[case] value: &str, #[case] expected: Option<u64>) {
+        assert_eq!(expected, parse_mapping_chain_id(value));
+    }
+
+    #[test]
+    fn only_default_mappings_are_client_wide() {
+        let address_to = AddressPolicyRegistryType::AddressTo {
+            address: Address::zero(),
+        };
+
+        assert!(
+            try_validate_mapping_chain(ANY_CHAIN_ID, &AddressPolicyRegistryType::Default).is_ok()
+        );
+        assert!(try_validate_mapping_chain(137, &address_to).is_ok());
+        assert_eq!(
+            "only DEFAULT mappings can have chain_id *",
+            try_validate_mapping_chain(ANY_CHAIN_ID, &address_to).unwrap_err()
+        );
+    }
+}
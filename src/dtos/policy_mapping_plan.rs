@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+
+use crate::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, serialize_mapping_chain_id, try_validate_mapping_chain,
+};
+use crate::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingTarget, MappingType,
+};
+use crate::dtos::policy_mapping_window::PolicyMappingWindow;
+use crate::dtos::policy_value_bands::try_validate_value_bands;
+use crate::http::errors::{unknown_error_response, validation_error_response};
+use crate::result::error::LambdaError;
+use crate::validations::http::supported_chain_id::is_supported_mapping_chain_id;
+use chrono::{DateTime, Utc};
+use common::deserializers::h160::h160_option;
+use ethers::types::H160;
+use http::{Response, StatusCode};
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::plan::{
+    AddressPolicyRegistryPlan, AddressPolicyRegistryUpdate,
+};
+use model::address_policy_registry::value_band::PolicyValueBand;
+use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
+use repositories::address_policy_registry::MAX_ATOMIC_PLAN_CHANGES;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+pub const MAX_DESIRED_MAPPINGS: usize = 1000;
+pub const ALLOW_PARTIAL_QUERY_PARAM: &str = "allow_partial";
+pub const PARTIALLY_APPLIED_ERROR_CODE: &str = "partially_applied";
+
+/// Full set of mappings a client wants to have. Stored mappings left out of it are deleted.
+#[derive(Deserialize)]
+pub struct PolicyMappingPlanRequest {
+    pub mappings: Vec<DesiredPolicyMapping>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct DesiredPolicyMapping {
+    #[serde(default, deserialize_with = "h160_option")]
+    pub address: Option<H160>,
+
+    /// A chain id, or `*` for a client wide DEFAULT mapping.
+    #[serde(deserialize_with = "deserialize_mapping_chain_id")]
+    #[validate(custom = "is_supported_mapping_chain_id")]
+    pub chain_id: u64,
+
+    #[validate(length(min = 1))]
+    pub policy: String,
+
+    /// Function selector of CONTRACT_CALL mappings.
+    #[serde(default)]
+    pub selector: Option<FunctionSelector>,
+
+    /// Key of KEY_ID mappings.
+    #[serde(default)]
+    pub key_id: Option<Uuid>,
+
+    /// End user of CLIENT_USER mappings.
+    #[serde(default)]
+    pub client_user_id: Option<String>,
+
+    /// Address group of GROUP mappings.
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// When not present the type is inferred from the other fields, see
+    /// [`MappingType::inferred_from`].
+    #[serde(default)]
+    pub r#type: Option<MappingType>,
+
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+
+    /// Bands sorted by their upper bound picking another policy depending on the transaction
+    /// value. `policy` applies to values above every band.
+    #[serde(default)]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+impl PolicyMappingPlanRequest {
+    /// Validates the desired mappings and builds them for `client_id`. The whole request is
+    /// rejected if any mapping is invalid or repeated.
+    pub fn desired_mappings(
+        self,
+        client_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<AddressPolicyRegistry>, Response<String>> {
+        if self.mappings.len() > MAX_DESIRED_MAPPINGS {
+            return Err(validation_error_response(
+                format!("mappings can't contain more than {MAX_DESIRED_MAPPINGS} items"),
+                None,
+            ));
+        }
+
+        let mut keys = HashSet::with_capacity(self.mappings.len());
+        let mut mappings = Vec::with_capacity(self.mappings.len());
+        for (index, item) in self.mappings.into_iter().enumerate() {
+            let mapping = item.into_mapping(client_id, now).map_err(|message| {
+                validation_error_response(format!("mappings[{index}]: {message}"), None)
+            })?;
+
+            if !keys.insert((mapping.chain_id, mapping.r#type.clone())) {
+                return Err(validation_error_response(
+                    format!("mappings[{index}]: mapping is repeated in the request"),
+                    None,
+                ));
+            }
+            mappings.push(mapping);
+        }
+
+        Ok(mappings)
+    }
+}
+
+impl DesiredPolicyMapping {
+    fn into_mapping(
+        self,
+        client_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<AddressPolicyRegistry, String> {
+        self.validate().map_err(|e| e.to_string())?;
+        self.window.try_validate(now)?;
+        try_validate_value_bands(&self.value_bands)?;
+
+        let target = MappingTarget {
+            address: self.address,
+            selector: self.selector,
+            key_id: self.key_id,
+            client_user_id: self.client_user_id,
+            group: self.group,
+        };
+        let mapping_type = self
+            .r#type
+            .unwrap_or_else(|| MappingType::inferred_from(&target))
+            .try_into_registry_type(target)?;
+        try_validate_mapping_chain(self.chain_id, &mapping_type)?;
+        Ok(
+            AddressPolicyRegistryBuilder::new(client_id.to_owned(), self.chain_id, self.policy)
+                .active_between(self.window.effective_from, self.window.expires_at)
+                .value_bands(self.value_bands)
+                .build(mapping_type),
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct PlannedPolicyMapping {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub r#type: MappingType,
+    pub policy: String,
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+impl From<&AddressPolicyRegistry> for PlannedPolicyMapping {
+    fn from(value: &AddressPolicyRegistry) -> Self {
+        Self {
+            chain_id: value.chain_id,
+            address: mapping_address_to_string(&value.r#type),
+            selector: mapping_selector_to_string(&value.r#type),
+            key_id: mapping_key_id(&value.r#type),
+            client_user_id: mapping_client_user_id(&value.r#type),
+            group: mapping_group(&value.r#type),
+            r#type: MappingType::from(&value.r#type),
+            policy: value.policy.clone(),
+            window: PolicyMappingWindow::from(value),
+            value_bands: value.value_bands.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct PlannedPolicyMappingUpdate {
+    /// Desired state of the mapping.
+    #[serde(flatten)]
+    pub mapping: PlannedPolicyMapping,
+    pub previous_policy: String,
+}
+
+impl From<&AddressPolicyRegistryUpdate> for PlannedPolicyMappingUpdate {
+    fn from(value: &AddressPolicyRegistryUpdate) -> Self {
+        Self {
+            mapping: PlannedPolicyMapping::from(&value.desired),
+            previous_policy: value.current.policy.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PolicyMappingPlanResponse {
+    pub creates: Vec<PlannedPolicyMapping>,
+    pub updates: Vec<PlannedPolicyMappingUpdate>,
+    pub deletes: Vec<PlannedPolicyMapping>,
+}
+
+impl From<&AddressPolicyRegistryPlan> for PolicyMappingPlanResponse {
+    fn from(value: &AddressPolicyRegistryPlan) -> Self {
+        Self {
+            creates: value
+                .creates
+                .iter()
+                .map(PlannedPolicyMapping::from)
+                .collect(),
+            updates: value
+                .updates
+                .iter()
+                .map(PlannedPolicyMappingUpdate::from)
+                .collect(),
+            deletes: value
+                .deletes
+                .iter()
+                .map(PlannedPolicyMapping::from)
+                .collect(),
+        }
+    }
+}
+
+/// Error returned when a plan written in several transactions fails after some of them were
+/// written.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PartiallyAppliedResponse {
+    pub code: String,
+    pub message: String,
+
+    /// Changes that were written before the failure.
+    pub applied: PolicyMappingPlanResponse,
+}
+
+/// Rejects plans too big for a single transaction unless the caller sets `allow_partial`, as a
+/// failure can leave them partially applied.
+pub fn validate_plan_can_be_applied(
+    plan: &AddressPolicyRegistryPlan,
+    allow_partial: bool,
+) -> Result<(), Response<String>> {
+    if plan.len() > MAX_ATOMIC_PLAN_CHANGES && !allow_partial {
+        return Err(validation_error_response(
+            format!(
+                "the plan has {} changes and only {MAX_ATOMIC_PLAN_CHANGES} can be applied atomically, \
+                set {ALLOW_PARTIAL_QUERY_PARAM}=true to apply it in several transactions",
+                plan.len()
+            ),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn partially_applied_response(
+    applied: &AddressPolicyRegistryPlan,
+    cause: anyhow::Error,
+) -> Response<String> {
+    tracing::error!(error = ?cause, "policy mapping plan partially applied");
+    let body = PartiallyAppliedResponse {
+        code: PARTIALLY_APPLIED_ERROR_CODE.to_owned(),
+        message: format!(
+            "the plan was partially applied, {} of its changes were written",
+            applied.len()
+        ),
+        applied: PolicyMappingPlanResponse::from(applied),
+    };
+
+    match serde_json::to_string(&body) {
+        Ok(body) => {
+            let mut response = Response::new(body);
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        }
+        Err(e) => unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting partially applied response"),
+        )),
+    }
+}
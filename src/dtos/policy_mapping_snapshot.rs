@@ -0,0 +1,335 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use common::deserializers::json_from_string::deserialize_json_string;
+use model::address_policy_registry::value_band::PolicyValueBand;
+use repositories::address_policy_registry::AddressPolicyRegistryDynamoDbResource;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Version of the snapshot layout. Snapshots of any other version are rejected on import.
+pub const POLICY_MAPPING_SNAPSHOT_VERSION: u32 = 1;
+
+/// CSV has nowhere to put the snapshot header, it goes in a leading comment line.
+const CSV_HEADER_PREFIX: &str = "#";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SnapshotFormat {
+    /// One JSON document per line, the header first.
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+impl SnapshotFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Jsonl => "application/x-ndjson",
+            SnapshotFormat::Csv => "text/csv",
+        }
+    }
+}
+
+impl FromStr for SnapshotFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "JSONL" => Ok(SnapshotFormat::Jsonl),
+            "CSV" => Ok(SnapshotFormat::Csv),
+            other => Err(anyhow!("Not supported SnapshotFormat variant: {other}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PolicyMappingSnapshotHeader {
+    pub format_version: u32,
+    pub client_id: String,
+    pub exported_at: DateTime<Utc>,
+    /// Number of rows that follow, so truncated snapshots are detected.
+    pub rows: usize,
+}
+
+/// Point in time copy of every row a client has in the address policy registry table.
+pub struct PolicyMappingSnapshot {
+    pub header: PolicyMappingSnapshotHeader,
+    pub rows: Vec<AddressPolicyRegistryDynamoDbResource>,
+}
+
+/// CSV needs every record to have the same columns, so unlike the table resource empty values
+/// are written instead of skipped.
+#[derive(Deserialize, Serialize)]
+struct SnapshotCsvRow {
+    pk: String,
+    sk: String,
+    client_id: String,
+    chain_id: u64,
+    policy: String,
+    created_at: DateTime<Utc>,
+    address: Option<String>,
+    version: u64,
+    effective_from: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    expires_at_ttl: Option<i64>,
+    #[serde(
+        serialize_with = "value_bands_to_json",
+        deserialize_with = "deserialize_json_string"
+    )]
+    value_bands: Vec<PolicyValueBand>,
+}
+
+/// CSV fields can't hold lists, value bands are written as a JSON array.
+fn value_bands_to_json<S: Serializer>(
+    value_bands: &[PolicyValueBand],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let json = serde_json::to_string(value_bands).map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&json)
+}
+
+impl PolicyMappingSnapshot {
+    pub fn new(
+        client_id: String,
+        rows: Vec<AddressPolicyRegistryDynamoDbResource>,
+        exported_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            header: PolicyMappingSnapshotHeader {
+                format_version: POLICY_MAPPING_SNAPSHOT_VERSION,
+                client_id,
+                exported_at,
+                rows: rows.len(),
+            },
+            rows,
+        }
+    }
+
+    pub fn encode(&self, format: SnapshotFormat) -> Result<String, anyhow::Error> {
+        let header = serde_json::to_string(&self.header)?;
+
+        match format {
+            SnapshotFormat::Jsonl => {
+                let mut document = header;
+                document.push('\n');
+                for row in &self.rows {
+                    document.push_str(&serde_json::to_string(row)?);
+                    document.push('\n');
+                }
+                Ok(document)
+            }
+            SnapshotFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                for row in &self.rows {
+                    writer.serialize(SnapshotCsvRow::from(row))?;
+                }
+                let rows = writer.into_inner().map_err(|e| anyhow!(e.into_error()))?;
+
+                Ok(format!(
+                    "{CSV_HEADER_PREFIX}{header}\n{}",
+                    String::from_utf8(rows)?
+                ))
+            }
+        }
+    }
+
+    /// Parses a snapshot written by [`PolicyMappingSnapshot::encode`]. Errors are validation
+    /// messages meant for the caller.
+    pub fn decode(document: &str, format: SnapshotFormat) -> Result<Self, String> {
+        let (header, body) = document.split_once('\n').unwrap_or((document, ""));
+        let header = match format {
+            SnapshotFormat::Jsonl => Some(header),
+            SnapshotFormat::Csv => header.strip_prefix(CSV_HEADER_PREFIX),
+        }
+        .ok_or_else(|| "snapshot header not found".to_owned())?;
+
+        let header = serde_json::from_str::<PolicyMappingSnapshotHeader>(header)
+            .map_err(|e| format!("invalid snapshot header: {e}"))?;
+        if header.format_version != POLICY_MAPPING_SNAPSHOT_VERSION {
+            return Err(format!(
+                "snapshot version {} is not supported",
+                header.format_version
+            ));
+        }
+
+        let rows = match format {
+            SnapshotFormat::Jsonl => body
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(index, line)| {
+                    serde_json::from_str(line).map_err(|e| format!("rows[{index}]: {e}"))
+                })
+                .collect::<Result<Vec<AddressPolicyRegistryDynamoDbResource>, _>>()?,
+            SnapshotFormat::Csv => csv::Reader::from_reader(body.as_bytes())
+                .deserialize::<SnapshotCsvRow>()
+                .enumerate()
+                .map(|(index, row)| {
+                    row.map(AddressPolicyRegistryDynamoDbResource::from)
+                        .map_err(|e| format!("rows[{index}]: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        if rows.len() != header.rows {
+            return Err(format!(
+                "snapshot header announces {} rows but {} were found",
+                header.rows,
+                rows.len()
+            ));
+        }
+
+        Ok(Self { header, rows })
+    }
+}
+
+impl From<&AddressPolicyRegistryDynamoDbResource> for SnapshotCsvRow {
+    fn from(value: &AddressPolicyRegistryDynamoDbResource) -> Self {
+        Self {
+            pk: value.pk.clone(),
+            sk: value.sk.clone(),
+            client_id: value.client_id.clone(),
+            chain_id: value.chain_id,
+            policy: value.policy.clone(),
+            created_at: value.created_at,
+            address: value.address.clone(),
+            version: value.version,
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+            expires_at_ttl: value.expires_at_ttl,
+            value_bands: value.value_bands.clone(),
+        }
+    }
+}
+
+impl From<SnapshotCsvRow> for AddressPolicyRegistryDynamoDbResource {
+    fn from(value: SnapshotCsvRow) -> Self {
+        Self {
+            pk: value.pk,
+            sk: value.sk,
+            client_id: value.client_id,
+            chain_id: value.chain_id,
+            policy: value.policy,
+            created_at: value.created_at,
+            address: value.address,
+            version: value.version,
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+            expires_at_ttl: value.expires_at_ttl,
+            value_bands: value.value_bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::constants::{
+        ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use ethers::types::U256;
+    use model::address_policy_registry::value_band::PolicyValueBand;
+    use repositories::address_policy_registry::AddressPolicyRegistryDynamoDbResource;
+    use rstest::rstest;
+
+    use super::{PolicyMappingSnapshot, SnapshotFormat};
+
+    fn rows() -> Vec<AddressPolicyRegistryDynamoDbResource> {
+        let pk =
+            format!("CLIENT#{CLIENT_ID_FOR_MOCK_REQUESTS}#CHAIN_ID#{CHAIN_ID_FOR_MOCK_REQUESTS}");
+        let expires_at = Utc::now() + Duration::days(1);
+
+        vec![
+            AddressPolicyRegistryDynamoDbResource {
+                pk: pk.clone(),
+                sk: "ADDRESS#DEFAULT".to_owned(),
+                client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                policy: "policy, with a comma".to_owned(),
+                created_at: Utc::now(),
+                address: None,
+                version: 1,
+                effective_from: None,
+                expires_at: None,
+                expires_at_ttl: None,
+                value_bands: vec![],
+            },
+            AddressPolicyRegistryDynamoDbResource {
+                pk,
+                sk: format!("ADDRESS_FROM#{ADDRESS_FOR_MOCK_REQUESTS}"),
+                client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                policy: "some_policy".to_owned(),
+                created_at: Utc::now(),
+                address: Some(ADDRESS_FOR_MOCK_REQUESTS.to_owned()),
+                version: 3,
+                effective_from: Some(Utc::now()),
+                expires_at: Some(expires_at),
+                expires_at_ttl: Some(expires_at.timestamp()),
+                value_bands: vec![PolicyValueBand {
+                    below: U256::exp10(18),
+                    policy: "basic".to_owned(),
+                }],
+            },
+        ]
+    }
+
+    #[rstest]
+    #[case::jsonl(SnapshotFormat::Jsonl)]
+    #[case::csv(SnapshotFormat::Csv)]
+    fn snapshot_round_trip(#[case] format: SnapshotFormat) {
+        let snapshot =
+            PolicyMappingSnapshot::new(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(), rows(), Utc::now());
+
+        let document = snapshot.encode(format).unwrap();
+        let decoded = PolicyMappingSnapshot::decode(&document, format).unwrap();
+
+        assert_eq!(snapshot.header, decoded.header);
+        assert_eq!(
+            serde_json::to_value(&snapshot.rows).unwrap(),
+            serde_json::to_value(&decoded.rows).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case::jsonl(SnapshotFormat::Jsonl)]
+    #[case::csv(SnapshotFormat::Csv)]
+    fn empty_snapshot_round_trip(#[case] format: SnapshotFormat) {
+        let snapshot =
+            PolicyMappingSnapshot::new(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(), vec![], Utc::now());
+
+        let document = snapshot.encode(format).unwrap();
+        let decoded = PolicyMappingSnapshot::decode(&document, format).unwrap();
+
+        assert_eq!(0, decoded.header.rows);
+        assert!(decoded.rows.is_empty());
+    }
+
+    #[test]
+    fn truncated_snapshot_is_rejected() {
+        let snapshot =
+            PolicyMappingSnapshot::new(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(), rows(), Utc::now());
+        let document = snapshot.encode(SnapshotFormat::Jsonl).unwrap();
+        let truncated = document.lines().take(2).collect::<Vec<_>>().join("\n");
+
+        let error = PolicyMappingSnapshot::decode(&truncated, SnapshotFormat::Jsonl)
+            .err()
+            .unwrap();
+
+        assert_eq!("snapshot header announces 2 rows but 1 were found", error);
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let document = format!(
+            r#"{{"format_version":2,"client_id":"{CLIENT_ID_FOR_MOCK_REQUESTS}","exported_at":"2024-01-01T00:00:00Z","rows":0}}"#
+        );
+
+        let error = PolicyMappingSnapshot::decode(&document, SnapshotFormat::Jsonl)
+            .err()
+            .unwrap();
+
+        assert_eq!("snapshot version 2 is not supported", error);
+    }
+}
@@ -0,0 +1,440 @@
+use std::str::FromStr;
+
+use crate::http::errors::validation_error_response;
+use common::serializers::h160::h160_to_lowercase_hex_string;
+use ethers::types::Address;
+use http::Response;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::AddressPolicyRegistryType;
+use repositories::address_policy_registry::MappingTypeFilter;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const DEFAULT_ADDRESS_VALUE: &str = "default";
+
+/// Policy mapping type as exposed by the policy mapping API.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MappingType {
+    Default,
+    AddressTo,
+    AddressFrom,
+    ContractCall,
+    KeyId,
+    ClientUser,
+    Group,
+}
+
+/// What a mapping applies to, as sent by the clients. Which fields a mapping needs depends on its
+/// type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MappingTarget {
+    pub address: Option<Address>,
+    pub selector: Option<FunctionSelector>,
+    pub key_id: Option<Uuid>,
+    pub client_user_id: Option<String>,
+    pub group: Option<String>,
+}
+
+impl MappingType {
+    /// Infers the mapping type when the request does not specify one. Mappings with a key id, a
+    /// client user id or a group are KEY_ID, CLIENT_USER and GROUP mappings, mappings with a
+    /// function selector are CONTRACT_CALL mappings, mappings without an address are DEFAULT
+    /// mappings and the rest are ADDRESS_TO mappings.
+    pub fn inferred_from(target: &MappingTarget) -> Self {
+        if target.key_id.is_some() {
+            return MappingType::KeyId;
+        }
+        if target.client_user_id.is_some() {
+            return MappingType::ClientUser;
+        }
+        if target.group.is_some() {
+            return MappingType::Group;
+        }
+
+        match (target.address, target.selector) {
+            (_, Some(_)) => MappingType::ContractCall,
+            (None, None) => MappingType::Default,
+            (Some(_), None) => MappingType::AddressTo,
+        }
+    }
+
+    /// Builds the registry type for this mapping type. DEFAULT, KEY_ID, CLIENT_USER and GROUP
+    /// mappings can't have an address while the rest require one, only CONTRACT_CALL mappings
+    /// have a function selector and only KEY_ID, CLIENT_USER and GROUP mappings have a key id, a
+    /// client user id or a group respectively.
+    pub fn into_registry_type(
+        self,
+        target: MappingTarget,
+    ) -> Result<AddressPolicyRegistryType, Response<String>> {
+        self.try_into_registry_type(target)
+            .map_err(|message| validation_error_response(message, None))
+    }
+
+    /// Same as [`MappingType::into_registry_type`] but returns the bare validation message, for
+    /// callers that report errors per item instead of failing the whole request.
+    pub fn try_into_registry_type(
+        self,
+        target: MappingTarget,
+    ) -> Result<AddressPolicyRegistryType, String> {
+        if target.key_id.is_some() && self != MappingType::KeyId {
+            return Err(format!("{} mappings can't have a key_id", self.as_str()));
+        }
+        if target.client_user_id.is_some() && self != MappingType::ClientUser {
+            return Err(format!(
+                "{} mappings can't have a client_user_id",
+                self.as_str()
+            ));
+        }
+        if target.group.is_some() && self != MappingType::Group {
+            return Err(format!("{} mappings can't have a group", self.as_str()));
+        }
+
+        match (self, target.address, target.selector) {
+            (MappingType::KeyId | MappingType::ClientUser | MappingType::Group, Some(_), _) => {
+                Err(format!("{} mappings can't have an address", self.as_str()))
+            }
+            (MappingType::KeyId, None, None) => target
+                .key_id
+                .map(|key_id| AddressPolicyRegistryType::KeyId { key_id })
+                .ok_or_else(|| "KEY_ID mappings require a key_id".to_owned()),
+            (MappingType::ClientUser, None, None) => target
+                .client_user_id
+                .filter(|client_user_id| !client_user_id.is_empty())
+                .map(|client_user_id| AddressPolicyRegistryType::ClientUser { client_user_id })
+                .ok_or_else(|| "CLIENT_USER mappings require a client_user_id".to_owned()),
+            (MappingType::Group, None, None) => target
+                .group
+                .filter(|name| !name.is_empty())
+                .map(|name| AddressPolicyRegistryType::Group { name })
+                .ok_or_else(|| "GROUP mappings require a group".to_owned()),
+            (MappingType::Default, None, None) => Ok(AddressPolicyRegistryType::Default),
+            (MappingType::AddressTo, Some(address), None) => {
+                Ok(AddressPolicyRegistryType::AddressTo { address })
+            }
+            (MappingType::AddressFrom, Some(address), None) => {
+                Ok(AddressPolicyRegistryType::AddressFrom { address })
+            }
+            (MappingType::ContractCall, Some(address), Some(selector)) => {
+                Ok(AddressPolicyRegistryType::ContractCall { address, selector })
+            }
+            (MappingType::ContractCall, None, _) => {
+                Err("CONTRACT_CALL mappings require an address".to_owned())
+            }
+            (MappingType::ContractCall, Some(_), None) => {
+                Err("CONTRACT_CALL mappings require a selector".to_owned())
+            }
+            (mapping_type, _, Some(_)) => Err(format!(
+                "{} mappings can't have a selector",
+                mapping_type.as_str()
+            )),
+            (MappingType::Default, Some(_), _) => {
+                Err("DEFAULT mappings can't have an address".to_owned())
+            }
+            (mapping_type, None, _) => Err(format!(
+                "{} mappings require an address",
+                mapping_type.as_str()
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MappingType::Default => "DEFAULT",
+            MappingType::AddressTo => "ADDRESS_TO",
+            MappingType::AddressFrom => "ADDRESS_FROM",
+            MappingType::ContractCall => "CONTRACT_CALL",
+            MappingType::KeyId => "KEY_ID",
+            MappingType::ClientUser => "CLIENT_USER",
+            MappingType::Group => "GROUP",
+        }
+    }
+}
+
+impl From<&AddressPolicyRegistryType> for MappingType {
+    fn from(value: &AddressPolicyRegistryType) -> Self {
+        match value {
+            AddressPolicyRegistryType::Default => MappingType::Default,
+            AddressPolicyRegistryType::AddressTo { .. } => MappingType::AddressTo,
+            AddressPolicyRegistryType::AddressFrom { .. } => MappingType::AddressFrom,
+            AddressPolicyRegistryType::ContractCall { .. } => MappingType::ContractCall,
+            AddressPolicyRegistryType::KeyId { .. } => MappingType::KeyId,
+            AddressPolicyRegistryType::ClientUser { .. } => MappingType::ClientUser,
+            AddressPolicyRegistryType::Group { .. } => MappingType::Group,
+        }
+    }
+}
+
+impl From<MappingType> for MappingTypeFilter {
+    fn from(value: MappingType) -> Self {
+        match value {
+            MappingType::Default => MappingTypeFilter::Default,
+            MappingType::AddressTo => MappingTypeFilter::AddressTo,
+            MappingType::AddressFrom => MappingTypeFilter::AddressFrom,
+            MappingType::ContractCall => MappingTypeFilter::ContractCall,
+            MappingType::KeyId => MappingTypeFilter::KeyId,
+            MappingType::ClientUser => MappingTypeFilter::ClientUser,
+            MappingType::Group => MappingTypeFilter::Group,
+        }
+    }
+}
+
+impl FromStr for MappingType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "DEFAULT" => Ok(MappingType::Default),
+            "ADDRESS_TO" => Ok(MappingType::AddressTo),
+            "ADDRESS_FROM" => Ok(MappingType::AddressFrom),
+            "CONTRACT_CALL" => Ok(MappingType::ContractCall),
+            "KEY_ID" => Ok(MappingType::KeyId),
+            "CLIENT_USER" => Ok(MappingType::ClientUser),
+            "GROUP" => Ok(MappingType::Group),
+            other => Err(anyhow::anyhow!(
+                "Not supported MappingType variant: {other}"
+            )),
+        }
+    }
+}
+
+/// Returns the address of a mapping as shown in responses: the lowercase hex address or
+/// `default` for mappings that don't have one.
+pub fn mapping_address_to_string(mapping_type: &AddressPolicyRegistryType) -> String {
+    match mapping_type {
+        AddressPolicyRegistryType::Default
+        | AddressPolicyRegistryType::KeyId { .. }
+        | AddressPolicyRegistryType::ClientUser { .. }
+        | AddressPolicyRegistryType::Group { .. } => DEFAULT_ADDRESS_VALUE.to_owned(),
+        AddressPolicyRegistryType::AddressTo { address }
+        | AddressPolicyRegistryType::AddressFrom { address }
+        | AddressPolicyRegistryType::ContractCall { address, .. } => {
+            h160_to_lowercase_hex_string(*address)
+        }
+    }
+}
+
+/// Returns the function selector of CONTRACT_CALL mappings as shown in responses.
+pub fn mapping_selector_to_string(mapping_type: &AddressPolicyRegistryType) -> Option<String> {
+    match mapping_type {
+        AddressPolicyRegistryType::ContractCall { selector, .. } => Some(selector.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the key id of KEY_ID mappings as shown in responses.
+pub fn mapping_key_id(mapping_type: &AddressPolicyRegistryType) -> Option<Uuid> {
+    match mapping_type {
+        AddressPolicyRegistryType::KeyId { key_id } => Some(*key_id),
+        _ => None,
+    }
+}
+
+/// Returns the client user id of CLIENT_USER mappings as shown in responses.
+pub fn mapping_client_user_id(mapping_type: &AddressPolicyRegistryType) -> Option<String> {
+    match mapping_type {
+        AddressPolicyRegistryType::ClientUser { client_user_id } => Some(client_user_id.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the group name of GROUP mappings as shown in responses.
+pub fn mapping_group(mapping_type: &AddressPolicyRegistryType) -> Option<String> {
+    match mapping_type {
+        AddressPolicyRegistryType::Group { name } => Some(name.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MappingTarget, MappingType};
+    use common::test_tools::http::constants::ADDRESS_FOR_MOCK_REQUESTS;
+    use ethers::types::Address;
+    use http::StatusCode;
+    use model::address_policy_registry::function_selector::FunctionSelector;
+    use model::address_policy_registry::AddressPolicyRegistryType;
+    use rstest::rstest;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    const SELECTOR: FunctionSelector = FunctionSelector([0xa9, 0x05, 0x9c, 0xbb]);
+
+    fn target(with_address: bool, selector: Option<FunctionSelector>) -> MappingTarget {
+        MappingTarget {
+            address: with_address.then(|| Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+            selector,
+            ..MappingTarget::default()
+        }
+    }
+
+    #[test]
+    fn into_registry_type_ok() {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+
+        assert_eq!(
+            AddressPolicyRegistryType::Default,
+            MappingType::Default
+                .into_registry_type(target(false, None))
+                .unwrap()
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::AddressTo { address },
+            MappingType::AddressTo
+                .into_registry_type(target(true, None))
+                .unwrap()
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::AddressFrom { address },
+            MappingType::AddressFrom
+                .into_registry_type(target(true, None))
+                .unwrap()
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::ContractCall {
+                address,
+                selector: SELECTOR
+            },
+            MappingType::ContractCall
+                .into_registry_type(target(true, Some(SELECTOR)))
+                .unwrap()
+        );
+
+        let key_id = Uuid::new_v4();
+        assert_eq!(
+            AddressPolicyRegistryType::KeyId { key_id },
+            MappingType::KeyId
+                .into_registry_type(MappingTarget {
+                    key_id: Some(key_id),
+                    ..MappingTarget::default()
+                })
+                .unwrap()
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::ClientUser {
+                client_user_id: "some_end_user".to_owned()
+            },
+            MappingType::ClientUser
+                .into_registry_type(MappingTarget {
+                    client_user_id: Some("some_end_user".to_owned()),
+                    ..MappingTarget::default()
+                })
+                .unwrap()
+        );
+        assert_eq!(
+            AddressPolicyRegistryType::Group {
+                name: "exchanges".to_owned()
+            },
+            MappingType::Group
+                .into_registry_type(MappingTarget {
+                    group: Some("exchanges".to_owned()),
+                    ..MappingTarget::default()
+                })
+                .unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case::default_with_address(MappingType::Default, true, None)]
+    #[case::address_to_without_address(MappingType::AddressTo, false, None)]
+    #[case::address_from_without_address(MappingType::AddressFrom, false, None)]
+    #[case::address_to_with_selector(MappingType::AddressTo, true, Some(SELECTOR))]
+    #[case::contract_call_without_selector(MappingType::ContractCall, true, None)]
+    #[case::contract_call_without_address(MappingType::ContractCall, false, Some(SELECTOR))]
+    #[case::key_id_without_key_id(MappingType::KeyId, false, None)]
+    #[case::key_id_with_address(MappingType::KeyId, true, None)]
+    #[case::client_user_without_client_user_id(MappingType::ClientUser, false, None)]
+    #[case::group_without_group(MappingType::Group, false, None)]
+    #[case::group_with_address(MappingType::Group, true, None)]
+    fn into_registry_type_invalid(
+        #[case] mapping_type: MappingType,
+        #[case] with_address: bool,
+        #[case] selector: Option<FunctionSelector>,
+    ) {
+        let error = mapping_type
+            .into_registry_type(target(with_address, selector))
+            .unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+
+        let message = mapping_type
+            .try_into_registry_type(target(with_address, selector))
+            .unwrap_err();
+        assert!(error.body().contains(&message));
+    }
+
+    #[rstest]
+    #[case::key_id_on_address_to(MappingType::AddressTo, "ADDRESS_TO mappings can't have a key_id")]
+    #[case::key_id_on_client_user(
+        MappingType::ClientUser,
+        "CLIENT_USER mappings can't have a key_id"
+    )]
+    fn into_registry_type_unexpected_key_id(
+        #[case] mapping_type: MappingType,
+        #[case] expected_message: &str,
+    ) {
+        let target = MappingTarget {
+            key_id: Some(Uuid::new_v4()),
+            ..target(true, None)
+        };
+
+        assert_eq!(
+            expected_message,
+            mapping_type.try_into_registry_type(target).unwrap_err()
+        );
+    }
+
+    #[rstest]
+    #[case::default(false, None, MappingType::Default)]
+    #[case::address_to(true, None, MappingType::AddressTo)]
+    #[case::contract_call(true, Some(SELECTOR), MappingType::ContractCall)]
+    fn inferred_from(
+        #[case] with_address: bool,
+        #[case] selector: Option<FunctionSelector>,
+        #[case] expected: MappingType,
+    ) {
+        assert_eq!(
+            expected,
+            MappingType::inferred_from(&target(with_address, selector))
+        );
+    }
+
+    #[test]
+    fn inferred_from_key_owner() {
+        assert_eq!(
+            MappingType::KeyId,
+            MappingType::inferred_from(&MappingTarget {
+                key_id: Some(Uuid::new_v4()),
+                ..MappingTarget::default()
+            })
+        );
+        assert_eq!(
+            MappingType::ClientUser,
+            MappingType::inferred_from(&MappingTarget {
+                client_user_id: Some("some_end_user".to_owned()),
+                ..MappingTarget::default()
+            })
+        );
+        assert_eq!(
+            MappingType::Group,
+            MappingType::inferred_from(&MappingTarget {
+                group: Some("exchanges".to_owned()),
+                ..MappingTarget::default()
+            })
+        );
+    }
+
+    #[test]
+    fn into_registry_type_unexpected_group() {
+        let target = MappingTarget {
+            group: Some("exchanges".to_owned()),
+            ..target(true, None)
+        };
+
+        assert_eq!(
+            "ADDRESS_TO mappings can't have a group",
+            MappingType::AddressTo
+                .try_into_registry_type(target)
+                .unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::http::errors::validation_error_response;
+use chrono::{DateTime, Utc};
+use http::Response;
+use model::address_policy_registry::AddressPolicyRegistry;
+use serde::{Deserialize, Serialize};
+
+/// Time window during which a policy mapping applies, as exposed by the policy mapping API.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PolicyMappingWindow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl PolicyMappingWindow {
+    /// Checks the window of a mapping being created at `now`. A mapping has to expire after it
+    /// becomes effective, and it can't be created already expired.
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), Response<String>> {
+        self.try_validate(now)
+            .map_err(|message| validation_error_response(message, None))
+    }
+
+    /// Same as [`PolicyMappingWindow::validate`] but returns the bare validation message, for
+    /// callers that report errors per item instead of failing the whole request.
+    pub fn try_validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        match (self.effective_from, self.expires_at) {
+            (Some(effective_from), Some(expires_at)) if expires_at <= effective_from => {
+                Err("expires_at must be later than effective_from".to_owned())
+            }
+            (_, Some(expires_at)) if expires_at <= now => {
+                Err("expires_at must be in the future".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<&AddressPolicyRegistry> for PolicyMappingWindow {
+    fn from(value: &AddressPolicyRegistry) -> Self {
+        Self {
+            effective_from: value.effective_from,
+            expires_at: value.expires_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PolicyMappingWindow;
+    use chrono::{Duration, Utc};
+    use http::StatusCode;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::unbounded(None, None)]
+    #[case::scheduled(Some(Duration::hours(1)), None)]
+    #[case::temporary(None, Some(Duration::hours(1)))]
+    #[case::window(Some(Duration::hours(-1)), Some(Duration::hours(1)))]
+    fn validate_window_ok(
+        #[case] effective_from: Option<Duration>,
+        #[case] expires_at: Option<Duration>,
+    ) {
+        let now = Utc::now();
+        let window = PolicyMappingWindow {
+            effective_from: effective_from.map(|offset| now + offset),
+            expires_at: expires_at.map(|offset| now + offset),
+        };
+
+        assert!(window.validate(now).is_ok());
+    }
+
+    #[rstest]
+    #[case::expires_before_effective(
+        Some(Duration::hours(2)),
+        Some(Duration::hours(1)),
+        "expires_at must be later than effective_from"
+    )]
+    #[case::already_expired(None, Some(Duration::hours(-1)), "expires_at must be in the future")]
+    fn validate_window_invalid(
+        #[case] effective_from: Option<Duration>,
+        #[case] expires_at: Option<Duration>,
+        #[case] expected_message: &str,
+    ) {
+        let now = Utc::now();
+        let window = PolicyMappingWindow {
+            effective_from: effective_from.map(|offset| now + offset),
+            expires_at: expires_at.map(|offset| now + offset),
+        };
+
+        assert_eq!(expected_message, window.try_validate(now).unwrap_err());
+        let error = window.validate(now).unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+        assert!(error.body().contains(expected_message));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::dtos::policy_mapping_chain::{deserialize_mapping_chain_id, serialize_mapping_chain_id};
+use crate::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingType,
+};
+use model::address_policy_registry::value_band::PolicyValueBand;
+use model::address_policy_registry::AddressPolicyRegistry;
+use repositories::address_policy_registry::policy_resolver::{CandidateOutcome, PolicyCandidate};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub struct ResolvedPolicyMapping {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub policy: String,
+    pub r#type: MappingType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PolicyCandidateResponse {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub pk: String,
+    pub sk: String,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub r#type: MappingType,
+    pub outcome: CandidateOutcomeResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CandidateOutcomeResponse {
+    Selected,
+    NotFound,
+    NotActive,
+    ShadowedByHigherPrecedence,
+}
+
+impl From<CandidateOutcome> for CandidateOutcomeResponse {
+    fn from(outcome: CandidateOutcome) -> Self {
+        match outcome {
+            CandidateOutcome::Selected => Self::Selected,
+            CandidateOutcome::NotFound => Self::NotFound,
+            CandidateOutcome::NotActive => Self::NotActive,
+            CandidateOutcome::Shadowed => Self::ShadowedByHigherPrecedence,
+        }
+    }
+}
+
+impl From<PolicyCandidate> for PolicyCandidateResponse {
+    fn from(candidate: PolicyCandidate) -> Self {
+        Self {
+            chain_id: candidate.chain_id,
+            pk: candidate.key.pk,
+            sk: candidate.key.sk,
+            address: mapping_address_to_string(&candidate.r#type),
+            selector: mapping_selector_to_string(&candidate.r#type),
+            key_id: mapping_key_id(&candidate.r#type),
+            client_user_id: mapping_client_user_id(&candidate.r#type),
+            group: mapping_group(&candidate.r#type),
+            r#type: MappingType::from(&candidate.r#type),
+            outcome: candidate.outcome.into(),
+        }
+    }
+}
+
+impl From<AddressPolicyRegistry> for ResolvedPolicyMapping {
+    fn from(mapping: AddressPolicyRegistry) -> Self {
+        Self {
+            chain_id: mapping.chain_id,
+            address: mapping_address_to_string(&mapping.r#type),
+            selector: mapping_selector_to_string(&mapping.r#type),
+            key_id: mapping_key_id(&mapping.r#type),
+            client_user_id: mapping_client_user_id(&mapping.r#type),
+            group: mapping_group(&mapping.r#type),
+            r#type: MappingType::from(&mapping.r#type),
+            policy: mapping.policy,
+            value_bands: mapping.value_bands,
+        }
+    }
+}
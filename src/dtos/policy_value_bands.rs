@@ -0,0 +1,97 @@
+use crate::http::errors::validation_error_response;
+use http::Response;
+use model::address_policy_registry::value_band::PolicyValueBand;
+
+/// Checks the value bands of a mapping being created. Bands have to be sorted by their upper
+/// bound with no two bands sharing one, so every value falls in a single band.
+pub fn validate_value_bands(value_bands: &[PolicyValueBand]) -> Result<(), Response<String>> {
+    try_validate_value_bands(value_bands)
+        .map_err(|message| validation_error_response(message, None))
+}
+
+/// Same as [`validate_value_bands`] but returns the bare validation message, for callers that
+/// report errors per item instead of failing the whole request.
+pub fn try_validate_value_bands(value_bands: &[PolicyValueBand]) -> Result<(), String> {
+    let mut lower_bound = None;
+    for (index, band) in value_bands.iter().enumerate() {
+        if band.policy.is_empty() {
+            return Err(format!("value_bands[{index}]: policy can't be empty"));
+        }
+        if band.below.is_zero() {
+            return Err(format!(
+                "value_bands[{index}]: below must be greater than 0"
+            ));
+        }
+        if matches!(lower_bound, Some(lower_bound) if band.below <= lower_bound) {
+            return Err(format!(
+                "value_bands[{index}]: overlaps value_bands[{}], bands must be sorted by below",
+                index - 1
+            ));
+        }
+        lower_bound = Some(band.below);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{try_validate_value_bands, validate_value_bands};
+    use ethers::types::U256;
+    use http::StatusCode;
+    use model::address_policy_registry::value_band::PolicyValueBand;
+    use rstest::rstest;
+
+    fn bands(bounds: &[u64]) -> Vec<PolicyValueBand> {
+        bounds
+            .iter()
+            .map(|below| PolicyValueBand {
+                below: U256::from(*below),
+                policy: format!("policy_below_{below}"),
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::no_bands(&[])]
+    #[case::single_band(&[1])]
+    #[case::sorted_bands(&[10, 100, 1000])]
+    fn validate_value_bands_ok(#[case] bounds: &[u64]) {
+        assert!(validate_value_bands(&bands(bounds)).is_ok());
+    }
+
+    #[rstest]
+    #[case::zero_bound(&[0, 10], "value_bands[0]: below must be greater than 0")]
+    #[case::repeated_bound(
+        &[10, 10],
+        "value_bands[1]: overlaps value_bands[0], bands must be sorted by below"
+    )]
+    #[case::unsorted(
+        &[10, 100, 50],
+        "value_bands[2]: overlaps value_bands[1], bands must be sorted by below"
+    )]
+    fn validate_value_bands_invalid(#[case] bounds: &[u64], #[case] expected_message: &str) {
+        let bands = bands(bounds);
+
+        assert_eq!(
+            expected_message,
+            try_validate_value_bands(&bands).unwrap_err()
+        );
+        let error = validate_value_bands(&bands).unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+        assert!(error.body().contains(expected_message));
+    }
+
+    #[test]
+    fn validate_value_bands_empty_policy() {
+        let bands = vec![PolicyValueBand {
+            below: U256::one(),
+            policy: String::new(),
+        }];
+
+        assert_eq!(
+            "value_bands[0]: policy can't be empty",
+            try_validate_value_bands(&bands).unwrap_err()
+        );
+    }
+}
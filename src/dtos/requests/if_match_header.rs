@@ -0,0 +1,98 @@
+use std::str::FromStr;
+
+use crate::http::errors::validation_error_response;
+use crate::lambda_structure::http_lambda_main::{RequestExtractor, RequestExtractorError};
+use http::Response;
+use lambda_http::Request;
+
+pub const IF_MATCH_HEADER_NAME: &str = "if-match";
+pub const ETAG_HEADER_NAME: &str = "ETag";
+
+const ANY_VALUE: &str = "*";
+const WEAK_PREFIX: &str = "W/";
+
+/// Value of an `If-Match` header carrying an entity version previously returned as an `ETag`.
+#[derive(Debug, PartialEq)]
+pub enum IfMatchHeader {
+    Any,
+    Version(u64),
+}
+
+impl IfMatchHeader {
+    /// Version the entity must have for the request to go through. `None` means any version.
+    pub fn expected_version(&self) -> Option<u64> {
+        match self {
+            IfMatchHeader::Any => None,
+            IfMatchHeader::Version(version) => Some(*version),
+        }
+    }
+
+    /// Extracts the expected version from the request's `If-Match` header, if there is one.
+    pub fn extract_expected_version(request: &Request) -> Result<Option<u64>, Response<String>> {
+        match request.extract_header::<String>(IF_MATCH_HEADER_NAME) {
+            Ok(value) => Ok(Self::from_str(&value)?.expected_version()),
+            Err(RequestExtractorError::HeaderNotFoundError(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl FromStr for IfMatchHeader {
+    type Err = Response<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if value == ANY_VALUE {
+            return Ok(Self::Any);
+        }
+
+        value
+            .strip_prefix(WEAK_PREFIX)
+            .unwrap_or(value)
+            .trim_matches('"')
+            .parse::<u64>()
+            .map(Self::Version)
+            .map_err(|_| {
+                validation_error_response(format!("invalid If-Match header value {s}"), None)
+            })
+    }
+}
+
+/// Formats an entity version as a strong `ETag` value.
+pub fn version_etag(version: u64) -> String {
+    format!("\"{version}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rstest::rstest;
+
+    use super::{version_etag, IfMatchHeader};
+
+    #[rstest]
+    #[case::any("*", IfMatchHeader::Any)]
+    #[case::quoted("\"3\"", IfMatchHeader::Version(3))]
+    #[case::unquoted("7", IfMatchHeader::Version(7))]
+    #[case::weak("W/\"2\"", IfMatchHeader::Version(2))]
+    fn parse_if_match_ok(#[case] value: &str, #[case] expected: IfMatchHeader) {
+        assert_eq!(expected, IfMatchHeader::from_str(value).unwrap());
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::not_a_number("\"abc\"")]
+    #[case::negative("\"-1\"")]
+    fn parse_if_match_invalid(#[case] value: &str) {
+        assert!(IfMatchHeader::from_str(value).is_err());
+    }
+
+    #[test]
+    fn etag_round_trip() {
+        assert_eq!(
+            IfMatchHeader::Version(5),
+            IfMatchHeader::from_str(&version_etag(5)).unwrap()
+        );
+    }
+}
@@ -1,3 +1,4 @@
 pub mod address_or_default_path_param;
+pub mod if_match_header;
 pub mod send_to_approvers_sm;
 pub mod transaction_request;
@@ -3,8 +3,10 @@ use common::deserializers::{bytes::bytes, h160::h160, u256::unsigned_integer_256
 use ethers::prelude::transaction::eip712::TypedData;
 use ethers::types::transaction::eip712::Eip712;
 use ethers::types::{Bytes, H160, U256};
+use model::address_policy_registry::function_selector::FunctionSelector;
 use rlp::RlpStream;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 #[derive(Debug, thiserror::Error)]
 pub enum TransactionError {
@@ -97,6 +99,42 @@ impl TransactionRequest {
         }
     }
 
+    /// Address the transaction goes to. Sponsored transactions take it from the `to` field of
+    /// the forward request in their typed data, so it is `None` when that field is missing or
+    /// isn't an address.
+    pub fn destination(&self) -> Option<H160> {
+        match self {
+            TransactionRequest::Legacy { to, .. } | TransactionRequest::Eip1559 { to, .. } => {
+                Some(*to)
+            }
+            TransactionRequest::Sponsored { typed_data, .. } => typed_data
+                .message
+                .get("to")
+                .and_then(|to| to.as_str())
+                .and_then(|to| H160::from_str(to).ok()),
+        }
+    }
+
+    /// Selector of the contract function the transaction calls, taken from its calldata.
+    /// Sponsored transactions carry typed data instead of calldata and have none.
+    pub fn function_selector(&self) -> Option<FunctionSelector> {
+        match self {
+            TransactionRequest::Legacy { data, .. } | TransactionRequest::Eip1559 { data, .. } => {
+                FunctionSelector::from_calldata(data)
+            }
+            TransactionRequest::Sponsored { .. } => None,
+        }
+    }
+
+    /// Native value the transaction moves. Sponsored transactions don't move any.
+    pub fn native_value(&self) -> U256 {
+        match self {
+            TransactionRequest::Legacy { value, .. }
+            | TransactionRequest::Eip1559 { value, .. } => *value,
+            TransactionRequest::Sponsored { .. } => U256::zero(),
+        }
+    }
+
     // NOTE: This should be in a business model struct and not in a DTO, but since we are using it
     // as both is here.
     // NOTE: This design is not correct if we are going to support transaction that does not
@@ -192,6 +230,9 @@ impl TransactionRequest {
 mod tests {
     use std::str::FromStr;
 
+    use common::test_tools::http::constants::ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS;
+    use model::order::helpers::sponsored_typed_data;
+
     use super::*;
 
     const TX_RLP_LEGACY: &str = "0xf83980820100833000009425dfe735c17fec1d86a458657189060d65be69a80194640651604161065132510616516510651616961083aa36a78080";
@@ -273,6 +314,43 @@ mod tests {
             Bytes::from(rlp)
         );
     }
+
+    #[test]
+    pub fn eip1559_policy_fields() {
+        let to = H160::from_str("0x25DFE735C17FEC1d86A458657189060D65Be69a8").unwrap();
+        let transaction = TransactionRequest::Eip1559 {
+            to,
+            gas: "300000".into(),
+            max_fee_per_gas: "100".into(),
+            max_priority_fee_per_gas: "80000".into(),
+            value: "1".into(),
+            nonce: 0.into(),
+            data: Bytes::from_str("0xa9059cbb0000").unwrap(),
+            chain_id: 11155111,
+        };
+
+        assert_eq!(Some(to), transaction.destination());
+        assert_eq!(
+            Some("0xa9059cbb".to_owned()),
+            transaction.function_selector().map(|s| s.to_string())
+        );
+        assert_eq!(U256::one(), transaction.native_value());
+    }
+
+    #[test]
+    pub fn sponsored_policy_fields() {
+        let transaction = TransactionRequest::Sponsored {
+            typed_data: serde_json::from_value(sponsored_typed_data()).unwrap(),
+            chain_id: 11155111,
+        };
+
+        assert_eq!(
+            Some(H160::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap()),
+            transaction.destination()
+        );
+        assert_eq!(None, transaction.function_selector());
+        assert_eq!(U256::zero(), transaction.native_value());
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, PartialEq, Eq, Clone)]
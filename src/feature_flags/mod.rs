@@ -0,0 +1,168 @@
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use common::config::ConfigLoader;
+use common::feature_flags::{
+    FeatureFlags, LaunchDarklyFeatureFlags, LocalFeatureFlags, LOG_LEVEL_FLAG,
+};
+use secrets_provider::SecretsProvider;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::config::GlobalConfig;
+
+/// Log level of invocations the log level flag doesn't apply to.
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::WARN;
+
+/// Client built by the first [`feature_flags_bootstrap`] call of the lambda.
+static FEATURE_FLAGS: OnceLock<Arc<dyn FeatureFlags>> = OnceLock::new();
+
+/// Builds the feature flags client lambdas use. Flags are read from `feature_flags_file` when set,
+/// or else from LaunchDarkly. Without either, every flag takes its default value, so gated
+/// features like chains are off.
+///
+/// The client is built once per lambda, so the lambda macros and the persisted state gating
+/// features share the same flags.
+pub async fn feature_flags_bootstrap(
+    secrets_provider: impl SecretsProvider,
+) -> Arc<dyn FeatureFlags> {
+    if let Some(feature_flags) = FEATURE_FLAGS.get() {
+        return feature_flags.clone();
+    }
+
+    let feature_flags = build_feature_flags(secrets_provider).await;
+    FEATURE_FLAGS.get_or_init(|| feature_flags).clone()
+}
+
+async fn build_feature_flags(secrets_provider: impl SecretsProvider) -> Arc<dyn FeatureFlags> {
+    let config = ConfigLoader::load_default::<GlobalConfig>().await;
+
+    if let Some(path) = config.feature_flags_file {
+        return Arc::new(
+            LocalFeatureFlags::from_file(&path).expect("unable to load feature flags file"),
+        );
+    }
+
+    match config.launchdarkly_sdk_key_secret_name {
+        Some(secret_name) => {
+            let sdk_key = secrets_provider
+                .find(&secret_name)
+                .await
+                .expect("Could not retrieve LaunchDarkly SDK key secret from AWS Secrets Manager.")
+                .expect("LaunchDarkly SDK key secret not found")
+                .reveal();
+            Arc::new(LaunchDarklyFeatureFlags::new(sdk_key))
+        }
+        None => Arc::<LocalFeatureFlags>::default(),
+    }
+}
+
+/// Log level of an invocation for `client_id`, taken from the log level flag so debug logs can be
+/// turned on for a single client without deploying.
+pub async fn invocation_log_level(
+    feature_flags: &dyn FeatureFlags,
+    client_id: Option<&str>,
+) -> LevelFilter {
+    feature_flags
+        .string_flag(LOG_LEVEL_FLAG, client_id)
+        .await
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(DEFAULT_LOG_LEVEL)
+}
+
+/// Whether the boolean flag gating a feature, such as a chain, an order type or an endpoint, is on
+/// for `client_id`. Flags that are missing or can't be evaluated take `default`, which is logged
+/// as an error since the feature is then on or off regardless of its flag.
+pub async fn is_feature_enabled(
+    feature_flags: &dyn FeatureFlags,
+    flag: &str,
+    client_id: &str,
+    default: bool,
+) -> bool {
+    match feature_flags.bool_flag(flag, Some(client_id)).await {
+        Some(enabled) => enabled,
+        None => {
+            tracing::error!(
+                flag,
+                client_id,
+                default,
+                "feature flag could not be evaluated, using its default"
+            );
+            default
+        }
+    }
+}
+
+/// State of lambdas gating features with feature flags, see
+/// [`crate::validations::http::supported_chain_id::validate_chain_id_is_enabled`].
+pub trait FeatureFlagsState {
+    fn feature_flags(&self) -> &dyn FeatureFlags;
+}
+
+#[cfg(test)]
+mod tests {
+    use common::feature_flags::LocalFeatureFlags;
+    use rstest::rstest;
+    use tracing_subscriber::filter::LevelFilter;
+
+    use super::{invocation_log_level, is_feature_enabled};
+
+    #[rstest]
+    #[case::flagged_client(Some("debugged_client"), LevelFilter::DEBUG)]
+    #[case::other_client(Some("other_client"), LevelFilter::ERROR)]
+    #[case::without_client(None, LevelFilter::ERROR)]
+    #[tokio::test]
+    async fn log_level_from_flag(#[case] client_id: Option<&str>, #[case] expected: LevelFilter) {
+        let feature_flags = LocalFeatureFlags::from_json(
+            r#"{ "log-level": { "default": "error", "clients": { "debugged_client": "debug" } } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected,
+            invocation_log_level(&feature_flags, client_id).await
+        );
+    }
+
+    #[rstest]
+    #[case::missing_flag("{}")]
+    #[case::invalid_level(r#"{ "log-level": "loud" }"#)]
+    #[tokio::test]
+    async fn default_log_level(#[case] flags: &str) {
+        let feature_flags = LocalFeatureFlags::from_json(flags).unwrap();
+
+        assert_eq!(
+            LevelFilter::WARN,
+            invocation_log_level(&feature_flags, Some("some_client")).await
+        );
+    }
+
+    #[rstest]
+    #[case::enabled_client("beta_client", true)]
+    #[case::other_client("other_client", false)]
+    #[tokio::test]
+    async fn feature_enabled_by_flag(#[case] client_id: &str, #[case] expected: bool) {
+        let feature_flags = LocalFeatureFlags::from_json(
+            r#"{ "new-feature": { "default": false, "clients": { "beta_client": true } } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            expected,
+            is_feature_enabled(&feature_flags, "new-feature", client_id, false).await
+        );
+    }
+
+    #[rstest]
+    #[case::missing_flag("{}", false)]
+    #[case::missing_flag_enabled_by_default("{}", true)]
+    #[case::not_a_boolean(r#"{ "new-feature": "off" }"#, false)]
+    #[tokio::test]
+    async fn feature_enabled_by_default(#[case] flags: &str, #[case] default: bool) {
+        let feature_flags = LocalFeatureFlags::from_json(flags).unwrap();
+
+        assert_eq!(
+            default,
+            is_feature_enabled(&feature_flags, "new-feature", "some_client", default).await
+        );
+    }
+}
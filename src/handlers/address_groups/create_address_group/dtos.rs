@@ -0,0 +1,10 @@
+use common::deserializers::h160::from_array_h160;
+use ethers::types::H160;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateAddressGroupRequest {
+    pub name: String,
+    #[serde(deserialize_with = "from_array_h160")]
+    pub addresses: Vec<H160>,
+}
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use dtos::CreateAddressGroupRequest;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_group::AddressGroup;
+use mpc_signature_sm::dtos::address_group::{
+    validate_group_addresses, validate_group_name, AddressGroupResponse,
+};
+use mpc_signature_sm::http::errors::{conflict_error_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::{AddressGroupsRepository, AddressGroupsRepositoryError};
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub struct State<AGR: AddressGroupsRepository> {
+    address_groups_repository: Arc<AGR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client,
+        ));
+
+        State {
+            address_groups_repository,
+        }
+    },
+    create_address_group,
+    [validate_content_type]
+);
+
+async fn create_address_group(
+    request: Request,
+    state: &State<impl AddressGroupsRepository>,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<CreateAddressGroupRequest>()?;
+    validate_group_name(&body.name)?;
+    validate_group_addresses(&body.addresses)?;
+    let client_id = request.extract_client_id()?;
+
+    let group = AddressGroup {
+        client_id,
+        name: body.name,
+        addresses: body.addresses,
+    };
+    let response = AddressGroupResponse::from(group.clone());
+
+    state
+        .address_groups_repository
+        .create_group(group)
+        .await
+        .map_err(|e| match e {
+            AddressGroupsRepositoryError::AlreadyExists(message) => {
+                conflict_error_response(message)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error saving address group. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting create address group response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::CREATED,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::Body;
+    use mpc_signature_sm::dtos::{
+        address_group::AddressGroupResponse, responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_groups::{AddressGroupsRepositoryError, MockAddressGroupsRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+
+    use crate::{create_address_group, State};
+
+    struct TestFixture {
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_address_group_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_create_group()
+            .once()
+            .withf(|group| {
+                group.client_id == CLIENT_ID_FOR_MOCK_REQUESTS
+                    && group.name == "exchanges"
+                    && group.contains(&Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap())
+                    && group.addresses.len() == 2
+            })
+            .returning(|_| Ok(()));
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request(json!({
+            "name": "exchanges",
+            "addresses": [ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS]
+        }));
+
+        let response = create_address_group(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: AddressGroupResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("exchanges", body.name);
+        assert_eq!(
+            vec![
+                ADDRESS_FOR_MOCK_REQUESTS.to_lowercase(),
+                ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS.to_lowercase()
+            ],
+            body.addresses
+        );
+    }
+
+    #[rstest]
+    #[case::invalid_name(
+        json!({ "name": "Exchanges", "addresses": [ADDRESS_FOR_MOCK_REQUESTS] }),
+        "name must have between 1 and 64 lowercase letters, digits, _ or -"
+    )]
+    #[case::no_addresses(
+        json!({ "name": "exchanges", "addresses": [] }),
+        "addresses can't be empty"
+    )]
+    #[case::repeated_address(
+        json!({
+            "name": "exchanges",
+            "addresses": [ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_FOR_MOCK_REQUESTS]
+        }),
+        "addresses[1]: address is repeated in the request"
+    )]
+    #[tokio::test]
+    async fn create_address_group_invalid(
+        mut fixture: TestFixture,
+        #[case] body: Value,
+        #[case] expected_message: &str,
+    ) {
+        fixture
+            .mock_address_groups_repository
+            .expect_create_group()
+            .never();
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+
+        let response = create_address_group(build_request(body), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!(expected_message, body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_address_group_already_exists(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_create_group()
+            .once()
+            .returning(|group| {
+                Err(AddressGroupsRepositoryError::AlreadyExists(format!(
+                    "address group {} already exists",
+                    group.name
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request(json!({
+            "name": "exchanges",
+            "addresses": [ADDRESS_FOR_MOCK_REQUESTS]
+        }));
+
+        let response = create_address_group(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+    }
+}
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct Config {
+    pub address_groups_table_name: String,
+    pub address_policy_registry_table_name: String,
+    pub address_policy_registry_history_table_name: String,
+}
@@ -0,0 +1,9 @@
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct DeleteAddressGroupResponse {
+    pub name: String,
+}
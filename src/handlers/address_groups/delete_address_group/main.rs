@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use dtos::DeleteAddressGroupResponse;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::AddressPolicyRegistryType;
+use mpc_signature_sm::http::errors::{conflict_error_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::{AddressGroupsRepository, AddressGroupsRepositoryError};
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub const NAME_PATH_PARAM: &str = "name";
+
+pub struct State<AGR: AddressGroupsRepository, APRR: AddressPolicyRegistryRepository> {
+    address_groups_repository: Arc<AGR>,
+    address_policy_registry_repository: Arc<APRR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client,
+            ));
+
+        State {
+            address_groups_repository,
+            address_policy_registry_repository,
+        }
+    },
+    delete_address_group
+);
+
+/// Groups that GROUP mappings still point to can't be deleted, the mappings have to be deleted
+/// or moved to another group first.
+async fn delete_address_group(
+    request: Request,
+    state: &State<impl AddressGroupsRepository, impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let name: String = request.extract_path_param(NAME_PATH_PARAM)?;
+    let client_id = request.extract_client_id()?;
+
+    let now = Utc::now();
+    let group_type = AddressPolicyRegistryType::Group { name: name.clone() };
+    let referenced = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id.clone())
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mappings. {e:?}"
+            )))
+        })?
+        .iter()
+        // Expired mappings the TTL hasn't deleted yet don't match any transaction.
+        .filter(|mapping| !mapping.has_expired_at(now))
+        .any(|mapping| mapping.r#type == group_type);
+    if referenced {
+        return Err(conflict_error_response(format!(
+            "address group {name} is used by policy mappings"
+        )));
+    }
+
+    state
+        .address_groups_repository
+        .delete_group(client_id, name.clone())
+        .await
+        .map_err(|e| match e {
+            AddressGroupsRepositoryError::Conflict(message) => conflict_error_response(message),
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error deleting address group. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&DeleteAddressGroupResponse { name }).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting delete address group response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::{
+        constants::{CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS},
+        helpers::build_request_custom_auth,
+    };
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
+    use mpc_signature_sm::dtos::responses::http_error::LambdaErrorResponse;
+    use repositories::address_groups::{AddressGroupsRepositoryError, MockAddressGroupsRepository};
+    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+
+    use crate::{delete_address_group, dtos::DeleteAddressGroupResponse, State, NAME_PATH_PARAM};
+
+    struct TestFixture {
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+        }
+    }
+
+    fn group_mapping(name: &str) -> AddressPolicyRegistry {
+        AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "some_policy".to_owned(),
+        )
+        .group(name.to_owned())
+    }
+
+    fn build_request(name: &str) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let path_params = HashMap::from([(NAME_PATH_PARAM.to_owned(), name.to_owned())]);
+
+        build_request_custom_auth(auth, Body::default()).with_path_parameters(path_params)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_address_group_ok(mut fixture: TestFixture) {
+        let expired = AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "some_policy".to_owned(),
+        )
+        .active_between(None, Some(Utc::now() - Duration::hours(1)))
+        .group("exchanges".to_owned());
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .withf(|client_id| client_id == CLIENT_ID_FOR_MOCK_REQUESTS)
+            .returning(move |_| Ok(vec![group_mapping("custodians"), expired.clone()]));
+        fixture
+            .mock_address_groups_repository
+            .expect_delete_group()
+            .once()
+            .withf(|client_id, name| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS && name == "exchanges"
+            })
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = delete_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: DeleteAddressGroupResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("exchanges", body.name);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_address_group_repository_error(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(vec![]));
+        fixture
+            .mock_address_groups_repository
+            .expect_delete_group()
+            .once()
+            .returning(|_, _| {
+                Err(AddressGroupsRepositoryError::Unknown(anyhow::anyhow!(
+                    "timeout!"
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = delete_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_address_group_used_by_mappings(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(vec![group_mapping("exchanges")]));
+        fixture
+            .mock_address_groups_repository
+            .expect_delete_group()
+            .never();
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = delete_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("conflict", body.code);
+        assert_eq!(
+            "address group exchanges is used by policy mappings",
+            body.message
+        );
+    }
+}
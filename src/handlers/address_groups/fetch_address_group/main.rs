@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use mpc_signature_sm::dtos::address_group::AddressGroupResponse;
+use mpc_signature_sm::http::errors::{not_found_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+
+use crate::config::Config;
+
+mod config;
+
+pub const ADDRESS_GROUP_NOT_FOUND_CODE: &str = "address_group_not_found";
+pub const NAME_PATH_PARAM: &str = "name";
+
+pub struct State<AGR: AddressGroupsRepository> {
+    address_groups_repository: Arc<AGR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client,
+        ));
+
+        State {
+            address_groups_repository,
+        }
+    },
+    fetch_address_group
+);
+
+async fn fetch_address_group(
+    request: Request,
+    state: &State<impl AddressGroupsRepository>,
+) -> HttpLambdaResponse {
+    let name: String = request.extract_path_param(NAME_PATH_PARAM)?;
+    let client_id = request.extract_client_id()?;
+
+    let group = state
+        .address_groups_repository
+        .get_group(client_id, name.clone())
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address group. {e:?}"
+            )))
+        })?
+        .ok_or_else(|| {
+            not_found_response(
+                ADDRESS_GROUP_NOT_FOUND_CODE,
+                format!("there was no address group found with name {name}"),
+            )
+        })?;
+
+    let response = serde_json::to_string(&AddressGroupResponse::from(group)).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting address group response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::{collections::HashMap, sync::Arc};
+
+    use common::test_tools::http::{
+        constants::{ADDRESS_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS},
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_group::AddressGroup;
+    use mpc_signature_sm::dtos::{
+        address_group::AddressGroupResponse, responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_groups::{AddressGroupsRepositoryError, MockAddressGroupsRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+
+    use crate::{fetch_address_group, State, ADDRESS_GROUP_NOT_FOUND_CODE, NAME_PATH_PARAM};
+
+    struct TestFixture {
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+        }
+    }
+
+    fn build_request(name: &str) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let path_params = HashMap::from([(NAME_PATH_PARAM.to_owned(), name.to_owned())]);
+
+        build_request_custom_auth(auth, Body::default()).with_path_parameters(path_params)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_address_group_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .withf(|client_id, name| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS && name == "exchanges"
+            })
+            .returning(|client_id, name| {
+                Ok(Some(AddressGroup {
+                    client_id,
+                    name,
+                    addresses: vec![Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()],
+                }))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+
+        let response = fetch_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: AddressGroupResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            AddressGroupResponse {
+                name: "exchanges".to_owned(),
+                addresses: vec![ADDRESS_FOR_MOCK_REQUESTS.to_owned()],
+            },
+            body
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_address_group_not_found(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .returning(|_, _| Ok(None));
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+
+        let response = fetch_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(ADDRESS_GROUP_NOT_FOUND_CODE, body.code);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_address_group_repository_error(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .returning(|_, _| {
+                Err(AddressGroupsRepositoryError::Unknown(anyhow::anyhow!(
+                    "timeout!"
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+
+        let response = fetch_address_group(build_request("exchanges"), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+}
@@ -0,0 +1,10 @@
+use mpc_signature_sm::dtos::address_group::AddressGroupResponse;
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct FetchAllAddressGroupsResponse {
+    pub groups: Vec<AddressGroupResponse>,
+}
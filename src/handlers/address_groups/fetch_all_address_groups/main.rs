@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use dtos::FetchAllAddressGroupsResponse;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use mpc_signature_sm::dtos::address_group::AddressGroupResponse;
+use mpc_signature_sm::http::errors::unknown_error_response;
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub struct State<AGR: AddressGroupsRepository> {
+    address_groups_repository: Arc<AGR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client,
+        ));
+
+        State {
+            address_groups_repository,
+        }
+    },
+    fetch_all_address_groups
+);
+
+async fn fetch_all_address_groups(
+    request: Request,
+    state: &State<impl AddressGroupsRepository>,
+) -> HttpLambdaResponse {
+    let client_id = request.extract_client_id()?;
+
+    let groups = state
+        .address_groups_repository
+        .get_all_groups(client_id)
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address groups. {e:?}"
+            )))
+        })?;
+
+    let response = serde_json::to_string(&FetchAllAddressGroupsResponse {
+        groups: groups.into_iter().map(AddressGroupResponse::from).collect(),
+    })
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting address groups response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::StatusCode;
+    use lambda_http::Body;
+    use model::address_group::AddressGroup;
+    use repositories::address_groups::{AddressGroupsRepositoryError, MockAddressGroupsRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+
+    use crate::{dtos::FetchAllAddressGroupsResponse, fetch_all_address_groups, State};
+
+    struct TestFixture {
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+        }
+    }
+
+    fn group(name: &str, address: &str) -> AddressGroup {
+        AddressGroup {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            name: name.to_owned(),
+            addresses: vec![Address::from_str(address).unwrap()],
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_address_groups_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_get_all_groups()
+            .once()
+            .withf(|client_id| client_id == CLIENT_ID_FOR_MOCK_REQUESTS)
+            .returning(|_| {
+                Ok(vec![
+                    group("exchanges", ADDRESS_FOR_MOCK_REQUESTS),
+                    group("custodians", ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS),
+                ])
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+
+        let response =
+            fetch_all_address_groups(build_request_custom_auth(auth, Body::default()), &state)
+                .await
+                .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchAllAddressGroupsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(2, body.groups.len());
+        assert_eq!("exchanges", body.groups[0].name);
+        assert_eq!(
+            vec![ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS.to_lowercase()],
+            body.groups[1].addresses
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_address_groups_repository_error(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_get_all_groups()
+            .once()
+            .returning(|_| {
+                Err(AddressGroupsRepositoryError::Unknown(anyhow::anyhow!(
+                    "timeout!"
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+
+        let response =
+            fetch_all_address_groups(build_request_custom_auth(auth, Body::default()), &state)
+                .await
+                .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+}
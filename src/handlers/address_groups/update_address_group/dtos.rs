@@ -0,0 +1,10 @@
+use common::deserializers::h160::from_array_h160;
+use ethers::types::H160;
+use serde::Deserialize;
+
+/// The addresses replace the ones the group had.
+#[derive(Deserialize)]
+pub struct UpdateAddressGroupRequest {
+    #[serde(deserialize_with = "from_array_h160")]
+    pub addresses: Vec<H160>,
+}
@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use dtos::UpdateAddressGroupRequest;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_group::AddressGroup;
+use mpc_signature_sm::dtos::address_group::{
+    validate_group_addresses, validate_group_name, AddressGroupResponse,
+};
+use mpc_signature_sm::http::errors::{
+    conflict_error_response, not_found_response, unknown_error_response,
+};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::{AddressGroupsRepository, AddressGroupsRepositoryError};
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub const ADDRESS_GROUP_NOT_FOUND_CODE: &str = "address_group_not_found";
+pub const NAME_PATH_PARAM: &str = "name";
+
+pub struct State<AGR: AddressGroupsRepository> {
+    address_groups_repository: Arc<AGR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client,
+        ));
+
+        State {
+            address_groups_repository,
+        }
+    },
+    update_address_group,
+    [validate_content_type]
+);
+
+async fn update_address_group(
+    request: Request,
+    state: &State<impl AddressGroupsRepository>,
+) -> HttpLambdaResponse {
+    let name: String = request.extract_path_param(NAME_PATH_PARAM)?;
+    validate_group_name(&name)?;
+    let body = request.extract_body::<UpdateAddressGroupRequest>()?;
+    validate_group_addresses(&body.addresses)?;
+    let client_id = request.extract_client_id()?;
+
+    let group = AddressGroup {
+        client_id,
+        name,
+        addresses: body.addresses,
+    };
+    let response = AddressGroupResponse::from(group.clone());
+
+    state
+        .address_groups_repository
+        .update_group(group)
+        .await
+        .map_err(|e| match e {
+            AddressGroupsRepositoryError::GroupNotFound(message) => {
+                not_found_response(ADDRESS_GROUP_NOT_FOUND_CODE, message)
+            }
+            AddressGroupsRepositoryError::Conflict(message) => conflict_error_response(message),
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error updating address group. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting update address group response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use mpc_signature_sm::dtos::{
+        address_group::AddressGroupResponse, responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_groups::{AddressGroupsRepositoryError, MockAddressGroupsRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+
+    use crate::{update_address_group, State, ADDRESS_GROUP_NOT_FOUND_CODE, NAME_PATH_PARAM};
+
+    struct TestFixture {
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+        }
+    }
+
+    fn build_request(name: &str, body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let path_params = HashMap::from([(NAME_PATH_PARAM.to_owned(), name.to_owned())]);
+
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+            .with_path_parameters(path_params)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_address_group_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_update_group()
+            .once()
+            .withf(|group| {
+                group.client_id == CLIENT_ID_FOR_MOCK_REQUESTS
+                    && group.name == "exchanges"
+                    && group.addresses.len() == 1
+            })
+            .returning(|_| Ok(()));
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request(
+            "exchanges",
+            json!({ "addresses": [ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS] }),
+        );
+
+        let response = update_address_group(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: AddressGroupResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            vec![ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS.to_lowercase()],
+            body.addresses
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_address_group_not_found(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_update_group()
+            .once()
+            .returning(|group| {
+                Err(AddressGroupsRepositoryError::GroupNotFound(format!(
+                    "address group {} not found",
+                    group.name
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request(
+            "exchanges",
+            json!({ "addresses": [ADDRESS_FOR_MOCK_REQUESTS] }),
+        );
+
+        let response = update_address_group(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(ADDRESS_GROUP_NOT_FOUND_CODE, body.code);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_address_group_conflict(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_update_group()
+            .once()
+            .returning(|group| {
+                Err(AddressGroupsRepositoryError::Conflict(format!(
+                    "address group {} kept changing while it was updated",
+                    group.name
+                )))
+            });
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request(
+            "exchanges",
+            json!({ "addresses": [ADDRESS_FOR_MOCK_REQUESTS] }),
+        );
+
+        let response = update_address_group(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_address_group_without_addresses(mut fixture: TestFixture) {
+        fixture
+            .mock_address_groups_repository
+            .expect_update_group()
+            .never();
+
+        let state = State {
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+        };
+        let request = build_request("exchanges", json!({ "addresses": [] }));
+
+        let response = update_address_group(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("addresses can't be empty", body.message);
+    }
+}
@@ -0,0 +1,14 @@
+use mpc_signature_sm::dtos::policy_mapping_plan::PolicyMappingPlanResponse;
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub struct ApplyPolicyMappingsResponse {
+    /// Changes that were written.
+    #[serde(flatten)]
+    pub plan: PolicyMappingPlanResponse,
+
+    /// Whether every change was written in a single transaction. Bigger plans are only written,
+    /// in several transactions, when the caller allows it.
+    pub atomic: bool,
+}
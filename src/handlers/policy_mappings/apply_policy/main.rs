@@ -0,0 +1,629 @@
+use std::sync::Arc;
+
+use crate::config::Config;
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::ApplyPolicyMappingsResponse;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+use mpc_signature_sm::dtos::address_group::validate_mapping_groups_exist;
+use mpc_signature_sm::dtos::policy_mapping_plan::{
+    partially_applied_response, validate_plan_can_be_applied, PolicyMappingPlanRequest,
+    PolicyMappingPlanResponse, ALLOW_PARTIAL_QUERY_PARAM,
+};
+use mpc_signature_sm::http::errors::{conflict_error_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_policies_belong_to_client;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chains_are_enabled_for_client;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError, MAX_ATOMIC_PLAN_CHANGES,
+};
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+
+mod config;
+mod dtos;
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    address_policy_registry_repository: Arc<APRR>,
+    address_groups_repository: Arc<AGR>,
+    policy_catalog: PC,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    apply_policy,
+    [validate_content_type]
+);
+
+/// Takes the client's mappings to the ones in the request, creating, updating and deleting
+/// whatever is needed. Plans too big for a single transaction are only applied when the caller
+/// sets `allow_partial`, as a failure can leave them partially applied.
+async fn apply_policy(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<PolicyMappingPlanRequest>()?;
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+    let allow_partial = request
+        .extract_query_param::<bool>(ALLOW_PARTIAL_QUERY_PARAM)?
+        .unwrap_or(false);
+
+    let now = Utc::now();
+    let desired = body.desired_mappings(&client_id, now)?;
+    validate_mapping_chains_are_enabled_for_client(
+        &request,
+        desired.iter().map(|mapping| mapping.chain_id),
+        state.feature_flags.as_ref(),
+    )
+    .await?;
+
+    validate_policies_belong_to_client(
+        &state.policy_catalog,
+        &client_id,
+        desired.iter().flat_map(|mapping| mapping.policies()),
+    )
+    .await?;
+    validate_mapping_groups_exist(
+        state.address_groups_repository.as_ref(),
+        &client_id,
+        desired.iter().map(|mapping| &mapping.r#type),
+    )
+    .await?;
+
+    let current = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id)
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mappings. {e:?}"
+            )))
+        })?;
+
+    let plan = AddressPolicyRegistryPlan::between(current, desired, now);
+    let atomic = plan.len() <= MAX_ATOMIC_PLAN_CHANGES;
+    let planned = PolicyMappingPlanResponse::from(&plan);
+    validate_plan_can_be_applied(&plan, allow_partial)?;
+
+    if !plan.is_empty() {
+        state
+            .address_policy_registry_repository
+            .apply_policy_plan(plan, subject)
+            .await
+            .map_err(|e| match e {
+                AddressPolicyRegistryRepositoryError::VersionMismatch(message) => {
+                    conflict_error_response(message)
+                }
+                AddressPolicyRegistryRepositoryError::PartiallyApplied(applied, cause) => {
+                    partially_applied_response(&applied, cause)
+                }
+                e => unknown_error_response(LambdaError::Unknown(
+                    anyhow::anyhow!(e).context("applying policy mapping plan"),
+                )),
+            })?;
+    }
+
+    let response = serde_json::to_string(&ApplyPolicyMappingsResponse {
+        plan: planned,
+        atomic,
+    })
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting apply policy mappings response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
+    use mpc_signature_sm::{
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::{
+            policy_mapping_plan::{
+                PartiallyAppliedResponse, ALLOW_PARTIAL_QUERY_PARAM, PARTIALLY_APPLIED_ERROR_CODE,
+            },
+            policy_mapping_type::MappingType,
+            responses::http_error::LambdaErrorResponse,
+        },
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+        MAX_ATOMIC_PLAN_CHANGES,
+    };
+    use repositories::cache::MockCacheRepositoryTest;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{apply_policy, dtos::ApplyPolicyMappingsResponse, State};
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy(policy_name: &str, status: StatusCode, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    fn stored_mappings() -> Vec<AddressPolicyRegistry> {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let builder = |policy: &str| {
+            AddressPolicyRegistryBuilder::new(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                policy.to_owned(),
+            )
+        };
+
+        vec![
+            builder("default_policy").default(),
+            builder("old_policy").address_to(address),
+            builder("old_policy").address_from(address),
+        ]
+    }
+
+    /// More stored mappings than fit in a single transaction.
+    fn many_stored_mappings() -> Vec<AddressPolicyRegistry> {
+        (0..=MAX_ATOMIC_PLAN_CHANGES as u64)
+            .map(|index| {
+                AddressPolicyRegistryBuilder::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "old_policy".to_owned(),
+                )
+                .address_to(Address::from_low_u64_be(index + 1))
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "default_policy" },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "new_policy", "address": ADDRESS_FOR_MOCK_REQUESTS },
+                { "chain_id": 137, "policy": "new_policy" },
+            ]
+        }));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .withf(|plan, subject| {
+                plan.creates.len() == 1
+                    && plan.updates.len() == 1
+                    && plan.updates[0].desired.version == plan.updates[0].current.version + 1
+                    && plan.deletes.len() == 1
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ApplyPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.atomic);
+        assert_eq!(1, body.plan.creates.len());
+        assert_eq!("old_policy", body.plan.updates[0].previous_policy);
+        assert_eq!(MappingType::AddressFrom, body.plan.deletes[0].r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_nothing_to_change(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "default_policy" },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "old_policy", "address": ADDRESS_FOR_MOCK_REQUESTS },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "old_policy", "address": ADDRESS_FOR_MOCK_REQUESTS, "type": "ADDRESS_FROM" },
+            ]
+        }));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("old_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ApplyPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.plan.creates.is_empty());
+        assert!(body.plan.updates.is_empty());
+        assert!(body.plan.deletes.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_group_not_found(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "default_policy" },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "new_policy", "group": "exchanges" },
+            ]
+        }));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .withf(|client_id, name| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS && name == "exchanges"
+            })
+            .returning(|_, _| Ok(None));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("address group exchanges not found", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [
+                { "chain_id": "*", "policy": "default_policy" },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "new_policy" },
+            ]
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags().with_flag(
+                &chain_feature_flag(CHAIN_ID_FOR_MOCK_REQUESTS),
+                json!(false),
+            )),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("forbidden", body.code);
+        assert_eq!(
+            format!("chain_id {CHAIN_ID_FOR_MOCK_REQUESTS} is not enabled for this client"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_concurrent_change(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [{ "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "default_policy" }]
+        }));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .returning(|_, _| {
+                Err(AddressPolicyRegistryRepositoryError::VersionMismatch(
+                    "policy mappings changed since the plan was computed".to_owned(),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            "policy mappings changed since the plan was computed",
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_not_atomic_without_allow_partial(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({ "mappings": [] }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(many_stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.message.contains(ALLOW_PARTIAL_QUERY_PARAM));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_not_atomic_allowed(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request =
+            build_request(json!({ "mappings": [] })).with_query_string_parameters(HashMap::from([
+                (ALLOW_PARTIAL_QUERY_PARAM.to_owned(), "true".to_owned()),
+            ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(many_stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .withf(|plan, _| plan.deletes.len() == MAX_ATOMIC_PLAN_CHANGES + 1)
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ApplyPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(!body.atomic);
+        assert_eq!(MAX_ATOMIC_PLAN_CHANGES + 1, body.plan.deletes.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn apply_policy_partially_applied(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request =
+            build_request(json!({ "mappings": [] })).with_query_string_parameters(HashMap::from([
+                (ALLOW_PARTIAL_QUERY_PARAM.to_owned(), "true".to_owned()),
+            ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(many_stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .returning(|plan, _| {
+                let applied = AddressPolicyRegistryPlan {
+                    deletes: plan.deletes[..MAX_ATOMIC_PLAN_CHANGES].to_vec(),
+                    ..AddressPolicyRegistryPlan::default()
+                };
+                Err(AddressPolicyRegistryRepositoryError::PartiallyApplied(
+                    Box::new(applied),
+                    anyhow::anyhow!("policy mappings changed since the plan was computed"),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = apply_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let body: PartiallyAppliedResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(PARTIALLY_APPLIED_ERROR_CODE, body.code);
+        assert_eq!(MAX_ATOMIC_PLAN_CHANGES, body.applied.deletes.len());
+        assert!(body.applied.creates.is_empty());
+    }
+}
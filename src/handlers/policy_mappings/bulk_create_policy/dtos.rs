@@ -0,0 +1,154 @@
+use common::deserializers::h160::h160_option;
+use ethers::types::H160;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::value_band::PolicyValueBand;
+use model::address_policy_registry::AddressPolicyRegistry;
+use mpc_signature_sm::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, serialize_optional_mapping_chain_id,
+};
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingType,
+};
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+use mpc_signature_sm::validations::http::supported_chain_id::is_supported_mapping_chain_id;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize)]
+pub struct BulkCreatePolicyMappingsRequest {
+    /// Mappings are deserialized one by one so a malformed item doesn't fail the whole request.
+    pub mappings: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct BulkCreatePolicyMappingItem {
+    #[serde(default, deserialize_with = "h160_option")]
+    pub address: Option<H160>,
+
+    /// A chain id, or `*` for a client wide DEFAULT mapping.
+    #[serde(deserialize_with = "deserialize_mapping_chain_id")]
+    #[validate(custom = "is_supported_mapping_chain_id")]
+    pub chain_id: u64,
+
+    pub policy: String,
+
+    /// Function selector of CONTRACT_CALL mappings.
+    #[serde(default)]
+    pub selector: Option<FunctionSelector>,
+
+    /// Key of KEY_ID mappings.
+    #[serde(default)]
+    pub key_id: Option<Uuid>,
+
+    /// End user of CLIENT_USER mappings.
+    #[serde(default)]
+    pub client_user_id: Option<String>,
+
+    /// Address group of GROUP mappings.
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// When not present the type is inferred from the other fields, see
+    /// [`MappingType::inferred_from`].
+    #[serde(default)]
+    pub r#type: Option<MappingType>,
+
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+
+    /// Bands sorted by their upper bound picking another policy depending on the transaction
+    /// value. `policy` applies to values above every band.
+    #[serde(default)]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BulkCreateStatus {
+    Created,
+    Duplicate,
+    Invalid,
+    /// The mapping was valid but writing it failed, it can be retried.
+    Failed,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct BulkCreatePolicyMappingResult {
+    /// Position of the mapping in the request.
+    pub index: usize,
+    pub status: BulkCreateStatus,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_mapping_chain_id"
+    )]
+    pub chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<MappingType>,
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BulkCreatePolicyMappingResult {
+    pub fn for_mapping(
+        index: usize,
+        mapping: &AddressPolicyRegistry,
+        status: BulkCreateStatus,
+        reason: Option<String>,
+    ) -> Self {
+        Self {
+            index,
+            status,
+            chain_id: Some(mapping.chain_id),
+            address: Some(mapping_address_to_string(&mapping.r#type)),
+            selector: mapping_selector_to_string(&mapping.r#type),
+            key_id: mapping_key_id(&mapping.r#type),
+            client_user_id: mapping_client_user_id(&mapping.r#type),
+            group: mapping_group(&mapping.r#type),
+            policy: Some(mapping.policy.clone()),
+            r#type: Some(MappingType::from(&mapping.r#type)),
+            window: PolicyMappingWindow::from(mapping),
+            reason,
+        }
+    }
+
+    pub fn unparseable(index: usize, reason: String) -> Self {
+        Self {
+            index,
+            status: BulkCreateStatus::Invalid,
+            chain_id: None,
+            address: None,
+            selector: None,
+            key_id: None,
+            client_user_id: None,
+            group: None,
+            policy: None,
+            r#type: None,
+            window: PolicyMappingWindow::default(),
+            reason: Some(reason),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct BulkCreatePolicyMappingsResponse {
+    pub results: Vec<BulkCreatePolicyMappingResult>,
+}
@@ -0,0 +1,835 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::{
+    BulkCreatePolicyMappingItem, BulkCreatePolicyMappingResult, BulkCreatePolicyMappingsRequest,
+    BulkCreatePolicyMappingsResponse, BulkCreateStatus,
+};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::{
+    AddressPolicyRegistry, AddressPolicyRegistryBuilder, AddressPolicyRegistryType, ANY_CHAIN_ID,
+};
+use mpc_signature_sm::config::SupportedChain;
+use mpc_signature_sm::dtos::address_group::missing_mapping_groups;
+use mpc_signature_sm::dtos::policy_mapping_chain::try_validate_mapping_chain;
+use mpc_signature_sm::dtos::policy_mapping_type::{MappingTarget, MappingType};
+use mpc_signature_sm::dtos::policy_value_bands::try_validate_value_bands;
+use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::check_policies_belong_to_client;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryPk, AddressPolicyRegistryRepository, PutPolicyOutcome,
+};
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+use validator::Validate;
+
+mod config;
+mod dtos;
+
+pub const MAX_BULK_MAPPINGS: usize = 500;
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    address_policy_registry_repository: Arc<APRR>,
+    address_groups_repository: Arc<AGR>,
+    policy_catalog: PC,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    bulk_create_policy,
+    [validate_content_type]
+);
+
+async fn bulk_create_policy(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<BulkCreatePolicyMappingsRequest>()?;
+    if body.mappings.is_empty() || body.mappings.len() > MAX_BULK_MAPPINGS {
+        return Err(validation_error_response(
+            format!("mappings must contain between 1 and {MAX_BULK_MAPPINGS} items"),
+            None,
+        ));
+    }
+
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+    let now = Utc::now();
+    let mut results = Vec::with_capacity(body.mappings.len());
+
+    let mut mappings = Vec::with_capacity(body.mappings.len());
+    for (index, item) in body.mappings.into_iter().enumerate() {
+        match parse_mapping(&client_id, item, now) {
+            Ok(mapping) => mappings.push((index, mapping)),
+            Err(reason) => results.push(BulkCreatePolicyMappingResult::unparseable(index, reason)),
+        }
+    }
+
+    // Each distinct chain is checked against its feature flag only once.
+    let mut enabled_chains: HashMap<u64, bool> = HashMap::new();
+    for (_, mapping) in &mappings {
+        if mapping.chain_id != ANY_CHAIN_ID && !enabled_chains.contains_key(&mapping.chain_id) {
+            let is_enabled = mapping
+                .chain_id
+                .is_enabled_for_client(state.feature_flags.as_ref(), &client_id)
+                .await;
+            enabled_chains.insert(mapping.chain_id, is_enabled);
+        }
+    }
+
+    let valid_policies = check_policies_belong_to_client(
+        &state.policy_catalog,
+        &client_id,
+        mappings.iter().flat_map(|(_, mapping)| mapping.policies()),
+    )
+    .await?;
+    let missing_groups = missing_mapping_groups(
+        state.address_groups_repository.as_ref(),
+        &client_id,
+        mappings.iter().map(|(_, mapping)| &mapping.r#type),
+    )
+    .await?;
+
+    let mut requested_keys = HashSet::new();
+    let mut candidates = Vec::with_capacity(mappings.len());
+    for (index, mapping) in mappings {
+        let key = mapping_key(&mapping);
+        let invalid_policy = mapping.policies().find(|policy| !valid_policies[*policy]);
+        let missing_group = match &mapping.r#type {
+            AddressPolicyRegistryType::Group { name } if missing_groups.contains(name) => {
+                Some(name)
+            }
+            _ => None,
+        };
+        if enabled_chains.get(&mapping.chain_id) == Some(&false) {
+            let reason = format!(
+                "chain_id {} is not enabled for this client",
+                mapping.chain_id
+            );
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index,
+                &mapping,
+                BulkCreateStatus::Invalid,
+                Some(reason),
+            ));
+        } else if let Some(policy) = invalid_policy {
+            let reason = format!(r#"invalid policy "{policy}""#);
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index,
+                &mapping,
+                BulkCreateStatus::Invalid,
+                Some(reason),
+            ));
+        } else if let Some(name) = missing_group {
+            let reason = format!("address group {name} not found");
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index,
+                &mapping,
+                BulkCreateStatus::Invalid,
+                Some(reason),
+            ));
+        } else if !requested_keys.insert(key.clone()) {
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index,
+                &mapping,
+                BulkCreateStatus::Duplicate,
+                Some("mapping is repeated in the request".to_owned()),
+            ));
+        } else {
+            candidates.push((index, key, mapping));
+        }
+    }
+
+    let existing_keys: HashSet<AddressPolicyRegistryPk> = if candidates.is_empty() {
+        HashSet::new()
+    } else {
+        state
+            .address_policy_registry_repository
+            .get_policies_by_keys(candidates.iter().map(|(_, key, _)| key.clone()).collect())
+            .await
+            .map_err(|e| {
+                unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                    "there was an error fetching existing address policy mappings. {e:?}"
+                )))
+            })?
+            .iter()
+            // Expired mappings the TTL hasn't deleted yet can be replaced.
+            .filter(|mapping| !mapping.has_expired_at(now))
+            .map(mapping_key)
+            .collect()
+    };
+
+    let mut to_create = Vec::with_capacity(candidates.len());
+    for (index, key, mapping) in candidates {
+        if existing_keys.contains(&key) {
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index,
+                &mapping,
+                BulkCreateStatus::Duplicate,
+                Some("mapping already exists".to_owned()),
+            ));
+        } else {
+            to_create.push((index, mapping));
+        }
+    }
+
+    if !to_create.is_empty() {
+        let (indexes, mappings): (Vec<_>, Vec<_>) = to_create.into_iter().unzip();
+        let outcomes = state
+            .address_policy_registry_repository
+            .put_policies(mappings.clone(), subject)
+            .await;
+
+        for ((index, mapping), outcome) in indexes.into_iter().zip(mappings).zip(outcomes) {
+            let (status, reason) = match outcome {
+                PutPolicyOutcome::Created => (BulkCreateStatus::Created, None),
+                // Created by another request since the existing mappings were fetched.
+                PutPolicyOutcome::AlreadyExists => (
+                    BulkCreateStatus::Duplicate,
+                    Some("mapping already exists".to_owned()),
+                ),
+                PutPolicyOutcome::Failed => (
+                    BulkCreateStatus::Failed,
+                    Some("mapping could not be saved".to_owned()),
+                ),
+            };
+            results.push(BulkCreatePolicyMappingResult::for_mapping(
+                index, &mapping, status, reason,
+            ));
+        }
+    }
+
+    results.sort_by_key(|result| result.index);
+    let response =
+        serde_json::to_string(&BulkCreatePolicyMappingsResponse { results }).map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(
+                anyhow::anyhow!(e).context("converting bulk create policy mappings response"),
+            ))
+        })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+fn parse_mapping(
+    client_id: &str,
+    item: serde_json::Value,
+    now: DateTime<Utc>,
+) -> Result<AddressPolicyRegistry, String> {
+    let item: BulkCreatePolicyMappingItem =
+        serde_json::from_value(item).map_err(|e| e.to_string())?;
+    item.validate().map_err(|e| e.to_string())?;
+    item.window.try_validate(now)?;
+    try_validate_value_bands(&item.value_bands)?;
+
+    let target = MappingTarget {
+        address: item.address,
+        selector: item.selector,
+        key_id: item.key_id,
+        client_user_id: item.client_user_id,
+        group: item.group,
+    };
+    let mapping_type = item
+        .r#type
+        .unwrap_or_else(|| MappingType::inferred_from(&target))
+        .try_into_registry_type(target)?;
+    try_validate_mapping_chain(item.chain_id, &mapping_type)?;
+    Ok(
+        AddressPolicyRegistryBuilder::new(client_id.to_owned(), item.chain_id, item.policy)
+            .active_between(item.window.effective_from, item.window.expires_at)
+            .value_bands(item.value_bands)
+            .build(mapping_type),
+    )
+}
+
+fn mapping_key(mapping: &AddressPolicyRegistry) -> AddressPolicyRegistryPk {
+    AddressPolicyRegistryPk::from_type(mapping.client_id.clone(), mapping.chain_id, &mapping.r#type)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::Body;
+    use model::address_group::AddressGroup;
+    use model::address_policy_registry::{AddressPolicyRegistryBuilder, AddressPolicyRegistryType};
+    use mpc_signature_sm::{
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::responses::http_error::LambdaErrorResponse,
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::{
+        MockAddressPolicyRegistryRepository, PutPolicyOutcome,
+    };
+    use repositories::cache::MockCacheRepositoryTest;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        bulk_create_policy,
+        dtos::{BulkCreatePolicyMappingsResponse, BulkCreateStatus},
+        State,
+    };
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(mappings: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        let body = json!({ "mappings": mappings });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy(policy_name: &str, status: StatusCode, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+            { "address": ADDRESS_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "type": "ADDRESS_FROM" },
+            { "address": "invalid_address", "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+            { "address": ADDRESS_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "not_owned_policy" },
+            { "address": ADDRESS_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "type": "ADDRESS_FROM" },
+            { "address": ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy(
+            "not_owned_policy",
+            StatusCode::NOT_FOUND,
+            &fixture.mock_server,
+        )
+        .await;
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .once()
+            .withf(|keys| keys.len() == 3)
+            .returning(|_| {
+                Ok(vec![AddressPolicyRegistryBuilder::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "another_policy".to_owned(),
+                )
+                .address_to(
+                    Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+                )])
+            });
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .once()
+            .withf(|mappings, subject| {
+                subject.as_deref() == Some("user_sub")
+                    && mappings.len() == 2
+                    && mappings[0].r#type == AddressPolicyRegistryType::Default
+                    && mappings[1].r#type
+                        == AddressPolicyRegistryType::AddressFrom {
+                            address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                        }
+            })
+            .returning(|mappings, _| vec![PutPolicyOutcome::Created; mappings.len()]);
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        let statuses: Vec<BulkCreateStatus> =
+            body.results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            vec![
+                BulkCreateStatus::Created,
+                BulkCreateStatus::Created,
+                BulkCreateStatus::Invalid,
+                BulkCreateStatus::Invalid,
+                BulkCreateStatus::Duplicate,
+                BulkCreateStatus::Duplicate,
+            ],
+            statuses
+        );
+        assert_eq!(
+            Some(r#"invalid policy "not_owned_policy""#.to_owned()),
+            body.results[3].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_missing_group(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "group": "exchanges" },
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "group": "custodians" },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .times(2)
+            .returning(|client_id, name| {
+                Ok((name == "exchanges").then(|| AddressGroup {
+                    client_id,
+                    name,
+                    addresses: vec![Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()],
+                }))
+            });
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .once()
+            .withf(|keys| keys.len() == 1)
+            .returning(|_| Ok(vec![]));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .once()
+            .withf(|mappings, _| {
+                mappings.len() == 1
+                    && mappings[0].r#type
+                        == AddressPolicyRegistryType::Group {
+                            name: "exchanges".to_owned(),
+                        }
+            })
+            .returning(|mappings, _| vec![PutPolicyOutcome::Created; mappings.len()]);
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        let statuses: Vec<BulkCreateStatus> =
+            body.results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            vec![BulkCreateStatus::Created, BulkCreateStatus::Invalid],
+            statuses
+        );
+        assert_eq!(
+            Some("address group custodians not found".to_owned()),
+            body.results[1].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+            { "chain_id": 137, "policy": "some_policy" },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .once()
+            .withf(|keys| keys.len() == 1)
+            .returning(|_| Ok(vec![]));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .once()
+            .withf(|mappings, _| {
+                mappings.len() == 1 && mappings[0].chain_id == CHAIN_ID_FOR_MOCK_REQUESTS
+            })
+            .returning(|mappings, _| vec![PutPolicyOutcome::Created; mappings.len()]);
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(
+                supported_chains_feature_flags().with_flag(&chain_feature_flag(137), json!(false)),
+            ),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        let statuses: Vec<BulkCreateStatus> =
+            body.results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            vec![BulkCreateStatus::Created, BulkCreateStatus::Invalid],
+            statuses
+        );
+        assert_eq!(
+            Some("chain_id 137 is not enabled for this client".to_owned()),
+            body.results[1].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_write_outcomes(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+            { "address": ADDRESS_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+            { "address": ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .once()
+            .returning(|_| Ok(vec![]));
+        // The second mapping was created by another request after the existing ones were fetched.
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .once()
+            .returning(|_, _| {
+                vec![
+                    PutPolicyOutcome::Created,
+                    PutPolicyOutcome::AlreadyExists,
+                    PutPolicyOutcome::Failed,
+                ]
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        let statuses: Vec<BulkCreateStatus> =
+            body.results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            vec![
+                BulkCreateStatus::Created,
+                BulkCreateStatus::Duplicate,
+                BulkCreateStatus::Failed,
+            ],
+            statuses
+        );
+        assert_eq!(
+            Some("mapping could not be saved".to_owned()),
+            body.results[2].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_time_bounded(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let expires_at = Utc::now() + Duration::hours(1);
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "expires_at": Utc::now() - Duration::hours(1) },
+            { "address": ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "expires_at": expires_at },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+
+        // The stored mapping already expired, it's replaced by the new one.
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .once()
+            .returning(|_| {
+                Ok(vec![AddressPolicyRegistryBuilder::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "another_policy".to_owned(),
+                )
+                .active_between(None, Some(Utc::now() - Duration::minutes(1)))
+                .address_to(
+                    Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap(),
+                )])
+            });
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .once()
+            .withf(move |mappings, _| {
+                mappings.len() == 1
+                    && mappings[0].effective_from.is_none()
+                    && mappings[0].expires_at == Some(expires_at)
+            })
+            .returning(|mappings, _| vec![PutPolicyOutcome::Created; mappings.len()]);
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(BulkCreateStatus::Invalid, body.results[0].status);
+        assert_eq!(
+            Some("expires_at must be in the future".to_owned()),
+            body.results[0].reason
+        );
+        assert_eq!(BulkCreateStatus::Created, body.results[1].status);
+        assert_eq!(Some(expires_at), body.results[1].window.expires_at);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_value_bands(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            {
+                "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+                "policy": "some_policy",
+                "value_bands": [{ "below": "1000", "policy": "missing_policy" }]
+            },
+            {
+                "address": ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+                "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+                "policy": "some_policy",
+                "value_bands": [
+                    { "below": "1000", "policy": "some_policy" },
+                    { "below": "1000", "policy": "some_policy" }
+                ]
+            },
+        ]));
+
+        mock_maestro_policy("some_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy(
+            "missing_policy",
+            StatusCode::NOT_FOUND,
+            &fixture.mock_server,
+        )
+        .await;
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .never();
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(BulkCreateStatus::Invalid, body.results[0].status);
+        assert_eq!(
+            Some(r#"invalid policy "missing_policy""#.to_owned()),
+            body.results[0].reason
+        );
+        assert_eq!(BulkCreateStatus::Invalid, body.results[1].status);
+        assert_eq!(
+            Some(
+                "value_bands[1]: overlaps value_bands[0], bands must be sorted by below".to_owned()
+            ),
+            body.results[1].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_nothing_to_create(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!([
+            { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "type": "ADDRESS_TO" },
+        ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_by_keys()
+            .never();
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: BulkCreatePolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(BulkCreateStatus::Invalid, body.results[0].status);
+        assert_eq!(
+            Some("ADDRESS_TO mappings require an address".to_owned()),
+            body.results[0].reason
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn bulk_create_policy_empty_request(#[future] fixture: TestFixture) {
+        let fixture = fixture.await;
+        let request = build_request(json!([]));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = bulk_create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+    }
+}
@@ -1,16 +1,80 @@
-use common::deserializers::h160::h160;
+use common::deserializers::h160::h160_option;
 use ethers::types::H160;
-use mpc_signature_sm::validations::http::supported_chain_id::is_supported_chain_id;
-use serde::Deserialize;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use model::address_policy_registry::value_band::PolicyValueBand;
+use mpc_signature_sm::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, serialize_mapping_chain_id,
+};
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+use mpc_signature_sm::validations::http::supported_chain_id::is_supported_mapping_chain_id;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use validator::Validate;
 
 #[derive(Deserialize, Validate)]
 pub struct CreatePolicyMappingRequest {
-    #[serde(default, deserialize_with = "h160")]
-    pub address: H160,
+    #[serde(default, deserialize_with = "h160_option")]
+    pub address: Option<H160>,
 
-    #[validate(custom = "is_supported_chain_id")]
+    /// A chain id, or `*` for a client wide DEFAULT mapping.
+    #[serde(deserialize_with = "deserialize_mapping_chain_id")]
+    #[validate(custom = "is_supported_mapping_chain_id")]
     pub chain_id: u64,
 
     pub policy: String,
+
+    /// Function selector of CONTRACT_CALL mappings.
+    #[serde(default)]
+    pub selector: Option<FunctionSelector>,
+
+    /// Key of KEY_ID mappings.
+    #[serde(default)]
+    pub key_id: Option<Uuid>,
+
+    /// End user of CLIENT_USER mappings.
+    #[serde(default)]
+    pub client_user_id: Option<String>,
+
+    /// Address group of GROUP mappings.
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// When not present the type is inferred from the other fields, see
+    /// [`MappingType::inferred_from`].
+    #[serde(default)]
+    pub r#type: Option<MappingType>,
+
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+
+    /// Bands sorted by their upper bound picking another policy depending on the transaction
+    /// value. `policy` applies to values above every band.
+    #[serde(default)]
+    pub value_bands: Vec<PolicyValueBand>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct CreatePolicyMappingResponse {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub policy: String,
+    pub r#type: MappingType,
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
 }
@@ -0,0 +1,151 @@
+use super::dtos::{CreatePolicyMappingRequest, CreatePolicyMappingResponse};
+use chrono::Utc;
+use common::feature_flags::FeatureFlags;
+use http::StatusCode;
+use lambda_http::Request;
+use model::address_policy_registry::{
+    AddressPolicyRegistry, AddressPolicyRegistryBuilder, AddressPolicyRegistryType,
+};
+use mpc_signature_sm::dtos::address_group::validate_mapping_groups_exist;
+use mpc_signature_sm::dtos::policy_mapping_chain::validate_mapping_chain;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingTarget, MappingType,
+};
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+use mpc_signature_sm::dtos::policy_value_bands::validate_value_bands;
+use mpc_signature_sm::http::errors::{
+    conflict_error_response, unknown_error_response, validation_error_response,
+};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::policy_catalog::{PolicyCatalog, PolicyCatalogState};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chain_is_enabled_for_client;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
+};
+use std::sync::Arc;
+use validator::Validate;
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    pub address_policy_registry_repository: Arc<APRR>,
+    pub address_groups_repository: Arc<AGR>,
+    pub policy_catalog: PC,
+    pub feature_flags: Arc<dyn FeatureFlags>,
+}
+
+impl<APRR: AddressPolicyRegistryRepository, AGR: AddressGroupsRepository, PC: PolicyCatalog>
+    PolicyCatalogState for State<APRR, AGR, PC>
+{
+    type PolicyCatalog = PC;
+
+    fn policy_catalog(&self) -> &PC {
+        &self.policy_catalog
+    }
+}
+
+pub async fn create_policy(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<CreatePolicyMappingRequest>()?;
+    body.validate()
+        .map_err(|e| validation_error_response(e.to_string(), None))?;
+    body.window.validate(Utc::now())?;
+    validate_value_bands(&body.value_bands)?;
+
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+
+    let target = MappingTarget {
+        address: body.address,
+        selector: body.selector,
+        key_id: body.key_id,
+        client_user_id: body.client_user_id.clone(),
+        group: body.group.clone(),
+    };
+    let mapping_type = body
+        .r#type
+        .unwrap_or_else(|| MappingType::inferred_from(&target))
+        .into_registry_type(target)?;
+    validate_mapping_chain(body.chain_id, &mapping_type)?;
+    validate_mapping_chain_is_enabled_for_client(
+        &request,
+        body.chain_id,
+        state.feature_flags.as_ref(),
+    )
+    .await?;
+
+    validate_mapping_groups_exist(
+        state.address_groups_repository.as_ref(),
+        &client_id,
+        [&mapping_type],
+    )
+    .await?;
+
+    let mapping = create_policy_mapping(client_id, body, mapping_type);
+    let response = CreatePolicyMappingResponse {
+        chain_id: mapping.chain_id,
+        address: mapping_address_to_string(&mapping.r#type),
+        selector: mapping_selector_to_string(&mapping.r#type),
+        key_id: mapping_key_id(&mapping.r#type),
+        client_user_id: mapping_client_user_id(&mapping.r#type),
+        group: mapping_group(&mapping.r#type),
+        policy: mapping.policy.clone(),
+        r#type: MappingType::from(&mapping.r#type),
+        window: PolicyMappingWindow::from(&mapping),
+        value_bands: mapping.value_bands.clone(),
+    };
+
+    state
+        .address_policy_registry_repository
+        .put_policy(mapping, subject)
+        .await
+        .map_err(|e| match e {
+            AddressPolicyRegistryRepositoryError::AlreadyExists(message) => {
+                conflict_error_response(message)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error saving address policy mapping. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting create policy mapping response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::CREATED,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+fn create_policy_mapping(
+    client_id: String,
+    request_body: CreatePolicyMappingRequest,
+    mapping_type: AddressPolicyRegistryType,
+) -> AddressPolicyRegistry {
+    AddressPolicyRegistryBuilder::new(client_id, request_body.chain_id, request_body.policy)
+        .active_between(
+            request_body.window.effective_from,
+            request_body.window.expires_at,
+        )
+        .value_bands(request_body.value_bands)
+        .build(mapping_type)
+}
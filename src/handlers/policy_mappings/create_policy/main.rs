@@ -1,32 +1,21 @@
 use crate::config::Config;
 use common::aws_clients::dynamodb::get_dynamodb_client;
 use common::config::ConfigLoader;
-use dtos::CreatePolicyMappingRequest;
-use http::{Response, StatusCode};
+use handler::{create_policy, State};
 use lambda_http::{run, service_fn, Error, Request};
-use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
-use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
-use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
 use mpc_signature_sm::http_lambda_main;
-use mpc_signature_sm::lambda_structure::http_lambda_main::{
-    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
-};
 use mpc_signature_sm::maestro::maestro_bootstrap;
-use mpc_signature_sm::maestro::state::MaestroState;
-use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::maestro::policy_catalog::MaestroPolicyCatalog;
 use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_mapping_policies_belong_to_client;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
 use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
-use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
 use std::sync::Arc;
-use validator::Validate;
 
 mod config;
 mod dtos;
-
-pub struct State<APRR: AddressPolicyRegistryRepository> {
-    address_policy_registry_repository: Arc<APRR>,
-    maestro: MaestroState,
-}
+pub mod handler;
 
 http_lambda_main!(
     {
@@ -38,169 +27,139 @@ http_lambda_main!(
             .await
             .expect("unable to initialize maestro");
 
+        let config = config.await;
         let address_policy_registry_repository =
             Arc::new(AddressPolicyRegistryRepositoryImpl::new(
-                config.await.address_policy_registry_table_name.clone(),
-                dynamodb_client,
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
             ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
 
         State {
             address_policy_registry_repository,
-            maestro,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
         }
     },
     create_policy,
-    [validate_content_type]
+    [validate_content_type],
+    [validate_mapping_policies_belong_to_client]
 );
 
-async fn create_policy(
-    request: Request,
-    state: &State<impl AddressPolicyRegistryRepository>,
-) -> HttpLambdaResponse {
-    let body = request.extract_body::<CreatePolicyMappingRequest>()?;
-    body.validate()
-        .map_err(|e| validation_error_response(e.to_string(), None))?;
-
-    let client_id = request.extract_client_id()?;
-
-    if !check_policy_belongs_to_client(&client_id, &body.policy, &state.maestro).await? {
-        return Err(validation_error_response(
-            format!(r#"invalid policy "{}""#, body.policy),
-            None,
-        ));
-    }
-
-    let mapping = create_policy_mapping(client_id, body);
-
-    state
-        .address_policy_registry_repository
-        .put_policy(mapping)
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error saving address policy mapping. {e:?}"
-            )))
-        })?;
-
-    LambdaProxyHttpResponse {
-        status_code: StatusCode::CREATED,
-        ..LambdaProxyHttpResponse::default()
-    }
-    .try_into()
-}
-
-fn create_policy_mapping(
-    client_id: String,
-    request_body: CreatePolicyMappingRequest,
-) -> AddressPolicyRegistry {
-    AddressPolicyRegistryBuilder::new(client_id, request_body.chain_id, request_body.policy)
-        .address_to(request_body.address)
-}
-
-async fn check_policy_belongs_to_client(
-    client_id: &str,
-    policy: &str,
-    maestro: &MaestroState,
-) -> Result<bool, Response<String>> {
-    let response = maestro
-        .http
-        .get(format!(
-            "{}/{client_id}/policy/{policy}",
-            maestro.config.maestro_url
-        ))
-        .send()
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error with maestro's request: {e:?}"
-            )))
-        })?;
-
-    Ok(StatusCode::OK == response.status())
-}
-
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use chrono::{Duration, Utc};
     use common::test_tools::http::{
         constants::{
             ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
         },
         helpers::build_request_custom_auth,
     };
+    use ethers::types::{Address, U256};
     use http::{Request, StatusCode};
     use lambda_http::Body;
+    use model::address_group::AddressGroup;
+    use model::address_policy_registry::{
+        function_selector::FunctionSelector, AddressPolicyRegistryType, ANY_CHAIN_ID,
+    };
     use mpc_signature_sm::{
-        dtos::responses::http_error::LambdaErrorResponse,
-        maestro::{
-            config::MaestroConfig,
-            session::{login, MaestroLoginInformation},
-            state::MaestroState,
-        },
-        rest::middlewares::AuthenticationMiddleware,
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::{policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse},
+        lambda_structure::http_lambda_main::HttpLambdaResponse,
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+        validations::http::policy_ownership::validate_mapping_policies_belong_to_client,
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
     };
-    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use repositories::cache::MockCacheRepositoryTest;
     use rstest::{fixture, rstest};
-    use serde_json::json;
+    use serde_json::{json, Value};
+    use std::str::FromStr;
     use wiremock::{
         matchers::{method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
-    use crate::{create_policy, State};
+    use crate::{
+        dtos::CreatePolicyMappingResponse,
+        handler::{create_policy, State},
+    };
 
     struct TestFixture {
         pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
-        pub maestro: MaestroState,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
         pub mock_server: MockServer,
     }
 
     #[fixture]
     async fn fixture() -> TestFixture {
         let mock_server = MockServer::start().await;
-        let config = MaestroConfig {
-            maestro_url: mock_server.uri(),
-            service_name: "test".to_owned(),
-            maestro_api_key_secret_name: "dummy_secret_name_api_key".to_owned(),
-            maestro_tenant_name: "tenant".to_owned(),
-        };
-
-        let http_client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
-            .with(AuthenticationMiddleware::new(
-                &login,
-                Arc::new(MaestroLoginInformation {
-                    maestro_url: config.maestro_url.clone(),
-                    service_name: config.service_name.clone(),
-                    maestro_api_key: "dummy_api_secret".to_owned(),
-                    tenant_name: "tenant".to_owned(),
-                }),
-                Some("dummy_token".to_owned()),
-            ))
-            .build();
-
         TestFixture {
             mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
-            maestro: MaestroState {
-                http: http_client,
-                config,
-            },
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
             mock_server,
         }
     }
 
     fn build_request(address: &str, chain_id: u64, policy: &str) -> Request<Body> {
-        let body = Body::Text(
-            json!({
-                "address": address,
-                "chain_id": chain_id,
-                "policy": policy
-            })
-            .to_string(),
-        );
+        build_request_from_body(json!({
+            "address": address,
+            "chain_id": chain_id,
+            "policy": policy
+        }))
+    }
 
-        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
-        build_request_custom_auth(auth, body)
+    fn build_request_from_body(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy_found(policy_name: &str, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "serialized_policy": "base64_policy",
+                "policy_name": policy_name,
+                "display_name": "Some Policy",
+            })))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    /// Runs the state validations of the lambda before its handler, the way `http_lambda_main!`
+    /// does.
+    async fn create(
+        request: Request<Body>,
+        state: &State<
+            MockAddressPolicyRegistryRepository,
+            MockAddressGroupsRepository,
+            MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        >,
+    ) -> HttpLambdaResponse {
+        validate_mapping_policies_belong_to_client(&request, state).await?;
+        create_policy(request, state).await
     }
 
     #[rstest]
@@ -214,7 +173,9 @@ mod tests {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
         let response = create_policy(request, &state).await.unwrap_err();
@@ -243,7 +204,9 @@ mod tests {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
         let response = create_policy(request, &state).await.unwrap_err();
@@ -259,10 +222,48 @@ mod tests {
         );
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(
+            ADDRESS_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "some_policy",
+        );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags().with_flag(
+                &chain_feature_flag(CHAIN_ID_FOR_MOCK_REQUESTS),
+                json!(false),
+            )),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("forbidden", body.code);
+        assert_eq!(
+            format!("chain_id {CHAIN_ID_FOR_MOCK_REQUESTS} is not enabled for this client"),
+            body.message
+        );
+    }
+
     #[rstest]
     #[tokio::test]
     async fn invalid_policy_ok(#[future] fixture: TestFixture) {
-        let fixture = fixture.await;
+        let mut fixture = fixture.await;
         let policy_name = "some_policy";
         let request = build_request(
             ADDRESS_FOR_MOCK_REQUESTS,
@@ -278,15 +279,21 @@ mod tests {
             .expect(1)
             .mount(&fixture.mock_server)
             .await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
 
         let state = State {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
-        let response = create_policy(request, &state).await.unwrap_err();
+        let response = create(request, &state).await.unwrap_err();
 
         assert_eq!(StatusCode::BAD_REQUEST, response.status());
         let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
@@ -309,30 +316,632 @@ mod tests {
             .mock_address_policy_registry_repository
             .expect_put_policy()
             .once()
-            .returning(|_| Ok(()));
+            .withf(|mapping, _| {
+                mapping.r#type
+                    == AddressPolicyRegistryType::AddressTo {
+                        address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                    }
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::AddressTo, body.r#type);
+        assert_eq!(ADDRESS_FOR_MOCK_REQUESTS, body.address);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_address_from_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request_from_body(json!({
+            "address": ADDRESS_FOR_MOCK_REQUESTS,
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": policy_name,
+            "type": "ADDRESS_FROM"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.r#type
+                    == AddressPolicyRegistryType::AddressFrom {
+                        address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                    }
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::AddressFrom, body.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_contract_call_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request_from_body(json!({
+            "address": ADDRESS_FOR_MOCK_REQUESTS,
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": policy_name,
+            "selector": "0xa9059cbb"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.r#type
+                    == AddressPolicyRegistryType::ContractCall {
+                        address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                        selector: FunctionSelector([0xa9, 0x05, 0x9c, 0xbb]),
+                    }
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::ContractCall, body.r#type);
+        assert_eq!(Some("0xa9059cbb".to_owned()), body.selector);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_client_user_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": policy_name,
+            "client_user_id": "some_end_user"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.r#type
+                    == AddressPolicyRegistryType::ClientUser {
+                        client_user_id: "some_end_user".to_owned(),
+                    }
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::ClientUser, body.r#type);
+        assert_eq!(Some("some_end_user".to_owned()), body.client_user_id);
+        assert_eq!(None, body.key_id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_key_id_policy_with_address(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "address": ADDRESS_FOR_MOCK_REQUESTS,
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "some_policy",
+            "key_id": "b3b1a1e6-6a5e-4c43-9f5c-5a1d2a2c0d8e"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("KEY_ID mappings can't have an address", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_client_wide_default_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request_from_body(json!({
+            "chain_id": "*",
+            "policy": policy_name
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.chain_id == ANY_CHAIN_ID
+                    && mapping.r#type == AddressPolicyRegistryType::Default
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("*", body["chain_id"]);
+        assert_eq!("DEFAULT", body["type"]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_client_wide_address_policy(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "address": ADDRESS_FOR_MOCK_REQUESTS,
+            "chain_id": "*",
+            "policy": "some_policy"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("only DEFAULT mappings can have chain_id *", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_value_bands_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "board",
+            "value_bands": [
+                { "below": "1000000000000000000", "policy": "basic" },
+                { "below": "100000000000000000000", "policy": "dual" }
+            ]
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.value_bands.len() == 2
+                    && mapping.policy_for_value(U256::exp10(19)) == "dual"
+            })
+            .returning(|_, _| Ok(()));
+
+        for policy_name in ["basic", "board", "dual"] {
+            mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+        }
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(2, body.value_bands.len());
+        assert_eq!(U256::exp10(18), body.value_bands[0].below);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_overlapping_value_bands(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "board",
+            "value_bands": [
+                { "below": "100000000000000000000", "policy": "dual" },
+                { "below": "1000000000000000000", "policy": "basic" }
+            ]
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            "value_bands[1]: overlaps value_bands[0], bands must be sorted by below",
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_default_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": policy_name,
+            "type": "DEFAULT"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, subject| {
+                mapping.r#type == AddressPolicyRegistryType::Default
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::Default, body.r#type);
+        assert_eq!("default", body.address);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_time_bounded_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let effective_from = Utc::now() + Duration::hours(1);
+        let expires_at = effective_from + Duration::days(1);
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": policy_name,
+            "effective_from": effective_from,
+            "expires_at": expires_at
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(move |mapping, _| {
+                mapping.effective_from == Some(effective_from)
+                    && mapping.expires_at == Some(expires_at)
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(Some(effective_from), body.window.effective_from);
+        assert_eq!(Some(expires_at), body.window.expires_at);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_policy_invalid_window(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let effective_from = Utc::now() + Duration::hours(2);
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "some_policy",
+            "effective_from": effective_from,
+            "expires_at": effective_from - Duration::hours(1)
+        }));
 
         Mock::given(method("GET"))
-            .and(path(format!(
-                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
-            )))
-            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
-                "serialized_policy": "base64_policy",
-                "policy_name": policy_name,
-                "display_name": "Some Policy",
-            })))
-            .expect(1)
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .expect(0)
             .mount(&fixture.mock_server)
             .await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("expires_at must be later than effective_from", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_address_from_policy_without_address(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "some_policy",
+            "type": "ADDRESS_FROM"
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("ADDRESS_FROM mappings require an address", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_group_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "some_policy",
+            "group": "exchanges"
+        }));
+
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .withf(|client_id, name| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS && name == "exchanges"
+            })
+            .returning(|client_id, name| {
+                Ok(Some(AddressGroup {
+                    client_id,
+                    name,
+                    addresses: vec![Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()],
+                }))
+            });
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .withf(|mapping, _| {
+                mapping.r#type
+                    == AddressPolicyRegistryType::Group {
+                        name: "exchanges".to_owned(),
+                    }
+            })
+            .returning(|_, _| Ok(()));
+
+        mock_maestro_policy_found("some_policy", &fixture.mock_server).await;
 
         let state = State {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
-        let response = create_policy(request, &state).await.unwrap();
+        let response = create(request, &state).await.unwrap();
 
         assert_eq!(StatusCode::CREATED, response.status());
+        let body: CreatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::Group, body.r#type);
+        assert_eq!(Some("exchanges".to_owned()), body.group);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_group_policy_group_not_found(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request_from_body(json!({
+            "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            "policy": "some_policy",
+            "group": "exchanges"
+        }));
+
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .returning(|_, _| Ok(None));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+
+        mock_maestro_policy_found("some_policy", &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("address group exchanges not found", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_policy_already_exists(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let request = build_request(
+            ADDRESS_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            policy_name,
+        );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .once()
+            .returning(|_, _| {
+                Err(AddressPolicyRegistryRepositoryError::AlreadyExists(
+                    "policy mapping already exists".to_owned(),
+                ))
+            });
+
+        mock_maestro_policy_found(policy_name, &fixture.mock_server).await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = create(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("conflict", body.code);
+        assert_eq!("policy mapping already exists", body.message);
     }
 }
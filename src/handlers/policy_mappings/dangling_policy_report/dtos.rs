@@ -0,0 +1,73 @@
+use model::address_policy_registry::AddressPolicyRegistry;
+use mpc_signature_sm::dtos::policy_mapping_chain::serialize_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingType,
+};
+use mpc_signature_sm::lambda_structure::event::InvocationPayload;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Input of the scheduled rule that triggers the report. The registry can't list its clients
+/// without scanning the whole table, so the rule lists them instead and has to be kept up to date
+/// as clients are onboarded: clients missing from it aren't reported on.
+#[derive(Deserialize, Debug)]
+pub struct DanglingPolicyReportRequest {
+    pub client_ids: Vec<String>,
+}
+
+/// The report covers several clients, so no client id is logged.
+impl InvocationPayload for DanglingPolicyReportRequest {}
+
+#[derive(Serialize, Debug, Default)]
+pub struct DanglingPolicyReport {
+    pub clients_scanned: usize,
+    pub mappings_scanned: usize,
+    pub policies_checked: usize,
+    pub dangling_mappings: Vec<DanglingPolicyMapping>,
+    /// Clients that couldn't be scanned, so none of their mappings are in the report.
+    pub failed_clients: Vec<FailedClientScan>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FailedClientScan {
+    pub client_id: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DanglingPolicyMapping {
+    pub client_id: String,
+    #[serde(serialize_with = "serialize_mapping_chain_id")]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub r#type: MappingType,
+    pub policy: String,
+    /// Policies of the mapping, its own or the ones of its value bands, that are missing.
+    pub missing_policies: Vec<String>,
+}
+
+impl From<AddressPolicyRegistry> for DanglingPolicyMapping {
+    fn from(value: AddressPolicyRegistry) -> Self {
+        Self {
+            address: mapping_address_to_string(&value.r#type),
+            selector: mapping_selector_to_string(&value.r#type),
+            key_id: mapping_key_id(&value.r#type),
+            client_user_id: mapping_client_user_id(&value.r#type),
+            group: mapping_group(&value.r#type),
+            r#type: MappingType::from(&value.r#type),
+            client_id: value.client_id,
+            chain_id: value.chain_id,
+            policy: value.policy,
+            missing_policies: vec![],
+        }
+    }
+}
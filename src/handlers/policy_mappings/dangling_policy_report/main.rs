@@ -0,0 +1,427 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::config::Config;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::aws_clients::secrets_manager::get_secrets_provider;
+use common::config::ConfigLoader;
+use dtos::{
+    DanglingPolicyMapping, DanglingPolicyReport, DanglingPolicyReportRequest, FailedClientScan,
+};
+use mpc_signature_sm::lambda_main;
+use mpc_signature_sm::lambda_structure::lambda_trait::Lambda;
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::OrchestrationError;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+use rusoto_dynamodb::DynamoDbClient;
+
+mod config;
+mod dtos;
+
+pub struct State<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> {
+    address_policy_registry_repository: APRR,
+    policy_catalog: PC,
+}
+
+/// Scheduled lambda that reports policy mappings pointing at policies, their own or the ones of
+/// their value bands, that no longer exist in Maestro (or no longer belong to the mapping's
+/// client).
+pub struct DanglingPolicyReportLambda;
+
+#[async_trait]
+impl Lambda for DanglingPolicyReportLambda {
+    type PersistedMemory = State<
+        AddressPolicyRegistryRepositoryImpl<DynamoDbClient>,
+        MaestroPolicyCatalog<CacheRepositoryImpl<DynamoDbClient>>,
+    >;
+    type InputBody = DanglingPolicyReportRequest;
+    type Output = DanglingPolicyReport;
+    type Error = OrchestrationError;
+
+    async fn bootstrap() -> Result<Self::PersistedMemory, Self::Error> {
+        let config = ConfigLoader::load_default::<Config>().await;
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider).await?;
+
+        Ok(State {
+            address_policy_registry_repository: AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name,
+                config.address_policy_registry_history_table_name,
+                dynamodb_client.clone(),
+            ),
+            policy_catalog: MaestroPolicyCatalog::new(
+                maestro,
+                CacheRepositoryImpl::new(config.cache_table_name, dynamodb_client),
+            ),
+        })
+    }
+
+    async fn run(
+        request: Self::InputBody,
+        state: &Self::PersistedMemory,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(build_report(request, state).await)
+    }
+}
+
+lambda_main!(DanglingPolicyReportLambda);
+
+/// Scans every client of the request. A client that can't be scanned is listed in the report
+/// instead of failing the whole report.
+async fn build_report(
+    request: DanglingPolicyReportRequest,
+    state: &State<impl AddressPolicyRegistryRepository, impl PolicyCatalog>,
+) -> DanglingPolicyReport {
+    let mut report = DanglingPolicyReport::default();
+
+    for client_id in request.client_ids {
+        if let Err(e) = scan_client(&client_id, state, &mut report).await {
+            tracing::error!(
+                client_id = %client_id,
+                error = ?e,
+                "unable to scan the policy mappings of the client"
+            );
+            report.failed_clients.push(FailedClientScan {
+                client_id,
+                error: format!("{e:#}"),
+            });
+        }
+    }
+
+    report
+}
+
+/// Adds the mappings of `client_id` to the report, only once all of them are checked.
+async fn scan_client(
+    client_id: &str,
+    state: &State<impl AddressPolicyRegistryRepository, impl PolicyCatalog>,
+    report: &mut DanglingPolicyReport,
+) -> anyhow::Result<()> {
+    let mappings = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id.to_owned())
+        .await
+        .map_err(|e| {
+            anyhow!(e).context(format!("fetching policy mappings of client {client_id}"))
+        })?;
+
+    let policies = mappings
+        .iter()
+        .flat_map(|mapping| mapping.policies())
+        .collect::<BTreeSet<_>>();
+
+    let mut policy_exists = HashMap::with_capacity(policies.len());
+    for policy in policies {
+        let exists = state
+            .policy_catalog
+            .policy_belongs_to_client(client_id, policy)
+            .await
+            .map_err(|e| anyhow!(e).context(format!("checking policy {policy}")))?;
+        policy_exists.insert(policy.to_owned(), exists);
+    }
+
+    report.clients_scanned += 1;
+    report.mappings_scanned += mappings.len();
+    report.policies_checked += policy_exists.len();
+
+    let dangling = mappings
+        .into_iter()
+        .filter_map(|mapping| {
+            let missing_policies = mapping
+                .policies()
+                .filter(|policy| !policy_exists[*policy])
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            (!missing_policies.is_empty()).then(|| DanglingPolicyMapping {
+                missing_policies,
+                ..DanglingPolicyMapping::from(mapping)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if !dangling.is_empty() {
+        tracing::warn!(
+            client_id = %client_id,
+            dangling_mappings = dangling.len(),
+            "found policy mappings pointing at missing policies"
+        );
+    }
+
+    report.dangling_mappings.extend(dangling);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use async_trait::async_trait;
+    use common::test_tools::http::constants::{
+        ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use ethers::types::{Address, U256};
+    use mockall::{mock, predicate::eq};
+    use model::address_policy_registry::value_band::PolicyValueBand;
+    use model::address_policy_registry::AddressPolicyRegistryBuilder;
+    use model::order::policy::Policy;
+    use mpc_signature_sm::{
+        dtos::policy_mapping_type::MappingType, maestro::policy_catalog::PolicyCatalog,
+        result::error::LambdaError,
+    };
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+    };
+
+    use crate::{build_report, dtos::DanglingPolicyReportRequest, State};
+
+    mock! {
+        PolicyCatalog {}
+
+        #[async_trait]
+        impl PolicyCatalog for PolicyCatalog {
+            async fn policy_belongs_to_client(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<bool, LambdaError>;
+
+            async fn get_policy(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<Option<Policy>, LambdaError>;
+        }
+    }
+
+    fn request() -> DanglingPolicyReportRequest {
+        DanglingPolicyReportRequest {
+            client_ids: vec![CLIENT_ID_FOR_MOCK_REQUESTS.to_owned()],
+        }
+    }
+
+    #[tokio::test]
+    async fn report_dangling_mappings() {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let mut repository = MockAddressPolicyRegistryRepository::new();
+        repository
+            .expect_get_all_policies()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned()))
+            .once()
+            .returning(move |client_id| {
+                Ok(vec![
+                    AddressPolicyRegistryBuilder::new(
+                        client_id.clone(),
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "live_policy".to_owned(),
+                    )
+                    .default(),
+                    AddressPolicyRegistryBuilder::new(
+                        client_id.clone(),
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "deleted_policy".to_owned(),
+                    )
+                    .address_to(address),
+                    AddressPolicyRegistryBuilder::new(
+                        client_id,
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "deleted_policy".to_owned(),
+                    )
+                    .address_from(address),
+                ])
+            });
+
+        // Each distinct policy is checked once.
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .times(2)
+            .returning(|_, policy| Ok(policy == "live_policy"));
+
+        let state = State {
+            address_policy_registry_repository: repository,
+            policy_catalog,
+        };
+
+        let report = build_report(request(), &state).await;
+
+        assert_eq!(1, report.clients_scanned);
+        assert_eq!(3, report.mappings_scanned);
+        assert_eq!(2, report.policies_checked);
+        assert_eq!(2, report.dangling_mappings.len());
+        assert!(report
+            .dangling_mappings
+            .iter()
+            .all(|mapping| mapping.policy == "deleted_policy"
+                && mapping.address == ADDRESS_FOR_MOCK_REQUESTS));
+        assert_eq!(MappingType::AddressTo, report.dangling_mappings[0].r#type);
+        assert_eq!(MappingType::AddressFrom, report.dangling_mappings[1].r#type);
+        assert_eq!(
+            vec!["deleted_policy".to_owned()],
+            report.dangling_mappings[0].missing_policies
+        );
+    }
+
+    #[tokio::test]
+    async fn report_mappings_with_dangling_value_bands() {
+        let mut repository = MockAddressPolicyRegistryRepository::new();
+        repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|client_id| {
+                let mut mapping = AddressPolicyRegistryBuilder::new(
+                    client_id,
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "live_policy".to_owned(),
+                )
+                .default();
+                mapping.value_bands = vec![
+                    PolicyValueBand {
+                        below: U256::from(100),
+                        policy: "deleted_policy".to_owned(),
+                    },
+                    PolicyValueBand {
+                        below: U256::from(1000),
+                        policy: "live_band_policy".to_owned(),
+                    },
+                ];
+                Ok(vec![mapping])
+            });
+
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .times(3)
+            .returning(|_, policy| Ok(policy != "deleted_policy"));
+
+        let state = State {
+            address_policy_registry_repository: repository,
+            policy_catalog,
+        };
+
+        let report = build_report(request(), &state).await;
+
+        assert_eq!(3, report.policies_checked);
+        assert_eq!(1, report.dangling_mappings.len());
+        assert_eq!("live_policy", report.dangling_mappings[0].policy);
+        assert_eq!(
+            vec!["deleted_policy".to_owned()],
+            report.dangling_mappings[0].missing_policies
+        );
+    }
+
+    #[tokio::test]
+    async fn report_client_without_mappings() {
+        let mut repository = MockAddressPolicyRegistryRepository::new();
+        repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(vec![]));
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog.expect_policy_belongs_to_client().never();
+
+        let state = State {
+            address_policy_registry_repository: repository,
+            policy_catalog,
+        };
+
+        let report = build_report(request(), &state).await;
+
+        assert_eq!(1, report.clients_scanned);
+        assert_eq!(0, report.mappings_scanned);
+        assert!(report.dangling_mappings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn report_failed_clients_and_keeps_scanning() {
+        let mut repository = MockAddressPolicyRegistryRepository::new();
+        repository
+            .expect_get_all_policies()
+            .with(eq("failing_client".to_owned()))
+            .once()
+            .returning(|_| {
+                Err(AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow::anyhow!("timeout"),
+                ))
+            });
+        repository
+            .expect_get_all_policies()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS.to_owned()))
+            .once()
+            .returning(|client_id| {
+                Ok(vec![AddressPolicyRegistryBuilder::new(
+                    client_id,
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "deleted_policy".to_owned(),
+                )
+                .default()])
+            });
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .once()
+            .returning(|_, _| Ok(false));
+
+        let state = State {
+            address_policy_registry_repository: repository,
+            policy_catalog,
+        };
+        let request = DanglingPolicyReportRequest {
+            client_ids: vec![
+                "failing_client".to_owned(),
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            ],
+        };
+
+        let report = build_report(request, &state).await;
+
+        assert_eq!(1, report.clients_scanned);
+        assert_eq!(1, report.dangling_mappings.len());
+        assert_eq!(1, report.failed_clients.len());
+        assert_eq!("failing_client", report.failed_clients[0].client_id);
+        assert!(report.failed_clients[0].error.contains("timeout"));
+    }
+
+    #[tokio::test]
+    async fn report_failed_client_without_partial_results() {
+        let mut repository = MockAddressPolicyRegistryRepository::new();
+        repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|client_id| {
+                Ok(vec![AddressPolicyRegistryBuilder::new(
+                    client_id,
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "some_policy".to_owned(),
+                )
+                .default()])
+            });
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .once()
+            .returning(|_, _| Err(LambdaError::Unknown(anyhow::anyhow!("maestro is down"))));
+
+        let state = State {
+            address_policy_registry_repository: repository,
+            policy_catalog,
+        };
+
+        let report = build_report(request(), &state).await;
+
+        assert_eq!(0, report.clients_scanned);
+        assert_eq!(0, report.mappings_scanned);
+        assert_eq!(1, report.failed_clients.len());
+        assert!(report.failed_clients[0]
+            .error
+            .contains("checking policy some_policy"));
+    }
+}
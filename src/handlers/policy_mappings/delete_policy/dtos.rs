@@ -0,0 +1,29 @@
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+use mpc_signature_sm::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, serialize_mapping_chain_id,
+};
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct DeletePolicyMappingResponse {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub r#type: MappingType,
+}
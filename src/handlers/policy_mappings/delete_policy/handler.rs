@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use super::dtos::DeletePolicyMappingResponse;
+use common::feature_flags::FeatureFlags;
+use http::StatusCode;
+use lambda_http::Request;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use mpc_signature_sm::dtos::policy_mapping_chain::extract_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingTarget, MappingType,
+};
+use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
+use mpc_signature_sm::dtos::requests::if_match_header::IfMatchHeader;
+use mpc_signature_sm::feature_flags::FeatureFlagsState;
+use mpc_signature_sm::http::errors::{precondition_failed_error_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
+};
+use uuid::Uuid;
+
+pub const ADDRESS_PATH_PARAM: &str = "address";
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const TYPE_QUERY_PARAM: &str = "type";
+pub const SELECTOR_QUERY_PARAM: &str = "selector";
+pub const KEY_ID_QUERY_PARAM: &str = "key_id";
+pub const CLIENT_USER_ID_QUERY_PARAM: &str = "client_user_id";
+pub const GROUP_QUERY_PARAM: &str = "group";
+
+pub struct State<APRR: AddressPolicyRegistryRepository> {
+    pub address_policy_registry_repository: Arc<APRR>,
+    pub feature_flags: Arc<dyn FeatureFlags>,
+}
+
+impl<APRR: AddressPolicyRegistryRepository> FeatureFlagsState for State<APRR> {
+    fn feature_flags(&self) -> &dyn FeatureFlags {
+        self.feature_flags.as_ref()
+    }
+}
+
+pub async fn delete_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let chain_id = extract_mapping_chain_id(&request, CHAIN_ID_PATH_PARAM)?;
+    let address = request
+        .extract_path_param::<AddressOrDefaultPathParam>(ADDRESS_PATH_PARAM)?
+        .extract_address();
+    let target = MappingTarget {
+        address,
+        selector: request.extract_query_param::<FunctionSelector>(SELECTOR_QUERY_PARAM)?,
+        key_id: request.extract_query_param::<Uuid>(KEY_ID_QUERY_PARAM)?,
+        client_user_id: request.extract_query_param::<String>(CLIENT_USER_ID_QUERY_PARAM)?,
+        group: request.extract_query_param::<String>(GROUP_QUERY_PARAM)?,
+    };
+    let mapping_type = request
+        .extract_query_param::<MappingType>(TYPE_QUERY_PARAM)?
+        .unwrap_or_else(|| MappingType::inferred_from(&target))
+        .into_registry_type(target)?;
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+    let expected_version = IfMatchHeader::extract_expected_version(&request)?;
+
+    let response = DeletePolicyMappingResponse {
+        chain_id,
+        address: mapping_address_to_string(&mapping_type),
+        selector: mapping_selector_to_string(&mapping_type),
+        key_id: mapping_key_id(&mapping_type),
+        client_user_id: mapping_client_user_id(&mapping_type),
+        group: mapping_group(&mapping_type),
+        r#type: MappingType::from(&mapping_type),
+    };
+
+    state
+        .address_policy_registry_repository
+        .delete_policy(client_id, chain_id, mapping_type, expected_version, subject)
+        .await
+        .map_err(|e| match e {
+            AddressPolicyRegistryRepositoryError::VersionMismatch(message) => {
+                precondition_failed_error_response(message)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error saving address policy mapping. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting delete policy mapping response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
@@ -3,73 +3,40 @@ use std::sync::Arc;
 use crate::config::Config;
 use common::aws_clients::dynamodb::get_dynamodb_client;
 use common::config::ConfigLoader;
-use ethers::types::H160;
-use http::StatusCode;
+use handler::{delete_policy, State};
 use lambda_http::{run, service_fn, Error, Request};
-use mpc_signature_sm::http::errors::unknown_error_response;
-use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
 use mpc_signature_sm::http_lambda_main;
-use mpc_signature_sm::lambda_structure::http_lambda_main::{
-    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
-};
-use mpc_signature_sm::result::error::LambdaError;
-use mpc_signature_sm::validations::http::supported_chain_id::validate_chain_id_is_supported;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chain_id_is_enabled;
 use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
-use repositories::address_policy_registry::AddressPolicyRegistryRepository;
 
 mod config;
-
-pub const ADDRESS_PATH_PARAM: &str = "address";
-pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
-
-pub struct State<APRR: AddressPolicyRegistryRepository> {
-    address_policy_registry_repository: Arc<APRR>,
-}
+mod dtos;
+pub mod handler;
 
 http_lambda_main!(
     {
         let config = ConfigLoader::load_default::<Config>();
         let dynamodb_client = get_dynamodb_client().await;
 
+        let config = config.await;
         let address_policy_registry_repository =
             Arc::new(AddressPolicyRegistryRepositoryImpl::new(
-                config.await.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
                 dynamodb_client,
             ));
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
 
         State {
             address_policy_registry_repository,
+            feature_flags,
         }
     },
     delete_policy,
-    [validate_chain_id_is_supported]
+    [],
+    [validate_mapping_chain_id_is_enabled]
 );
 
-async fn delete_policy(
-    request: Request,
-    state: &State<impl AddressPolicyRegistryRepository>,
-) -> HttpLambdaResponse {
-    let chain_id: u64 = request.extract_path_param(CHAIN_ID_PATH_PARAM)?;
-    let address: H160 = request.extract_path_param(ADDRESS_PATH_PARAM)?;
-    let client_id = request.extract_client_id()?;
-
-    state
-        .address_policy_registry_repository
-        .delete_policy(client_id, chain_id, Some(address))
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error saving address policy mapping. {e:?}"
-            )))
-        })?;
-
-    LambdaProxyHttpResponse {
-        status_code: StatusCode::OK,
-        ..LambdaProxyHttpResponse::default()
-    }
-    .try_into()
-}
-
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, sync::Arc};
@@ -80,14 +47,24 @@ mod tests {
         },
         helpers::build_request_custom_auth,
     };
-    use http::{Request, StatusCode};
+    use http::{HeaderValue, Request, StatusCode};
     use lambda_http::{Body, RequestExt};
-    use mpc_signature_sm::dtos::responses::http_error::LambdaErrorResponse;
-    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use mpc_signature_sm::config::supported_chains_feature_flags;
+    use mpc_signature_sm::dtos::{
+        policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+    };
     use rstest::{fixture, rstest};
     use serde_json::json;
 
-    use crate::{delete_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM};
+    use crate::{
+        dtos::DeletePolicyMappingResponse,
+        handler::{
+            delete_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM, TYPE_QUERY_PARAM,
+        },
+    };
 
     struct TestFixture {
         pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
@@ -101,7 +78,7 @@ mod tests {
     }
 
     fn build_request(address: &str, chain_id: u64) -> Request<Body> {
-        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
         let mut path_params: HashMap<String, String> = HashMap::new();
         path_params.insert(CHAIN_ID_PATH_PARAM.to_owned(), chain_id.to_string());
         path_params.insert(ADDRESS_PATH_PARAM.to_owned(), address.to_owned());
@@ -123,6 +100,7 @@ mod tests {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
         let response = delete_policy(request, &state).await.unwrap_err();
@@ -134,24 +112,116 @@ mod tests {
     }
 
     #[rstest]
+    #[case::address(ADDRESS_FOR_MOCK_REQUESTS, None, MappingType::AddressTo)]
+    #[case::default("default", None, MappingType::Default)]
+    #[case::address_from(
+        ADDRESS_FOR_MOCK_REQUESTS,
+        Some("ADDRESS_FROM"),
+        MappingType::AddressFrom
+    )]
     #[tokio::test]
-    async fn delete_policy_ok(mut fixture: TestFixture) {
-        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS);
+    async fn delete_policy_ok(
+        mut fixture: TestFixture,
+        #[case] address: &str,
+        #[case] type_query_param: Option<&str>,
+        #[case] expected_type: MappingType,
+    ) {
+        let mut request = build_request(address, CHAIN_ID_FOR_MOCK_REQUESTS);
+        if let Some(mapping_type) = type_query_param {
+            request = request.with_query_string_parameters(HashMap::from([(
+                TYPE_QUERY_PARAM.to_owned(),
+                mapping_type.to_owned(),
+            )]));
+        }
 
         fixture
             .mock_address_policy_registry_repository
             .expect_delete_policy()
             .once()
-            .returning(|_, _, _| Ok(()));
+            .withf(move |_, _, mapping_type, expected_version, subject| {
+                MappingType::from(mapping_type) == expected_type
+                    && expected_version.is_none()
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _, _, _, _| Ok(()));
 
         let state = State {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
         let response = delete_policy(request, &state).await.unwrap();
 
         assert_eq!(StatusCode::OK, response.status());
+        let body: DeletePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(expected_type, body.r#type);
+    }
+
+    #[rstest]
+    #[case::any_version("*", None)]
+    #[case::specific_version("\"5\"", Some(5))]
+    #[tokio::test]
+    async fn delete_policy_if_match_ok(
+        mut fixture: TestFixture,
+        #[case] if_match: &'static str,
+        #[case] expected: Option<u64>,
+    ) {
+        let mut request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS);
+        request
+            .headers_mut()
+            .insert("if-match", HeaderValue::from_static(if_match));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_delete_policy()
+            .once()
+            .withf(move |_, _, _, expected_version, _| *expected_version == expected)
+            .returning(|_, _, _, _, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = delete_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn delete_policy_version_mismatch(mut fixture: TestFixture) {
+        let mut request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS);
+        request
+            .headers_mut()
+            .insert("if-match", HeaderValue::from_static("\"1\""));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_delete_policy()
+            .once()
+            .returning(|_, _, _, _, _| {
+                Err(AddressPolicyRegistryRepositoryError::VersionMismatch(
+                    "stale version".to_owned(),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = delete_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("precondition_failed", body.code);
+        assert_eq!("stale version", body.message);
     }
 }
@@ -0,0 +1,42 @@
+use common::deserializers::h160::h160;
+use ethers::types::H160;
+use model::order::policy::Policy;
+use mpc_signature_sm::dtos::policy_resolution::{PolicyCandidateResponse, ResolvedPolicyMapping};
+use mpc_signature_sm::dtos::requests::transaction_request::TransactionRequest;
+use repositories::address_policy_registry::policy_resolver::PolicyResolution;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct EvaluatePolicyRequest {
+    /// Address signing the transaction.
+    #[serde(deserialize_with = "h160")]
+    pub from: H160,
+    pub transaction: TransactionRequest,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct EvaluatePolicyResponse {
+    pub policy: Option<ResolvedPolicyMapping>,
+    /// Policy that applies to the transaction value, out of the value bands of `policy`.
+    pub applied_policy: Option<String>,
+    /// Maestro policy named by `applied_policy`, with its approval levels. Missing when no
+    /// mapping applies or the policy doesn't exist in Maestro anymore.
+    pub maestro_policy: Option<Policy>,
+    pub candidates: Vec<PolicyCandidateResponse>,
+}
+
+impl EvaluatePolicyResponse {
+    pub fn new(resolution: PolicyResolution, maestro_policy: Option<Policy>) -> Self {
+        Self {
+            policy: resolution.policy.map(ResolvedPolicyMapping::from),
+            applied_policy: resolution.applied_policy,
+            maestro_policy,
+            candidates: resolution
+                .candidates
+                .into_iter()
+                .map(PolicyCandidateResponse::from)
+                .collect(),
+        }
+    }
+}
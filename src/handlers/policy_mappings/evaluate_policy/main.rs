@@ -0,0 +1,572 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::{EvaluatePolicyRequest, EvaluatePolicyResponse};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use mpc_signature_sm::config::SupportedChain;
+use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_chain_is_enabled_for_client;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::policy_resolver::resolve_policy;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+use repositories::keys::keys_repository_impl::KeysRepositoryImpl;
+use repositories::keys::KeysRepository;
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    KR: KeysRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    address_policy_registry_repository: Arc<APRR>,
+    keys_repository: Arc<KR>,
+    address_groups_repository: Arc<AGR>,
+    policy_catalog: PC,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let keys_repository = Arc::new(KeysRepositoryImpl::new(
+            config.keys_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            keys_repository,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    evaluate_policy_mapping,
+    [validate_content_type]
+);
+
+/// Dry run of a signature request: tells which mapping and Maestro policy would apply to the
+/// transaction. Nothing is persisted and no order is created.
+async fn evaluate_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl KeysRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<EvaluatePolicyRequest>()?;
+    let client_id = request.extract_client_id()?;
+
+    let chain_id = body.transaction.get_chain_id();
+    if !chain_id.is_supported() {
+        return Err(validation_error_response(
+            format!("chain_id {chain_id} is not supported"),
+            None,
+        ));
+    }
+    validate_chain_is_enabled_for_client(&request, chain_id, state.feature_flags.as_ref()).await?;
+
+    let address_to = body.transaction.destination().ok_or_else(|| {
+        validation_error_response(
+            "transaction doesn't have a valid to address".to_owned(),
+            None,
+        )
+    })?;
+
+    let resolution = resolve_policy(
+        state.address_policy_registry_repository.as_ref(),
+        state.keys_repository.as_ref(),
+        state.address_groups_repository.as_ref(),
+        client_id.clone(),
+        chain_id,
+        body.from,
+        address_to,
+        body.transaction.function_selector(),
+        body.transaction.native_value(),
+    )
+    .await
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+            "there was an error resolving address policy mapping. {e:?}"
+        )))
+    })?;
+
+    let maestro_policy = match &resolution.applied_policy {
+        Some(policy) => state
+            .policy_catalog
+            .get_policy(&client_id, policy)
+            .await
+            .map_err(unknown_error_response)?,
+        None => None,
+    };
+
+    let response = serde_json::to_string(&EvaluatePolicyResponse::new(resolution, maestro_policy))
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(
+                anyhow::anyhow!(e).context("converting policy evaluation response"),
+            ))
+        })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use http::{Request, StatusCode};
+    use lambda_http::Body;
+    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryType};
+    use model::order::helpers::sponsored_typed_data;
+    use mpc_signature_sm::{
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::{
+            policy_mapping_type::MappingType, policy_resolution::CandidateOutcomeResponse,
+            responses::http_error::LambdaErrorResponse,
+        },
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use repositories::cache::MockCacheRepositoryTest;
+    use repositories::keys::{KeysRepositoryError, MockKeysRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{dtos::EvaluatePolicyResponse, evaluate_policy_mapping, State};
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_keys_repository: MockKeysRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        let mut mock_keys_repository = MockKeysRepository::new();
+        mock_keys_repository
+            .expect_get_key_by_address()
+            .returning(|_| Err(KeysRepositoryError::KeyNotFound("not found".to_owned())));
+
+        let mut mock_address_groups_repository = MockAddressGroupsRepository::new();
+        mock_address_groups_repository
+            .expect_get_groups_containing()
+            .returning(|_, _| Ok(vec![]));
+
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_keys_repository,
+            mock_address_groups_repository,
+            // Policy details are never cached.
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::new(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    fn eip1559_transaction(chain_id: u64) -> Value {
+        json!({
+            "to": ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            "gas": "300000",
+            "max_fee_per_gas": "100",
+            "max_priority_fee_per_gas": "80000",
+            "value": "1",
+            "nonce": "0",
+            "data": "0x",
+            "chain_id": chain_id,
+        })
+    }
+
+    fn mapping(mapping_type: AddressPolicyRegistryType) -> AddressPolicyRegistry {
+        AddressPolicyRegistry {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+            policy: "some_policy".to_owned(),
+            r#type: mapping_type,
+            version: 1,
+            effective_from: None,
+            expires_at: None,
+            value_bands: vec![],
+        }
+    }
+
+    async fn mock_maestro_policy(response: ResponseTemplate, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/some_policy"
+            )))
+            .respond_with(response)
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_eip1559_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": eip1559_transaction(CHAIN_ID_FOR_MOCK_REQUESTS),
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(2)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::AddressTo { .. } => Ok(Some(mapping(mapping_type))),
+                _ => Ok(None),
+            });
+        mock_maestro_policy(
+            ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "policy_name": "some_policy",
+                "display_name": "Some policy",
+                "serialized_policy": "base64_policy",
+                "approvals": [{ "level": "DOMAIN", "name": "compliance" }]
+            })),
+            &fixture.mock_server,
+        )
+        .await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: EvaluatePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!(MappingType::AddressTo, resolved.r#type);
+        assert_eq!(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, resolved.address);
+        assert_eq!(Some("some_policy".to_owned()), body.applied_policy);
+        let maestro_policy = body.maestro_policy.unwrap();
+        assert_eq!("some_policy", maestro_policy.name);
+        assert_eq!(1, maestro_policy.approvals.len());
+        assert_eq!("DOMAIN", maestro_policy.approvals[0].level);
+        assert_eq!("compliance", maestro_policy.approvals[0].name);
+        assert_eq!(4, body.candidates.len());
+        assert_eq!(
+            CandidateOutcomeResponse::Selected,
+            body.candidates[1].outcome
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_sponsored_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": {
+                "typed_data": sponsored_typed_data(),
+                "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            },
+        }));
+
+        // Sponsored transactions have no calldata, so CONTRACT_CALL mappings are not looked up.
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(3)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::Default => Ok(Some(mapping(mapping_type))),
+                _ => Ok(None),
+            });
+        mock_maestro_policy(
+            ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "policy_name": "some_policy",
+                "display_name": "Some policy",
+                "serialized_policy": "base64_policy",
+            })),
+            &fixture.mock_server,
+        )
+        .await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: EvaluatePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::Default, body.policy.unwrap().r#type);
+        assert!(body.maestro_policy.unwrap().approvals.is_empty());
+        let address_to = body
+            .candidates
+            .iter()
+            .find(|candidate| candidate.r#type == MappingType::AddressTo)
+            .unwrap();
+        assert_eq!(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, address_to.address);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_policy_missing_in_maestro(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": eip1559_transaction(CHAIN_ID_FOR_MOCK_REQUESTS),
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .returning(|_, _, mapping_type| Ok(Some(mapping(mapping_type))));
+        mock_maestro_policy(
+            ResponseTemplate::new(StatusCode::NOT_FOUND),
+            &fixture.mock_server,
+        )
+        .await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: EvaluatePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(Some("some_policy".to_owned()), body.applied_policy);
+        assert!(body.maestro_policy.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_without_mappings_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": eip1559_transaction(CHAIN_ID_FOR_MOCK_REQUESTS),
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(4)
+            .returning(|_, _, _| Ok(None));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: EvaluatePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.policy.is_none());
+        assert!(body.applied_policy.is_none());
+        assert!(body.maestro_policy.is_none());
+        // Maestro is not asked for anything.
+        assert!(fixture
+            .mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_unsupported_chain(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": eip1559_transaction(28731237918),
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("chain_id 28731237918 is not supported", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": eip1559_transaction(CHAIN_ID_FOR_MOCK_REQUESTS),
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags().with_flag(
+                &chain_feature_flag(CHAIN_ID_FOR_MOCK_REQUESTS),
+                json!(false),
+            )),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("forbidden", body.code);
+        assert_eq!(
+            format!("chain_id {CHAIN_ID_FOR_MOCK_REQUESTS} is not enabled for this client"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn evaluate_sponsored_without_to(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let mut typed_data = sponsored_typed_data();
+        typed_data["message"].as_object_mut().unwrap().remove("to");
+        let request = build_request(json!({
+            "from": ADDRESS_FOR_MOCK_REQUESTS,
+            "transaction": {
+                "typed_data": typed_data,
+                "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS,
+            },
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = evaluate_policy_mapping(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("transaction doesn't have a valid to address", body.message);
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use mpc_signature_sm::dtos::policy_mapping_snapshot::{PolicyMappingSnapshot, SnapshotFormat};
+use mpc_signature_sm::http::errors::unknown_error_response;
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+
+use crate::config::Config;
+
+mod config;
+
+pub const FORMAT_QUERY_PARAM: &str = "format";
+
+pub struct State<APRR: AddressPolicyRegistryRepository> {
+    address_policy_registry_repository: Arc<APRR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client,
+            ));
+
+        State {
+            address_policy_registry_repository,
+        }
+    },
+    export_policy
+);
+
+/// Returns a snapshot of every row the client has in the address policy registry, as JSONL
+/// (default) or CSV.
+async fn export_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let client_id = request.extract_client_id()?;
+    let format = request
+        .extract_query_param::<SnapshotFormat>(FORMAT_QUERY_PARAM)?
+        .unwrap_or_default();
+
+    let rows = state
+        .address_policy_registry_repository
+        .export_policies(client_id.clone())
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error exporting address policy mappings. {e:?}"
+            )))
+        })?;
+
+    let document = PolicyMappingSnapshot::new(client_id, rows, Utc::now())
+        .encode(format)
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(
+                e.context("encoding policy mapping snapshot"),
+            ))
+        })?;
+
+    let mut response = LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(document),
+        ..LambdaProxyHttpResponse::default()
+    };
+    response
+        .headers
+        .insert("Content-Type".to_owned(), format.content_type().to_owned());
+
+    response.try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS},
+        helpers::build_request_custom_auth,
+    };
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_policy_registry::AddressPolicyRegistryBuilder;
+    use mpc_signature_sm::dtos::policy_mapping_snapshot::{
+        PolicyMappingSnapshot, SnapshotFormat, POLICY_MAPPING_SNAPSHOT_VERSION,
+    };
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryDynamoDbResource, AddressPolicyRegistryRepositoryError,
+        MockAddressPolicyRegistryRepository,
+    };
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+
+    use crate::{export_policy, State, FORMAT_QUERY_PARAM};
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+        }
+    }
+
+    fn build_request(format: Option<&str>) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let request = build_request_custom_auth(auth, Body::default());
+        match format {
+            Some(format) => request.with_query_string_parameters(HashMap::from([(
+                FORMAT_QUERY_PARAM.to_owned(),
+                format.to_owned(),
+            )])),
+            None => request,
+        }
+    }
+
+    fn stored_rows() -> Vec<AddressPolicyRegistryDynamoDbResource> {
+        vec![AddressPolicyRegistryDynamoDbResource::from(
+            AddressPolicyRegistryBuilder::new(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                "some_policy".to_owned(),
+            )
+            .default(),
+        )]
+    }
+
+    #[rstest]
+    #[case::default_format(None, SnapshotFormat::Jsonl)]
+    #[case::jsonl(Some("jsonl"), SnapshotFormat::Jsonl)]
+    #[case::csv(Some("CSV"), SnapshotFormat::Csv)]
+    #[tokio::test]
+    async fn export_policy_ok(
+        mut fixture: TestFixture,
+        #[case] format: Option<&str>,
+        #[case] expected_format: SnapshotFormat,
+    ) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_export_policies()
+            .once()
+            .returning(|_| Ok(stored_rows()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = export_policy(build_request(format), &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            expected_format.content_type(),
+            response.headers()["Content-Type"]
+        );
+        let snapshot = PolicyMappingSnapshot::decode(response.body(), expected_format).unwrap();
+        assert_eq!(
+            POLICY_MAPPING_SNAPSHOT_VERSION,
+            snapshot.header.format_version
+        );
+        assert_eq!(CLIENT_ID_FOR_MOCK_REQUESTS, snapshot.header.client_id);
+        assert_eq!(1, snapshot.rows.len());
+        assert_eq!("some_policy", snapshot.rows[0].policy);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn export_policy_unknown_format(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_export_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = export_policy(build_request(Some("xml")), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn export_policy_repository_error(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_export_policies()
+            .once()
+            .returning(|_| {
+                Err(AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow::anyhow!("timeout"),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = export_policy(build_request(None), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+}
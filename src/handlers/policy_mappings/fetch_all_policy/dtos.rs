@@ -1,11 +1,27 @@
-use model::address_policy_registry::AddressPolicyRegistryType;
+use model::address_policy_registry::value_band::PolicyValueBand;
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
 use serde::{self, Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Deserialize, Serialize)]
 pub struct Address {
     pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
     pub policy: String,
     pub r#type: MappingType,
+    /// Listings include mappings that are not active yet or already expired.
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -17,22 +33,7 @@ pub struct Chain {
 #[derive(Deserialize, Serialize)]
 pub struct FetchAllPolicyResponse {
     pub chains: Vec<Chain>,
-}
-
-#[derive(Deserialize, Serialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum MappingType {
-    Default,
-    AddressFrom,
-    AddressTo,
-}
-
-impl From<AddressPolicyRegistryType> for MappingType {
-    fn from(value: AddressPolicyRegistryType) -> Self {
-        match value {
-            AddressPolicyRegistryType::Default => MappingType::Default,
-            AddressPolicyRegistryType::AddressFrom { .. } => MappingType::AddressFrom,
-            AddressPolicyRegistryType::AddressTo { .. } => MappingType::AddressTo,
-        }
-    }
+    /// Client wide mappings (chain `*`), they apply on every chain without a mapping of its own.
+    pub global: Vec<Address>,
+    pub next_cursor: Option<String>,
 }
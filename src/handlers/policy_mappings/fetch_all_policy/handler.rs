@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use http::{Response, StatusCode};
+use lambda_http::Request;
+use mpc_signature_sm::dtos::policy_mapping_chain::parse_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingType,
+};
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor, RequestExtractorError,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryFilters, AddressPolicyRegistryPage, AddressPolicyRegistryRepository,
+    AddressPolicyRegistryRepositoryError, MappingTypeFilter,
+};
+
+use super::dtos::{Address, Chain, FetchAllPolicyResponse};
+
+pub const POLICY_NOT_FOUND_CODE: &str = "policy_not_found";
+pub const ADDRESS_PATH_PARAM: &str = "address";
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const LIMIT_QUERY_PARAM: &str = "limit";
+pub const CURSOR_QUERY_PARAM: &str = "cursor";
+pub const CHAIN_ID_QUERY_PARAM: &str = "chain_id";
+pub const TYPE_QUERY_PARAM: &str = "type";
+/// Matches the policy of the mapping itself. Policies only referenced by its value bands don't
+/// match, use the dangling policy report to find every mapping pointing at a policy.
+pub const POLICY_QUERY_PARAM: &str = "policy";
+pub const ADDRESS_PREFIX_QUERY_PARAM: &str = "address_prefix";
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+pub struct State<APRR: AddressPolicyRegistryRepository> {
+    pub address_policy_registry_repository: Arc<APRR>,
+}
+
+pub async fn fetch_all_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let client_id = request.extract_client_id()?;
+    let limit = request.extract_query_param::<i64>(LIMIT_QUERY_PARAM)?;
+    let cursor = request.extract_query_param::<String>(CURSOR_QUERY_PARAM)?;
+    let filters = extract_filters(&request)?;
+
+    if let Some(limit) = limit {
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(validation_error_response(
+                format!("{LIMIT_QUERY_PARAM} must be between 1 and {MAX_PAGE_LIMIT}"),
+                None,
+            ));
+        }
+    }
+
+    let page = state
+        .address_policy_registry_repository
+        .get_policies_page(client_id, filters, limit, cursor)
+        .await
+        .map_err(|e| match e {
+            AddressPolicyRegistryRepositoryError::InvalidCursor(message) => {
+                validation_error_response(message, None)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mapping. {e:?}"
+            ))),
+        })?;
+
+    let fetch_policy_response = convert_page_to_fetch_all_policy_response(page);
+
+    let final_response = serde_json::to_string(&fetch_policy_response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting policy response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(final_response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+fn extract_filters(request: &Request) -> Result<AddressPolicyRegistryFilters, Response<String>> {
+    let address_prefix = request.extract_query_param::<String>(ADDRESS_PREFIX_QUERY_PARAM)?;
+    if let Some(address_prefix) = &address_prefix {
+        if !is_address_prefix(address_prefix) {
+            return Err(validation_error_response(
+                format!("{ADDRESS_PREFIX_QUERY_PARAM} must be a 0x prefixed hex string"),
+                None,
+            ));
+        }
+    }
+
+    Ok(AddressPolicyRegistryFilters {
+        chain_id: request
+            .extract_query_param::<String>(CHAIN_ID_QUERY_PARAM)?
+            .map(|chain_id| {
+                parse_mapping_chain_id(&chain_id).ok_or_else(|| {
+                    RequestExtractorError::QueryParamWithWrongTypeError(
+                        CHAIN_ID_QUERY_PARAM.to_owned(),
+                    )
+                })
+            })
+            .transpose()?,
+        mapping_type: request
+            .extract_query_param::<MappingType>(TYPE_QUERY_PARAM)?
+            .map(MappingTypeFilter::from),
+        policy: request.extract_query_param::<String>(POLICY_QUERY_PARAM)?,
+        address_prefix,
+    })
+}
+
+fn is_address_prefix(address_prefix: &str) -> bool {
+    address_prefix
+        .strip_prefix("0x")
+        .map(|digits| digits.len() <= 40 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+fn convert_page_to_fetch_all_policy_response(
+    page: AddressPolicyRegistryPage,
+) -> FetchAllPolicyResponse {
+    let mut chains_map = std::collections::HashMap::new();
+    let mut global = Vec::new();
+
+    for policy in page.policies {
+        let addresses = if policy.is_client_wide() {
+            &mut global
+        } else {
+            &mut chains_map
+                .entry(policy.chain_id)
+                .or_insert_with(|| Chain {
+                    chain_id: policy.chain_id,
+                    addresses: Vec::new(),
+                })
+                .addresses
+        };
+
+        addresses.push(Address {
+            address: mapping_address_to_string(&policy.r#type),
+            selector: mapping_selector_to_string(&policy.r#type),
+            key_id: mapping_key_id(&policy.r#type),
+            client_user_id: mapping_client_user_id(&policy.r#type),
+            group: mapping_group(&policy.r#type),
+            r#type: MappingType::from(&policy.r#type),
+            window: PolicyMappingWindow::from(&policy),
+            policy: policy.policy,
+            value_bands: policy.value_bands,
+        });
+    }
+
+    FetchAllPolicyResponse {
+        chains: chains_map.into_values().collect(),
+        global,
+        next_cursor: page.next_cursor,
+    }
+}
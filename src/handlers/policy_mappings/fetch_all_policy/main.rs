@@ -2,42 +2,27 @@ use std::sync::Arc;
 
 use common::aws_clients::dynamodb::get_dynamodb_client;
 use common::config::ConfigLoader;
-use common::serializers::h160::h160_to_lowercase_hex_string;
-use dtos::{Address, Chain, FetchAllPolicyResponse, MappingType};
-use http::StatusCode;
+use handler::{fetch_all_policy, State};
 use lambda_http::{run, service_fn, Error, Request};
-use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryType};
-use mpc_signature_sm::http::errors::unknown_error_response;
-use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
 use mpc_signature_sm::http_lambda_main;
-use mpc_signature_sm::lambda_structure::http_lambda_main::{
-    CustomFieldsExtractor, HttpLambdaResponse,
-};
-use mpc_signature_sm::result::error::LambdaError;
 use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
-use repositories::address_policy_registry::AddressPolicyRegistryRepository;
 
 use crate::config::Config;
 
 mod config;
 mod dtos;
-
-pub const POLICY_NOT_FOUND_CODE: &str = "policy_not_found";
-pub const ADDRESS_PATH_PARAM: &str = "address";
-pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
-
-pub struct State<APRR: AddressPolicyRegistryRepository> {
-    address_policy_registry_repository: Arc<APRR>,
-}
+pub mod handler;
 
 http_lambda_main!(
     {
         let config = ConfigLoader::load_default::<Config>();
         let dynamodb_client = get_dynamodb_client().await;
 
+        let config = config.await;
         let address_policy_registry_repository =
             Arc::new(AddressPolicyRegistryRepositoryImpl::new(
-                config.await.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
                 dynamodb_client,
             ));
 
@@ -48,72 +33,6 @@ http_lambda_main!(
     fetch_all_policy
 );
 
-async fn fetch_all_policy(
-    request: Request,
-    state: &State<impl AddressPolicyRegistryRepository>,
-) -> HttpLambdaResponse {
-    let client_id = request.extract_client_id()?;
-
-    let policies = state
-        .address_policy_registry_repository
-        .get_all_policies(client_id)
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error fetching address policy mapping. {e:?}"
-            )))
-        })?;
-
-    let fetch_policy_response = convert_policies_to_fetch_all_policy_response(policies);
-
-    let final_response = serde_json::to_string(&fetch_policy_response).map_err(|e| {
-        unknown_error_response(LambdaError::Unknown(
-            anyhow::anyhow!(e).context("converting policy response"),
-        ))
-    })?;
-
-    LambdaProxyHttpResponse {
-        status_code: StatusCode::OK,
-        body: Some(final_response),
-        ..LambdaProxyHttpResponse::default()
-    }
-    .try_into()
-}
-
-fn convert_policies_to_fetch_all_policy_response(
-    policies: Vec<AddressPolicyRegistry>,
-) -> FetchAllPolicyResponse {
-    let mut chains_map = std::collections::HashMap::new();
-
-    for policy in policies {
-        let chain_entry = chains_map.entry(policy.chain_id).or_insert_with(|| Chain {
-            chain_id: policy.chain_id,
-            addresses: Vec::new(),
-        });
-
-        let address = match policy.r#type {
-            AddressPolicyRegistryType::Default => "default".to_owned(),
-            AddressPolicyRegistryType::AddressTo { address } => {
-                h160_to_lowercase_hex_string(address)
-            }
-            // TODO: At this stage this does not exist, we need to rethink the response dto
-            AddressPolicyRegistryType::AddressFrom { address } => {
-                h160_to_lowercase_hex_string(address)
-            }
-        };
-
-        chain_entry.addresses.push(Address {
-            address,
-            policy: policy.policy,
-            r#type: MappingType::from(policy.r#type),
-        });
-    }
-
-    FetchAllPolicyResponse {
-        chains: chains_map.into_values().collect(),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use common::test_tools::http::{
@@ -125,16 +44,29 @@ mod tests {
     use ethers::types::Address;
     use http::{Request, StatusCode};
     use lambda_http::{Body, RequestExt};
-    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryType};
-    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use model::address_policy_registry::{
+        AddressPolicyRegistry, AddressPolicyRegistryType, ANY_CHAIN_ID,
+    };
+    use mpc_signature_sm::dtos::responses::http_error::LambdaErrorResponse;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryFilters, AddressPolicyRegistryPage,
+        AddressPolicyRegistryRepositoryError, MappingTypeFilter,
+        MockAddressPolicyRegistryRepository,
+    };
     use rstest::{fixture, rstest};
     use serde_json::json;
     use std::str::FromStr;
     use std::{collections::HashMap, sync::Arc};
 
+    use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+
     use crate::{
-        dtos::FetchAllPolicyResponse, fetch_all_policy, State, ADDRESS_PATH_PARAM,
-        CHAIN_ID_PATH_PARAM,
+        dtos::FetchAllPolicyResponse,
+        handler::{
+            fetch_all_policy, State, ADDRESS_PATH_PARAM, ADDRESS_PREFIX_QUERY_PARAM,
+            CHAIN_ID_PATH_PARAM, CHAIN_ID_QUERY_PARAM, CURSOR_QUERY_PARAM, LIMIT_QUERY_PARAM,
+            POLICY_QUERY_PARAM, TYPE_QUERY_PARAM,
+        },
     };
 
     struct TestFixture {
@@ -167,39 +99,70 @@ mod tests {
 
         fixture
             .mock_address_policy_registry_repository
-            .expect_get_all_policies()
+            .expect_get_policies_page()
             .once()
-            .returning(move |_| {
-                Ok(vec![
-                    AddressPolicyRegistry {
-                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
-                        chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
-                        policy: policy.to_owned(),
-                        r#type: AddressPolicyRegistryType::AddressTo {
-                            address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+            .returning(move |_, _, _, _| {
+                Ok(AddressPolicyRegistryPage {
+                    next_cursor: None,
+                    policies: vec![
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                            policy: policy.to_owned(),
+                            r#type: AddressPolicyRegistryType::AddressTo {
+                                address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                            },
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
                         },
-                    },
-                    AddressPolicyRegistry {
-                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
-                        chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
-                        policy: default_policy.to_owned(),
-                        r#type: AddressPolicyRegistryType::Default,
-                    },
-                    AddressPolicyRegistry {
-                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
-                        chain_id: second_chain_id, // This is now safe to use
-                        policy: policy.to_owned(),
-                        r#type: AddressPolicyRegistryType::AddressTo {
-                            address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                            policy: default_policy.to_owned(),
+                            r#type: AddressPolicyRegistryType::Default,
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
                         },
-                    },
-                    AddressPolicyRegistry {
-                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
-                        chain_id: second_chain_id, // This is now safe to use
-                        policy: default_policy.to_owned(),
-                        r#type: AddressPolicyRegistryType::Default,
-                    },
-                ])
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                            policy: policy.to_owned(),
+                            r#type: AddressPolicyRegistryType::AddressFrom {
+                                address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                            },
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
+                        },
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: second_chain_id, // This is now safe to use
+                            policy: policy.to_owned(),
+                            r#type: AddressPolicyRegistryType::AddressTo {
+                                address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
+                            },
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
+                        },
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: second_chain_id, // This is now safe to use
+                            policy: default_policy.to_owned(),
+                            r#type: AddressPolicyRegistryType::Default,
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
+                        },
+                    ],
+                })
             });
 
         let state = State {
@@ -221,7 +184,15 @@ mod tests {
             .iter()
             .find(|c| c.chain_id == CHAIN_ID_FOR_MOCK_REQUESTS)
             .unwrap();
-        assert_eq!(first_chain.addresses.len(), 2);
+        assert_eq!(first_chain.addresses.len(), 3);
+        assert_eq!(
+            first_chain
+                .addresses
+                .iter()
+                .filter(|a| a.r#type == MappingType::AddressFrom)
+                .count(),
+            1
+        );
 
         // Validate the second chain
         let second_chain = body
@@ -230,5 +201,270 @@ mod tests {
             .find(|c| c.chain_id == second_chain_id)
             .unwrap();
         assert_eq!(second_chain.addresses.len(), 2);
+        assert!(body.next_cursor.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_policy_page_ok(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([
+                (LIMIT_QUERY_PARAM.to_owned(), "1".to_owned()),
+                (CURSOR_QUERY_PARAM.to_owned(), "some_cursor".to_owned()),
+            ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .once()
+            .withf(|_, _, limit, cursor| {
+                *limit == Some(1) && cursor.as_deref() == Some("some_cursor")
+            })
+            .returning(|_, _, _, _| {
+                Ok(AddressPolicyRegistryPage {
+                    policies: vec![AddressPolicyRegistry {
+                        client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                        chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                        policy: "default_policy".to_owned(),
+                        r#type: AddressPolicyRegistryType::Default,
+                        version: 1,
+                        effective_from: None,
+                        expires_at: None,
+                        value_bands: vec![],
+                    }],
+                    next_cursor: Some("next_cursor".to_owned()),
+                })
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchAllPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.chains.len());
+        assert_eq!(Some("next_cursor".to_owned()), body.next_cursor);
+    }
+
+    #[rstest]
+    #[case::zero("0")]
+    #[case::too_big("1001")]
+    #[case::not_a_number("ten")]
+    #[tokio::test]
+    async fn fetch_all_policy_invalid_limit(mut fixture: TestFixture, #[case] limit: &str) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                LIMIT_QUERY_PARAM.to_owned(),
+                limit.to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_policy_invalid_cursor(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                CURSOR_QUERY_PARAM.to_owned(),
+                "invalid".to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .once()
+            .returning(|_, _, _, _| {
+                Err(AddressPolicyRegistryRepositoryError::InvalidCursor(
+                    "invalid cursor invalid".to_owned(),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("invalid cursor invalid", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_policy_global_ok(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .once()
+            .returning(|_, _, _, _| {
+                Ok(AddressPolicyRegistryPage {
+                    next_cursor: None,
+                    policies: vec![
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                            policy: "chain_policy".to_owned(),
+                            r#type: AddressPolicyRegistryType::Default,
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
+                        },
+                        AddressPolicyRegistry {
+                            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                            chain_id: ANY_CHAIN_ID,
+                            policy: "client_wide_policy".to_owned(),
+                            r#type: AddressPolicyRegistryType::Default,
+                            version: 1,
+                            effective_from: None,
+                            expires_at: None,
+                            value_bands: vec![],
+                        },
+                    ],
+                })
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchAllPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.chains.len());
+        assert_eq!(CHAIN_ID_FOR_MOCK_REQUESTS, body.chains[0].chain_id);
+        assert_eq!(1, body.global.len());
+        assert_eq!("client_wide_policy", body.global[0].policy);
+        assert_eq!(MappingType::Default, body.global[0].r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_policy_global_filter_ok(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                CHAIN_ID_QUERY_PARAM.to_owned(),
+                "*".to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .once()
+            .withf(|_, filters, _, _| filters.chain_id == Some(ANY_CHAIN_ID))
+            .returning(|_, _, _, _| Ok(AddressPolicyRegistryPage::default()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_all_policy_filters_ok(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([
+                (
+                    CHAIN_ID_QUERY_PARAM.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS.to_string(),
+                ),
+                (TYPE_QUERY_PARAM.to_owned(), "ADDRESS_FROM".to_owned()),
+                (POLICY_QUERY_PARAM.to_owned(), "some_policy".to_owned()),
+                (ADDRESS_PREFIX_QUERY_PARAM.to_owned(), "0x1c96".to_owned()),
+            ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .once()
+            .withf(|_, filters, _, _| {
+                *filters
+                    == AddressPolicyRegistryFilters {
+                        chain_id: Some(CHAIN_ID_FOR_MOCK_REQUESTS),
+                        mapping_type: Some(MappingTypeFilter::AddressFrom),
+                        policy: Some("some_policy".to_owned()),
+                        address_prefix: Some("0x1c96".to_owned()),
+                    }
+            })
+            .returning(|_, _, _, _| Ok(AddressPolicyRegistryPage::default()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchAllPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.chains.is_empty());
+    }
+
+    #[rstest]
+    #[case::invalid_chain_id(CHAIN_ID_QUERY_PARAM, "mainnet")]
+    #[case::invalid_type(TYPE_QUERY_PARAM, "ADDRESS_ANY")]
+    #[case::address_prefix_without_0x(ADDRESS_PREFIX_QUERY_PARAM, "1c96")]
+    #[case::address_prefix_not_hex(ADDRESS_PREFIX_QUERY_PARAM, "0xzz")]
+    #[tokio::test]
+    async fn fetch_all_policy_invalid_filters(
+        mut fixture: TestFixture,
+        #[case] param: &str,
+        #[case] value: &str,
+    ) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(param.to_owned(), value.to_owned())]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policies_page()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_all_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
     }
 }
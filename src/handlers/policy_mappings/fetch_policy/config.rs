@@ -3,4 +3,5 @@ use serde::Deserialize;
 #[derive(Deserialize)]
 pub struct Config {
     pub address_policy_registry_table_name: String,
+    pub address_policy_registry_history_table_name: String,
 }
@@ -2,8 +2,18 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use model::address_policy_registry::value_band::PolicyValueBand;
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+
 #[derive(Serialize)]
 #[cfg_attr(test, derive(Deserialize))]
 pub struct FetchPolicyResponse {
     pub policy: String,
+    pub r#type: MappingType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_bands: Vec<PolicyValueBand>,
+    /// Mappings are returned before they become effective, along with their window.
+    #[serde(flatten)]
+    pub window: PolicyMappingWindow,
 }
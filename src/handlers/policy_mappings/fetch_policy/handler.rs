@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use http::StatusCode;
+use lambda_http::Request;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use mpc_signature_sm::dtos::policy_mapping_chain::extract_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{MappingTarget, MappingType};
+use mpc_signature_sm::dtos::policy_mapping_window::PolicyMappingWindow;
+use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
+use mpc_signature_sm::dtos::requests::if_match_header::{version_etag, ETAG_HEADER_NAME};
+use mpc_signature_sm::http::errors::{not_found_response, unknown_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use uuid::Uuid;
+
+use super::dtos::FetchPolicyResponse;
+
+pub const POLICY_NOT_FOUND_CODE: &str = "policy_not_found";
+pub const ADDRESS_PATH_PARAM: &str = "address";
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const TYPE_QUERY_PARAM: &str = "type";
+pub const SELECTOR_QUERY_PARAM: &str = "selector";
+pub const KEY_ID_QUERY_PARAM: &str = "key_id";
+pub const CLIENT_USER_ID_QUERY_PARAM: &str = "client_user_id";
+pub const GROUP_QUERY_PARAM: &str = "group";
+
+pub struct State<APRR: AddressPolicyRegistryRepository> {
+    pub address_policy_registry_repository: Arc<APRR>,
+}
+
+pub async fn fetch_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let chain_id = extract_mapping_chain_id(&request, CHAIN_ID_PATH_PARAM)?;
+    let client_id = request.extract_client_id()?;
+    let address = request
+        .extract_path_param::<AddressOrDefaultPathParam>(ADDRESS_PATH_PARAM)?
+        .extract_address();
+    let target = MappingTarget {
+        address,
+        selector: request.extract_query_param::<FunctionSelector>(SELECTOR_QUERY_PARAM)?,
+        key_id: request.extract_query_param::<Uuid>(KEY_ID_QUERY_PARAM)?,
+        client_user_id: request.extract_query_param::<String>(CLIENT_USER_ID_QUERY_PARAM)?,
+        group: request.extract_query_param::<String>(GROUP_QUERY_PARAM)?,
+    };
+    let mapping_type = request
+        .extract_query_param::<MappingType>(TYPE_QUERY_PARAM)?
+        .unwrap_or_else(|| MappingType::inferred_from(&target));
+    let registry_type = mapping_type.into_registry_type(target)?;
+
+    let policy = state
+        .address_policy_registry_repository
+        .get_stored_policy(client_id, chain_id, registry_type)
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mapping. {e:?}"
+            )))
+        })?
+        .ok_or_else(|| {
+            not_found_response(
+                POLICY_NOT_FOUND_CODE,
+                format!(
+                    "there was no {} policy found for chain id {chain_id} and address {address:?}",
+                    mapping_type.as_str()
+                ),
+            )
+        })?;
+
+    let etag = version_etag(policy.version);
+    let response = serde_json::to_string(&FetchPolicyResponse {
+        r#type: MappingType::from(&policy.r#type),
+        window: PolicyMappingWindow::from(&policy),
+        policy: policy.policy,
+        value_bands: policy.value_bands,
+    })
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting policy response"),
+        ))
+    })?;
+
+    let mut proxy_response = LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    };
+    proxy_response
+        .headers
+        .insert(ETAG_HEADER_NAME.to_owned(), etag);
+    proxy_response.try_into()
+}
@@ -2,41 +2,27 @@ use std::sync::Arc;
 
 use common::aws_clients::dynamodb::get_dynamodb_client;
 use common::config::ConfigLoader;
-use dtos::FetchPolicyResponse;
-use http::StatusCode;
+use handler::{fetch_policy, State};
 use lambda_http::{run, service_fn, Error, Request};
-use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
-use mpc_signature_sm::http::errors::{not_found_response, unknown_error_response};
-use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
 use mpc_signature_sm::http_lambda_main;
-use mpc_signature_sm::lambda_structure::http_lambda_main::{
-    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
-};
-use mpc_signature_sm::result::error::LambdaError;
 use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
-use repositories::address_policy_registry::AddressPolicyRegistryRepository;
 
 use crate::config::Config;
 
 mod config;
 mod dtos;
-
-pub const POLICY_NOT_FOUND_CODE: &str = "policy_not_found";
-pub const ADDRESS_PATH_PARAM: &str = "address";
-pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
-
-pub struct State<APRR: AddressPolicyRegistryRepository> {
-    address_policy_registry_repository: Arc<APRR>,
-}
+pub mod handler;
 
 http_lambda_main!(
     {
         let config = ConfigLoader::load_default::<Config>();
         let dynamodb_client = get_dynamodb_client().await;
 
+        let config = config.await;
         let address_policy_registry_repository =
             Arc::new(AddressPolicyRegistryRepositoryImpl::new(
-                config.await.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
                 dynamodb_client,
             ));
 
@@ -47,53 +33,9 @@ http_lambda_main!(
     fetch_policy
 );
 
-async fn fetch_policy(
-    request: Request,
-    state: &State<impl AddressPolicyRegistryRepository>,
-) -> HttpLambdaResponse {
-    let chain_id: u64 = request.extract_path_param(CHAIN_ID_PATH_PARAM)?;
-    let client_id = request.extract_client_id()?;
-    let address = request
-        .extract_path_param::<AddressOrDefaultPathParam>(ADDRESS_PATH_PARAM)?
-        .extract_address();
-
-    let policy = state
-        .address_policy_registry_repository
-        .get_policy(client_id, chain_id, address)
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error fetching address policy mapping. {e:?}"
-            )))
-        })?
-        .ok_or_else(|| {
-            not_found_response(
-                POLICY_NOT_FOUND_CODE,
-                format!(
-                    "there was no policy found for chain id {chain_id} and address {address:?}"
-                ),
-            )
-        })?;
-
-    let response = serde_json::to_string(&FetchPolicyResponse {
-        policy: policy.policy,
-    })
-    .map_err(|e| {
-        unknown_error_response(LambdaError::Unknown(
-            anyhow::anyhow!(e).context("converting policy response"),
-        ))
-    })?;
-
-    LambdaProxyHttpResponse {
-        status_code: StatusCode::OK,
-        body: Some(response),
-        ..LambdaProxyHttpResponse::default()
-    }
-    .try_into()
-}
-
 #[cfg(test)]
 mod tests {
+    use chrono::{Duration, Utc};
     use common::test_tools::http::{
         constants::{
             ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
@@ -103,8 +45,13 @@ mod tests {
     use ethers::types::Address;
     use http::{Request, StatusCode};
     use lambda_http::{Body, RequestExt};
-    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryType};
-    use mpc_signature_sm::dtos::responses::http_error::LambdaErrorResponse;
+    use model::address_policy_registry::{
+        function_selector::FunctionSelector, AddressPolicyRegistry, AddressPolicyRegistryBuilder,
+        AddressPolicyRegistryType,
+    };
+    use mpc_signature_sm::dtos::{
+        policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse,
+    };
     use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
     use rstest::{fixture, rstest};
     use serde_json::json;
@@ -112,7 +59,11 @@ mod tests {
     use std::{collections::HashMap, sync::Arc};
 
     use crate::{
-        dtos::FetchPolicyResponse, fetch_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM,
+        dtos::FetchPolicyResponse,
+        handler::{
+            fetch_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM, SELECTOR_QUERY_PARAM,
+            TYPE_QUERY_PARAM,
+        },
     };
 
     struct TestFixture {
@@ -167,7 +118,7 @@ mod tests {
 
         fixture
             .mock_address_policy_registry_repository
-            .expect_get_policy()
+            .expect_get_stored_policy()
             .once()
             .returning(|_, _, _| {
                 Ok(Some(AddressPolicyRegistry {
@@ -177,6 +128,10 @@ mod tests {
                     r#type: AddressPolicyRegistryType::AddressTo {
                         address: Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap(),
                     },
+                    version: 3,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
                 }))
             });
 
@@ -189,7 +144,165 @@ mod tests {
         let response = fetch_policy(request, &state).await.unwrap();
 
         assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("\"3\"", response.headers().get("ETag").unwrap());
         let body: FetchPolicyResponse = serde_json::from_str(response.body()).unwrap();
         assert_eq!(policy, body.policy);
+        assert_eq!(MappingType::AddressTo, body.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_scheduled_policy_ok(mut fixture: TestFixture) {
+        let effective_from = Utc::now() + Duration::hours(1);
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .returning(move |_, _, _| {
+                Ok(Some(
+                    AddressPolicyRegistryBuilder::new(
+                        CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "some_policy".to_owned(),
+                    )
+                    .active_between(Some(effective_from), None)
+                    .address_to(Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(Some(effective_from), body.window.effective_from);
+        assert_eq!(None, body.window.expires_at);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_address_from_policy_ok(mut fixture: TestFixture) {
+        let policy = "some_policy";
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                TYPE_QUERY_PARAM.to_owned(),
+                "ADDRESS_FROM".to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .withf(move |_, _, mapping_type| {
+                *mapping_type == AddressPolicyRegistryType::AddressFrom { address }
+            })
+            .returning(move |_, _, mapping_type| {
+                Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: policy.to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                }))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(policy, body.policy);
+        assert_eq!(MappingType::AddressFrom, body.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_contract_call_policy_ok(mut fixture: TestFixture) {
+        let policy = "some_policy";
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                SELECTOR_QUERY_PARAM.to_owned(),
+                "0xa9059cbb".to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .withf(move |_, _, mapping_type| {
+                *mapping_type
+                    == AddressPolicyRegistryType::ContractCall {
+                        address,
+                        selector: FunctionSelector([0xa9, 0x05, 0x9c, 0xbb]),
+                    }
+            })
+            .returning(move |_, _, mapping_type| {
+                Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: policy.to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                }))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchPolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(MappingType::ContractCall, body.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_default_with_address_from_type(mut fixture: TestFixture) {
+        let request =
+            build_request("default", CHAIN_ID_FOR_MOCK_REQUESTS).with_query_string_parameters(
+                HashMap::from([(TYPE_QUERY_PARAM.to_owned(), "ADDRESS_FROM".to_owned())]),
+            );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("ADDRESS_FROM mappings require an address", body.message);
     }
 }
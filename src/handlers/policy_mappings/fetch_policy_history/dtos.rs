@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+use model::address_policy_registry::{AddressPolicyRegistryChange, AddressPolicyRegistryTerms};
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct PolicyMappingHistoryEntry {
+    pub change: AddressPolicyRegistryChange,
+    pub r#type: MappingType,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub previous_policy: Option<String>,
+    pub new_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_terms: Option<AddressPolicyRegistryTerms>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_terms: Option<AddressPolicyRegistryTerms>,
+    pub client_id: String,
+    pub subject: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct FetchPolicyHistoryResponse {
+    pub history: Vec<PolicyMappingHistoryEntry>,
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,395 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use dtos::{FetchPolicyHistoryResponse, PolicyMappingHistoryEntry};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::function_selector::FunctionSelector;
+use mpc_signature_sm::dtos::policy_mapping_chain::extract_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingTarget, MappingType,
+};
+use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
+use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryHistoryPage, AddressPolicyRegistryRepository,
+    AddressPolicyRegistryRepositoryError,
+};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub const ADDRESS_PATH_PARAM: &str = "address";
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const TYPE_QUERY_PARAM: &str = "type";
+pub const SELECTOR_QUERY_PARAM: &str = "selector";
+pub const KEY_ID_QUERY_PARAM: &str = "key_id";
+pub const CLIENT_USER_ID_QUERY_PARAM: &str = "client_user_id";
+pub const GROUP_QUERY_PARAM: &str = "group";
+pub const LIMIT_QUERY_PARAM: &str = "limit";
+pub const CURSOR_QUERY_PARAM: &str = "cursor";
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+pub struct State<APRR: AddressPolicyRegistryRepository> {
+    address_policy_registry_repository: Arc<APRR>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client,
+            ));
+
+        State {
+            address_policy_registry_repository,
+        }
+    },
+    fetch_policy_history
+);
+
+async fn fetch_policy_history(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository>,
+) -> HttpLambdaResponse {
+    let chain_id = extract_mapping_chain_id(&request, CHAIN_ID_PATH_PARAM)?;
+    let client_id = request.extract_client_id()?;
+    let address = request
+        .extract_path_param::<AddressOrDefaultPathParam>(ADDRESS_PATH_PARAM)?
+        .extract_address();
+    let target = MappingTarget {
+        address,
+        selector: request.extract_query_param::<FunctionSelector>(SELECTOR_QUERY_PARAM)?,
+        key_id: request.extract_query_param::<Uuid>(KEY_ID_QUERY_PARAM)?,
+        client_user_id: request.extract_query_param::<String>(CLIENT_USER_ID_QUERY_PARAM)?,
+        group: request.extract_query_param::<String>(GROUP_QUERY_PARAM)?,
+    };
+    let mapping_type = request
+        .extract_query_param::<MappingType>(TYPE_QUERY_PARAM)?
+        .unwrap_or_else(|| MappingType::inferred_from(&target))
+        .into_registry_type(target)?;
+    let limit = request.extract_query_param::<i64>(LIMIT_QUERY_PARAM)?;
+    let cursor = request.extract_query_param::<String>(CURSOR_QUERY_PARAM)?;
+
+    if let Some(limit) = limit {
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(validation_error_response(
+                format!("{LIMIT_QUERY_PARAM} must be between 1 and {MAX_PAGE_LIMIT}"),
+                None,
+            ));
+        }
+    }
+
+    let page = state
+        .address_policy_registry_repository
+        .get_policy_history(client_id, chain_id, mapping_type, limit, cursor)
+        .await
+        .map_err(|e| match e {
+            AddressPolicyRegistryRepositoryError::InvalidCursor(message) => {
+                validation_error_response(message, None)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mapping history. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&convert_page_to_history_response(page)).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting policy history response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+fn convert_page_to_history_response(
+    page: AddressPolicyRegistryHistoryPage,
+) -> FetchPolicyHistoryResponse {
+    FetchPolicyHistoryResponse {
+        history: page
+            .entries
+            .into_iter()
+            .map(|entry| PolicyMappingHistoryEntry {
+                change: entry.change,
+                r#type: MappingType::from(&entry.r#type),
+                address: mapping_address_to_string(&entry.r#type),
+                selector: mapping_selector_to_string(&entry.r#type),
+                key_id: mapping_key_id(&entry.r#type),
+                client_user_id: mapping_client_user_id(&entry.r#type),
+                group: mapping_group(&entry.r#type),
+                previous_policy: entry.previous_policy,
+                new_policy: entry.new_policy,
+                previous_terms: entry.previous_terms,
+                new_terms: entry.new_terms,
+                client_id: entry.client_id,
+                subject: entry.subject,
+                changed_at: entry.changed_at,
+            })
+            .collect(),
+        next_cursor: page.next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_policy_registry::{
+        AddressPolicyRegistryChange, AddressPolicyRegistryHistory, AddressPolicyRegistryType,
+    };
+    use mpc_signature_sm::dtos::{
+        policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryHistoryPage, AddressPolicyRegistryRepositoryError,
+        MockAddressPolicyRegistryRepository,
+    };
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+    use std::str::FromStr;
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{
+        dtos::FetchPolicyHistoryResponse, fetch_policy_history, State, ADDRESS_PATH_PARAM,
+        CHAIN_ID_PATH_PARAM, CURSOR_QUERY_PARAM, LIMIT_QUERY_PARAM, TYPE_QUERY_PARAM,
+    };
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+        }
+    }
+
+    fn build_request(address: &str, chain_id: u64) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let mut path_params: HashMap<String, String> = HashMap::new();
+        path_params.insert(CHAIN_ID_PATH_PARAM.to_owned(), chain_id.to_string());
+        path_params.insert(ADDRESS_PATH_PARAM.to_owned(), address.to_owned());
+
+        build_request_custom_auth(auth, Body::default()).with_path_parameters(path_params)
+    }
+
+    fn history_entry(
+        mapping_type: AddressPolicyRegistryType,
+        change: AddressPolicyRegistryChange,
+        previous_policy: Option<&str>,
+        new_policy: Option<&str>,
+    ) -> AddressPolicyRegistryHistory {
+        AddressPolicyRegistryHistory {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+            r#type: mapping_type,
+            change,
+            previous_policy: previous_policy.map(str::to_owned),
+            new_policy: new_policy.map(str::to_owned),
+            previous_terms: None,
+            new_terms: None,
+            subject: Some("user_sub".to_owned()),
+            changed_at: Utc::now(),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_policy_history_ok(mut fixture: TestFixture) {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([
+                (LIMIT_QUERY_PARAM.to_owned(), "2".to_owned()),
+                (CURSOR_QUERY_PARAM.to_owned(), "some_cursor".to_owned()),
+            ]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policy_history()
+            .once()
+            .withf(move |client_id, chain_id, mapping_type, limit, cursor| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS
+                    && *chain_id == CHAIN_ID_FOR_MOCK_REQUESTS
+                    && *mapping_type == AddressPolicyRegistryType::AddressTo { address }
+                    && *limit == Some(2)
+                    && cursor.as_deref() == Some("some_cursor")
+            })
+            .returning(move |_, _, mapping_type, _, _| {
+                let mut created = history_entry(
+                    mapping_type.clone(),
+                    AddressPolicyRegistryChange::Created,
+                    None,
+                    Some("old_policy"),
+                );
+                created.changed_at = created.changed_at - Duration::minutes(5);
+
+                Ok(AddressPolicyRegistryHistoryPage {
+                    entries: vec![
+                        history_entry(
+                            mapping_type,
+                            AddressPolicyRegistryChange::Updated,
+                            Some("old_policy"),
+                            Some("new_policy"),
+                        ),
+                        created,
+                    ],
+                    next_cursor: Some("next_cursor".to_owned()),
+                })
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy_history(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchPolicyHistoryResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(Some("next_cursor".to_owned()), body.next_cursor);
+        assert_eq!(2, body.history.len());
+
+        let latest = &body.history[0];
+        assert_eq!(AddressPolicyRegistryChange::Updated, latest.change);
+        assert_eq!(MappingType::AddressTo, latest.r#type);
+        assert_eq!(ADDRESS_FOR_MOCK_REQUESTS, latest.address);
+        assert_eq!(Some("old_policy".to_owned()), latest.previous_policy);
+        assert_eq!(Some("new_policy".to_owned()), latest.new_policy);
+        assert_eq!(Some("user_sub".to_owned()), latest.subject);
+        assert!(latest.changed_at > body.history[1].changed_at);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_default_policy_history_ok(mut fixture: TestFixture) {
+        let request =
+            build_request("default", CHAIN_ID_FOR_MOCK_REQUESTS).with_query_string_parameters(
+                HashMap::from([(TYPE_QUERY_PARAM.to_owned(), "DEFAULT".to_owned())]),
+            );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policy_history()
+            .once()
+            .withf(|_, _, mapping_type, limit, cursor| {
+                *mapping_type == AddressPolicyRegistryType::Default
+                    && limit.is_none()
+                    && cursor.is_none()
+            })
+            .returning(|_, _, _, _, _| {
+                Ok(AddressPolicyRegistryHistoryPage {
+                    entries: vec![],
+                    next_cursor: None,
+                })
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy_history(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: FetchPolicyHistoryResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.history.is_empty());
+        assert!(body.next_cursor.is_none());
+    }
+
+    #[rstest]
+    #[case::zero("0")]
+    #[case::too_big("1001")]
+    #[tokio::test]
+    async fn fetch_policy_history_invalid_limit(mut fixture: TestFixture, #[case] limit: &str) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                LIMIT_QUERY_PARAM.to_owned(),
+                limit.to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policy_history()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy_history(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("limit must be between 1 and 1000", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn fetch_policy_history_invalid_cursor(mut fixture: TestFixture) {
+        let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS)
+            .with_query_string_parameters(HashMap::from([(
+                CURSOR_QUERY_PARAM.to_owned(),
+                "not_a_cursor".to_owned(),
+            )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_policy_history()
+            .once()
+            .returning(|_, _, _, _, _| {
+                Err(AddressPolicyRegistryRepositoryError::InvalidCursor(
+                    "invalid cursor".to_owned(),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+        };
+
+        let response = fetch_policy_history(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("invalid cursor", body.message);
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use mpc_signature_sm::dtos::policy_mapping_plan::{
+    PlannedPolicyMapping, PlannedPolicyMappingUpdate,
+};
+use mpc_signature_sm::dtos::policy_mapping_snapshot::SnapshotFormat;
+use serde::{Deserialize, Serialize};
+
+/// What to do with snapshot mappings that already exist with a different policy or window.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ImportConflictStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+}
+
+#[derive(Deserialize)]
+pub struct ImportPolicyMappingsRequest {
+    /// Document returned by the export endpoint.
+    pub snapshot: String,
+
+    #[serde(default)]
+    pub format: SnapshotFormat,
+
+    #[serde(default)]
+    pub on_conflict: ImportConflictStrategy,
+
+    /// Restores the mappings of a chain on another one, for example `{"11155111": 1}` restores
+    /// Sepolia mappings on Mainnet. Chains left out are restored as they are.
+    #[serde(default)]
+    pub chain_id_map: HashMap<u64, u64>,
+
+    /// Returns what would be restored without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct SkippedSnapshotMapping {
+    #[serde(flatten)]
+    pub mapping: PlannedPolicyMapping,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct ImportPolicyMappingsResponse {
+    pub dry_run: bool,
+    /// Whether every change was written in a single transaction.
+    pub atomic: bool,
+    pub created: Vec<PlannedPolicyMapping>,
+    pub overwritten: Vec<PlannedPolicyMappingUpdate>,
+    /// Snapshot mappings already stored exactly as they are in the snapshot.
+    pub unchanged: usize,
+    pub skipped: Vec<SkippedSnapshotMapping>,
+}
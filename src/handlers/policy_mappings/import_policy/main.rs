@@ -0,0 +1,810 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::config::Config;
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::{
+    ImportConflictStrategy, ImportPolicyMappingsRequest, ImportPolicyMappingsResponse,
+    SkippedSnapshotMapping,
+};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+use model::address_policy_registry::{AddressPolicyRegistry, INITIAL_VERSION};
+use mpc_signature_sm::config::SupportedChain;
+use mpc_signature_sm::dtos::address_group::validate_mapping_groups_exist;
+use mpc_signature_sm::dtos::policy_mapping_chain::try_validate_mapping_chain;
+use mpc_signature_sm::dtos::policy_mapping_plan::{
+    partially_applied_response, validate_plan_can_be_applied, PlannedPolicyMapping,
+    PlannedPolicyMappingUpdate, ALLOW_PARTIAL_QUERY_PARAM, MAX_DESIRED_MAPPINGS,
+};
+use mpc_signature_sm::dtos::policy_mapping_snapshot::PolicyMappingSnapshot;
+use mpc_signature_sm::dtos::policy_value_bands::try_validate_value_bands;
+use mpc_signature_sm::http::errors::{
+    conflict_error_response, unknown_error_response, validation_error_response,
+};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_policies_belong_to_client;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chains_are_enabled_for_client;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError, MAX_ATOMIC_PLAN_CHANGES,
+};
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+
+mod config;
+mod dtos;
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    address_policy_registry_repository: Arc<APRR>,
+    address_groups_repository: Arc<AGR>,
+    policy_catalog: PC,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    import_policy,
+    [validate_content_type]
+);
+
+/// Restores a snapshot returned by the export endpoint into the caller's mappings. Snapshots of
+/// other clients can be restored too, their mappings are written for the caller. Stored
+/// mappings that are not in the snapshot are left alone. Like apply, restores too big for a
+/// single transaction are only written when the caller sets `allow_partial`.
+async fn import_policy(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<ImportPolicyMappingsRequest>()?;
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+    let allow_partial = request
+        .extract_query_param::<bool>(ALLOW_PARTIAL_QUERY_PARAM)?
+        .unwrap_or(false);
+
+    let snapshot = PolicyMappingSnapshot::decode(&body.snapshot, body.format)
+        .map_err(|message| validation_error_response(message, None))?;
+    if snapshot.rows.len() > MAX_DESIRED_MAPPINGS {
+        return Err(validation_error_response(
+            format!("snapshot can't contain more than {MAX_DESIRED_MAPPINGS} mappings"),
+            None,
+        ));
+    }
+
+    let now = Utc::now();
+    let mut mappings = Vec::with_capacity(snapshot.rows.len());
+    let mut skipped = Vec::new();
+    let mut keys = HashSet::with_capacity(snapshot.rows.len());
+    for (index, row) in snapshot.rows.into_iter().enumerate() {
+        let mut mapping = AddressPolicyRegistry::try_from(row).map_err(|e| {
+            validation_error_response(format!("rows[{index}]: invalid mapping. {e}"), None)
+        })?;
+        mapping.client_id = client_id.clone();
+        mapping.version = INITIAL_VERSION;
+        if let Some(chain_id) = body.chain_id_map.get(&mapping.chain_id) {
+            mapping.chain_id = *chain_id;
+        }
+
+        if !mapping.is_client_wide() && !mapping.chain_id.is_supported() {
+            return Err(validation_error_response(
+                format!(
+                    "rows[{index}]: chain_id {} is not supported",
+                    mapping.chain_id
+                ),
+                None,
+            ));
+        }
+
+        try_validate_mapping_chain(mapping.chain_id, &mapping.r#type)
+            .and_then(|_| try_validate_value_bands(&mapping.value_bands))
+            .map_err(|message| {
+                validation_error_response(format!("rows[{index}]: {message}"), None)
+            })?;
+
+        if !keys.insert((mapping.chain_id, mapping.r#type.clone())) {
+            return Err(validation_error_response(
+                format!("rows[{index}]: mapping is repeated in the snapshot"),
+                None,
+            ));
+        }
+
+        if mapping.has_expired_at(now) {
+            skipped.push(SkippedSnapshotMapping {
+                mapping: PlannedPolicyMapping::from(&mapping),
+                reason: "mapping has expired".to_owned(),
+            });
+            continue;
+        }
+
+        mappings.push(mapping);
+    }
+    validate_mapping_chains_are_enabled_for_client(
+        &request,
+        mappings.iter().map(|mapping| mapping.chain_id),
+        state.feature_flags.as_ref(),
+    )
+    .await?;
+
+    validate_policies_belong_to_client(
+        &state.policy_catalog,
+        &client_id,
+        mappings.iter().flat_map(|mapping| mapping.policies()),
+    )
+    .await?;
+    validate_mapping_groups_exist(
+        state.address_groups_repository.as_ref(),
+        &client_id,
+        mappings.iter().map(|mapping| &mapping.r#type),
+    )
+    .await?;
+
+    let current = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id)
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mappings. {e:?}"
+            )))
+        })?;
+
+    let restored = mappings.len();
+    let mut plan = AddressPolicyRegistryPlan::between(current, mappings, now);
+    plan.deletes.clear();
+    let unchanged = restored - plan.creates.len() - plan.updates.len();
+
+    if body.on_conflict == ImportConflictStrategy::Skip {
+        skipped.extend(plan.updates.drain(..).map(|update| SkippedSnapshotMapping {
+            mapping: PlannedPolicyMapping::from(&update.desired),
+            reason: "mapping already exists".to_owned(),
+        }));
+    }
+
+    if !body.dry_run {
+        validate_plan_can_be_applied(&plan, allow_partial)?;
+    }
+
+    let response = ImportPolicyMappingsResponse {
+        dry_run: body.dry_run,
+        atomic: plan.len() <= MAX_ATOMIC_PLAN_CHANGES,
+        created: plan
+            .creates
+            .iter()
+            .map(PlannedPolicyMapping::from)
+            .collect(),
+        overwritten: plan
+            .updates
+            .iter()
+            .map(PlannedPolicyMappingUpdate::from)
+            .collect(),
+        unchanged,
+        skipped,
+    };
+
+    if !body.dry_run && !plan.is_empty() {
+        state
+            .address_policy_registry_repository
+            .apply_policy_plan(plan, subject)
+            .await
+            .map_err(|e| match e {
+                AddressPolicyRegistryRepositoryError::VersionMismatch(message) => {
+                    conflict_error_response(message)
+                }
+                AddressPolicyRegistryRepositoryError::PartiallyApplied(applied, cause) => {
+                    partially_applied_response(&applied, cause)
+                }
+                e => unknown_error_response(LambdaError::Unknown(
+                    anyhow::anyhow!(e).context("importing policy mapping snapshot"),
+                )),
+            })?;
+    }
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting import policy mappings response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::{Address, H160};
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
+    use mpc_signature_sm::{
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::{
+            policy_mapping_plan::{
+                PartiallyAppliedResponse, ALLOW_PARTIAL_QUERY_PARAM, MAX_DESIRED_MAPPINGS,
+                PARTIALLY_APPLIED_ERROR_CODE,
+            },
+            policy_mapping_snapshot::{PolicyMappingSnapshot, SnapshotFormat},
+            policy_mapping_type::MappingType,
+            responses::http_error::LambdaErrorResponse,
+        },
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryDynamoDbResource, AddressPolicyRegistryRepositoryError,
+        MockAddressPolicyRegistryRepository, MAX_ATOMIC_PLAN_CHANGES,
+    };
+    use repositories::cache::MockCacheRepositoryTest;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{dtos::ImportPolicyMappingsResponse, import_policy, State};
+
+    const SEPOLIA_CHAIN_ID: u64 = 11155111;
+    const STAGING_CLIENT_ID: &str = "staging_client";
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy(policy_name: &str, status: StatusCode, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    /// Snapshot exported by another client on Sepolia.
+    fn staging_snapshot(format: SnapshotFormat) -> String {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let builder = |policy: &str| {
+            AddressPolicyRegistryBuilder::new(
+                STAGING_CLIENT_ID.to_owned(),
+                SEPOLIA_CHAIN_ID,
+                policy.to_owned(),
+            )
+        };
+        let expired = builder("expired_policy")
+            .active_between(None, Some(Utc::now() - Duration::hours(1)))
+            .address_from(address);
+
+        let rows = vec![
+            builder("default_policy").default(),
+            builder("new_policy").address_to(address),
+            expired,
+        ]
+        .into_iter()
+        .map(AddressPolicyRegistryDynamoDbResource::from)
+        .collect();
+
+        PolicyMappingSnapshot::new(STAGING_CLIENT_ID.to_owned(), rows, Utc::now())
+            .encode(format)
+            .unwrap()
+    }
+
+    /// Snapshot of `count` ADDRESS_TO mappings on Mainnet, all of them to `new_policy`.
+    fn big_snapshot(count: u64) -> Value {
+        let rows = (1..=count)
+            .map(|index| {
+                AddressPolicyRegistryDynamoDbResource::from(
+                    AddressPolicyRegistryBuilder::new(
+                        STAGING_CLIENT_ID.to_owned(),
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "new_policy".to_owned(),
+                    )
+                    .address_to(H160::from_low_u64_be(index)),
+                )
+            })
+            .collect();
+        let snapshot = PolicyMappingSnapshot::new(STAGING_CLIENT_ID.to_owned(), rows, Utc::now())
+            .encode(SnapshotFormat::Jsonl)
+            .unwrap();
+
+        json!({ "snapshot": snapshot, "on_conflict": "OVERWRITE" })
+    }
+
+    /// Mappings the caller already has on Mainnet.
+    fn stored_mappings() -> Vec<AddressPolicyRegistry> {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        vec![AddressPolicyRegistryBuilder::new(
+            CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "old_policy".to_owned(),
+        )
+        .address_to(address)]
+    }
+
+    fn request_body(format: SnapshotFormat, on_conflict: &str, dry_run: bool) -> Value {
+        json!({
+            "snapshot": staging_snapshot(format),
+            "format": format,
+            "on_conflict": on_conflict,
+            "chain_id_map": { "11155111": CHAIN_ID_FOR_MOCK_REQUESTS },
+            "dry_run": dry_run,
+        })
+    }
+
+    #[rstest]
+    #[case::jsonl(SnapshotFormat::Jsonl)]
+    #[case::csv(SnapshotFormat::Csv)]
+    #[tokio::test]
+    async fn import_policy_overwrite(
+        #[future] fixture: TestFixture,
+        #[case] format: SnapshotFormat,
+    ) {
+        let mut fixture = fixture.await;
+        let request = build_request(request_body(format, "OVERWRITE", false));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .withf(|plan, subject| {
+                plan.creates.len() == 1
+                    && plan.creates[0].client_id == CLIENT_ID_FOR_MOCK_REQUESTS
+                    && plan.creates[0].chain_id == CHAIN_ID_FOR_MOCK_REQUESTS
+                    && plan.updates.len() == 1
+                    && plan.updates[0].desired.policy == "new_policy"
+                    && plan.deletes.is_empty()
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ImportPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.atomic);
+        assert_eq!(1, body.created.len());
+        assert_eq!(MappingType::Default, body.created[0].r#type);
+        assert_eq!(1, body.overwritten.len());
+        assert_eq!("old_policy", body.overwritten[0].previous_policy);
+        assert_eq!(0, body.unchanged);
+        assert_eq!(1, body.skipped.len());
+        assert_eq!("mapping has expired", body.skipped[0].reason);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_skip_existing(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(request_body(SnapshotFormat::Jsonl, "SKIP", false));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .withf(|plan, _| plan.creates.len() == 1 && plan.updates.is_empty())
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ImportPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.created.len());
+        assert!(body.overwritten.is_empty());
+        assert_eq!(2, body.skipped.len());
+        assert_eq!("mapping already exists", body.skipped[1].reason);
+        assert_eq!(MappingType::AddressTo, body.skipped[1].mapping.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_dry_run(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(request_body(SnapshotFormat::Jsonl, "OVERWRITE", true));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ImportPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.dry_run);
+        assert_eq!(1, body.created.len());
+        assert_eq!(1, body.overwritten.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_group_not_found(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let rows = vec![AddressPolicyRegistryBuilder::new(
+            STAGING_CLIENT_ID.to_owned(),
+            SEPOLIA_CHAIN_ID,
+            "new_policy".to_owned(),
+        )
+        .group("exchanges".to_owned())]
+        .into_iter()
+        .map(AddressPolicyRegistryDynamoDbResource::from)
+        .collect();
+        let snapshot = PolicyMappingSnapshot::new(STAGING_CLIENT_ID.to_owned(), rows, Utc::now())
+            .encode(SnapshotFormat::Jsonl)
+            .unwrap();
+        let request = build_request(json!({
+            "snapshot": snapshot,
+            "chain_id_map": { "11155111": CHAIN_ID_FOR_MOCK_REQUESTS },
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_groups_repository
+            .expect_get_group()
+            .once()
+            .withf(|client_id, name| {
+                client_id == CLIENT_ID_FOR_MOCK_REQUESTS && name == "exchanges"
+            })
+            .returning(|_, _| Ok(None));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("address group exchanges not found", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_mapped_chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let rows = vec![AddressPolicyRegistryBuilder::new(
+            STAGING_CLIENT_ID.to_owned(),
+            SEPOLIA_CHAIN_ID,
+            "new_policy".to_owned(),
+        )
+        .default()]
+        .into_iter()
+        .map(AddressPolicyRegistryDynamoDbResource::from)
+        .collect();
+        let snapshot = PolicyMappingSnapshot::new(STAGING_CLIENT_ID.to_owned(), rows, Utc::now())
+            .encode(SnapshotFormat::Jsonl)
+            .unwrap();
+        let request = build_request(json!({
+            "snapshot": snapshot,
+            "chain_id_map": { "11155111": CHAIN_ID_FOR_MOCK_REQUESTS },
+        }));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags().with_flag(
+                &chain_feature_flag(CHAIN_ID_FOR_MOCK_REQUESTS),
+                json!(false),
+            )),
+        };
+
+        let response = import_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            format!("chain_id {CHAIN_ID_FOR_MOCK_REQUESTS} is not enabled for this client"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[case::not_a_snapshot(json!({ "snapshot": "not a snapshot" }), "invalid snapshot header")]
+    #[case::unsupported_chain(
+        json!({
+            "snapshot": staging_snapshot(SnapshotFormat::Jsonl),
+            "chain_id_map": { "11155111": 5 },
+        }),
+        "rows[0]: chain_id 5 is not supported"
+    )]
+    #[case::wrong_format(
+        json!({ "snapshot": staging_snapshot(SnapshotFormat::Jsonl), "format": "CSV" }),
+        "snapshot header not found"
+    )]
+    #[tokio::test]
+    async fn import_policy_invalid_snapshot(
+        #[future] fixture: TestFixture,
+        #[case] body: Value,
+        #[case] expected_message: &str,
+    ) {
+        let mut fixture = fixture.await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(build_request(body), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.message.starts_with(expected_message));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_too_many_rows(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(big_snapshot(MAX_DESIRED_MAPPINGS as u64 + 1));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            format!("snapshot can't contain more than {MAX_DESIRED_MAPPINGS} mappings"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_not_atomic_without_allow_partial(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(big_snapshot(MAX_ATOMIC_PLAN_CHANGES as u64 + 1));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(vec![]));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.message.contains(ALLOW_PARTIAL_QUERY_PARAM));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn import_policy_partially_applied(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(big_snapshot(MAX_ATOMIC_PLAN_CHANGES as u64 + 1))
+            .with_query_string_parameters(HashMap::from([(
+                ALLOW_PARTIAL_QUERY_PARAM.to_owned(),
+                "true".to_owned(),
+            )]));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(vec![]));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .returning(|plan, _| {
+                let applied = AddressPolicyRegistryPlan {
+                    creates: plan.creates[..MAX_ATOMIC_PLAN_CHANGES].to_vec(),
+                    ..AddressPolicyRegistryPlan::default()
+                };
+                Err(AddressPolicyRegistryRepositoryError::PartiallyApplied(
+                    Box::new(applied),
+                    anyhow::anyhow!("policy mappings changed since the plan was computed"),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = import_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let body: PartiallyAppliedResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(PARTIALLY_APPLIED_ERROR_CODE, body.code);
+        assert_eq!(MAX_ATOMIC_PLAN_CHANGES, body.applied.creates.len());
+    }
+}
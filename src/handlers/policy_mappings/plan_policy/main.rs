@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use crate::config::Config;
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::plan::AddressPolicyRegistryPlan;
+use mpc_signature_sm::dtos::policy_mapping_plan::{
+    PolicyMappingPlanRequest, PolicyMappingPlanResponse,
+};
+use mpc_signature_sm::http::errors::unknown_error_response;
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_policies_belong_to_client;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+
+mod config;
+
+pub struct State<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> {
+    address_policy_registry_repository: Arc<APRR>,
+    policy_catalog: PC,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+
+        State {
+            address_policy_registry_repository,
+            policy_catalog,
+        }
+    },
+    plan_policy,
+    [validate_content_type]
+);
+
+/// Returns the changes needed to take the client's mappings to the ones in the request, without
+/// writing anything.
+async fn plan_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository, impl PolicyCatalog>,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<PolicyMappingPlanRequest>()?;
+    let client_id = request.extract_client_id()?;
+
+    let now = Utc::now();
+    let desired = body.desired_mappings(&client_id, now)?;
+
+    validate_policies_belong_to_client(
+        &state.policy_catalog,
+        &client_id,
+        desired.iter().flat_map(|mapping| mapping.policies()),
+    )
+    .await?;
+
+    let current = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id)
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mappings. {e:?}"
+            )))
+        })?;
+
+    let plan = AddressPolicyRegistryPlan::between(current, desired, now);
+
+    let response = serde_json::to_string(&PolicyMappingPlanResponse::from(&plan)).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting policy mapping plan response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::Address;
+    use http::{Request, StatusCode};
+    use lambda_http::Body;
+    use model::address_policy_registry::{AddressPolicyRegistry, AddressPolicyRegistryBuilder};
+    use mpc_signature_sm::{
+        dtos::{
+            policy_mapping_plan::PolicyMappingPlanResponse, policy_mapping_type::MappingType,
+            responses::http_error::LambdaErrorResponse,
+        },
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use repositories::cache::MockCacheRepositoryTest;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{plan_policy, State};
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy(policy_name: &str, status: StatusCode, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    fn stored_mappings() -> Vec<AddressPolicyRegistry> {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let builder = |policy: &str| {
+            AddressPolicyRegistryBuilder::new(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                CHAIN_ID_FOR_MOCK_REQUESTS,
+                policy.to_owned(),
+            )
+        };
+
+        vec![
+            builder("default_policy").default(),
+            builder("old_policy").address_to(address),
+            builder("old_policy").address_from(address),
+        ]
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn plan_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "default_policy" },
+                { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "new_policy", "address": ADDRESS_FOR_MOCK_REQUESTS },
+                { "chain_id": 137, "policy": "new_policy" },
+            ]
+        }));
+
+        mock_maestro_policy("default_policy", StatusCode::OK, &fixture.mock_server).await;
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+        };
+
+        let response = plan_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: PolicyMappingPlanResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.creates.len());
+        assert_eq!(137, body.creates[0].chain_id);
+        assert_eq!(1, body.updates.len());
+        assert_eq!(MappingType::AddressTo, body.updates[0].mapping.r#type);
+        assert_eq!("new_policy", body.updates[0].mapping.policy);
+        assert_eq!("old_policy", body.updates[0].previous_policy);
+        assert_eq!(1, body.deletes.len());
+        assert_eq!(MappingType::AddressFrom, body.deletes[0].r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn plan_policy_invalid_policy(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "mappings": [{ "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "not_owned_policy" }]
+        }));
+
+        mock_maestro_policy(
+            "not_owned_policy",
+            StatusCode::NOT_FOUND,
+            &fixture.mock_server,
+        )
+        .await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+        };
+
+        let response = plan_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(r#"invalid policy "not_owned_policy""#, body.message);
+    }
+
+    #[rstest]
+    #[case::repeated_mapping(json!({ "mappings": [
+        { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy" },
+        { "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "other_policy" },
+    ]}))]
+    #[case::unsupported_chain(json!({ "mappings": [{ "chain_id": 28731237918u64, "policy": "some_policy" }]}))]
+    #[case::empty_policy(json!({ "mappings": [{ "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "" }]}))]
+    #[case::address_on_default(json!({ "mappings": [{ "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "type": "DEFAULT", "address": ADDRESS_FOR_MOCK_REQUESTS }]}))]
+    #[case::overlapping_value_bands(json!({ "mappings": [{ "chain_id": CHAIN_ID_FOR_MOCK_REQUESTS, "policy": "some_policy", "value_bands": [
+        { "below": "100", "policy": "basic" },
+        { "below": "10", "policy": "dual" },
+    ]}]}))]
+    #[tokio::test]
+    async fn plan_policy_invalid_request(#[future] fixture: TestFixture, #[case] body: Value) {
+        let mut fixture = fixture.await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+        };
+
+        let response = plan_policy(build_request(body), &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.message.starts_with("mappings["));
+    }
+}
@@ -0,0 +1,83 @@
+use model::address_policy_registry::AddressPolicyRegistry;
+use mpc_signature_sm::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, deserialize_optional_mapping_chain_ids,
+    serialize_mapping_chain_id,
+};
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingType,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct RepointPolicyMappingsRequest {
+    #[validate(length(min = 1))]
+    pub from_policy: String,
+
+    #[validate(length(min = 1))]
+    pub to_policy: String,
+
+    /// When present only mappings on these chains are re-pointed. `*` selects the client wide
+    /// DEFAULT mapping.
+    #[serde(default, deserialize_with = "deserialize_optional_mapping_chain_ids")]
+    pub chain_ids: Option<Vec<u64>>,
+
+    /// Returns the mappings that would change without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct PolicyMappingKey {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub r#type: MappingType,
+}
+
+impl From<&AddressPolicyRegistry> for PolicyMappingKey {
+    fn from(value: &AddressPolicyRegistry) -> Self {
+        Self {
+            chain_id: value.chain_id,
+            address: mapping_address_to_string(&value.r#type),
+            selector: mapping_selector_to_string(&value.r#type),
+            key_id: mapping_key_id(&value.r#type),
+            client_user_id: mapping_client_user_id(&value.r#type),
+            group: mapping_group(&value.r#type),
+            r#type: MappingType::from(&value.r#type),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct SkippedPolicyMapping {
+    #[serde(flatten)]
+    pub key: PolicyMappingKey,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct RepointPolicyMappingsResponse {
+    pub from_policy: String,
+    pub to_policy: String,
+    pub dry_run: bool,
+    pub changed: Vec<PolicyMappingKey>,
+    /// Mappings that were modified or deleted by someone else while re-pointing.
+    pub skipped: Vec<SkippedPolicyMapping>,
+}
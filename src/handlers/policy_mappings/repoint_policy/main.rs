@@ -0,0 +1,663 @@
+use std::sync::Arc;
+
+use crate::config::Config;
+use chrono::Utc;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::{
+    PolicyMappingKey, RepointPolicyMappingsRequest, RepointPolicyMappingsResponse,
+    SkippedPolicyMapping,
+};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::plan::{
+    AddressPolicyRegistryPlan, AddressPolicyRegistryUpdate,
+};
+use model::address_policy_registry::AddressPolicyRegistry;
+use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{MaestroPolicyCatalog, PolicyCatalog};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_policies_belong_to_client;
+use mpc_signature_sm::validations::http::supported_chain_id::{
+    is_supported_mapping_chain_id, validate_mapping_chains_are_enabled_for_client,
+};
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
+};
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+use validator::Validate;
+
+mod config;
+mod dtos;
+
+pub struct State<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> {
+    address_policy_registry_repository: Arc<APRR>,
+    policy_catalog: PC,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    repoint_policy,
+    [validate_content_type]
+);
+
+/// Points every mapping of the client using `from_policy`, as its own policy or as the policy of
+/// one of its value bands, at `to_policy`. Nothing is re-pointed if any of those mappings is on
+/// a chain that isn't enabled for the client.
+async fn repoint_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository, impl PolicyCatalog>,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<RepointPolicyMappingsRequest>()?;
+    body.validate()
+        .map_err(|e| validation_error_response(e.to_string(), None))?;
+
+    if body.from_policy == body.to_policy {
+        return Err(validation_error_response(
+            "from_policy and to_policy must be different".to_owned(),
+            None,
+        ));
+    }
+
+    if let Some(chain_id) = body
+        .chain_ids
+        .iter()
+        .flatten()
+        .find(|chain_id| is_supported_mapping_chain_id(**chain_id).is_err())
+    {
+        return Err(validation_error_response(
+            format!("chain_id {chain_id} is not supported"),
+            None,
+        ));
+    }
+
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+
+    validate_policies_belong_to_client(
+        &state.policy_catalog,
+        &client_id,
+        [body.to_policy.as_str()],
+    )
+    .await?;
+
+    let now = Utc::now();
+    let mappings = state
+        .address_policy_registry_repository
+        .get_all_policies(client_id.clone())
+        .await
+        .map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error fetching address policy mappings. {e:?}"
+            )))
+        })?
+        .into_iter()
+        .filter(|mapping| mapping.policies().any(|policy| policy == body.from_policy))
+        .filter(|mapping| !mapping.has_expired_at(now))
+        .filter(|mapping| match &body.chain_ids {
+            Some(chain_ids) => chain_ids.contains(&mapping.chain_id),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    validate_mapping_chains_are_enabled_for_client(
+        &request,
+        mappings.iter().map(|mapping| mapping.chain_id),
+        state.feature_flags.as_ref(),
+    )
+    .await?;
+
+    let mut changed = Vec::with_capacity(mappings.len());
+    let mut skipped = Vec::new();
+    for mapping in mappings {
+        let key = PolicyMappingKey::from(&mapping);
+        if body.dry_run {
+            changed.push(key);
+            continue;
+        }
+
+        let desired = repointed_mapping(&mapping, &body.from_policy, &body.to_policy);
+        let plan = AddressPolicyRegistryPlan {
+            updates: vec![AddressPolicyRegistryUpdate {
+                current: mapping,
+                desired,
+            }],
+            ..AddressPolicyRegistryPlan::default()
+        };
+
+        // Mappings changed by someone else since they were read are left alone.
+        match state
+            .address_policy_registry_repository
+            .apply_policy_plan(plan, subject.clone())
+            .await
+        {
+            Ok(()) => changed.push(key),
+            Err(AddressPolicyRegistryRepositoryError::VersionMismatch(_)) => {
+                skipped.push(SkippedPolicyMapping {
+                    key,
+                    reason: "mapping was modified or deleted while re-pointing".to_owned(),
+                })
+            }
+            Err(e) => {
+                return Err(unknown_error_response(LambdaError::Unknown(
+                    anyhow::anyhow!(e).context("re-pointing address policy mapping"),
+                )))
+            }
+        }
+    }
+
+    let response = serde_json::to_string(&RepointPolicyMappingsResponse {
+        from_policy: body.from_policy,
+        to_policy: body.to_policy,
+        dry_run: body.dry_run,
+        changed,
+        skipped,
+    })
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting re-point policy mappings response"),
+        ))
+    })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+/// `mapping` with `from_policy` replaced by `to_policy` wherever it's used, ready to be written
+/// over the stored version.
+fn repointed_mapping(
+    mapping: &AddressPolicyRegistry,
+    from_policy: &str,
+    to_policy: &str,
+) -> AddressPolicyRegistry {
+    let mut desired = mapping.clone();
+    let policies = std::iter::once(&mut desired.policy)
+        .chain(desired.value_bands.iter_mut().map(|band| &mut band.policy));
+    for policy in policies {
+        if policy == from_policy {
+            *policy = to_policy.to_owned();
+        }
+    }
+    desired.version = mapping.version + 1;
+    desired
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::{Address, U256};
+    use http::{Request, StatusCode};
+    use lambda_http::Body;
+    use model::address_policy_registry::value_band::PolicyValueBand;
+    use model::address_policy_registry::{
+        AddressPolicyRegistry, AddressPolicyRegistryBuilder, ANY_CHAIN_ID,
+    };
+    use mpc_signature_sm::{
+        config::{chain_feature_flag, supported_chains_feature_flags},
+        dtos::{policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse},
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+    };
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+    };
+    use repositories::cache::MockCacheRepositoryTest;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{dtos::RepointPolicyMappingsResponse, repoint_policy, State};
+
+    const OTHER_CHAIN_ID: u64 = 137;
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        pub mock_server: MockServer,
+    }
+
+    #[fixture]
+    async fn fixture() -> TestFixture {
+        let mock_server = MockServer::start().await;
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
+            mock_server,
+        }
+    }
+
+    fn build_request(body: Value) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" });
+        build_request_custom_auth(auth, Body::Text(body.to_string()))
+    }
+
+    async fn mock_maestro_policy(policy_name: &str, status: StatusCode, mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    fn stored_mappings() -> Vec<AddressPolicyRegistry> {
+        let address = Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap();
+        let builder = |chain_id: u64, policy: &str| {
+            AddressPolicyRegistryBuilder::new(
+                CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                chain_id,
+                policy.to_owned(),
+            )
+        };
+
+        let mut mappings = vec![
+            builder(CHAIN_ID_FOR_MOCK_REQUESTS, "old_policy").default(),
+            builder(CHAIN_ID_FOR_MOCK_REQUESTS, "old_policy").address_to(address),
+            builder(CHAIN_ID_FOR_MOCK_REQUESTS, "unrelated_policy").address_from(address),
+            builder(OTHER_CHAIN_ID, "old_policy").default(),
+        ];
+        for mapping in &mut mappings {
+            mapping.version = 2;
+        }
+        mappings
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_ok(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .times(3)
+            .withf(|plan, subject| {
+                let update = &plan.updates[0];
+                plan.len() == 1
+                    && update.current.version == 2
+                    && update.desired.version == 3
+                    && update.desired.policy == "new_policy"
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: RepointPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(!body.dry_run);
+        assert_eq!(3, body.changed.len());
+        assert!(body.skipped.is_empty());
+        assert_eq!(MappingType::Default, body.changed[0].r#type);
+        assert_eq!(ADDRESS_FOR_MOCK_REQUESTS, body.changed[1].address);
+        assert_eq!(OTHER_CHAIN_ID, body.changed[2].chain_id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_chain_not_enabled(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(
+                supported_chains_feature_flags()
+                    .with_flag(&chain_feature_flag(OTHER_CHAIN_ID), json!(false)),
+            ),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            format!("chain_id {OTHER_CHAIN_ID} is not enabled for this client"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_dry_run_limited_to_chains(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+            "chain_ids": [CHAIN_ID_FOR_MOCK_REQUESTS],
+            "dry_run": true,
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: RepointPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.dry_run);
+        assert_eq!(2, body.changed.len());
+        assert!(body
+            .changed
+            .iter()
+            .all(|key| key.chain_id == CHAIN_ID_FOR_MOCK_REQUESTS));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_client_wide_mapping(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+            "chain_ids": ["*"],
+            "dry_run": true,
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| {
+                let mut mappings = stored_mappings();
+                mappings.push(
+                    AddressPolicyRegistryBuilder::new(
+                        CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                        ANY_CHAIN_ID,
+                        "old_policy".to_owned(),
+                    )
+                    .default(),
+                );
+                Ok(mappings)
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: RepointPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.changed.len());
+        assert_eq!(ANY_CHAIN_ID, body.changed[0].chain_id);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_skips_concurrent_changes(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+            "chain_ids": [CHAIN_ID_FOR_MOCK_REQUESTS],
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| Ok(stored_mappings()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .returning(|_, _| Ok(()));
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .returning(|_, _| {
+                Err(AddressPolicyRegistryRepositoryError::VersionMismatch(
+                    "policy mappings changed since the plan was computed".to_owned(),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: RepointPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.changed.len());
+        assert_eq!(1, body.skipped.len());
+        assert_eq!(MappingType::AddressTo, body.skipped[0].key.r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_value_bands(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "new_policy",
+        }));
+
+        mock_maestro_policy("new_policy", StatusCode::OK, &fixture.mock_server).await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .once()
+            .returning(|_| {
+                let mut mapping = AddressPolicyRegistryBuilder::new(
+                    CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    CHAIN_ID_FOR_MOCK_REQUESTS,
+                    "unrelated_policy".to_owned(),
+                )
+                .default();
+                mapping.value_bands = vec![
+                    PolicyValueBand {
+                        below: U256::from(100),
+                        policy: "old_policy".to_owned(),
+                    },
+                    PolicyValueBand {
+                        below: U256::from(1000),
+                        policy: "other_band_policy".to_owned(),
+                    },
+                ];
+                Ok(vec![mapping])
+            });
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_apply_policy_plan()
+            .once()
+            .withf(|plan, _| {
+                let desired = &plan.updates[0].desired;
+                desired.policy == "unrelated_policy"
+                    && desired.value_bands[0].policy == "new_policy"
+                    && desired.value_bands[1].policy == "other_band_policy"
+            })
+            .returning(|_, _| Ok(()));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: RepointPolicyMappingsResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(1, body.changed.len());
+        assert_eq!(MappingType::Default, body.changed[0].r#type);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn repoint_policy_invalid_to_policy(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let request = build_request(json!({
+            "from_policy": "old_policy",
+            "to_policy": "not_owned_policy",
+        }));
+
+        mock_maestro_policy(
+            "not_owned_policy",
+            StatusCode::NOT_FOUND,
+            &fixture.mock_server,
+        )
+        .await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_all_policies()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(r#"invalid policy "not_owned_policy""#, body.message);
+    }
+
+    #[rstest]
+    #[case::same_policy(json!({ "from_policy": "old_policy", "to_policy": "old_policy" }))]
+    #[case::empty_policy(json!({ "from_policy": "", "to_policy": "new_policy" }))]
+    #[case::unsupported_chain(json!({ "from_policy": "old_policy", "to_policy": "new_policy", "chain_ids": [28731237918u64] }))]
+    #[case::any_chain_id_as_number(json!({ "from_policy": "old_policy", "to_policy": "new_policy", "chain_ids": [0] }))]
+    #[tokio::test]
+    async fn repoint_policy_invalid_request(#[future] fixture: TestFixture, #[case] body: Value) {
+        let fixture = fixture.await;
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = repoint_policy(build_request(body), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+}
@@ -0,0 +1,28 @@
+use mpc_signature_sm::dtos::policy_resolution::{PolicyCandidateResponse, ResolvedPolicyMapping};
+use repositories::address_policy_registry::policy_resolver::PolicyResolution;
+#[cfg(test)]
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct ResolvePolicyResponse {
+    pub policy: Option<ResolvedPolicyMapping>,
+    /// Policy that applies to the transaction value, out of the value bands of `policy`.
+    pub applied_policy: Option<String>,
+    pub candidates: Vec<PolicyCandidateResponse>,
+}
+
+impl From<PolicyResolution> for ResolvePolicyResponse {
+    fn from(resolution: PolicyResolution) -> Self {
+        Self {
+            policy: resolution.policy.map(ResolvedPolicyMapping::from),
+            applied_policy: resolution.applied_policy,
+            candidates: resolution
+                .candidates
+                .into_iter()
+                .map(PolicyCandidateResponse::from)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,816 @@
+use std::sync::Arc;
+
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use dtos::ResolvePolicyResponse;
+use ethers::types::{Address, Bytes, U256};
+use http::StatusCode;
+use lambda_http::{run, service_fn, Error, Request};
+use model::address_policy_registry::function_selector::FunctionSelector;
+use mpc_signature_sm::feature_flags::FeatureFlagsState;
+use mpc_signature_sm::http::errors::unknown_error_response;
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::http_lambda_main;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor, RequestExtractorError,
+};
+use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_chain_id_is_enabled;
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::policy_resolver::resolve_policy;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::keys::keys_repository_impl::KeysRepositoryImpl;
+use repositories::keys::KeysRepository;
+
+use crate::config::Config;
+
+mod config;
+mod dtos;
+
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const FROM_QUERY_PARAM: &str = "from";
+pub const TO_QUERY_PARAM: &str = "to";
+/// Calldata of the transaction, CONTRACT_CALL mappings are only looked up when it is present.
+pub const DATA_QUERY_PARAM: &str = "data";
+/// Native value of the transaction in wei, as a decimal number. Defaults to 0.
+pub const VALUE_QUERY_PARAM: &str = "value";
+
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    KR: KeysRepository,
+    AGR: AddressGroupsRepository,
+> {
+    address_policy_registry_repository: Arc<APRR>,
+    keys_repository: Arc<KR>,
+    address_groups_repository: Arc<AGR>,
+    feature_flags: Arc<dyn FeatureFlags>,
+}
+
+impl<APRR: AddressPolicyRegistryRepository, KR: KeysRepository, AGR: AddressGroupsRepository>
+    FeatureFlagsState for State<APRR, KR, AGR>
+{
+    fn feature_flags(&self) -> &dyn FeatureFlags {
+        self.feature_flags.as_ref()
+    }
+}
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let keys_repository = Arc::new(KeysRepositoryImpl::new(
+            config.keys_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client,
+        ));
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            keys_repository,
+            address_groups_repository,
+            feature_flags,
+        }
+    },
+    resolve_policy_mapping,
+    [],
+    [validate_chain_id_is_enabled]
+);
+
+fn extract_address_query_param(
+    request: &Request,
+    param_name: &str,
+) -> Result<Address, RequestExtractorError> {
+    request
+        .extract_query_param::<Address>(param_name)?
+        .ok_or_else(|| RequestExtractorError::QueryParamNotFoundError(param_name.to_owned()))
+}
+
+fn extract_value_query_param(request: &Request) -> Result<U256, RequestExtractorError> {
+    match request.extract_query_param::<String>(VALUE_QUERY_PARAM)? {
+        Some(value) => U256::from_dec_str(&value).map_err(|_| {
+            RequestExtractorError::QueryParamWithWrongTypeError(VALUE_QUERY_PARAM.to_owned())
+        }),
+        None => Ok(U256::zero()),
+    }
+}
+
+async fn resolve_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl KeysRepository,
+        impl AddressGroupsRepository,
+    >,
+) -> HttpLambdaResponse {
+    let chain_id: u64 = request.extract_path_param(CHAIN_ID_PATH_PARAM)?;
+    let client_id = request.extract_client_id()?;
+    let address_from = extract_address_query_param(&request, FROM_QUERY_PARAM)?;
+    let address_to = extract_address_query_param(&request, TO_QUERY_PARAM)?;
+    let selector = request
+        .extract_query_param::<Bytes>(DATA_QUERY_PARAM)?
+        .and_then(|data| FunctionSelector::from_calldata(&data));
+    let value = extract_value_query_param(&request)?;
+
+    let resolution = resolve_policy(
+        state.address_policy_registry_repository.as_ref(),
+        state.keys_repository.as_ref(),
+        state.address_groups_repository.as_ref(),
+        client_id,
+        chain_id,
+        address_from,
+        address_to,
+        selector,
+        value,
+    )
+    .await
+    .map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+            "there was an error resolving address policy mapping. {e:?}"
+        )))
+    })?;
+
+    let response =
+        serde_json::to_string(&ResolvePolicyResponse::from(resolution)).map_err(|e| {
+            unknown_error_response(LambdaError::Unknown(
+                anyhow::anyhow!(e).context("converting policy resolution response"),
+            ))
+        })?;
+
+    LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    }
+    .try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use common::test_tools::http::{
+        constants::{
+            ADDRESS_FOR_MOCK_REQUESTS, ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+        },
+        helpers::build_request_custom_auth,
+    };
+    use ethers::types::{Address, U256};
+    use http::{Request, StatusCode};
+    use lambda_http::{Body, RequestExt};
+    use model::address_group::AddressGroup;
+    use model::address_policy_registry::{
+        value_band::PolicyValueBand, AddressPolicyRegistry, AddressPolicyRegistryType, ANY_CHAIN_ID,
+    };
+    use model::key::Key;
+    use mpc_signature_sm::config::supported_chains_feature_flags;
+    use mpc_signature_sm::dtos::{
+        policy_mapping_type::MappingType, policy_resolution::CandidateOutcomeResponse,
+        responses::http_error::LambdaErrorResponse,
+    };
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+    };
+    use repositories::keys::{KeysRepositoryError, MockKeysRepository};
+    use rstest::{fixture, rstest};
+    use serde_json::json;
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
+    use uuid::Uuid;
+
+    use crate::{
+        dtos::ResolvePolicyResponse, resolve_policy_mapping, State, CHAIN_ID_PATH_PARAM,
+        DATA_QUERY_PARAM, FROM_QUERY_PARAM, TO_QUERY_PARAM, VALUE_QUERY_PARAM,
+    };
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_keys_repository: MockKeysRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_keys_repository: keys_repository(None),
+            mock_address_groups_repository: address_groups_repository(vec![]),
+        }
+    }
+
+    /// Address groups repository holding the groups of the client.
+    fn address_groups_repository(groups: Vec<AddressGroup>) -> MockAddressGroupsRepository {
+        let mut mock_address_groups_repository = MockAddressGroupsRepository::new();
+        mock_address_groups_repository
+            .expect_get_groups_containing()
+            .returning(move |_, address| {
+                Ok(groups
+                    .iter()
+                    .filter(|group| group.contains(&address))
+                    .map(|group| group.name.clone())
+                    .collect())
+            });
+        mock_address_groups_repository
+    }
+
+    /// Keys repository where the sender of the transaction is `key`, or isn't a key at all.
+    fn keys_repository(key: Option<Key>) -> MockKeysRepository {
+        let mut mock_keys_repository = MockKeysRepository::new();
+        mock_keys_repository
+            .expect_get_key_by_address()
+            .returning(move |_| {
+                key.clone()
+                    .ok_or_else(|| KeysRepositoryError::KeyNotFound("not found".to_owned()))
+            });
+        mock_keys_repository
+    }
+
+    fn build_request(query_params: HashMap<String, String>) -> Request<Body> {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        let path_params = HashMap::from([(
+            CHAIN_ID_PATH_PARAM.to_owned(),
+            CHAIN_ID_FOR_MOCK_REQUESTS.to_string(),
+        )]);
+
+        build_request_custom_auth(auth, Body::default())
+            .with_path_parameters(path_params)
+            .with_query_string_parameters(query_params)
+    }
+
+    fn from_and_to_query_params() -> HashMap<String, String> {
+        HashMap::from([
+            (
+                FROM_QUERY_PARAM.to_owned(),
+                ADDRESS_FOR_MOCK_REQUESTS.to_owned(),
+            ),
+            (
+                TO_QUERY_PARAM.to_owned(),
+                ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS.to_owned(),
+            ),
+        ])
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_address_to_policy_ok(mut fixture: TestFixture) {
+        let policy = "some_policy";
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(2)
+            .returning(move |_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::AddressTo { .. } => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: policy.to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                })),
+                _ => Ok(None),
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!(policy, resolved.policy);
+        assert_eq!(MappingType::AddressTo, resolved.r#type);
+        assert_eq!(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS, resolved.address);
+
+        let outcomes: Vec<(MappingType, CandidateOutcomeResponse)> = body
+            .candidates
+            .into_iter()
+            .map(|candidate| (candidate.r#type, candidate.outcome))
+            .collect();
+        assert_eq!(
+            vec![
+                (MappingType::AddressFrom, CandidateOutcomeResponse::NotFound),
+                (MappingType::AddressTo, CandidateOutcomeResponse::Selected),
+                (
+                    MappingType::Default,
+                    CandidateOutcomeResponse::ShadowedByHigherPrecedence
+                ),
+                (
+                    MappingType::Default,
+                    CandidateOutcomeResponse::ShadowedByHigherPrecedence
+                ),
+            ],
+            outcomes
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_scheduled_policy_not_active(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(4)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::AddressTo { .. } => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "scheduled_policy".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: Some(Utc::now() + Duration::hours(1)),
+                    expires_at: None,
+                    value_bands: vec![],
+                })),
+                _ => Ok(None),
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.policy.is_none());
+        let outcomes: Vec<CandidateOutcomeResponse> = body
+            .candidates
+            .into_iter()
+            .map(|candidate| candidate.outcome)
+            .collect();
+        assert_eq!(
+            vec![
+                CandidateOutcomeResponse::NotFound,
+                CandidateOutcomeResponse::NotActive,
+                CandidateOutcomeResponse::NotFound,
+                CandidateOutcomeResponse::NotFound,
+            ],
+            outcomes
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_contract_call_policy_ok(mut fixture: TestFixture) {
+        let mut query_params = from_and_to_query_params();
+        query_params.insert(
+            DATA_QUERY_PARAM.to_owned(),
+            "0xa9059cbb000000000000000000000000".to_owned(),
+        );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .withf(|_, _, mapping_type| {
+                matches!(
+                    mapping_type,
+                    AddressPolicyRegistryType::ContractCall { selector, .. }
+                        if selector.to_string() == "0xa9059cbb"
+                )
+            })
+            .returning(|_, _, mapping_type| {
+                Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "some_policy".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                }))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(query_params), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!(MappingType::ContractCall, resolved.r#type);
+        assert_eq!(Some("0xa9059cbb".to_owned()), resolved.selector);
+        assert_eq!(5, body.candidates.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_client_user_policy_ok(mut fixture: TestFixture) {
+        let key = Key {
+            key_id: Uuid::new_v4(),
+            address: ADDRESS_FOR_MOCK_REQUESTS.to_owned(),
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            client_user_id: "some_end_user".to_owned(),
+            created_at: Utc::now(),
+            order_type: "KEY_CREATION_ORDER".to_owned(),
+            order_version: "1".to_owned(),
+            owning_user_id: Uuid::new_v4(),
+            public_key: "some_public_key".to_owned(),
+        };
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(3)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::ClientUser { .. } => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "end_user_policy".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                })),
+                _ => Ok(None),
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(keys_repository(Some(key))),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!("end_user_policy", resolved.policy);
+        assert_eq!(MappingType::ClientUser, resolved.r#type);
+        assert_eq!(Some("some_end_user".to_owned()), resolved.client_user_id);
+
+        let types: Vec<MappingType> = body
+            .candidates
+            .into_iter()
+            .map(|candidate| candidate.r#type)
+            .collect();
+        assert_eq!(
+            vec![
+                MappingType::AddressFrom,
+                MappingType::KeyId,
+                MappingType::ClientUser,
+                MappingType::AddressTo,
+                MappingType::Default,
+                MappingType::Default,
+            ],
+            types
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_group_policy_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(3)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::Group { .. } => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "exchanges_policy".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                })),
+                _ => Ok(None),
+            });
+        let exchanges = AddressGroup {
+            client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+            name: "exchanges".to_owned(),
+            addresses: vec![Address::from_str(ADDRESS_OF_RECIPIENT_FOR_MOCK_REQUESTS).unwrap()],
+        };
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(address_groups_repository(vec![exchanges])),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!("exchanges_policy", resolved.policy);
+        assert_eq!(MappingType::Group, resolved.r#type);
+        assert_eq!(Some("exchanges".to_owned()), resolved.group);
+        assert_eq!(5, body.candidates.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_value_band_policy_ok(mut fixture: TestFixture) {
+        let mut query_params = from_and_to_query_params();
+        query_params.insert(
+            VALUE_QUERY_PARAM.to_owned(),
+            "5000000000000000000".to_owned(),
+        );
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(3)
+            .returning(|_, _, mapping_type| match mapping_type {
+                AddressPolicyRegistryType::Default => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id: CHAIN_ID_FOR_MOCK_REQUESTS,
+                    policy: "board".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![
+                        PolicyValueBand {
+                            below: U256::exp10(18),
+                            policy: "basic".to_owned(),
+                        },
+                        PolicyValueBand {
+                            below: U256::exp10(20),
+                            policy: "dual".to_owned(),
+                        },
+                    ],
+                })),
+                _ => Ok(None),
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(query_params), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(Some("dual".to_owned()), body.applied_policy);
+        let resolved = body.policy.unwrap();
+        assert_eq!("board", resolved.policy);
+        assert_eq!(2, resolved.value_bands.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_invalid_value(mut fixture: TestFixture) {
+        let mut query_params = from_and_to_query_params();
+        query_params.insert(VALUE_QUERY_PARAM.to_owned(), "-1".to_owned());
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(query_params), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!(
+            "value with wrong type in request query string",
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_client_wide_default_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(4)
+            .returning(|_, chain_id, mapping_type| match chain_id {
+                ANY_CHAIN_ID => Ok(Some(AddressPolicyRegistry {
+                    client_id: CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                    chain_id,
+                    policy: "client_wide_policy".to_owned(),
+                    r#type: mapping_type,
+                    version: 1,
+                    effective_from: None,
+                    expires_at: None,
+                    value_bands: vec![],
+                })),
+                _ => Ok(None),
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        let resolved = body.policy.unwrap();
+        assert_eq!("client_wide_policy", resolved.policy);
+        assert_eq!(ANY_CHAIN_ID, resolved.chain_id);
+        let client_wide_candidate = body.candidates.last().unwrap();
+        assert_eq!(ANY_CHAIN_ID, client_wide_candidate.chain_id);
+        assert_eq!(
+            CandidateOutcomeResponse::Selected,
+            client_wide_candidate.outcome
+        );
+        assert!(response.body().contains(r#""chain_id":"*""#));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_without_mappings_ok(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .times(4)
+            .returning(|_, _, _| Ok(None));
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: ResolvePolicyResponse = serde_json::from_str(response.body()).unwrap();
+        assert!(body.policy.is_none());
+        assert_eq!(4, body.candidates.len());
+        assert!(body
+            .candidates
+            .iter()
+            .all(|candidate| candidate.outcome == CandidateOutcomeResponse::NotFound));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_missing_to_address(mut fixture: TestFixture) {
+        let request = build_request(HashMap::from([(
+            FROM_QUERY_PARAM.to_owned(),
+            ADDRESS_FOR_MOCK_REQUESTS.to_owned(),
+        )]));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("to not found in request query string", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_invalid_from_address(mut fixture: TestFixture) {
+        let mut query_params = from_and_to_query_params();
+        query_params.insert(FROM_QUERY_PARAM.to_owned(), "invalid_address".to_owned());
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(query_params), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("from with wrong type in request query string", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn resolve_repository_error(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .returning(|_, _, _| {
+                Err(AddressPolicyRegistryRepositoryError::Unknown(
+                    anyhow::anyhow!("timeout!"),
+                ))
+            });
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            keys_repository: Arc::new(fixture.mock_keys_repository),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = resolve_policy_mapping(build_request(from_and_to_query_params()), &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+}
@@ -0,0 +1,423 @@
+use std::sync::Arc;
+
+use crate::config::Config;
+use common::aws_clients::dynamodb::get_dynamodb_client;
+use common::config::ConfigLoader;
+use common::feature_flags::FeatureFlags;
+use lambda_http::{run, service_fn, Error, Request};
+use mpc_signature_sm::feature_flags::FeatureFlagsState;
+use mpc_signature_sm::lambda_structure::http_lambda_main::HttpLambdaResponse;
+use mpc_signature_sm::maestro::maestro_bootstrap;
+use mpc_signature_sm::maestro::policy_catalog::{
+    MaestroPolicyCatalog, PolicyCatalog, PolicyCatalogState,
+};
+use mpc_signature_sm::validations::http::content_type::validate_content_type;
+use mpc_signature_sm::validations::http::policy_ownership::validate_mapping_policies_belong_to_client;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chain_id_is_enabled;
+use mpc_signature_sm::{http_lambda_main, http_router};
+use repositories::address_groups::address_groups_repository_impl::AddressGroupsRepositoryImpl;
+use repositories::address_groups::AddressGroupsRepository;
+use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
+use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
+
+mod config;
+
+// The handlers are the same ones the single operation lambdas serve.
+#[path = "../create_policy"]
+pub mod create_policy {
+    mod dtos;
+    pub mod handler;
+}
+
+#[path = "../fetch_policy"]
+pub mod fetch_policy {
+    mod dtos;
+    pub mod handler;
+}
+
+#[path = "../fetch_all_policy"]
+pub mod fetch_all_policy {
+    mod dtos;
+    pub mod handler;
+}
+
+#[path = "../update_policy"]
+pub mod update_policy {
+    mod dtos;
+    pub mod handler;
+}
+
+#[path = "../delete_policy"]
+pub mod delete_policy {
+    mod dtos;
+    pub mod handler;
+}
+
+/// Shared by every route. Each handler gets a view of it with the dependencies it needs.
+pub struct State<
+    APRR: AddressPolicyRegistryRepository,
+    AGR: AddressGroupsRepository,
+    PC: PolicyCatalog,
+> {
+    pub address_policy_registry_repository: Arc<APRR>,
+    pub address_groups_repository: Arc<AGR>,
+    pub policy_catalog: Arc<PC>,
+    pub feature_flags: Arc<dyn FeatureFlags>,
+}
+
+impl<APRR: AddressPolicyRegistryRepository, AGR: AddressGroupsRepository, PC: PolicyCatalog>
+    FeatureFlagsState for State<APRR, AGR, PC>
+{
+    fn feature_flags(&self) -> &dyn FeatureFlags {
+        self.feature_flags.as_ref()
+    }
+}
+
+impl<APRR: AddressPolicyRegistryRepository, AGR: AddressGroupsRepository, PC: PolicyCatalog>
+    PolicyCatalogState for State<APRR, AGR, PC>
+{
+    type PolicyCatalog = PC;
+
+    fn policy_catalog(&self) -> &PC {
+        self.policy_catalog.as_ref()
+    }
+}
+
+http_router!(
+    route_policy_mapping_request,
+    State<impl AddressPolicyRegistryRepository, impl AddressGroupsRepository, impl PolicyCatalog>,
+    [
+        POST "/policy_mappings" => create_policy_mapping [validate_content_type]
+            [validate_mapping_policies_belong_to_client],
+        GET "/policy_mappings" => fetch_all_policy_mappings [],
+        GET "/policy_mappings/{chain_id}/{address}" => fetch_policy_mapping [],
+        PUT "/policy_mappings/{chain_id}/{address}" => update_policy_mapping [validate_content_type]
+            [validate_mapping_chain_id_is_enabled, validate_mapping_policies_belong_to_client],
+        DELETE "/policy_mappings/{chain_id}/{address}" => delete_policy_mapping []
+            [validate_mapping_chain_id_is_enabled],
+    ]
+);
+
+http_lambda_main!(
+    {
+        let config = ConfigLoader::load_default::<Config>();
+        let dynamodb_client = get_dynamodb_client().await;
+
+        let secrets_provider = get_secrets_provider().await;
+        let maestro = maestro_bootstrap(secrets_provider)
+            .await
+            .expect("unable to initialize maestro");
+
+        let config = config.await;
+        let address_policy_registry_repository =
+            Arc::new(AddressPolicyRegistryRepositoryImpl::new(
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
+            ));
+        let address_groups_repository = Arc::new(AddressGroupsRepositoryImpl::new(
+            config.address_groups_table_name.clone(),
+            dynamodb_client.clone(),
+        ));
+        let policy_catalog = Arc::new(MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        ));
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
+        State {
+            address_policy_registry_repository,
+            address_groups_repository,
+            policy_catalog,
+            feature_flags,
+        }
+    },
+    route_policy_mapping_request
+);
+
+async fn create_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let state = create_policy::handler::State {
+        address_policy_registry_repository: state.address_policy_registry_repository.clone(),
+        address_groups_repository: state.address_groups_repository.clone(),
+        policy_catalog: state.policy_catalog.clone(),
+        feature_flags: state.feature_flags.clone(),
+    };
+    create_policy::handler::create_policy(request, &state).await
+}
+
+async fn fetch_all_policy_mappings(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let state = fetch_all_policy::handler::State {
+        address_policy_registry_repository: state.address_policy_registry_repository.clone(),
+    };
+    fetch_all_policy::handler::fetch_all_policy(request, &state).await
+}
+
+async fn fetch_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let state = fetch_policy::handler::State {
+        address_policy_registry_repository: state.address_policy_registry_repository.clone(),
+    };
+    fetch_policy::handler::fetch_policy(request, &state).await
+}
+
+async fn update_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let state = update_policy::handler::State {
+        address_policy_registry_repository: state.address_policy_registry_repository.clone(),
+        policy_catalog: state.policy_catalog.clone(),
+        feature_flags: state.feature_flags.clone(),
+    };
+    update_policy::handler::update_policy(request, &state).await
+}
+
+async fn delete_policy_mapping(
+    request: Request,
+    state: &State<
+        impl AddressPolicyRegistryRepository,
+        impl AddressGroupsRepository,
+        impl PolicyCatalog,
+    >,
+) -> HttpLambdaResponse {
+    let state = delete_policy::handler::State {
+        address_policy_registry_repository: state.address_policy_registry_repository.clone(),
+        feature_flags: state.feature_flags.clone(),
+    };
+    delete_policy::handler::delete_policy(request, &state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+    use async_trait::async_trait;
+    use common::feature_flags::LocalFeatureFlags;
+    use common::test_tools::http::constants::{
+        ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use ethers::types::Address;
+    use http::{Method, Request, StatusCode};
+    use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+    use lambda_http::request::RequestContext;
+    use lambda_http::{Body, RequestExt};
+    use mockall::mock;
+    use model::address_policy_registry::AddressPolicyRegistryBuilder;
+    use model::order::policy::Policy;
+    use mpc_signature_sm::config::{chain_feature_flag, supported_chains_feature_flags};
+    use mpc_signature_sm::dtos::responses::http_error::LambdaErrorResponse;
+    use mpc_signature_sm::maestro::policy_catalog::PolicyCatalog;
+    use mpc_signature_sm::result::error::LambdaError;
+    use repositories::address_groups::MockAddressGroupsRepository;
+    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use rstest::{fixture, rstest};
+    use serde_json::{json, Value};
+
+    use crate::{route_policy_mapping_request, State};
+
+    const MAPPING_RESOURCE: &str = "/policy_mappings/{chain_id}/{address}";
+
+    mock! {
+        PolicyCatalog {}
+
+        #[async_trait]
+        impl PolicyCatalog for PolicyCatalog {
+            async fn policy_belongs_to_client(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<bool, LambdaError>;
+
+            async fn get_policy(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<Option<Policy>, LambdaError>;
+        }
+    }
+
+    struct TestFixture {
+        pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
+        pub mock_address_groups_repository: MockAddressGroupsRepository,
+        pub mock_policy_catalog: MockPolicyCatalog,
+        pub feature_flags: LocalFeatureFlags,
+    }
+
+    #[fixture]
+    fn fixture() -> TestFixture {
+        TestFixture {
+            mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
+            mock_address_groups_repository: MockAddressGroupsRepository::new(),
+            mock_policy_catalog: MockPolicyCatalog::new(),
+            feature_flags: supported_chains_feature_flags(),
+        }
+    }
+
+    fn build_request(method: Method, resource: &str, chain_id: u64) -> Request<Body> {
+        let authorizer = HashMap::from([(
+            "claims".to_owned(),
+            json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS }),
+        )]);
+        let request_context = RequestContext::ApiGatewayV1(ApiGatewayProxyRequestContext {
+            authorizer,
+            resource_path: Some(resource.to_owned()),
+            ..ApiGatewayProxyRequestContext::default()
+        });
+        let path_params = HashMap::from([
+            ("chain_id".to_owned(), chain_id.to_string()),
+            ("address".to_owned(), ADDRESS_FOR_MOCK_REQUESTS.to_owned()),
+        ]);
+
+        let mut request = Request::new(Body::default())
+            .with_request_context(request_context)
+            .with_path_parameters(path_params);
+        *request.method_mut() = method;
+        request
+    }
+
+    fn build_state(
+        fixture: TestFixture,
+    ) -> State<MockAddressPolicyRegistryRepository, MockAddressGroupsRepository, MockPolicyCatalog>
+    {
+        State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            address_groups_repository: Arc::new(fixture.mock_address_groups_repository),
+            policy_catalog: Arc::new(fixture.mock_policy_catalog),
+            feature_flags: Arc::new(fixture.feature_flags),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn routes_to_fetch_policy(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_get_stored_policy()
+            .once()
+            .returning(|_, _, _| {
+                Ok(Some(
+                    AddressPolicyRegistryBuilder::new(
+                        CLIENT_ID_FOR_MOCK_REQUESTS.to_owned(),
+                        CHAIN_ID_FOR_MOCK_REQUESTS,
+                        "some_policy".to_owned(),
+                    )
+                    .address_to(Address::from_str(ADDRESS_FOR_MOCK_REQUESTS).unwrap()),
+                ))
+            });
+        let request = build_request(Method::GET, MAPPING_RESOURCE, CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        let response = route_policy_mapping_request(request, &build_state(fixture))
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body: Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("some_policy", body["policy"]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn runs_route_validations(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_delete_policy()
+            .never();
+        let request = build_request(Method::DELETE, MAPPING_RESOURCE, 2);
+
+        let response = route_policy_mapping_request(request, &build_state(fixture))
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
+        assert_eq!("chain_id 2 is not supported", body.message);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn rejects_chain_not_enabled(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_delete_policy()
+            .never();
+        fixture.feature_flags = supported_chains_feature_flags().with_flag(
+            &chain_feature_flag(CHAIN_ID_FOR_MOCK_REQUESTS),
+            json!(false),
+        );
+        let request = build_request(Method::DELETE, MAPPING_RESOURCE, CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        let response = route_policy_mapping_request(request, &build_state(fixture))
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("forbidden", body.code);
+        assert_eq!(
+            format!("chain_id {CHAIN_ID_FOR_MOCK_REQUESTS} is not enabled for this client"),
+            body.message
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn create_requires_content_type(mut fixture: TestFixture) {
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_put_policy()
+            .never();
+        fixture
+            .mock_policy_catalog
+            .expect_policy_belongs_to_client()
+            .never();
+        let request = build_request(Method::POST, "/policy_mappings", CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        let response = route_policy_mapping_request(request, &build_state(fixture))
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn unknown_route(fixture: TestFixture) {
+        let request = build_request(Method::PATCH, MAPPING_RESOURCE, CHAIN_ID_FOR_MOCK_REQUESTS);
+
+        let response = route_policy_mapping_request(request, &build_state(fixture))
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("route_not_found", body.code);
+    }
+}
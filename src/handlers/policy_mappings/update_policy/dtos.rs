@@ -1,6 +1,32 @@
-use serde::Deserialize;
+use mpc_signature_sm::dtos::policy_mapping_chain::{
+    deserialize_mapping_chain_id, serialize_mapping_chain_id,
+};
+use mpc_signature_sm::dtos::policy_mapping_type::MappingType;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct UpdatePolicyMappingRequest {
     pub policy: String,
 }
+
+#[derive(Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct UpdatePolicyMappingResponse {
+    #[serde(
+        serialize_with = "serialize_mapping_chain_id",
+        deserialize_with = "deserialize_mapping_chain_id"
+    )]
+    pub chain_id: u64,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub policy: String,
+    pub r#type: MappingType,
+}
@@ -0,0 +1,138 @@
+use super::dtos::{UpdatePolicyMappingRequest, UpdatePolicyMappingResponse};
+use common::feature_flags::FeatureFlags;
+use http::StatusCode;
+use lambda_http::Request;
+use model::address_policy_registry::function_selector::FunctionSelector;
+use mpc_signature_sm::dtos::policy_mapping_chain::extract_mapping_chain_id;
+use mpc_signature_sm::dtos::policy_mapping_type::{
+    mapping_address_to_string, mapping_client_user_id, mapping_group, mapping_key_id,
+    mapping_selector_to_string, MappingTarget, MappingType,
+};
+use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
+use mpc_signature_sm::dtos::requests::if_match_header::{
+    version_etag, IfMatchHeader, ETAG_HEADER_NAME,
+};
+use mpc_signature_sm::feature_flags::FeatureFlagsState;
+use mpc_signature_sm::http::errors::{
+    not_found_response, precondition_failed_error_response, unknown_error_response,
+};
+use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
+use mpc_signature_sm::lambda_structure::http_lambda_main::{
+    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
+};
+use mpc_signature_sm::maestro::policy_catalog::{PolicyCatalog, PolicyCatalogState};
+use mpc_signature_sm::result::error::LambdaError;
+use repositories::address_policy_registry::{
+    AddressPolicyRegistryRepository, AddressPolicyRegistryRepositoryError,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
+pub const ADDRESS_PATH_PARAM: &str = "address";
+pub const TYPE_QUERY_PARAM: &str = "type";
+pub const SELECTOR_QUERY_PARAM: &str = "selector";
+pub const KEY_ID_QUERY_PARAM: &str = "key_id";
+pub const CLIENT_USER_ID_QUERY_PARAM: &str = "client_user_id";
+pub const GROUP_QUERY_PARAM: &str = "group";
+pub const POLICY_NOT_FOUND_CODE: &str = "policy_not_found";
+
+pub struct State<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> {
+    pub address_policy_registry_repository: Arc<APRR>,
+    pub policy_catalog: PC,
+    pub feature_flags: Arc<dyn FeatureFlags>,
+}
+
+impl<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> FeatureFlagsState
+    for State<APRR, PC>
+{
+    fn feature_flags(&self) -> &dyn FeatureFlags {
+        self.feature_flags.as_ref()
+    }
+}
+
+impl<APRR: AddressPolicyRegistryRepository, PC: PolicyCatalog> PolicyCatalogState
+    for State<APRR, PC>
+{
+    type PolicyCatalog = PC;
+
+    fn policy_catalog(&self) -> &PC {
+        &self.policy_catalog
+    }
+}
+
+pub async fn update_policy(
+    request: Request,
+    state: &State<impl AddressPolicyRegistryRepository, impl PolicyCatalog>,
+) -> HttpLambdaResponse {
+    let body = request.extract_body::<UpdatePolicyMappingRequest>()?;
+
+    let client_id = request.extract_client_id()?;
+    let subject = request.extract_subject()?;
+    let chain_id = extract_mapping_chain_id(&request, CHAIN_ID_PATH_PARAM)?;
+    let address = request
+        .extract_path_param::<AddressOrDefaultPathParam>(ADDRESS_PATH_PARAM)?
+        .extract_address();
+    let target = MappingTarget {
+        address,
+        selector: request.extract_query_param::<FunctionSelector>(SELECTOR_QUERY_PARAM)?,
+        key_id: request.extract_query_param::<Uuid>(KEY_ID_QUERY_PARAM)?,
+        client_user_id: request.extract_query_param::<String>(CLIENT_USER_ID_QUERY_PARAM)?,
+        group: request.extract_query_param::<String>(GROUP_QUERY_PARAM)?,
+    };
+    let mapping_type = request
+        .extract_query_param::<MappingType>(TYPE_QUERY_PARAM)?
+        .unwrap_or_else(|| MappingType::inferred_from(&target))
+        .into_registry_type(target)?;
+    let expected_version = IfMatchHeader::extract_expected_version(&request)?;
+
+    let response = UpdatePolicyMappingResponse {
+        chain_id,
+        address: mapping_address_to_string(&mapping_type),
+        selector: mapping_selector_to_string(&mapping_type),
+        key_id: mapping_key_id(&mapping_type),
+        client_user_id: mapping_client_user_id(&mapping_type),
+        group: mapping_group(&mapping_type),
+        policy: body.policy.clone(),
+        r#type: MappingType::from(&mapping_type),
+    };
+
+    let version = state
+        .address_policy_registry_repository
+        .update_policy(
+            client_id,
+            chain_id,
+            mapping_type,
+            body.policy,
+            expected_version,
+            subject,
+        )
+        .await
+        .map_err(|e| match e {
+            AddressPolicyRegistryRepositoryError::PolicyNotFound(message) => {
+                not_found_response(POLICY_NOT_FOUND_CODE, message)
+            }
+            AddressPolicyRegistryRepositoryError::VersionMismatch(message) => {
+                precondition_failed_error_response(message)
+            }
+            e => unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
+                "there was an error saving address policy mapping. {e:?}"
+            ))),
+        })?;
+
+    let response = serde_json::to_string(&response).map_err(|e| {
+        unknown_error_response(LambdaError::Unknown(
+            anyhow::anyhow!(e).context("converting update policy mapping response"),
+        ))
+    })?;
+
+    let mut proxy_response = LambdaProxyHttpResponse {
+        status_code: StatusCode::OK,
+        body: Some(response),
+        ..LambdaProxyHttpResponse::default()
+    };
+    proxy_response
+        .headers
+        .insert(ETAG_HEADER_NAME.to_owned(), version_etag(version));
+    proxy_response.try_into()
+}
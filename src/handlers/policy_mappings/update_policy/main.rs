@@ -1,36 +1,21 @@
 use crate::config::Config;
 use common::aws_clients::dynamodb::get_dynamodb_client;
 use common::config::ConfigLoader;
-use dtos::UpdatePolicyMappingRequest;
-use http::{Response, StatusCode};
+use handler::{update_policy, State};
 use lambda_http::{run, service_fn, Error, Request};
-use mpc_signature_sm::config::SupportedChain;
-use mpc_signature_sm::dtos::requests::address_or_default_path_param::AddressOrDefaultPathParam;
-use mpc_signature_sm::http::errors::{unknown_error_response, validation_error_response};
-use mpc_signature_sm::http::lambda_proxy::LambdaProxyHttpResponse;
 use mpc_signature_sm::http_lambda_main;
-use mpc_signature_sm::lambda_structure::http_lambda_main::{
-    CustomFieldsExtractor, HttpLambdaResponse, RequestExtractor,
-};
 use mpc_signature_sm::maestro::maestro_bootstrap;
-use mpc_signature_sm::maestro::state::MaestroState;
-use mpc_signature_sm::result::error::LambdaError;
+use mpc_signature_sm::maestro::policy_catalog::MaestroPolicyCatalog;
 use mpc_signature_sm::validations::http::content_type::validate_content_type;
-use mpc_signature_sm::validations::http::supported_chain_id::validate_chain_id_is_supported;
+use mpc_signature_sm::validations::http::policy_ownership::validate_mapping_policies_belong_to_client;
+use mpc_signature_sm::validations::http::supported_chain_id::validate_mapping_chain_id_is_enabled;
 use repositories::address_policy_registry::address_policy_registry_repository_impl::AddressPolicyRegistryRepositoryImpl;
-use repositories::address_policy_registry::AddressPolicyRegistryRepository;
+use repositories::cache::cache_repository_impl::CacheRepositoryImpl;
 use std::sync::Arc;
 
 mod config;
 mod dtos;
-
-pub const CHAIN_ID_PATH_PARAM: &str = "chain_id";
-pub const ADDRESS_PATH_PARAM: &str = "address";
-
-pub struct State<APRR: AddressPolicyRegistryRepository> {
-    address_policy_registry_repository: Arc<APRR>,
-    maestro: MaestroState,
-}
+pub mod handler;
 
 http_lambda_main!(
     {
@@ -42,84 +27,33 @@ http_lambda_main!(
             .await
             .expect("unable to initialize maestro");
 
+        let config = config.await;
         let address_policy_registry_repository =
             Arc::new(AddressPolicyRegistryRepositoryImpl::new(
-                config.await.address_policy_registry_table_name.clone(),
-                dynamodb_client,
+                config.address_policy_registry_table_name.clone(),
+                config.address_policy_registry_history_table_name.clone(),
+                dynamodb_client.clone(),
             ));
+        let policy_catalog = MaestroPolicyCatalog::new(
+            maestro,
+            CacheRepositoryImpl::new(config.cache_table_name.clone(), dynamodb_client),
+        );
+        let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
 
         State {
             address_policy_registry_repository,
-            maestro,
+            policy_catalog,
+            feature_flags,
         }
     },
     update_policy,
-    [validate_chain_id_is_supported, validate_content_type]
+    [validate_content_type],
+    [
+        validate_mapping_chain_id_is_enabled,
+        validate_mapping_policies_belong_to_client
+    ]
 );
 
-async fn update_policy(
-    request: Request,
-    state: &State<impl AddressPolicyRegistryRepository>,
-) -> HttpLambdaResponse {
-    let body = request.extract_body::<UpdatePolicyMappingRequest>()?;
-
-    let client_id = request.extract_client_id()?;
-    let chain_id: u64 = request.extract_path_param(CHAIN_ID_PATH_PARAM)?;
-    let address: AddressOrDefaultPathParam = request.extract_path_param(ADDRESS_PATH_PARAM)?;
-
-    if !chain_id.is_supported() {
-        return Err(validation_error_response(
-            format!("chain_id {chain_id} is not supported",),
-            None,
-        ));
-    }
-
-    if !check_policy_belongs_to_client(&client_id, &body.policy, &state.maestro).await? {
-        return Err(validation_error_response(
-            format!(r#"invalid policy "{}""#, body.policy),
-            None,
-        ));
-    }
-
-    state
-        .address_policy_registry_repository
-        .update_policy(client_id, chain_id, address.extract_address(), body.policy)
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error saving address policy mapping. {e:?}"
-            )))
-        })?;
-
-    LambdaProxyHttpResponse {
-        status_code: StatusCode::OK,
-        ..LambdaProxyHttpResponse::default()
-    }
-    .try_into()
-}
-
-async fn check_policy_belongs_to_client(
-    client_id: &str,
-    policy: &str,
-    maestro: &MaestroState,
-) -> Result<bool, Response<String>> {
-    let response = maestro
-        .http
-        .get(format!(
-            "{}/{client_id}/policy/{policy}",
-            maestro.config.maestro_url
-        ))
-        .send()
-        .await
-        .map_err(|e| {
-            unknown_error_response(LambdaError::Unknown(anyhow::anyhow!(
-                "there was an error with maestro's request: {e:?}"
-            )))
-        })?;
-
-    Ok(StatusCode::OK == response.status())
-}
-
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, sync::Arc};
@@ -127,22 +61,28 @@ mod tests {
     use common::test_tools::http::constants::{
         ADDRESS_FOR_MOCK_REQUESTS, CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
     };
-    use http::StatusCode;
+    use http::{HeaderValue, StatusCode};
     use lambda_http::{
         aws_lambda_events::apigw::ApiGatewayProxyRequestContext, request::RequestContext, Body,
         Request, RequestExt,
     };
+    use mpc_signature_sm::config::supported_chains_feature_flags;
 
     use mpc_signature_sm::{
-        dtos::responses::http_error::LambdaErrorResponse,
-        maestro::{
-            config::MaestroConfig,
-            session::{login, MaestroLoginInformation},
-            state::MaestroState,
+        dtos::{policy_mapping_type::MappingType, responses::http_error::LambdaErrorResponse},
+        lambda_structure::http_lambda_main::HttpLambdaResponse,
+        maestro::{policy_catalog::MaestroPolicyCatalog, test_tools::maestro_policy_catalog},
+        validations::http::{
+            policy_ownership::validate_mapping_policies_belong_to_client,
+            supported_chain_id::{
+                validate_mapping_chain_id_is_enabled, validate_mapping_chain_id_is_supported,
+            },
         },
-        rest::middlewares::AuthenticationMiddleware,
     };
-    use repositories::address_policy_registry::MockAddressPolicyRegistryRepository;
+    use repositories::address_policy_registry::{
+        AddressPolicyRegistryRepositoryError, MockAddressPolicyRegistryRepository,
+    };
+    use repositories::cache::MockCacheRepositoryTest;
     use rstest::{fixture, rstest};
     use serde_json::{json, Value};
     use wiremock::{
@@ -150,43 +90,28 @@ mod tests {
         Mock, MockServer, ResponseTemplate,
     };
 
-    use crate::{update_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM};
+    use crate::{
+        dtos::UpdatePolicyMappingResponse,
+        handler::{
+            update_policy, State, ADDRESS_PATH_PARAM, CHAIN_ID_PATH_PARAM, TYPE_QUERY_PARAM,
+        },
+    };
 
     struct TestFixture {
         pub mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository,
-        pub maestro: MaestroState,
+        pub policy_catalog: MaestroPolicyCatalog<MockCacheRepositoryTest>,
         pub mock_server: MockServer,
     }
 
     #[fixture]
     async fn fixture() -> TestFixture {
         let mock_server = MockServer::start().await;
-        let config = MaestroConfig {
-            maestro_url: mock_server.uri(),
-            service_name: "test".to_owned(),
-            maestro_api_key_secret_name: "dummy_secret_name_api_key".to_owned(),
-            maestro_tenant_name: "tenant".to_owned(),
-        };
-
-        let http_client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
-            .with(AuthenticationMiddleware::new(
-                &login,
-                Arc::new(MaestroLoginInformation {
-                    maestro_url: config.maestro_url.clone(),
-                    service_name: config.service_name.clone(),
-                    maestro_api_key: "dummy_api_secret".to_owned(),
-                    tenant_name: "tenant".to_owned(),
-                }),
-                Some("dummy_token".to_owned()),
-            ))
-            .build();
-
         TestFixture {
             mock_address_policy_registry_repository: MockAddressPolicyRegistryRepository::new(),
-            maestro: MaestroState {
-                http: http_client,
-                config,
-            },
+            policy_catalog: maestro_policy_catalog(
+                mock_server.uri(),
+                MockCacheRepositoryTest::empty(),
+            ),
             mock_server,
         }
     }
@@ -217,7 +142,7 @@ mod tests {
 
         let authorizer: HashMap<String, Value> = HashMap::from([(
             "claims".to_string(),
-            json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS }),
+            json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS, "sub": "user_sub" }),
         )]);
 
         let request_context = RequestContext::ApiGatewayV1(ApiGatewayProxyRequestContext {
@@ -230,6 +155,20 @@ mod tests {
             .with_request_context(request_context)
     }
 
+    /// Runs the state validations of the lambda before its handler, the way `http_lambda_main!`
+    /// does.
+    async fn update(
+        request: Request,
+        state: &State<
+            MockAddressPolicyRegistryRepository,
+            MaestroPolicyCatalog<MockCacheRepositoryTest>,
+        >,
+    ) -> HttpLambdaResponse {
+        validate_mapping_chain_id_is_enabled(&request, state).await?;
+        validate_mapping_policies_belong_to_client(&request, state).await?;
+        update_policy(request, state).await
+    }
+
     #[rstest]
     #[tokio::test]
     async fn update_invalid_address_ok(#[future] fixture: TestFixture) {
@@ -241,7 +180,8 @@ mod tests {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
         let response = update_policy(request, &state).await.unwrap_err();
@@ -252,22 +192,13 @@ mod tests {
         assert_eq!("address with wrong type in request path", body.message);
     }
 
-    #[rstest]
-    #[tokio::test]
-    async fn update_unssupported_chain_id_ok(#[future] fixture: TestFixture) {
-        let fixture = fixture.await;
+    #[test]
+    fn update_unssupported_chain_id_ok() {
         let unssuported_chain_id = 919191919191919191;
         let policy_name = "some_policy";
         let request = build_request(ADDRESS_FOR_MOCK_REQUESTS, unssuported_chain_id, policy_name);
 
-        let state = State {
-            address_policy_registry_repository: Arc::new(
-                fixture.mock_address_policy_registry_repository,
-            ),
-            maestro: fixture.maestro,
-        };
-
-        let response = update_policy(request, &state).await.unwrap_err();
+        let response = validate_mapping_chain_id_is_supported(&request).unwrap_err();
 
         assert_eq!(StatusCode::BAD_REQUEST, response.status());
         let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
@@ -281,7 +212,7 @@ mod tests {
     #[rstest]
     #[tokio::test]
     async fn update_invalid_policy_ok(#[future] fixture: TestFixture) {
-        let fixture = fixture.await;
+        let mut fixture = fixture.await;
         let policy_name = "some_policy";
         let request = build_request(
             ADDRESS_FOR_MOCK_REQUESTS,
@@ -293,19 +224,24 @@ mod tests {
             .and(path(format!(
                 "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
             )))
-            .respond_with(ResponseTemplate::new(StatusCode::INTERNAL_SERVER_ERROR))
+            .respond_with(ResponseTemplate::new(StatusCode::NOT_FOUND))
             .expect(1)
             .mount(&fixture.mock_server)
             .await;
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_update_policy()
+            .never();
 
         let state = State {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
-        let response = update_policy(request, &state).await.unwrap_err();
+        let response = update(request, &state).await.unwrap_err();
 
         assert_eq!(StatusCode::BAD_REQUEST, response.status());
         let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
@@ -314,19 +250,40 @@ mod tests {
     }
 
     #[rstest]
-    #[case::address(ADDRESS_FOR_MOCK_REQUESTS)]
-    #[case::default("default")]
+    #[case::address(ADDRESS_FOR_MOCK_REQUESTS, None, MappingType::AddressTo)]
+    #[case::default("default", None, MappingType::Default)]
+    #[case::address_from(
+        ADDRESS_FOR_MOCK_REQUESTS,
+        Some("ADDRESS_FROM"),
+        MappingType::AddressFrom
+    )]
     #[tokio::test]
-    async fn update_policy_ok(#[future] fixture: TestFixture, #[case] address: &str) {
+    async fn update_policy_ok(
+        #[future] fixture: TestFixture,
+        #[case] address: &str,
+        #[case] type_query_param: Option<&str>,
+        #[case] expected_type: MappingType,
+    ) {
         let mut fixture = fixture.await;
         let policy_name = "some_policy";
-        let request = build_request(address, CHAIN_ID_FOR_MOCK_REQUESTS, "some_policy");
+        let mut request = build_request(address, CHAIN_ID_FOR_MOCK_REQUESTS, "some_policy");
+        if let Some(mapping_type) = type_query_param {
+            request = request.with_query_string_parameters(HashMap::from([(
+                TYPE_QUERY_PARAM.to_owned(),
+                mapping_type.to_owned(),
+            )]));
+        }
 
         fixture
             .mock_address_policy_registry_repository
             .expect_update_policy()
             .once()
-            .returning(|_, _, _, _| Ok(()));
+            .withf(move |_, _, mapping_type, _, expected_version, subject| {
+                MappingType::from(mapping_type) == expected_type
+                    && expected_version.is_none()
+                    && subject.as_deref() == Some("user_sub")
+            })
+            .returning(|_, _, _, _, _, _| Ok(2));
 
         Mock::given(method("GET"))
             .and(path(format!(
@@ -345,11 +302,107 @@ mod tests {
             address_policy_registry_repository: Arc::new(
                 fixture.mock_address_policy_registry_repository,
             ),
-            maestro: fixture.maestro,
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
         };
 
-        let response = update_policy(request, &state).await.unwrap();
+        let response = update(request, &state).await.unwrap();
 
         assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("\"2\"", response.headers().get("ETag").unwrap());
+        let body: UpdatePolicyMappingResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(expected_type, body.r#type);
+        assert_eq!(policy_name, body.policy);
+    }
+
+    #[rstest]
+    #[case::matching_version(Ok(4), StatusCode::OK)]
+    #[case::stale_version(
+        Err(AddressPolicyRegistryRepositoryError::VersionMismatch("stale".to_owned())),
+        StatusCode::PRECONDITION_FAILED
+    )]
+    #[case::deleted_mapping(
+        Err(AddressPolicyRegistryRepositoryError::PolicyNotFound("gone".to_owned())),
+        StatusCode::NOT_FOUND
+    )]
+    #[tokio::test]
+    async fn update_policy_if_match(
+        #[future] fixture: TestFixture,
+        #[case] repository_result: Result<u64, AddressPolicyRegistryRepositoryError>,
+        #[case] expected_status: StatusCode,
+    ) {
+        let mut fixture = fixture.await;
+        let policy_name = "some_policy";
+        let mut request = build_request(
+            ADDRESS_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            policy_name,
+        );
+        request
+            .headers_mut()
+            .insert("if-match", HeaderValue::from_static("\"3\""));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_update_policy()
+            .once()
+            .withf(|_, _, _, _, expected_version, _| *expected_version == Some(3))
+            .return_once(move |_, _, _, _, _, _| repository_result);
+
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/{CLIENT_ID_FOR_MOCK_REQUESTS}/policy/{policy_name}"
+            )))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .expect(1)
+            .mount(&fixture.mock_server)
+            .await;
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = match update(request, &state).await {
+            Ok(response) | Err(response) => response,
+        };
+
+        assert_eq!(expected_status, response.status());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn update_policy_invalid_if_match(#[future] fixture: TestFixture) {
+        let mut fixture = fixture.await;
+        let mut request = build_request(
+            ADDRESS_FOR_MOCK_REQUESTS,
+            CHAIN_ID_FOR_MOCK_REQUESTS,
+            "some_policy",
+        );
+        request
+            .headers_mut()
+            .insert("if-match", HeaderValue::from_static("\"not_a_version\""));
+
+        fixture
+            .mock_address_policy_registry_repository
+            .expect_update_policy()
+            .never();
+
+        let state = State {
+            address_policy_registry_repository: Arc::new(
+                fixture.mock_address_policy_registry_repository,
+            ),
+            policy_catalog: fixture.policy_catalog,
+            feature_flags: Arc::new(supported_chains_feature_flags()),
+        };
+
+        let response = update_policy(request, &state).await.unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("validation", body.code);
     }
 }
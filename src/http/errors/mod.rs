@@ -9,9 +9,12 @@ use crate::result::error::OrchestrationError;
 pub const NOT_FOUND_ERROR_CODE: &str = "not_found";
 pub const SERVER_ERROR_CODE: &str = "server_error";
 pub const UNAUTHORIZED_ERROR_CODE: &str = "unauthorized";
+pub const FORBIDDEN_ERROR_CODE: &str = "forbidden";
 pub const VALIDATION_ERROR_CODE: &str = "validation";
 pub const UNPROCESSABLE_ERROR_CODE: &str = "unprocessable";
 pub const UNSUPPORTED_MEDIA_ERROR_CODE: &str = "unsupported_media_type";
+pub const CONFLICT_ERROR_CODE: &str = "conflict";
+pub const PRECONDITION_FAILED_ERROR_CODE: &str = "precondition_failed";
 
 // messages
 pub const INCOMPATIBLE_ORDER_REPLACEMENT_ERROR_MESSAGE: &str = "Error setting new gas values";
@@ -102,6 +105,10 @@ pub fn unauthorized_error_response(cause: Option<LambdaError>) -> Response<Strin
     )
 }
 
+pub fn forbidden_error_response(message: String) -> Response<String> {
+    error_response(FORBIDDEN_ERROR_CODE, message, StatusCode::FORBIDDEN, None)
+}
+
 pub fn unsupported_media_error_response(cause: Option<LambdaError>) -> Response<String> {
     error_response(
         UNSUPPORTED_MEDIA_ERROR_CODE,
@@ -111,6 +118,19 @@ pub fn unsupported_media_error_response(cause: Option<LambdaError>) -> Response<
     )
 }
 
+pub fn conflict_error_response(message: String) -> Response<String> {
+    error_response(CONFLICT_ERROR_CODE, message, StatusCode::CONFLICT, None)
+}
+
+pub fn precondition_failed_error_response(message: String) -> Response<String> {
+    error_response(
+        PRECONDITION_FAILED_ERROR_CODE,
+        message,
+        StatusCode::PRECONDITION_FAILED,
+        None,
+    )
+}
+
 pub fn unprocessable_entity_error_response(message: String) -> Response<String> {
     error_response(
         UNPROCESSABLE_ERROR_CODE,
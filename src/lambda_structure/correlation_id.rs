@@ -0,0 +1,125 @@
+use std::future::Future;
+
+use http::{HeaderValue, Response};
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt};
+use uuid::Uuid;
+
+/// Header used to receive, return and forward the correlation id.
+pub const CORRELATION_ID_HEADER_NAME: &str = "X-Correlation-Id";
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// Correlation id of a request: the incoming `X-Correlation-Id` header if present, or else the
+/// API Gateway request id. A new one is generated if neither is available.
+pub fn extract_correlation_id(request: &Request) -> String {
+    let incoming = request
+        .headers()
+        .get(CORRELATION_ID_HEADER_NAME)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_owned);
+
+    incoming
+        .or_else(|| match request.request_context() {
+            RequestContext::ApiGatewayV1(context) => context.request_id,
+            _ => None,
+        })
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Runs `future` with `correlation_id` as the current correlation id, see
+/// [`current_correlation_id`].
+pub async fn with_correlation_id<F: Future>(correlation_id: String, future: F) -> F::Output {
+    CORRELATION_ID.scope(correlation_id, future).await
+}
+
+/// Correlation id of the invocation being served, if running inside [`with_correlation_id`].
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(Clone::clone).ok()
+}
+
+/// Returns the correlation id to the caller as a response header.
+pub fn set_correlation_id_header(response: &mut Response<String>, correlation_id: &str) {
+    if let Ok(value) = HeaderValue::from_str(correlation_id) {
+        response
+            .headers_mut()
+            .insert(CORRELATION_ID_HEADER_NAME, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderValue, Response};
+    use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+    use lambda_http::request::RequestContext;
+    use lambda_http::{Body, Request, RequestExt};
+    use rstest::rstest;
+    use uuid::Uuid;
+
+    use super::{
+        current_correlation_id, extract_correlation_id, set_correlation_id_header,
+        with_correlation_id, CORRELATION_ID_HEADER_NAME,
+    };
+
+    fn build_request(header: Option<&'static str>, request_id: Option<&str>) -> Request {
+        let request_context = RequestContext::ApiGatewayV1(ApiGatewayProxyRequestContext {
+            request_id: request_id.map(str::to_owned),
+            ..ApiGatewayProxyRequestContext::default()
+        });
+        let mut request = Request::new(Body::Empty).with_request_context(request_context);
+        if let Some(header) = header {
+            request
+                .headers_mut()
+                .insert(CORRELATION_ID_HEADER_NAME, HeaderValue::from_static(header));
+        }
+        request
+    }
+
+    #[rstest]
+    #[case::incoming_header(Some("incoming-id"), Some("request-id"), "incoming-id")]
+    #[case::empty_header(Some(""), Some("request-id"), "request-id")]
+    #[case::api_gateway_request_id(None, Some("request-id"), "request-id")]
+    fn correlation_id_of_request(
+        #[case] header: Option<&'static str>,
+        #[case] request_id: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            expected,
+            extract_correlation_id(&build_request(header, request_id))
+        );
+    }
+
+    #[test]
+    fn generates_correlation_id() {
+        let correlation_id = extract_correlation_id(&build_request(None, None));
+
+        assert!(Uuid::parse_str(&correlation_id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn correlation_id_is_scoped_to_the_invocation() {
+        assert_eq!(None, current_correlation_id());
+
+        let inside =
+            with_correlation_id("some-id".to_owned(), async { current_correlation_id() }).await;
+
+        assert_eq!(Some("some-id".to_owned()), inside);
+        assert_eq!(None, current_correlation_id());
+    }
+
+    #[test]
+    fn correlation_id_header() {
+        let mut response = Response::new(String::new());
+
+        set_correlation_id_header(&mut response, "some-id");
+
+        assert_eq!(
+            "some-id",
+            response.headers().get(CORRELATION_ID_HEADER_NAME).unwrap()
+        );
+    }
+}
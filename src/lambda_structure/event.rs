@@ -32,6 +32,30 @@ impl EventContext {
     }
 }
 
+/// Details of a lambda payload logged with every line of its invocation, see
+/// [`Lambda::service`](super::lambda_trait::Lambda::service).
+pub trait InvocationPayload {
+    /// Context of the order the payload belongs to, if any.
+    fn event_context(&self) -> Option<&EventContext> {
+        None
+    }
+
+    /// Client the payload belongs to, if any. The log level of the invocation is evaluated for it.
+    fn client_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T: InvocationPayload> InvocationPayload for Event<T> {
+    fn event_context(&self) -> Option<&EventContext> {
+        Some(&self.context)
+    }
+
+    fn client_id(&self) -> Option<&str> {
+        self.payload.client_id()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LambdaResponseEvent {
     pub context: EventContext,
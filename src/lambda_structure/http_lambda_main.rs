@@ -15,6 +15,8 @@ use crate::result::error::LambdaError;
 
 /// Header name to extract the client id
 const CLIENT_ID_HEADER_NAME: &str = "client_id";
+/// Claim holding the identity of the caller
+const SUBJECT_CLAIM_NAME: &str = "sub";
 
 pub type HttpLambdaResponse = Result<Response<String>, Response<String>>;
 
@@ -26,11 +28,22 @@ pub type HttpLambdaResponse = Result<Response<String>, Response<String>>;
 // This allow us to return errors in a more "rusty" way and reduce lines of codes that handle the
 // error cases
 //
+// Each invocation is logged inside a span with its correlation id, see `extract_correlation_id`,
+// and the client id. The correlation id is returned in the `X-Correlation-Id` response header and
+// forwarded on Maestro requests. The log level of each invocation is taken from the log level
+// feature flag of its client.
+//
 // This macro support request validation as a third parameter. It validates the request it before
 // the business logic is executed. The general idea is to declare the validation functions with
 // the signature `Fn(&Request) -> Result<(), Response<String>>` and place it in the
 // `<root>/src/validations/http/` submodule.
 //
+// Validations that need the persisted state, or to await something like a Maestro call, go in
+// an optional fourth parameter. They are declared with the signature
+// `async fn(&Request, &State) -> Result<(), Response<String>>` and run after the plain ones. To
+// reuse them across lambdas, take the state as `&impl Trait` with a trait giving access to the
+// dependencies they need, like `FeatureFlagsState` or `PolicyCatalogState`.
+//
 // Example usage:
 // ```
 // http_lambdamain!(
@@ -41,6 +54,10 @@ pub type HttpLambdaResponse = Result<Response<String>, Response<String>>;
 //  validation_2,
 //  ..
 //  validation_n
+// ],
+// [
+//   state_validation_1,
+//   ..
 // ]
 // )
 #[macro_export]
@@ -49,6 +66,9 @@ macro_rules! http_lambda_main {
         http_lambda_main!($persisted_block, $handler, []);
     };
     ($persisted_block:block, $handler: ident, [$($validation:ident),*]) => {
+        http_lambda_main!($persisted_block, $handler, [$($validation),*], []);
+    };
+    ($persisted_block:block, $handler: ident, [$($validation:ident),*], [$($state_validation:ident),*]) => {
         #[tokio::main]
         async fn main() -> Result<(), Error> {
             use anyhow::anyhow;
@@ -57,8 +77,15 @@ macro_rules! http_lambda_main {
             use lambda_http::request::RequestContext;
             use lambda_http::{Body, RequestExt};
             use tracing_subscriber::{filter::LevelFilter, prelude::*, reload};
+            use mpc_signature_sm::feature_flags::{feature_flags_bootstrap, invocation_log_level};
             use mpc_signature_sm::http::errors::unauthorized_error_response;
-            use mpc_signature_sm::lambda_structure::http_lambda_main::{RequestExtractor};
+            use mpc_signature_sm::lambda_structure::correlation_id::{
+                extract_correlation_id, set_correlation_id_header, with_correlation_id,
+            };
+            use mpc_signature_sm::lambda_structure::http_lambda_main::{
+                CustomFieldsExtractor, RequestExtractor,
+            };
+            use tracing::Instrument;
             use tracing_log::LogTracer;
             use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 
@@ -77,42 +104,68 @@ macro_rules! http_lambda_main {
                 .with(bunyan_formatting_layer)
                 .init();
 
+            let feature_flags = feature_flags_bootstrap(get_secrets_provider().await).await;
+
             let persisted = { $persisted_block };
 
             let service = |mut request: Request| async {
-                let secrets_provider = get_secrets_provider().await;
+                // Every log line of the invocation carries these fields.
+                let correlation_id = extract_correlation_id(&request);
+                let span = tracing::info_span!(
+                    "invocation",
+                    correlation_id = %correlation_id,
+                    client_id = tracing::field::Empty,
+                );
+                let client_id = request.extract_client_id().ok();
+                if let Some(client_id) = &client_id {
+                    span.record("client_id", client_id.as_str());
+                }
+                let log_level =
+                    invocation_log_level(feature_flags.as_ref(), client_id.as_deref()).await;
+
+                let invocation = async {
+                    let secrets_provider = get_secrets_provider().await;
 
                     reload_handle
-                        .modify(|filter| *filter = LevelFilter::WARN)
+                        .modify(|filter| *filter = log_level)
                         .unwrap_or_else(|e| tracing::error!(error= ?e, "{:?}", e));
 
+                    let payload = match request.body() {
+                        Body::Empty => "No Payload".to_owned(),
+                        _ => match request.extract_body::<serde_json::Value>() {
+                            Ok(payload) => payload.to_string(),
+                            Err(e) => return e.into(),
+                        }
+                    };
+                    let context = match request.extract_context() {
+                        Ok(context) => context,
+                        Err(e) => return e.into(),
+                    };
+                    tracing::info!(payload = ?payload, context = ?context, "Execution started");
 
-                let payload = match request.body() {
-                    Body::Empty => "No Payload".to_owned(),
-                    _ => match request.extract_body::<serde_json::Value>() {
-                        Ok(payload) => payload.to_string(),
-                        Err(e) => return Ok(e.into()),
+                    $(
+                    if let Err(response) = $validation(&request) {
+                        return response;
                     }
-                };
-                let context = match request.extract_context() {
-                    Ok(context) => context,
-                    Err(e) => return Ok(e.into()),
-                };
-                tracing::info!(payload = ?payload, context = ?context, "Execution started");
+                    )*
 
-                $(
-                if let Err(response) = $validation(&request) {
-                    return Ok(response);
-                }
-                )*
+                    $(
+                    if let Err(response) = $state_validation(&request, &persisted).await {
+                        return response;
+                    }
+                    )*
 
-                let response: Result<Response<String>, Error> =
                     match $handler(request, &persisted).await {
-                        Ok(response) => Ok(response),
-                        Err(response) => Ok(response),
-                    };
+                        Ok(response) => response,
+                        Err(response) => response,
+                    }
+                };
+
+                let mut response: Response<String> =
+                    with_correlation_id(correlation_id.clone(), invocation.instrument(span)).await;
+                set_correlation_id_header(&mut response, &correlation_id);
 
-                response
+                Ok::<Response<String>, Error>(response)
             };
 
             run(service_fn(service)).await
@@ -131,6 +184,11 @@ pub trait RequestExtractor {
         header_name: &str,
     ) -> Result<T, RequestExtractorError>;
 
+    fn extract_query_param<T: FromStr>(
+        &self,
+        param_name: &str,
+    ) -> Result<Option<T>, RequestExtractorError>;
+
     fn extract_body<T: DeserializeOwned>(&self) -> Result<T, RequestExtractorError>;
 
     fn extract_context(&self) -> Result<ApiGatewayProxyRequestContext, RequestExtractorError>;
@@ -172,6 +230,21 @@ impl RequestExtractor for Request {
         }
     }
 
+    fn extract_query_param<T: FromStr>(
+        &self,
+        param_name: &str,
+    ) -> Result<Option<T>, RequestExtractorError> {
+        let query_parameters = self.query_string_parameters();
+        query_parameters
+            .first(param_name)
+            .map(|value| {
+                T::from_str(value).map_err(|_| {
+                    RequestExtractorError::QueryParamWithWrongTypeError(param_name.to_owned())
+                })
+            })
+            .transpose()
+    }
+
     fn extract_body<T: DeserializeOwned>(&self) -> Result<T, RequestExtractorError> {
         match self.body() {
             Body::Text(json_str) => Ok(serde_json::from_str(json_str)
@@ -191,6 +264,9 @@ impl RequestExtractor for Request {
 
 pub trait CustomFieldsExtractor {
     fn extract_client_id(&self) -> Result<String, RequestExtractorError>;
+
+    /// Subject of the authorizer claims, if the authorizer provides one.
+    fn extract_subject(&self) -> Result<Option<String>, RequestExtractorError>;
 }
 
 impl CustomFieldsExtractor for Request {
@@ -209,11 +285,25 @@ impl CustomFieldsExtractor for Request {
             _ => Err(RequestExtractorError::RequestContextNotFoundError),
         }
     }
+
+    fn extract_subject(&self) -> Result<Option<String>, RequestExtractorError> {
+        match self.request_context() {
+            RequestContext::ApiGatewayV1(context) => Ok(context
+                .authorizer
+                .get("claims")
+                .and_then(|claims| claims.get(SUBJECT_CLAIM_NAME))
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_owned)),
+            _ => Err(RequestExtractorError::RequestContextNotFoundError),
+        }
+    }
 }
 
 pub enum RequestExtractorError {
     PathParamNotFoundError(String),
     PathParamWithWrongTypeError(String),
+    QueryParamNotFoundError(String),
+    QueryParamWithWrongTypeError(String),
     HeaderNotFoundError(String),
     HeaderWithWrongTypeError(String),
     HeaderDeserializingError(ToStrError),
@@ -236,6 +326,18 @@ impl From<RequestExtractorError> for Response<String> {
                     None,
                 )
             }
+            RequestExtractorError::QueryParamNotFoundError(param_name) => {
+                validation_error_response(
+                    format!("{param_name} not found in request query string"),
+                    None,
+                )
+            }
+            RequestExtractorError::QueryParamWithWrongTypeError(param_name) => {
+                validation_error_response(
+                    format!("{param_name} with wrong type in request query string"),
+                    None,
+                )
+            }
             RequestExtractorError::HeaderNotFoundError(header_name) => validation_error_response(
                 format!("{header_name} not found in request headers"),
                 None,
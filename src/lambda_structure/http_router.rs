@@ -0,0 +1,227 @@
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt, Response};
+
+use crate::http::errors::not_found_response;
+
+pub const ROUTE_NOT_FOUND_CODE: &str = "route_not_found";
+
+/// Resource API Gateway matched the request with, e.g. `/policy_mappings/{chain_id}/{address}`.
+pub fn extract_resource_path(request: &Request) -> Option<String> {
+    match request.request_context() {
+        RequestContext::ApiGatewayV1(context) => context.resource_path,
+        _ => None,
+    }
+}
+
+pub fn route_not_found_response(request: &Request) -> Response<String> {
+    not_found_response(
+        ROUTE_NOT_FOUND_CODE,
+        format!(
+            "there is no route for {} {}",
+            request.method(),
+            extract_resource_path(request).unwrap_or_default()
+        ),
+    )
+}
+
+// This macro builds a handler that serves several http lambdas from a single one. The handler it
+// builds dispatches each request on its method and API Gateway resource to the handler of the
+// matching route, so it can be passed to `http_lambda_main!` and all the routes share the same
+// persisted `State`. Requests without a matching route get a 404 response.
+//
+// Each route has its own list of validations, run before its handler the same way
+// `http_lambda_main!` runs them, optionally followed by a list of validations taking the state.
+// Validations that apply to every route can still be passed to `http_lambda_main!`.
+//
+// Example usage:
+// ```
+// http_router!(
+//   router_fn,
+//   State<impl Repository>,
+//   [
+//     GET "/resource/{id}" => fetch_fn [],
+//     POST "/resource" => create_fn [validation_1, validation_2],
+//     PUT "/resource/{id}" => update_fn [validation_1] [state_validation_1],
+//     ..
+//   ]
+// );
+//
+// http_lambda_main!({ .. State }, router_fn);
+// ```
+#[macro_export]
+macro_rules! http_router {
+    ($router: ident, $state: ty, [$($method: ident $resource: literal => $handler: ident [$($validation: ident),*] $([$($state_validation: ident),*])?),* $(,)?]) => {
+        async fn $router(
+            request: lambda_http::Request,
+            state: &$state,
+        ) -> $crate::lambda_structure::http_lambda_main::HttpLambdaResponse {
+            let resource = $crate::lambda_structure::http_router::extract_resource_path(&request);
+
+            $(
+            if request.method().as_str() == stringify!($method)
+                && resource.as_deref() == Some($resource)
+            {
+                $(
+                $validation(&request)?;
+                )*
+                $($(
+                $state_validation(&request, state).await?;
+                )*)?
+                return $handler(request, state).await;
+            }
+            )*
+
+            Err($crate::lambda_structure::http_router::route_not_found_response(&request))
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderValue, Method, StatusCode};
+    use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+    use lambda_http::request::RequestContext;
+    use lambda_http::{Body, Request, RequestExt, Response};
+    use rstest::rstest;
+
+    use crate::dtos::responses::http_error::LambdaErrorResponse;
+    use crate::http::errors::validation_error_response;
+    use crate::http::lambda_proxy::LambdaProxyHttpResponse;
+    use crate::lambda_structure::http_lambda_main::HttpLambdaResponse;
+    use crate::validations::http::content_type::validate_content_type;
+
+    struct State {
+        name: String,
+        read_only: bool,
+    }
+
+    async fn fetch(_request: Request, state: &State) -> HttpLambdaResponse {
+        LambdaProxyHttpResponse {
+            status_code: StatusCode::OK,
+            body: Some(format!("fetched from {}", state.name)),
+            ..LambdaProxyHttpResponse::default()
+        }
+        .try_into()
+    }
+
+    async fn create(_request: Request, state: &State) -> HttpLambdaResponse {
+        LambdaProxyHttpResponse {
+            status_code: StatusCode::CREATED,
+            body: Some(format!("created in {}", state.name)),
+            ..LambdaProxyHttpResponse::default()
+        }
+        .try_into()
+    }
+
+    async fn delete(_request: Request, _state: &State) -> HttpLambdaResponse {
+        LambdaProxyHttpResponse {
+            status_code: StatusCode::NO_CONTENT,
+            ..LambdaProxyHttpResponse::default()
+        }
+        .try_into()
+    }
+
+    async fn validate_writable(_request: &Request, state: &State) -> Result<(), Response<String>> {
+        if state.read_only {
+            return Err(validation_error_response(
+                format!("{} is read only", state.name),
+                None,
+            ));
+        }
+
+        Ok(())
+    }
+
+    crate::http_router!(
+        route,
+        State,
+        [
+            GET "/items/{id}" => fetch [],
+            POST "/items" => create [validate_content_type],
+            DELETE "/items/{id}" => delete [] [validate_writable],
+        ]
+    );
+
+    fn build_request(method: Method, resource: &str) -> Request {
+        let request_context = RequestContext::ApiGatewayV1(ApiGatewayProxyRequestContext {
+            resource_path: Some(resource.to_owned()),
+            ..ApiGatewayProxyRequestContext::default()
+        });
+        let mut request = Request::new(Body::Empty).with_request_context(request_context);
+        *request.method_mut() = method;
+        request
+    }
+
+    fn state() -> State {
+        State {
+            name: "shared state".to_owned(),
+            read_only: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_on_method_and_resource() {
+        let state = state();
+
+        let response = route(build_request(Method::GET, "/items/{id}"), &state)
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("fetched from shared state", response.body());
+
+        let mut request = build_request(Method::POST, "/items");
+        request
+            .headers_mut()
+            .insert("Content-Type", HeaderValue::from_static("application/json"));
+        let response = route(request, &state).await.unwrap();
+        assert_eq!(StatusCode::CREATED, response.status());
+        assert_eq!("created in shared state", response.body());
+    }
+
+    #[tokio::test]
+    async fn runs_route_validations() {
+        let response = route(build_request(Method::POST, "/items"), &state())
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert!(response
+            .body()
+            .contains("Content-Type not found in request headers"));
+    }
+
+    #[tokio::test]
+    async fn runs_route_state_validations() {
+        let request = || build_request(Method::DELETE, "/items/{id}");
+
+        let response = route(request(), &state()).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+        let read_only_state = State {
+            read_only: true,
+            ..state()
+        };
+        let response = route(request(), &read_only_state).await.unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("shared state is read only", body.message);
+    }
+
+    #[rstest]
+    #[case::unknown_method(Method::PATCH, "/items/{id}")]
+    #[case::unknown_resource(Method::GET, "/items")]
+    #[tokio::test]
+    async fn unknown_route(#[case] method: Method, #[case] resource: &str) {
+        let response = route(build_request(method.clone(), resource), &state())
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!("route_not_found", body.code);
+        assert_eq!(
+            format!("there is no route for {method} {resource}"),
+            body.message
+        );
+    }
+}
@@ -1,14 +1,20 @@
 use async_trait::async_trait;
 use lambda_runtime::{Error, LambdaEvent};
 use serde::{de::DeserializeOwned, Serialize};
+use tracing::Instrument;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_subscriber::{filter::LevelFilter, prelude::*, reload};
 
+use super::correlation_id::with_correlation_id;
+use super::event::InvocationPayload;
+use crate::feature_flags::{feature_flags_bootstrap, invocation_log_level};
+use common::aws_clients::secrets_manager::get_secrets_provider;
+
 #[async_trait]
 pub trait Lambda {
     type PersistedMemory: Sync + Send;
-    type InputBody: DeserializeOwned + Send + Sync + std::fmt::Debug;
+    type InputBody: DeserializeOwned + InvocationPayload + Send + Sync + std::fmt::Debug;
     type Output: Serialize + Send + Sync;
     type Error: Into<Error> + std::error::Error + Sync + Send + 'static;
 
@@ -38,14 +44,17 @@ pub trait Lambda {
             .init();
 
         let reload_handle = &reload_handle;
+        let feature_flags = &feature_flags_bootstrap(get_secrets_provider().await).await;
 
         // Get a reference to avoid moving the original connections into the below closure.
         let persisted = &Self::bootstrap().await?;
 
         // Wrap our actual service call so we can pass in our connection data while preserving the expected Lambda signature.
         let service = move |event: LambdaEvent<Self::InputBody>| async move {
+            let log_level =
+                invocation_log_level(feature_flags.as_ref(), event.payload.client_id()).await;
             reload_handle
-                .modify(|filter| *filter = LevelFilter::WARN)
+                .modify(|filter| *filter = log_level)
                 .unwrap_or_else(|e| tracing::error!(error= ?e, "{:?}", e));
 
             Self::service(event, persisted).await
@@ -62,10 +71,29 @@ pub trait Lambda {
     ) -> Result<Self::Output, Self::Error> {
         let LambdaEvent { payload, context } = event;
 
-        tracing::info!(payload = ?payload, context = ?context, "Execution started");
+        // The AWS request id ties together every log line of the execution and is forwarded on
+        // Maestro requests. The client and order ids are logged too when the payload has them.
+        let correlation_id = context.request_id.clone();
+        let span = tracing::info_span!(
+            "invocation",
+            correlation_id = %correlation_id,
+            client_id = tracing::field::Empty,
+            order_id = tracing::field::Empty,
+        );
+        if let Some(client_id) = payload.client_id() {
+            span.record("client_id", client_id);
+        }
+        if let Some(event_context) = payload.event_context() {
+            span.record("order_id", tracing::field::display(event_context.order_id));
+        }
 
-        // Call operation.
-        Self::run(payload, connections).await
+        let execution = async {
+            tracing::info!(payload = ?payload, context = ?context, "Execution started");
+
+            // Call operation.
+            Self::run(payload, connections).await
+        };
+        with_correlation_id(correlation_id, execution.instrument(span)).await
     }
 }
 
@@ -79,3 +107,119 @@ macro_rules! lambda_main {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use lambda_runtime::{Context, LambdaEvent};
+    use serde::Deserialize;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{self, Layer};
+    use tracing_subscriber::prelude::*;
+
+    use super::Lambda;
+    use crate::lambda_structure::correlation_id::current_correlation_id;
+    use crate::lambda_structure::event::{Event, InvocationPayload};
+    use crate::result::error::OrchestrationError;
+
+    /// Collects the fields recorded on spans.
+    #[derive(Clone, Default)]
+    struct SpanFields(Arc<Mutex<HashMap<String, String>>>);
+
+    impl SpanFields {
+        fn get(&self, name: &str) -> Option<String> {
+            self.0.lock().unwrap().get(name).cloned()
+        }
+    }
+
+    impl Visit for SpanFields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_owned(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_owned(), value.to_owned());
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanFields {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: layer::Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: layer::Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ClientRequest {
+        client_id: String,
+    }
+
+    impl InvocationPayload for ClientRequest {
+        fn client_id(&self) -> Option<&str> {
+            Some(&self.client_id)
+        }
+    }
+
+    struct CorrelationIdLambda;
+
+    #[async_trait]
+    impl Lambda for CorrelationIdLambda {
+        type PersistedMemory = ();
+        type InputBody = Event<ClientRequest>;
+        type Output = Option<String>;
+        type Error = OrchestrationError;
+
+        async fn bootstrap() -> Result<Self::PersistedMemory, Self::Error> {
+            Ok(())
+        }
+
+        async fn run(
+            _payload: Self::InputBody,
+            _connections: &Self::PersistedMemory,
+        ) -> Result<Self::Output, Self::Error> {
+            Ok(current_correlation_id())
+        }
+    }
+
+    #[tokio::test]
+    async fn service_logs_invocation_in_span() {
+        let span_fields = SpanFields::default();
+        let _guard = tracing_subscriber::registry()
+            .with(span_fields.clone())
+            .set_default();
+
+        let event = Event::test_event_from(ClientRequest {
+            client_id: "some_client".to_owned(),
+        });
+        let order_id = event.context.order_id;
+        let mut context = Context::default();
+        context.request_id = "some_request_id".to_owned();
+
+        let output = CorrelationIdLambda::service(LambdaEvent::new(event, context), &())
+            .await
+            .unwrap();
+
+        assert_eq!(Some("some_request_id".to_owned()), output);
+        assert_eq!(
+            Some("some_request_id".to_owned()),
+            span_fields.get("correlation_id")
+        );
+        assert_eq!(Some("some_client".to_owned()), span_fields.get("client_id"));
+        assert_eq!(Some(order_id.to_string()), span_fields.get("order_id"));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod correlation_id;
 pub mod event;
 pub mod http_lambda_main;
+pub mod http_router;
 pub mod lambda_trait;
@@ -1,14 +1,16 @@
 pub mod config;
 pub mod dtos;
+pub mod policy_catalog;
 pub mod session;
 pub mod state;
+pub mod test_tools;
 
 use self::{
     config::MaestroConfig,
     session::{login, MaestroLoginInformation},
     state::MaestroState,
 };
-use crate::rest::middlewares::AuthenticationMiddleware;
+use crate::rest::middlewares::{AuthenticationMiddleware, CorrelationIdMiddleware};
 use crate::result::error::Result;
 use common::config::ConfigLoader;
 use secrets_provider::SecretsProvider;
@@ -42,6 +44,7 @@ pub async fn maestro_bootstrap(secrets_provider: impl SecretsProvider) -> Result
             login_information,
             Some(new_token),
         ))
+        .with(CorrelationIdMiddleware)
         .build();
 
     Ok(MaestroState {
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::Utc;
+use http::StatusCode;
+use model::cache::{CacheItem, DataType};
+use model::order::policy::{Approval, Policy};
+use repositories::cache::{CacheRepository, CacheRepositoryError};
+use serde::{Deserialize, Serialize};
+
+use super::state::MaestroState;
+use crate::result::error::LambdaError;
+
+/// How long a policy known to belong to a client is trusted before asking Maestro again.
+const OWNED_POLICY_TTL_SECONDS: i64 = 300;
+
+/// Negative results expire sooner so a policy just created in Maestro becomes usable quickly.
+/// Only Maestro answering that the policy doesn't exist or isn't the client's is cached, errors
+/// aren't.
+const NOT_OWNED_POLICY_TTL_SECONDS: i64 = 30;
+
+#[async_trait]
+pub trait PolicyCatalog: Sync + Send {
+    /// Returns `true` if `policy` exists in Maestro and belongs to `client_id`.
+    async fn policy_belongs_to_client(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<bool, LambdaError>;
+
+    /// Fetches `policy` with its approval levels. Returns `None` if it doesn't exist in Maestro
+    /// or doesn't belong to `client_id`. Details are always fetched from Maestro, never cached.
+    async fn get_policy(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<Option<Policy>, LambdaError>;
+}
+
+/// Lets lambdas serving several handlers share one catalog, and its cache, between them.
+#[async_trait]
+impl<T: PolicyCatalog + ?Sized> PolicyCatalog for Arc<T> {
+    async fn policy_belongs_to_client(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<bool, LambdaError> {
+        self.as_ref()
+            .policy_belongs_to_client(client_id, policy)
+            .await
+    }
+
+    async fn get_policy(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<Option<Policy>, LambdaError> {
+        self.as_ref().get_policy(client_id, policy).await
+    }
+}
+
+/// State of lambdas checking the policies of requests against Maestro, see
+/// [`crate::validations::http::policy_ownership::validate_mapping_policies_belong_to_client`].
+pub trait PolicyCatalogState {
+    type PolicyCatalog: PolicyCatalog;
+
+    fn policy_catalog(&self) -> &Self::PolicyCatalog;
+}
+
+/// Policy as returned by Maestro, only the fields needed to build a [`Policy`].
+#[derive(Deserialize)]
+struct MaestroPolicy {
+    policy_name: String,
+    #[serde(default)]
+    approvals: Vec<Approval>,
+}
+
+impl From<MaestroPolicy> for Policy {
+    fn from(policy: MaestroPolicy) -> Self {
+        Self {
+            name: policy.policy_name,
+            approvals: policy.approvals,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct PolicyOwnership {
+    belongs_to_client: bool,
+}
+
+#[derive(Clone, Copy)]
+struct CachedOwnership {
+    belongs_to_client: bool,
+    expires_at: i64,
+}
+
+/// Policy catalog backed by Maestro. Results are kept in memory for as long as the lambda stays
+/// warm and in the cache table so other lambdas can reuse them.
+pub struct MaestroPolicyCatalog<C: CacheRepository + Sync + Send> {
+    maestro: MaestroState,
+    cache_repository: C,
+    memory: Mutex<HashMap<String, CachedOwnership>>,
+}
+
+impl<C: CacheRepository + Sync + Send> MaestroPolicyCatalog<C> {
+    pub fn new(maestro: MaestroState, cache_repository: C) -> Self {
+        Self {
+            maestro,
+            cache_repository,
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cache_key(client_id: &str, policy: &str) -> String {
+        format!("{client_id}#{policy}")
+    }
+
+    fn from_memory(&self, key: &str, now: i64) -> Option<bool> {
+        let memory = self.memory.lock().ok()?;
+        match memory.get(key) {
+            Some(cached) if cached.expires_at > now => Some(cached.belongs_to_client),
+            _ => None,
+        }
+    }
+
+    fn remember(&self, key: &str, ownership: CachedOwnership) {
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(key.to_owned(), ownership);
+        }
+    }
+
+    async fn from_cache(&self, key: &str, now: i64) -> Option<CachedOwnership> {
+        let item = match self
+            .cache_repository
+            .get_item(key, DataType::PolicyOwnership)
+            .await
+        {
+            Ok(item) => item,
+            Err(CacheRepositoryError::KeyNotFound(_)) => return None,
+            Err(e) => {
+                tracing::warn!(error = ?e, "unable to read policy ownership from cache");
+                return None;
+            }
+        };
+
+        // DynamoDB TTL deletion is lazy, so expired items can still be returned.
+        if item.expires_at <= now {
+            return None;
+        }
+
+        match serde_json::from_value::<PolicyOwnership>(item.data) {
+            Ok(ownership) => Some(CachedOwnership {
+                belongs_to_client: ownership.belongs_to_client,
+                expires_at: item.expires_at,
+            }),
+            Err(e) => {
+                tracing::warn!(error = ?e, "invalid policy ownership cache item");
+                None
+            }
+        }
+    }
+
+    async fn store_in_cache(&self, key: &str, ownership: CachedOwnership) {
+        let data = match serde_json::to_value(PolicyOwnership {
+            belongs_to_client: ownership.belongs_to_client,
+        }) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(error = ?e, "unable to serialize policy ownership");
+                return;
+            }
+        };
+
+        let item = CacheItem {
+            sk: key.to_owned(),
+            pk: DataType::PolicyOwnership,
+            data,
+            created_at: Utc::now(),
+            expires_at: ownership.expires_at,
+        };
+
+        if let Err(e) = self.cache_repository.set_item(item).await {
+            tracing::warn!(error = ?e, "unable to store policy ownership in cache");
+        }
+    }
+
+    async fn request_policy(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<reqwest::Response, LambdaError> {
+        self.maestro
+            .http
+            .get(format!(
+                "{}/{client_id}/policy/{policy}",
+                self.maestro.config.maestro_url
+            ))
+            .send()
+            .await
+            .map_err(|e| {
+                LambdaError::Unknown(anyhow!("there was an error with maestro's request: {e:?}"))
+            })
+    }
+
+    async fn fetch_from_maestro(&self, client_id: &str, policy: &str) -> Result<bool, LambdaError> {
+        let response = self.request_policy(client_id, policy).await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => Ok(false),
+            status => Err(LambdaError::Unknown(anyhow!(
+                "maestro returned {status} checking policy {policy}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: CacheRepository + Sync + Send> PolicyCatalog for MaestroPolicyCatalog<C> {
+    async fn policy_belongs_to_client(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<bool, LambdaError> {
+        let key = Self::cache_key(client_id, policy);
+        let now = Utc::now().timestamp();
+
+        if let Some(belongs_to_client) = self.from_memory(&key, now) {
+            return Ok(belongs_to_client);
+        }
+
+        if let Some(ownership) = self.from_cache(&key, now).await {
+            self.remember(&key, ownership);
+            return Ok(ownership.belongs_to_client);
+        }
+
+        let belongs_to_client = self.fetch_from_maestro(client_id, policy).await?;
+        let ttl = if belongs_to_client {
+            OWNED_POLICY_TTL_SECONDS
+        } else {
+            NOT_OWNED_POLICY_TTL_SECONDS
+        };
+        let ownership = CachedOwnership {
+            belongs_to_client,
+            expires_at: now + ttl,
+        };
+
+        self.remember(&key, ownership);
+        self.store_in_cache(&key, ownership).await;
+
+        Ok(belongs_to_client)
+    }
+
+    async fn get_policy(
+        &self,
+        client_id: &str,
+        policy: &str,
+    ) -> Result<Option<Policy>, LambdaError> {
+        let response = self.request_policy(client_id, policy).await?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let policy = response.json::<MaestroPolicy>().await.map_err(|e| {
+                    LambdaError::Unknown(anyhow!("invalid policy returned by maestro: {e:?}"))
+                })?;
+                Ok(Some(policy.into()))
+            }
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => Ok(None),
+            status => Err(LambdaError::Unknown(anyhow!(
+                "maestro returned {status} fetching policy {policy}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use http::StatusCode;
+    use mockall::predicate::eq;
+    use model::cache::{DataType, GenericJsonCache};
+    use repositories::cache::{CacheRepositoryError, MockCacheRepositoryTest};
+    use rstest::rstest;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::PolicyCatalog;
+    use crate::maestro::test_tools::maestro_policy_catalog;
+
+    const CLIENT_ID: &str = "some_client";
+    const POLICY: &str = "some_policy";
+
+    async fn mock_maestro_policy(status: StatusCode, expected_calls: u64, server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path(format!("/{CLIENT_ID}/policy/{POLICY}")))
+            .respond_with(ResponseTemplate::new(status))
+            .expect(expected_calls)
+            .mount(server)
+            .await;
+    }
+
+    fn cached_item(belongs_to_client: bool, expires_at: i64) -> GenericJsonCache {
+        GenericJsonCache {
+            sk: format!("{CLIENT_ID}#{POLICY}"),
+            pk: DataType::PolicyOwnership,
+            data: json!({ "belongs_to_client": belongs_to_client }),
+            created_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    #[rstest]
+    #[case::owned(StatusCode::OK, true)]
+    #[case::not_found(StatusCode::NOT_FOUND, false)]
+    #[case::forbidden(StatusCode::FORBIDDEN, false)]
+    #[tokio::test]
+    async fn cache_miss_asks_maestro_once(#[case] status: StatusCode, #[case] expected: bool) {
+        let mock_server = MockServer::start().await;
+        mock_maestro_policy(status, 1, &mock_server).await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository
+            .expect_get_item()
+            .with(
+                eq(format!("{CLIENT_ID}#{POLICY}")),
+                eq(DataType::PolicyOwnership),
+            )
+            .once()
+            .returning(|key, _| Err(CacheRepositoryError::KeyNotFound(key.to_owned())));
+        cache_repository
+            .expect_set_item()
+            .once()
+            .withf(move |item| {
+                item.pk == DataType::PolicyOwnership
+                    && item.data == json!({ "belongs_to_client": expected })
+                    && item.expires_at > Utc::now().timestamp()
+            })
+            .returning(|_| Ok(()));
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        // The second lookup is served from memory.
+        for _ in 0..2 {
+            let result = catalog
+                .policy_belongs_to_client(CLIENT_ID, POLICY)
+                .await
+                .unwrap();
+            assert_eq!(expected, result);
+        }
+    }
+
+    #[rstest]
+    #[case::owned(true)]
+    #[case::not_owned(false)]
+    #[tokio::test]
+    async fn cache_hit_skips_maestro(#[case] belongs_to_client: bool) {
+        let mock_server = MockServer::start().await;
+        mock_maestro_policy(StatusCode::OK, 0, &mock_server).await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository
+            .expect_get_item()
+            .once()
+            .returning(move |_, _| Ok(cached_item(belongs_to_client, Utc::now().timestamp() + 60)));
+        cache_repository.expect_set_item().never();
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        let result = catalog
+            .policy_belongs_to_client(CLIENT_ID, POLICY)
+            .await
+            .unwrap();
+        assert_eq!(belongs_to_client, result);
+    }
+
+    #[rstest]
+    #[case::server_error(StatusCode::INTERNAL_SERVER_ERROR)]
+    #[case::unavailable(StatusCode::SERVICE_UNAVAILABLE)]
+    #[case::throttled(StatusCode::TOO_MANY_REQUESTS)]
+    #[tokio::test]
+    async fn maestro_errors_are_not_cached(#[case] status: StatusCode) {
+        let mock_server = MockServer::start().await;
+        // Every lookup asks Maestro again.
+        mock_maestro_policy(status, 2, &mock_server).await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository
+            .expect_get_item()
+            .times(2)
+            .returning(|key, _| Err(CacheRepositoryError::KeyNotFound(key.to_owned())));
+        cache_repository.expect_set_item().never();
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        for _ in 0..2 {
+            let result = catalog.policy_belongs_to_client(CLIENT_ID, POLICY).await;
+            assert!(result.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_cache_item_is_ignored() {
+        let mock_server = MockServer::start().await;
+        mock_maestro_policy(StatusCode::OK, 1, &mock_server).await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository
+            .expect_get_item()
+            .once()
+            .returning(|_, _| Ok(cached_item(false, Utc::now().timestamp() - 1)));
+        cache_repository
+            .expect_set_item()
+            .once()
+            .returning(|_| Ok(()));
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        let result = catalog
+            .policy_belongs_to_client(CLIENT_ID, POLICY)
+            .await
+            .unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn get_policy_returns_approvals() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{CLIENT_ID}/policy/{POLICY}")))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "policy_name": POLICY,
+                "display_name": "Some policy",
+                "serialized_policy": "base64_policy",
+                "approvals": [
+                    { "level": "DOMAIN", "name": "compliance" },
+                    { "level": "TENANT", "name": "treasury" }
+                ]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository.expect_get_item().never();
+        cache_repository.expect_set_item().never();
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        let policy = catalog
+            .get_policy(CLIENT_ID, POLICY)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(POLICY, policy.name);
+        let approvals: Vec<(&str, &str)> = policy
+            .approvals
+            .iter()
+            .map(|approval| (approval.level.as_str(), approval.name.as_str()))
+            .collect();
+        assert_eq!(
+            vec![("DOMAIN", "compliance"), ("TENANT", "treasury")],
+            approvals
+        );
+    }
+
+    #[rstest]
+    #[case::not_found(StatusCode::NOT_FOUND, true)]
+    #[case::maestro_error(StatusCode::INTERNAL_SERVER_ERROR, false)]
+    #[tokio::test]
+    async fn get_policy_without_policy(#[case] status: StatusCode, #[case] expected_ok: bool) {
+        let mock_server = MockServer::start().await;
+        mock_maestro_policy(status, 1, &mock_server).await;
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), MockCacheRepositoryTest::new());
+
+        let result = catalog.get_policy(CLIENT_ID, POLICY).await;
+        assert_eq!(expected_ok, result.is_ok());
+        assert!(result.unwrap_or_default().is_none());
+    }
+
+    #[tokio::test]
+    async fn cache_errors_fall_back_to_maestro() {
+        let mock_server = MockServer::start().await;
+        mock_maestro_policy(StatusCode::OK, 1, &mock_server).await;
+
+        let mut cache_repository = MockCacheRepositoryTest::new();
+        cache_repository
+            .expect_get_item()
+            .once()
+            .returning(|_, _| Err(CacheRepositoryError::Unknown(anyhow::anyhow!("timeout"))));
+        cache_repository
+            .expect_set_item()
+            .once()
+            .returning(|_| Err(CacheRepositoryError::Unknown(anyhow::anyhow!("timeout"))));
+
+        let catalog = maestro_policy_catalog(mock_server.uri(), cache_repository);
+
+        let result = catalog
+            .policy_belongs_to_client(CLIENT_ID, POLICY)
+            .await
+            .unwrap();
+        assert!(result);
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use repositories::cache::CacheRepository;
+
+use super::{
+    config::MaestroConfig,
+    policy_catalog::MaestroPolicyCatalog,
+    session::{login, MaestroLoginInformation},
+    state::MaestroState,
+};
+use crate::rest::middlewares::AuthenticationMiddleware;
+
+/// Maestro client for tests, pointed at `maestro_url` (usually a wiremock server) with a session
+/// already logged in.
+pub fn maestro_state(maestro_url: String) -> MaestroState {
+    let config = MaestroConfig {
+        maestro_url,
+        service_name: "test".to_owned(),
+        maestro_api_key_secret_name: "dummy_secret_name_api_key".to_owned(),
+        maestro_tenant_name: "tenant".to_owned(),
+    };
+
+    let http_client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(AuthenticationMiddleware::new(
+            &login,
+            Arc::new(MaestroLoginInformation {
+                maestro_url: config.maestro_url.clone(),
+                service_name: config.service_name.clone(),
+                maestro_api_key: "dummy_api_secret".to_owned(),
+                tenant_name: "tenant".to_owned(),
+            }),
+            Some("dummy_token".to_owned()),
+        ))
+        .build();
+
+    MaestroState {
+        http: http_client,
+        config,
+    }
+}
+
+/// Policy catalog for tests asking the Maestro at `maestro_url` and caching in `cache_repository`.
+pub fn maestro_policy_catalog<C: CacheRepository + Sync + Send>(
+    maestro_url: String,
+    cache_repository: C,
+) -> MaestroPolicyCatalog<C> {
+    MaestroPolicyCatalog::new(maestro_state(maestro_url), cache_repository)
+}
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use lambda_http::http::HeaderValue;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use task_local_extensions::Extensions;
+
+use crate::lambda_structure::correlation_id::{current_correlation_id, CORRELATION_ID_HEADER_NAME};
+
+/// Forwards the correlation id of the invocation being served, so downstream services can tie
+/// their logs to ours. Requests made outside an invocation are sent untouched.
+pub struct CorrelationIdMiddleware;
+
+#[async_trait]
+impl Middleware for CorrelationIdMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        if let Some(value) = current_correlation_id().and_then(|id| HeaderValue::from_str(&id).ok())
+        {
+            req.headers_mut().insert(CORRELATION_ID_HEADER_NAME, value);
+        }
+
+        next.run(req, extensions).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+    use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+    use wiremock::{
+        matchers::{header, header_exists, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::CorrelationIdMiddleware;
+    use crate::lambda_structure::correlation_id::{
+        with_correlation_id, CORRELATION_ID_HEADER_NAME,
+    };
+
+    fn build_rest_client() -> ClientWithMiddleware {
+        ClientBuilder::new(reqwest::Client::new())
+            .with(CorrelationIdMiddleware)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn forwards_correlation_id() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/policy"))
+            .and(header(CORRELATION_ID_HEADER_NAME, "some-id"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let response = with_correlation_id(
+            "some-id".to_owned(),
+            build_rest_client()
+                .get(format!("{}/policy", mock_server.uri()))
+                .send(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn no_correlation_id_outside_invocations() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/policy"))
+            .and(header_exists(CORRELATION_ID_HEADER_NAME))
+            .respond_with(ResponseTemplate::new(StatusCode::BAD_REQUEST))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/policy"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .mount(&mock_server)
+            .await;
+
+        let response = build_rest_client()
+            .get(format!("{}/policy", mock_server.uri()))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+    }
+}
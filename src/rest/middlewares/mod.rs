@@ -1,3 +1,5 @@
 mod authentication_middleware;
+mod correlation_id_middleware;
 
 pub use authentication_middleware::AuthenticationMiddleware;
+pub use correlation_id_middleware::CorrelationIdMiddleware;
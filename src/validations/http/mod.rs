@@ -1,2 +1,3 @@
 pub mod content_type;
+pub mod policy_ownership;
 pub mod supported_chain_id;
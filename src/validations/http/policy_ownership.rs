@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::http::errors::{unknown_error_response, validation_error_response};
+use crate::lambda_structure::http_lambda_main::{CustomFieldsExtractor, RequestExtractor};
+use crate::maestro::policy_catalog::{PolicyCatalog, PolicyCatalogState};
+use crate::result::error::LambdaError;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use lambda_http::{Request, Response};
+use serde::Deserialize;
+
+/// Policies checked against Maestro at the same time.
+const MAESTRO_CONCURRENT_REQUESTS: usize = 8;
+
+/// Policies the mapping in the body of a create or update request refers to.
+#[derive(Deserialize)]
+struct MappingPolicies {
+    policy: String,
+    #[serde(default)]
+    value_bands: Vec<ValueBandPolicy>,
+}
+
+#[derive(Deserialize)]
+struct ValueBandPolicy {
+    policy: String,
+}
+
+/// Tells, for each distinct policy, whether it exists in Maestro and belongs to the client. Each
+/// one is checked only once, a few of them at a time.
+pub async fn check_policies_belong_to_client<'a>(
+    policy_catalog: &impl PolicyCatalog,
+    client_id: &str,
+    policies: impl IntoIterator<Item = &'a str>,
+) -> Result<BTreeMap<String, bool>, Response<String>> {
+    let policies = policies.into_iter().collect::<BTreeSet<_>>();
+    stream::iter(policies)
+        .map(|policy| async move {
+            let belongs = policy_catalog
+                .policy_belongs_to_client(client_id, policy)
+                .await?;
+            Ok::<_, LambdaError>((policy.to_owned(), belongs))
+        })
+        .buffer_unordered(MAESTRO_CONCURRENT_REQUESTS)
+        .try_collect()
+        .await
+        .map_err(unknown_error_response)
+}
+
+/// Rejects mappings referring, in their policy or in their value bands, to a policy that doesn't
+/// exist in Maestro or belongs to another client.
+pub async fn validate_policies_belong_to_client<'a>(
+    policy_catalog: &impl PolicyCatalog,
+    client_id: &str,
+    policies: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Response<String>> {
+    let checked = check_policies_belong_to_client(policy_catalog, client_id, policies).await?;
+    match checked.into_iter().find(|(_, belongs)| !belongs) {
+        Some((policy, _)) => Err(validation_error_response(
+            format!(r#"invalid policy "{policy}""#),
+            None,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Same as [`validate_policies_belong_to_client`] for the mapping in the body of the request, for
+/// lambdas creating or updating a single mapping. Bodies that can't be parsed are left for the
+/// handler to reject.
+pub async fn validate_mapping_policies_belong_to_client(
+    request: &Request,
+    state: &impl PolicyCatalogState,
+) -> Result<(), Response<String>> {
+    let body = match request.extract_body::<MappingPolicies>() {
+        Ok(body) => body,
+        Err(_) => return Ok(()),
+    };
+    let client_id = request.extract_client_id()?;
+
+    let policies = std::iter::once(body.policy.as_str())
+        .chain(body.value_bands.iter().map(|band| band.policy.as_str()));
+    validate_policies_belong_to_client(state.policy_catalog(), &client_id, policies).await
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use common::test_tools::http::constants::CLIENT_ID_FOR_MOCK_REQUESTS;
+    use common::test_tools::http::helpers::build_request_custom_auth;
+    use http::StatusCode;
+    use lambda_http::Body;
+    use mockall::mock;
+    use mockall::predicate::eq;
+    use model::order::policy::Policy;
+    use serde_json::json;
+
+    use super::{validate_mapping_policies_belong_to_client, validate_policies_belong_to_client};
+    use crate::dtos::responses::http_error::LambdaErrorResponse;
+    use crate::maestro::policy_catalog::{PolicyCatalog, PolicyCatalogState};
+    use crate::result::error::LambdaError;
+
+    mock! {
+        PolicyCatalog {}
+
+        #[async_trait]
+        impl PolicyCatalog for PolicyCatalog {
+            async fn policy_belongs_to_client(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<bool, LambdaError>;
+
+            async fn get_policy(
+                &self,
+                client_id: &str,
+                policy: &str,
+            ) -> Result<Option<Policy>, LambdaError>;
+        }
+    }
+
+    struct State {
+        policy_catalog: MockPolicyCatalog,
+    }
+
+    impl PolicyCatalogState for State {
+        type PolicyCatalog = MockPolicyCatalog;
+
+        fn policy_catalog(&self) -> &MockPolicyCatalog {
+            &self.policy_catalog
+        }
+    }
+
+    #[tokio::test]
+    async fn accepts_owned_policies() {
+        let mut policy_catalog = MockPolicyCatalog::new();
+        // The repeated band policy is only checked once.
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS), eq("some_policy"))
+            .once()
+            .returning(|_, _| Ok(true));
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS), eq("band_policy"))
+            .once()
+            .returning(|_, _| Ok(true));
+        let policies = ["some_policy", "band_policy", "band_policy"];
+
+        validate_policies_belong_to_client(&policy_catalog, CLIENT_ID_FOR_MOCK_REQUESTS, policies)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_policy_of_other_client() {
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .once()
+            .returning(|_, _| Ok(false));
+        let response = validate_policies_belong_to_client(
+            &policy_catalog,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+            ["other_policy"],
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(r#"invalid policy "other_policy""#, body.message);
+    }
+
+    #[tokio::test]
+    async fn fails_when_maestro_fails() {
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .once()
+            .returning(|_, _| Err(LambdaError::Unknown(anyhow!("timeout"))));
+        let response = validate_policies_belong_to_client(
+            &policy_catalog,
+            CLIENT_ID_FOR_MOCK_REQUESTS,
+            ["some_policy"],
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+    }
+
+    #[tokio::test]
+    async fn rejects_mapping_with_band_policy_of_other_client() {
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS), eq("some_policy"))
+            .once()
+            .returning(|_, _| Ok(true));
+        policy_catalog
+            .expect_policy_belongs_to_client()
+            .with(eq(CLIENT_ID_FOR_MOCK_REQUESTS), eq("band_policy"))
+            .once()
+            .returning(|_, _| Ok(false));
+        let request = build_request_custom_auth(
+            json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS }),
+            Body::Text(
+                json!({
+                    "chain_id": 1,
+                    "policy": "some_policy",
+                    "value_bands": [{ "below": "1000", "policy": "band_policy" }]
+                })
+                .to_string(),
+            ),
+        );
+
+        let response =
+            validate_mapping_policies_belong_to_client(&request, &State { policy_catalog })
+                .await
+                .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        let body: LambdaErrorResponse = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(r#"invalid policy "band_policy""#, body.message);
+    }
+
+    #[tokio::test]
+    async fn leaves_unparseable_mapping_to_the_handler() {
+        let mut policy_catalog = MockPolicyCatalog::new();
+        policy_catalog.expect_policy_belongs_to_client().never();
+        let request = build_request_custom_auth(
+            json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS }),
+            Body::Text(json!({ "chain_id": 1 }).to_string()),
+        );
+
+        validate_mapping_policies_belong_to_client(&request, &State { policy_catalog })
+            .await
+            .unwrap();
+    }
+}
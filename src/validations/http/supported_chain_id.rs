@@ -1,8 +1,15 @@
+use std::collections::BTreeSet;
+
+use crate::dtos::policy_mapping_chain::extract_mapping_chain_id;
+use crate::feature_flags::FeatureFlagsState;
+use crate::lambda_structure::http_lambda_main::CustomFieldsExtractor;
 use crate::{config::SupportedChain, lambda_structure::http_lambda_main::RequestExtractor};
+use common::feature_flags::FeatureFlags;
 use lambda_http::{Request, Response};
+use model::address_policy_registry::ANY_CHAIN_ID;
 use validator::ValidationError;
 
-use crate::http::errors::validation_error_response;
+use crate::http::errors::{forbidden_error_response, validation_error_response};
 
 const CHAIN_ID_PATH_PARAM: &str = "chain_id";
 
@@ -19,6 +26,94 @@ pub fn validate_chain_id_is_supported(request: &Request) -> Result<(), Response<
     Ok(())
 }
 
+/// Same as [`validate_chain_id_is_supported`] for policy mapping paths, which also take `*` for
+/// client wide mappings.
+pub fn validate_mapping_chain_id_is_supported(request: &Request) -> Result<(), Response<String>> {
+    let chain_id = extract_mapping_chain_id(request, CHAIN_ID_PATH_PARAM)?;
+
+    if chain_id != ANY_CHAIN_ID && !chain_id.is_supported() {
+        return Err(validation_error_response(
+            format!("chain_id {chain_id} is not supported"),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Same as [`validate_chain_id_is_supported`], also rejecting with a 403 chains whose feature flag
+/// is off for the requesting client.
+pub async fn validate_chain_id_is_enabled(
+    request: &Request,
+    state: &impl FeatureFlagsState,
+) -> Result<(), Response<String>> {
+    validate_chain_id_is_supported(request)?;
+    let chain_id: u64 = request.extract_path_param(CHAIN_ID_PATH_PARAM)?;
+
+    validate_chain_is_enabled_for_client(request, chain_id, state.feature_flags()).await
+}
+
+/// Same as [`validate_chain_id_is_enabled`] for policy mapping paths. Client wide mappings aren't
+/// gated by any chain flag.
+pub async fn validate_mapping_chain_id_is_enabled(
+    request: &Request,
+    state: &impl FeatureFlagsState,
+) -> Result<(), Response<String>> {
+    validate_mapping_chain_id_is_supported(request)?;
+    let chain_id = extract_mapping_chain_id(request, CHAIN_ID_PATH_PARAM)?;
+
+    validate_mapping_chain_is_enabled_for_client(request, chain_id, state.feature_flags()).await
+}
+
+/// Rejects with a 403 a supported chain whose feature flag is off for the requesting client. Meant
+/// for handlers taking the chain id from somewhere other than the path.
+pub async fn validate_chain_is_enabled_for_client(
+    request: &Request,
+    chain_id: u64,
+    feature_flags: &dyn FeatureFlags,
+) -> Result<(), Response<String>> {
+    let client_id = request.extract_client_id()?;
+
+    if !chain_id
+        .is_enabled_for_client(feature_flags, &client_id)
+        .await
+    {
+        return Err(forbidden_error_response(format!(
+            "chain_id {chain_id} is not enabled for this client"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Same as [`validate_chain_is_enabled_for_client`] for the chain of a policy mapping, letting
+/// client wide mappings through.
+pub async fn validate_mapping_chain_is_enabled_for_client(
+    request: &Request,
+    chain_id: u64,
+    feature_flags: &dyn FeatureFlags,
+) -> Result<(), Response<String>> {
+    if chain_id == ANY_CHAIN_ID {
+        return Ok(());
+    }
+
+    validate_chain_is_enabled_for_client(request, chain_id, feature_flags).await
+}
+
+/// Same as [`validate_mapping_chain_is_enabled_for_client`] for the chains of several mappings,
+/// looking each distinct chain up once.
+pub async fn validate_mapping_chains_are_enabled_for_client(
+    request: &Request,
+    chain_ids: impl IntoIterator<Item = u64>,
+    feature_flags: &dyn FeatureFlags,
+) -> Result<(), Response<String>> {
+    for chain_id in chain_ids.into_iter().collect::<BTreeSet<_>>() {
+        validate_mapping_chain_is_enabled_for_client(request, chain_id, feature_flags).await?;
+    }
+
+    Ok(())
+}
+
 /// This is used for custom validations with validator crate
 pub fn is_supported_chain_id(chain_id: u64) -> Result<(), ValidationError> {
     if !chain_id.is_supported() {
@@ -27,16 +122,62 @@ pub fn is_supported_chain_id(chain_id: u64) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Same as [`is_supported_chain_id`] but also accepts the chain id of client wide mappings.
+pub fn is_supported_mapping_chain_id(chain_id: u64) -> Result<(), ValidationError> {
+    if chain_id == ANY_CHAIN_ID {
+        return Ok(());
+    }
+    is_supported_chain_id(chain_id)
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::config::supported_chains_feature_flags;
+    use crate::dtos::responses::http_error::LambdaErrorResponse;
+    use crate::feature_flags::FeatureFlagsState;
     use crate::validations::http::supported_chain_id::{
-        validate_chain_id_is_supported, CHAIN_ID_PATH_PARAM,
+        validate_chain_id_is_enabled, validate_chain_id_is_supported,
+        validate_mapping_chain_id_is_enabled, validate_mapping_chain_id_is_supported,
+        CHAIN_ID_PATH_PARAM,
     };
-    use common::test_tools::http::constants::CHAIN_ID_FOR_MOCK_REQUESTS;
+    use common::feature_flags::{FeatureFlags, LocalFeatureFlags};
+    use common::test_tools::http::constants::{
+        CHAIN_ID_FOR_MOCK_REQUESTS, CLIENT_ID_FOR_MOCK_REQUESTS,
+    };
+    use common::test_tools::http::helpers::build_request_custom_auth;
     use http::{Request, StatusCode};
     use lambda_http::{Body, RequestExt};
+    use rstest::rstest;
+    use serde_json::json;
     use std::collections::HashMap;
 
+    struct State {
+        feature_flags: LocalFeatureFlags,
+    }
+
+    impl FeatureFlagsState for State {
+        fn feature_flags(&self) -> &dyn FeatureFlags {
+            &self.feature_flags
+        }
+    }
+
+    fn build_client_request(chain_id: &str) -> lambda_http::Request {
+        let auth = json!({ "client_id": CLIENT_ID_FOR_MOCK_REQUESTS });
+        build_request_custom_auth(auth, Body::Empty).with_path_parameters::<HashMap<_, _>>(
+            HashMap::from([(CHAIN_ID_PATH_PARAM.to_owned(), chain_id.to_owned())]),
+        )
+    }
+
+    /// Every chain turned on, except 137 that is only on for the client of the mock requests.
+    fn state() -> State {
+        State {
+            feature_flags: supported_chains_feature_flags().with_flag(
+                "chain-137",
+                json!({ "default": false, "clients": { CLIENT_ID_FOR_MOCK_REQUESTS: true } }),
+            ),
+        }
+    }
+
     #[test]
     fn test_missing_path_param() {
         let request = Request::default();
@@ -67,4 +208,85 @@ mod tests {
             );
         validate_chain_id_is_supported(&request).expect("Chain_id should be supported");
     }
+
+    #[test]
+    fn test_mapping_chain_id_wildcard() {
+        let request =
+            Request::new(Body::Text("".to_owned())).with_path_parameters::<HashMap<_, _>>(
+                HashMap::from([(CHAIN_ID_PATH_PARAM.to_owned(), "*".to_owned())]),
+            );
+        validate_mapping_chain_id_is_supported(&request).expect("* should be supported");
+        let error = validate_chain_id_is_supported(&request).unwrap_err();
+        assert!(error
+            .body()
+            .contains("chain_id with wrong type in request path"));
+    }
+
+    #[rstest]
+    #[case::flagged_chain("137")]
+    #[case::chain_enabled_for_every_client("1")]
+    #[tokio::test]
+    async fn test_enabled_chain_id(#[case] chain_id: &str) {
+        let request = build_client_request(chain_id);
+
+        validate_chain_id_is_enabled(&request, &state())
+            .await
+            .expect("chain_id should be enabled");
+        validate_mapping_chain_id_is_enabled(&request, &state())
+            .await
+            .expect("chain_id should be enabled");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_chain_id() {
+        let state = State {
+            feature_flags: supported_chains_feature_flags().with_flag("chain-137", json!(false)),
+        };
+        let request = build_client_request("137");
+
+        let error = validate_chain_id_is_enabled(&request, &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, error.status());
+        let body: LambdaErrorResponse = serde_json::from_str(error.body()).unwrap();
+        assert_eq!("forbidden", body.code);
+        assert_eq!("chain_id 137 is not enabled for this client", body.message);
+    }
+
+    #[tokio::test]
+    async fn test_chain_id_without_flag_is_not_enabled() {
+        let state = State {
+            feature_flags: LocalFeatureFlags::default(),
+        };
+        let request = build_client_request("1");
+
+        let error = validate_chain_id_is_enabled(&request, &state)
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::FORBIDDEN, error.status());
+    }
+
+    #[tokio::test]
+    async fn test_not_supported_chain_id_is_not_enabled() {
+        let request = build_client_request("28731237918");
+
+        let error = validate_chain_id_is_enabled(&request, &state())
+            .await
+            .unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, error.status());
+    }
+
+    #[tokio::test]
+    async fn test_mapping_chain_id_wildcard_is_enabled() {
+        let state = State {
+            feature_flags: supported_chains_feature_flags().with_flag("chain-137", json!(false)),
+        };
+
+        validate_mapping_chain_id_is_enabled(&build_client_request("*"), &state)
+            .await
+            .expect("* should be enabled");
+    }
 }